@@ -0,0 +1,38 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rustls_pki_types::CertificateDer;
+use upki::revocation::RevocationCheckInput;
+
+// Input is a sequence of concatenated DER values: the end-entity certificate first,
+// followed by candidate issuers.
+fuzz_target!(|data: &[u8]| {
+    let _ = RevocationCheckInput::from_certificates(&split_der(data));
+});
+
+/// Split `data` into consecutive DER TLVs, stopping at the first malformed one.
+fn split_der(mut data: &[u8]) -> Vec<CertificateDer<'_>> {
+    let mut certs = vec![];
+    while let Some(len) = tlv_len(data) {
+        let (cert, rest) = data.split_at(len);
+        certs.push(CertificateDer::from(cert));
+        data = rest;
+    }
+    certs
+}
+
+fn tlv_len(data: &[u8]) -> Option<usize> {
+    let len = *data.get(1)?;
+    let (header, body) = match len {
+        0..=0x7f => (2, usize::from(len)),
+        0x81 => (3, usize::from(*data.get(2)?)),
+        0x82 => (
+            4,
+            usize::from(u16::from_be_bytes([*data.get(2)?, *data.get(3)?])),
+        ),
+        _ => return None,
+    };
+
+    let total = header + body;
+    (total <= data.len()).then_some(total)
+}
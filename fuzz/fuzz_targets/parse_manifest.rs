@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use upki::revocation::Manifest;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(manifest) = Manifest::from_bytes(data) {
+        let _ = manifest.introduce();
+    }
+});
@@ -14,7 +14,8 @@ use rustls::{
     ExtendedKeyPurpose, RootCertStore, SignatureScheme, SupportedCipherSuite,
 };
 use upki::revocation::{
-    CertSerial, CtTimestamp, IssuerSpkiHash, Manifest, RevocationCheckInput, RevocationStatus,
+    CertSerial, CtLogId, CtTimestamp, IssuerSpkiHash, Manifest, RevocationCheckInput,
+    RevocationStatus,
 };
 use upki::{self, Config, ConfigPath};
 use webpki::{EndEntityCert, ExtendedKeyUsage, InvalidNameContext, VerifiedPath};
@@ -102,7 +103,7 @@ impl ServerVerifier {
         for ts in sct_iter.map_err(sct_error)? {
             let ts = ts.map_err(sct_error)?;
             sct_timestamps.push(CtTimestamp {
-                log_id: ts.log_id,
+                log_id: CtLogId(ts.log_id),
                 timestamp: ts.timestamp_ms,
             });
         }
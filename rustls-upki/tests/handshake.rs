@@ -0,0 +1,111 @@
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use rustls::crypto::aws_lc_rs;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::{
+    CertificateError, ClientConfig, ClientConnection, RootCertStore, ServerConfig, ServerConnection,
+};
+use rustls_upki::{Policy, ServerVerifier};
+use tempfile::TempDir;
+use upki::revocation::{CtLogId, ManifestWriter, RevocationCheckInput};
+
+#[test]
+fn handshake_with_revoked_leaf() {
+    let ca = cert("ca");
+    let revoked = cert("revoked");
+    let good = cert("good");
+
+    // the upki cache says `revoked` is revoked, and `good` is not
+    let temp = TempDir::new().unwrap();
+    let dir = temp.path().join("revocation");
+    fs::create_dir(&dir).unwrap();
+    let mut writer = ManifestWriter::new(1_765_445_031, "handshake test");
+    writer.cover_log(CtLogId([0x01; 32]), 1_700_000_000_000, 1_760_000_000_000);
+    for (leaf, revoked) in [(&revoked, true), (&good, false)] {
+        let input = RevocationCheckInput::from_certificates(&[leaf.clone(), ca.clone()]).unwrap();
+        match revoked {
+            true => writer.revoked(&input.issuer_spki_hash, &input.cert_serial),
+            false => writer.not_revoked(&input.issuer_spki_hash, &input.cert_serial),
+        }
+    }
+    writer.write(&dir).unwrap();
+
+    let config_path = temp.path().join("config.toml");
+    fs::write(
+        &config_path,
+        format!(
+            "cache-dir = {:?}\n[revocation]\nfetch-url = \"\"\n",
+            temp.path()
+        ),
+    )
+    .unwrap();
+
+    assert_eq!(
+        handshake(&config_path, &ca, good),
+        Ok(()),
+        "good certificate should be accepted"
+    );
+    assert_eq!(
+        handshake(&config_path, &ca, revoked),
+        Err(rustls::Error::InvalidCertificate(CertificateError::Revoked))
+    );
+}
+
+/// Complete a handshake in memory, with a server presenting `leaf` to a client using
+/// a [`ServerVerifier`] that trusts `ca` and reads the upki config at `config_path`.
+fn handshake(
+    config_path: &Path,
+    ca: &CertificateDer<'static>,
+    leaf: CertificateDer<'static>,
+) -> Result<(), rustls::Error> {
+    let provider = Arc::new(aws_lc_rs::default_provider());
+    let mut roots = RootCertStore::empty();
+    roots.add(ca.clone()).unwrap();
+    let verifier = ServerVerifier::new(
+        Policy::default(),
+        Some(config_path.to_owned()),
+        Arc::new(roots),
+        provider.clone(),
+    )
+    .unwrap();
+
+    let client_config = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(verifier))
+        .with_no_client_auth();
+    let server_config = ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_no_client_auth()
+        .with_single_cert(
+            vec![leaf],
+            PrivateKeyDer::from_pem_file("tests/data/leaf.key").unwrap(),
+        )
+        .unwrap();
+
+    let mut client =
+        ClientConnection::new(Arc::new(client_config), "example.com".try_into().unwrap()).unwrap();
+    let mut server = ServerConnection::new(Arc::new(server_config)).unwrap();
+    while client.is_handshaking() || server.is_handshaking() {
+        let mut buf = Vec::new();
+        client.write_tls(&mut buf).unwrap();
+        server.read_tls(&mut &buf[..]).unwrap();
+        server.process_new_packets()?;
+
+        buf.clear();
+        server.write_tls(&mut buf).unwrap();
+        client.read_tls(&mut &buf[..]).unwrap();
+        client.process_new_packets()?;
+    }
+
+    Ok(())
+}
+
+fn cert(name: &str) -> CertificateDer<'static> {
+    CertificateDer::from_pem_file(format!("tests/data/{name}.pem")).unwrap()
+}
@@ -1,14 +1,21 @@
 #![crate_type = "staticlib"]
 #![allow(non_camel_case_types)]
 
-use core::ffi::c_char;
-use std::ffi::CStr;
+use core::cell::RefCell;
+use core::error::Error as StdError;
+use core::ffi::{c_char, c_int, c_void};
+use core::panic::UnwindSafe;
+use core::{iter, ptr, slice};
+use std::ffi::{CStr, CString};
+use std::io;
 use std::panic::catch_unwind;
 use std::path::Path;
-use std::slice;
 
 use rustls_pki_types::CertificateDer;
-use upki::revocation::{self, Manifest, RevocationCheckInput, RevocationStatus};
+use upki::revocation::{
+    self, CheckOutcome, IssuerSpkiHash, Manifest, MatchKind, NotCoveredReason,
+    RevocationCheckInput, RevocationStatus,
+};
 use upki::{Config, Error};
 
 /// Check the revocation status of a certificate.
@@ -18,6 +25,14 @@ use upki::{Config, Error};
 ///
 /// Returns a `upki_result` indicating success (with revocation status) or an error.
 ///
+/// `certificates_len` may be at most `UPKI_MAX_CERTIFICATES`; larger values are
+/// rejected with `UPKI_ERR_INVALID_INPUT` before `certificates` is read.
+///
+/// The manifest is read from the cache directory on every call.  Callers making
+/// many checks should load it once with `upki_manifest_from_bytes` and use
+/// `upki_manifest_check_revocation` instead.  The signature and behaviour of this
+/// function will not change as others are added.
+///
 /// # Safety
 ///
 /// - `config` must be a valid pointer returned by `upki_config_new`.
@@ -29,41 +44,762 @@ pub unsafe extern "C" fn upki_check_revocation(
     certificates: *const upki_certificate_der,
     certificates_len: usize,
 ) -> upki_result {
-    catch_unwind(|| {
-        if config.is_null() || certificates.is_null() {
+    catch_panic(
+        || match unsafe { check(config, ptr::null(), certificates, certificates_len) } {
+            Ok(outcome) => outcome.status.into(),
+            Err(result) => result,
+        },
+    )
+}
+
+/// Check the revocation status of a certificate, and describe how it was determined.
+///
+/// This is like `upki_check_revocation`, and returns the same values.  In addition,
+/// on success the full result is written to `out`.  `out` is not written on error.
+///
+/// # Safety
+///
+/// - `config` must be a valid pointer returned by `upki_config_new`.
+/// - `certificates` must point to `certificates_len` `upki_certificate` values.
+/// - Each `upki_certificate` must have a valid `data` pointer to `len` bytes.
+/// - `out` must not be `NULL`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn upki_check_revocation_full(
+    config: *const upki_config,
+    certificates: *const upki_certificate_der,
+    certificates_len: usize,
+    out: *mut upki_check_result,
+) -> upki_result {
+    catch_panic(|| {
+        if out.is_null() {
             return upki_result::UPKI_ERR_NULL_POINTER;
         }
 
-        let config = unsafe { &(*config).0 };
-        let certificates = unsafe { slice::from_raw_parts(certificates, certificates_len) };
+        match unsafe { check(config, ptr::null(), certificates, certificates_len) } {
+            Ok(outcome) => {
+                let result = upki_check_result::from(&outcome);
+                let status = result.status;
+                unsafe { *out = result };
+                status
+            }
+            Err(result) => result,
+        }
+    })
+}
 
-        let certs = certificates
-            .iter()
-            .map(|c| CertificateDer::from(unsafe { slice::from_raw_parts(c.data, c.len) }))
-            .collect::<Vec<_>>();
+/// Check whether a certificate is revoked, as a plain yes or no.
+///
+/// Returns 1 if the certificate is revoked, 0 if not, or -1 on error.  After an
+/// error, `upki_last_error_message` describes it, if it has a description.
+///
+/// Certificates not covered by the revocation data are treated according to the
+/// configured `uncovered-policy`: they are reported as revoked under `fail-closed`,
+/// and as not revoked otherwise.  Use `upki_check_revocation` to tell them apart.
+///
+/// # Safety
+///
+/// - `config` must be a valid pointer returned by `upki_config_new`.
+/// - `certificates` must point to `certificates_len` `upki_certificate` values.
+/// - Each `upki_certificate` must have a valid `data` pointer to `len` bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn upki_is_revoked(
+    config: *const upki_config,
+    certificates: *const upki_certificate_der,
+    certificates_len: usize,
+) -> c_int {
+    let result = catch_panic(|| {
+        if config.is_null() {
+            return upki_result::UPKI_ERR_NULL_POINTER;
+        }
 
-        let input = match RevocationCheckInput::from_certificates(&certs) {
+        let config = unsafe { &(*config).0 };
+        let input = match unsafe { input_from(certificates, certificates_len) } {
             Ok(input) => input,
-            Err(err) => return Error::Revocation(err).into(),
+            Err(result) => return result,
         };
+        match Manifest::from_config(config).and_then(|manifest| manifest.is_revoked(&input, config))
+        {
+            Ok(true) => upki_result::UPKI_REVOCATION_REVOKED,
+            Ok(false) => upki_result::UPKI_REVOCATION_NOT_REVOKED,
+            Err(err) => failed(err),
+        }
+    });
 
-        let manifest = match Manifest::from_config(config) {
-            Ok(manifest) => manifest,
-            Err(err) => return Error::Revocation(err).into(),
+    match result {
+        upki_result::UPKI_REVOCATION_REVOKED => 1,
+        upki_result::UPKI_REVOCATION_NOT_REVOKED => 0,
+        _ => -1,
+    }
+}
+
+/// The largest `certificates_len` accepted by the `upki_check_revocation*` functions.
+///
+/// The length of `certificates` cannot be validated, so this catches callers passing
+/// a nonsensical length rather than reading past the end of their array.  Real
+/// certificate chains are far shorter than this.
+pub const UPKI_MAX_CERTIFICATES: usize = 64;
+
+/// Shared implementation of the `upki_check_revocation*` functions.
+///
+/// `manifest` is loaded from `config` if it is `NULL`.
+unsafe fn check(
+    config: *const upki_config,
+    manifest: *const upki_manifest,
+    certificates: *const upki_certificate_der,
+    certificates_len: usize,
+) -> Result<CheckOutcome, upki_result> {
+    if config.is_null() {
+        return Err(upki_result::UPKI_ERR_NULL_POINTER);
+    }
+
+    let config = unsafe { &(*config).0 };
+    let input = unsafe { input_from(certificates, certificates_len) }?;
+    let loaded;
+    let manifest = match manifest.is_null() {
+        true => {
+            loaded = Manifest::from_config(config).map_err(failed)?;
+            &loaded
+        }
+        false => unsafe { &(*manifest).0 },
+    };
+
+    manifest
+        .check_detailed(&input, config)
+        .map_err(failed)
+}
+
+/// Build a check input from the `certificates_len` certificates at `certificates`.
+///
+/// The first certificate is the end-entity certificate, as for `upki_check_revocation`.
+unsafe fn input_from(
+    certificates: *const upki_certificate_der,
+    certificates_len: usize,
+) -> Result<RevocationCheckInput, upki_result> {
+    if certificates.is_null() {
+        return Err(upki_result::UPKI_ERR_NULL_POINTER);
+    }
+
+    if certificates_len > UPKI_MAX_CERTIFICATES {
+        return Err(upki_result::UPKI_ERR_INVALID_INPUT);
+    }
+
+    let certificates = unsafe { slice::from_raw_parts(certificates, certificates_len) };
+    let certs = certificates
+        .iter()
+        .map(|c| CertificateDer::from(unsafe { slice::from_raw_parts(c.data, c.len) }))
+        .collect::<Vec<_>>();
+
+    RevocationCheckInput::from_certificates(&certs).map_err(failed)
+}
+
+/// Check the revocation status of a stream of certificates, one at a time.
+///
+/// `next_input` is called to get each certificate chain to check.  It should set
+/// `*certificates` and `*certificates_len` as for `upki_check_revocation` and return
+/// `true`, or return `false` when there are no more.  `emit_result` is then called
+/// with the outcome: on success, `result` is the status and `check_result` points to
+/// the full result; on error, `result` is the error and `check_result` is `NULL`.
+/// An error for one chain does not stop the stream.  Both are passed `user_data`.
+///
+/// `manifest` is used if it is not `NULL`; otherwise the manifest is loaded from the
+/// cache directory described by `config`.  Either way, the filters are read once,
+/// before `next_input` is first called.  Returns `UPKI_OK` once `next_input` returns
+/// `false`, or an error if the manifest or filters cannot be loaded.
+///
+/// The callbacks are called on the calling thread, strictly alternating, and never
+/// after this returns.  The certificates set by `next_input` need only stay valid
+/// until the next call to `next_input`, and `check_result` only until `emit_result`
+/// returns.  Callbacks may call other functions in this library, except to free
+/// `config` or `manifest`, but should not call `upki_check_stream` recursively.
+///
+/// # Safety
+///
+/// - `config` must be a valid pointer returned by `upki_config_new`.
+/// - `manifest` must be `NULL` or a valid pointer returned by `upki_manifest_from_bytes`.
+/// - `next_input` and `emit_result` must not be `NULL`.
+/// - The certificates set by `next_input` must meet the requirements of
+///   `upki_check_revocation`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn upki_check_stream(
+    config: *const upki_config,
+    manifest: *const upki_manifest,
+    next_input: Option<
+        unsafe extern "C" fn(
+            user_data: *mut c_void,
+            certificates: *mut *const upki_certificate_der,
+            certificates_len: *mut usize,
+        ) -> bool,
+    >,
+    emit_result: Option<
+        unsafe extern "C" fn(
+            user_data: *mut c_void,
+            result: upki_result,
+            check_result: *const upki_check_result,
+        ),
+    >,
+    user_data: *mut c_void,
+) -> upki_result {
+    catch_panic(|| {
+        let (Some(next_input), Some(emit_result)) = (next_input, emit_result) else {
+            return upki_result::UPKI_ERR_NULL_POINTER;
         };
+        if config.is_null() {
+            return upki_result::UPKI_ERR_NULL_POINTER;
+        }
 
-        match manifest.check(&input, config) {
-            Ok(status) => match status {
-                RevocationStatus::NotCoveredByRevocationData => {
-                    upki_result::UPKI_REVOCATION_NOT_COVERED
+        let config = unsafe { &(*config).0 };
+        let loaded;
+        let manifest = match manifest.is_null() {
+            true => match Manifest::from_config(config) {
+                Ok(manifest) => {
+                    loaded = manifest;
+                    &loaded
                 }
-                RevocationStatus::CertainlyRevoked => upki_result::UPKI_REVOCATION_REVOKED,
-                RevocationStatus::NotRevoked => upki_result::UPKI_REVOCATION_NOT_REVOKED,
+                Err(err) => return failed(err),
             },
-            Err(err) => Error::Revocation(err).into(),
+            false => unsafe { &(*manifest).0 },
+        };
+
+        let inputs = iter::from_fn(|| {
+            let mut certificates = ptr::null();
+            let mut certificates_len = 0;
+            match unsafe { next_input(user_data, &mut certificates, &mut certificates_len) } {
+                true => Some(unsafe { input_from(certificates, certificates_len) }.map_err(Failed)),
+                false => None,
+            }
+        });
+        let outcomes = match manifest.check_iter(inputs, config) {
+            Ok(outcomes) => outcomes,
+            Err(err) => return failed(err),
+        };
+
+        for outcome in outcomes {
+            match outcome {
+                Ok(outcome) => {
+                    let result = upki_check_result::from(&outcome);
+                    unsafe { emit_result(user_data, result.status, &result) };
+                }
+                Err(Failed(result)) => unsafe { emit_result(user_data, result, ptr::null()) },
+            }
         }
+
+        upki_result::UPKI_OK
     })
-    .unwrap_or(upki_result::UPKI_ERR_PANICKED)
+}
+
+/// Check the revocation status of many certificate chains, into a caller-provided array.
+///
+/// `chains` points to `chains_len` chains, each as for `upki_check_revocation`.  The
+/// result for `chains[i]` is written to `out_results[i]`, so `out_results` needs
+/// exactly `chains_len` elements.  If a chain cannot be checked, the `status` of its
+/// result is the error and the other fields are zero.  Nothing is allocated for the
+/// results.
+///
+/// `*out_needed` is always set to `chains_len`.  If `results_len` is smaller, only
+/// the first `results_len` chains are checked and written, and this returns
+/// `UPKI_ERR_BUFFER_TOO_SMALL`; otherwise it returns `UPKI_OK`.  Either way,
+/// `*out_written` is set to the number of results written.  `out_results` may be
+/// `NULL` if `results_len` is 0, to query the size needed.
+///
+/// `manifest` is used if it is not `NULL`; otherwise the manifest is loaded from the
+/// cache directory described by `config`.  Either way, the filters are read once.  If
+/// the manifest or filters cannot be loaded, the error is returned and nothing is
+/// written to `out_results`.
+///
+/// # Safety
+///
+/// - `config` must be a valid pointer returned by `upki_config_new`.
+/// - `manifest` must be `NULL` or a valid pointer returned by `upki_manifest_from_bytes`.
+/// - `chains` must point to `chains_len` `upki_certificate_chain` values, each
+///   meeting the requirements of `upki_check_revocation`.
+/// - `out_results` must point to `results_len` writable `upki_check_result` values,
+///   or be `NULL` if `results_len` is 0.
+/// - `out_written` and `out_needed` must not be `NULL`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn upki_check_batch(
+    config: *const upki_config,
+    manifest: *const upki_manifest,
+    chains: *const upki_certificate_chain,
+    chains_len: usize,
+    out_results: *mut upki_check_result,
+    results_len: usize,
+    out_written: *mut usize,
+    out_needed: *mut usize,
+) -> upki_result {
+    catch_panic(|| {
+        if config.is_null()
+            || chains.is_null()
+            || out_written.is_null()
+            || out_needed.is_null()
+            || (out_results.is_null() && results_len > 0)
+        {
+            return upki_result::UPKI_ERR_NULL_POINTER;
+        }
+
+        unsafe {
+            *out_written = 0;
+            *out_needed = chains_len;
+        }
+        let count = chains_len.min(results_len);
+        if count > 0 {
+            let config = unsafe { &(*config).0 };
+            let loaded;
+            let manifest = match manifest.is_null() {
+                true => match Manifest::from_config(config) {
+                    Ok(manifest) => {
+                        loaded = manifest;
+                        &loaded
+                    }
+                    Err(err) => return failed(err),
+                },
+                false => unsafe { &(*manifest).0 },
+            };
+
+            let chains = unsafe { slice::from_raw_parts(chains, count) };
+            let inputs = chains.iter().map(|chain| {
+                unsafe { input_from(chain.certificates, chain.certificates_len) }.map_err(Failed)
+            });
+            let outcomes = match manifest.check_iter(inputs, config) {
+                Ok(outcomes) => outcomes,
+                Err(err) => return failed(err),
+            };
+
+            let results = unsafe { slice::from_raw_parts_mut(out_results, count) };
+            for (out, outcome) in results.iter_mut().zip(outcomes) {
+                *out = match outcome {
+                    Ok(outcome) => upki_check_result::from(&outcome),
+                    Err(Failed(result)) => upki_check_result::failed(result),
+                };
+                unsafe { *out_written += 1 };
+            }
+        }
+
+        match count < chains_len {
+            true => upki_result::UPKI_ERR_BUFFER_TOO_SMALL,
+            false => upki_result::UPKI_OK,
+        }
+    })
+}
+
+/// The full result of a revocation check, written by `upki_check_revocation_full`.
+///
+/// All fields are plain values: there is nothing to free.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct upki_check_result {
+    /// One of `UPKI_REVOCATION_NOT_COVERED`, `UPKI_REVOCATION_REVOKED` or
+    /// `UPKI_REVOCATION_NOT_REVOKED`.
+    pub status: upki_result,
+    /// Why the certificate was not covered.
+    ///
+    /// This is `UPKI_NOT_COVERED_REASON_NONE` unless `status` is
+    /// `UPKI_REVOCATION_NOT_COVERED`.
+    pub not_covered_reason: upki_not_covered_reason,
+    /// Whether `status` is definitive.
+    pub match_kind: upki_match_kind,
+    /// When the manifest used for the check was generated, as a UNIX timestamp in seconds.
+    pub manifest_generated_at: u64,
+}
+
+impl upki_check_result {
+    /// The result for a chain that could not be checked, because of `error`.
+    fn failed(error: upki_result) -> Self {
+        Self {
+            status: error,
+            not_covered_reason: upki_not_covered_reason::UPKI_NOT_COVERED_REASON_NONE,
+            match_kind: upki_match_kind::UPKI_MATCH_EXACT,
+            manifest_generated_at: 0,
+        }
+    }
+}
+
+impl From<&CheckOutcome> for upki_check_result {
+    fn from(outcome: &CheckOutcome) -> Self {
+        Self {
+            status: outcome.status.into(),
+            not_covered_reason: match outcome.not_covered_reason {
+                None => upki_not_covered_reason::UPKI_NOT_COVERED_REASON_NONE,
+                Some(NotCoveredReason::IssuerNotEnrolled) => {
+                    upki_not_covered_reason::UPKI_NOT_COVERED_REASON_ISSUER_NOT_ENROLLED
+                }
+                Some(NotCoveredReason::NoScts) => {
+                    upki_not_covered_reason::UPKI_NOT_COVERED_REASON_NO_SCTS
+                }
+                Some(NotCoveredReason::SctsNotCovered) => {
+                    upki_not_covered_reason::UPKI_NOT_COVERED_REASON_SCTS_NOT_COVERED
+                }
+                Some(NotCoveredReason::Bypassed) => {
+                    upki_not_covered_reason::UPKI_NOT_COVERED_REASON_BYPASSED
+                }
+                Some(NotCoveredReason::OutOfScope) => {
+                    upki_not_covered_reason::UPKI_NOT_COVERED_REASON_OUT_OF_SCOPE
+                }
+                Some(NotCoveredReason::NeedsConfirmation) => {
+                    upki_not_covered_reason::UPKI_NOT_COVERED_REASON_NEEDS_CONFIRMATION
+                }
+                Some(NotCoveredReason::CheckError) => {
+                    upki_not_covered_reason::UPKI_NOT_COVERED_REASON_CHECK_ERROR
+                }
+                Some(NotCoveredReason::InsufficientScts) => {
+                    upki_not_covered_reason::UPKI_NOT_COVERED_REASON_INSUFFICIENT_SCTS
+                }
+                Some(_) => upki_not_covered_reason::UPKI_NOT_COVERED_REASON_UNKNOWN,
+            },
+            match_kind: match outcome.match_kind {
+                MatchKind::Exact => upki_match_kind::UPKI_MATCH_EXACT,
+                MatchKind::Probabilistic => upki_match_kind::UPKI_MATCH_PROBABILISTIC,
+                _ => upki_match_kind::UPKI_MATCH_PROBABILISTIC,
+            },
+            manifest_generated_at: outcome.generated_at,
+        }
+    }
+}
+
+/// Why a certificate was not covered by revocation data.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum upki_not_covered_reason {
+    /// The certificate was covered.
+    UPKI_NOT_COVERED_REASON_NONE = 0,
+    /// No filter has data for the certificate's issuer.
+    UPKI_NOT_COVERED_REASON_ISSUER_NOT_ENROLLED = 1,
+    /// The certificate has no SCTs, and the configured policy does not allow
+    /// deciding its status without them.
+    UPKI_NOT_COVERED_REASON_NO_SCTS = 2,
+    /// None of the certificate's SCTs are within the coverage of the revocation data.
+    UPKI_NOT_COVERED_REASON_SCTS_NOT_COVERED = 3,
+    /// The certificate's issuer is configured to bypass the revocation data.
+    UPKI_NOT_COVERED_REASON_BYPASSED = 4,
+    /// The certificate's issuer is not among those the configuration is concerned with.
+    UPKI_NOT_COVERED_REASON_OUT_OF_SCOPE = 5,
+    /// The certificate may be revoked, but the configured policy requires that to be
+    /// confirmed by another revocation mechanism.
+    UPKI_NOT_COVERED_REASON_NEEDS_CONFIRMATION = 6,
+    /// The revocation data could not be read.
+    UPKI_NOT_COVERED_REASON_CHECK_ERROR = 7,
+    /// The certificate has fewer SCTs than the configuration requires.
+    UPKI_NOT_COVERED_REASON_INSUFFICIENT_SCTS = 8,
+    /// An unknown reason was added to the library.
+    UPKI_NOT_COVERED_REASON_UNKNOWN = 255,
+}
+
+/// How definitive a revocation check result is.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum upki_match_kind {
+    /// The result is exact.
+    UPKI_MATCH_EXACT = 0,
+    /// The result comes from a lookup outside the known coverage of the revocation
+    /// data, and may be wrong.
+    UPKI_MATCH_PROBABILISTIC = 1,
+}
+
+/// Check the revocation status of a certificate against a specific manifest.
+///
+/// This is like `upki_check_revocation`, but uses `manifest` rather than loading
+/// the manifest from the cache directory described by `config`.  The filters
+/// referenced by `manifest` are still read from that cache directory.
+///
+/// # Safety
+///
+/// - `config` must be a valid pointer returned by `upki_config_new`.
+/// - `manifest` must be a valid pointer returned by `upki_manifest_from_bytes`.
+/// - `certificates` must point to `certificates_len` `upki_certificate` values.
+/// - Each `upki_certificate` must have a valid `data` pointer to `len` bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn upki_manifest_check_revocation(
+    manifest: *const upki_manifest,
+    config: *const upki_config,
+    certificates: *const upki_certificate_der,
+    certificates_len: usize,
+) -> upki_result {
+    catch_panic(|| {
+        if manifest.is_null() {
+            return upki_result::UPKI_ERR_NULL_POINTER;
+        }
+
+        match unsafe { check(config, manifest, certificates, certificates_len) } {
+            Ok(outcome) => outcome.status.into(),
+            Err(result) => result,
+        }
+    })
+}
+
+/// Find out whether `manifest` has revocation data for an issuer.
+///
+/// `issuer_spki_hash` is the 32-byte hash computed by `upki_issuer_spki_hash`.  On
+/// success, writes whether the issuer is covered, by a filter or by a revoked range,
+/// to `out` and returns `UPKI_OK`.  This does no revocation lookup, but reads the
+/// filters referenced by `manifest` from the cache directory described by `config`.
+/// `out` is not written on error.
+///
+/// # Safety
+///
+/// - `manifest` must be a valid pointer returned by `upki_manifest_from_bytes`.
+/// - `config` must be a valid pointer returned by `upki_config_new`.
+/// - `issuer_spki_hash` must point to 32 bytes.
+/// - `out` must not be `NULL`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn upki_manifest_covers_issuer(
+    manifest: *const upki_manifest,
+    config: *const upki_config,
+    issuer_spki_hash: *const u8,
+    out: *mut bool,
+) -> upki_result {
+    catch_panic(|| {
+        if manifest.is_null() || config.is_null() || issuer_spki_hash.is_null() || out.is_null() {
+            return upki_result::UPKI_ERR_NULL_POINTER;
+        }
+
+        let mut issuer = [0; 32];
+        unsafe { ptr::copy_nonoverlapping(issuer_spki_hash, issuer.as_mut_ptr(), issuer.len()) };
+        let (manifest, config) = unsafe { (&(*manifest).0, &(*config).0) };
+        match manifest.covers_issuer(&IssuerSpkiHash(issuer), config) {
+            Ok(covered) => {
+                unsafe { *out = covered };
+                upki_result::UPKI_OK
+            }
+            Err(err) => failed(err),
+        }
+    })
+}
+
+/// Opaque type representing a `upki::revocation::Manifest`.
+pub struct upki_manifest(Manifest);
+
+/// Create a new `upki_manifest` by parsing the `data_len` bytes at `data`.
+///
+/// `data` is the contents of a `manifest.json` file.  On success, writes the manifest
+/// pointer to `out` and returns `UPKI_OK`.  The caller is responsible for freeing the
+/// manifest with `upki_manifest_free`.
+///
+/// # Safety
+///
+/// - `out` must not be `NULL`.
+/// - `data` must point to `data_len` bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn upki_manifest_from_bytes(
+    data: *const u8,
+    data_len: usize,
+    out: *mut *mut upki_manifest,
+) -> upki_result {
+    catch_panic(|| {
+        if data.is_null() || out.is_null() {
+            return upki_result::UPKI_ERR_NULL_POINTER;
+        }
+
+        let data = unsafe { slice::from_raw_parts(data, data_len) };
+        match Manifest::from_bytes(data) {
+            Ok(manifest) => {
+                unsafe { *out = Box::into_raw(Box::new(upki_manifest(manifest))) };
+                upki_result::UPKI_OK
+            }
+            Err(err) => failed(err),
+        }
+    })
+}
+
+/// Free a `upki_manifest` created by `upki_manifest_from_bytes`.
+///
+/// # Safety
+///
+/// `manifest` must be a valid pointer returned by `upki_manifest_from_bytes`,
+/// or null (in which case this is a no-op).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn upki_manifest_free(manifest: *mut upki_manifest) {
+    catch_panic(|| {
+        if !manifest.is_null() {
+            drop(unsafe { Box::from_raw(manifest) });
+        }
+        upki_result::UPKI_OK
+    });
+}
+
+/// Write the version of `manifest` to `out_buf`, as a null-terminated string.
+///
+/// This is the version that `manifest_generated_at` in `upki_check_result` reports for
+/// checks against `manifest`, so it can be logged alongside a decision.
+///
+/// `*out_written` is always set to the length of the version string in bytes,
+/// including the terminating null.  If `out_buf` is `NULL` this returns `UPKI_OK` and
+/// writes nothing else, so callers can query the size needed.  If `buf_len` is less
+/// than that size, `out_buf` is not written and this returns
+/// `UPKI_ERR_BUFFER_TOO_SMALL`.
+///
+/// # Safety
+///
+/// - `manifest` must be a valid pointer returned by `upki_manifest_from_bytes`.
+/// - `out_buf` must point to `buf_len` writable bytes, or be `NULL`.
+/// - `out_written` must not be `NULL`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn upki_manifest_version(
+    manifest: *const upki_manifest,
+    out_buf: *mut c_char,
+    buf_len: usize,
+    out_written: *mut usize,
+) -> upki_result {
+    catch_panic(|| {
+        if manifest.is_null() || out_written.is_null() {
+            return upki_result::UPKI_ERR_NULL_POINTER;
+        }
+
+        let version = unsafe { &(*manifest).0 }
+            .version()
+            .to_string();
+        let version = CString::new(version).expect("version has no nul bytes");
+        let bytes = version.as_bytes_with_nul();
+        unsafe { *out_written = bytes.len() };
+        if out_buf.is_null() {
+            return upki_result::UPKI_OK;
+        }
+        if buf_len < bytes.len() {
+            return upki_result::UPKI_ERR_BUFFER_TOO_SMALL;
+        }
+
+        unsafe { ptr::copy_nonoverlapping(bytes.as_ptr().cast(), out_buf, bytes.len()) };
+        upki_result::UPKI_OK
+    })
+}
+
+/// Audit the revocation cache described by `config`.
+///
+/// This loads the manifest, then reads and decodes every filter it refers to.  Returns
+/// `UPKI_OK` if nothing is wrong, or `UPKI_ERR_INTEGRITY` if any problem is found: a
+/// filter that is missing, corrupt or undecodable, or a manifest or coverage dated in
+/// the future.  Errors loading the manifest are returned as usual.
+///
+/// # Safety
+///
+/// `config` must be a valid pointer returned by `upki_config_new`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn upki_verify_integrity(config: *const upki_config) -> upki_result {
+    catch_panic(|| {
+        if config.is_null() {
+            return upki_result::UPKI_ERR_NULL_POINTER;
+        }
+
+        let config = unsafe { &(*config).0 };
+        let manifest = match Manifest::from_config(config) {
+            Ok(manifest) => manifest,
+            Err(err) => return failed(err),
+        };
+
+        match manifest.verify_integrity(config) {
+            Ok(()) => upki_result::UPKI_OK,
+            Err(problems) => {
+                let problems = problems
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>();
+                set_last_error(format!("integrity problems: {}", problems.join("; ")));
+                upki_result::UPKI_ERR_INTEGRITY
+            }
+        }
+    })
+}
+
+/// Compute the issuer SPKI hash of the DER-encoded issuer certificate at `cert`.
+///
+/// This is the SHA256 hash of the certificate's `SubjectPublicKeyInfo`, as used to
+/// identify the issuer in revocation data.  On success, writes the 32-byte hash to
+/// `out` and returns `UPKI_OK`.  `out` is not written on error.
+///
+/// # Safety
+///
+/// - `cert` must point to `cert_len` bytes.
+/// - `out` must point to 32 writable bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn upki_issuer_spki_hash(
+    cert: *const u8,
+    cert_len: usize,
+    out: *mut u8,
+) -> upki_result {
+    catch_panic(|| {
+        if cert.is_null() || out.is_null() {
+            return upki_result::UPKI_ERR_NULL_POINTER;
+        }
+
+        let cert = unsafe { slice::from_raw_parts(cert, cert_len) };
+        match revocation::issuer_spki_hash_from_cert(cert) {
+            Ok(hash) => {
+                unsafe { ptr::copy_nonoverlapping(hash.0.as_ptr(), out, hash.0.len()) };
+                upki_result::UPKI_OK
+            }
+            Err(err) => failed(err),
+        }
+    })
+}
+
+/// Describe the most recent error returned by a function on this thread.
+///
+/// Returns a null-terminated UTF-8 string, or `NULL` if no error has occurred on
+/// this thread.  Not every error has a description: `UPKI_ERR_NULL_POINTER`, for
+/// example, does not change what this returns.
+///
+/// The returned pointer is owned by the library, and is valid until the next call
+/// into the library on this thread.  It must not be freed.
+#[unsafe(no_mangle)]
+pub extern "C" fn upki_last_error_message() -> *const c_char {
+    LAST_ERROR.with_borrow(|last| match last {
+        Some(message) => message.as_ptr(),
+        None => ptr::null(),
+    })
+}
+
+/// Record `err` as this thread's last error, and return its `upki_result`.
+fn failed(err: impl Into<Error>) -> upki_result {
+    let err = err.into();
+    let mut message = err.to_string();
+    let mut source = StdError::source(&err);
+    while let Some(error) = source {
+        message.push_str(": ");
+        message.push_str(&error.to_string());
+        source = error.source();
+    }
+
+    set_last_error(message);
+    err.into()
+}
+
+/// An error already recorded by [`failed()`], for [`Manifest::check_iter()`] to
+/// report in place of an outcome.
+struct Failed(upki_result);
+
+impl From<revocation::Error> for Failed {
+    fn from(err: revocation::Error) -> Self {
+        Self(failed(err))
+    }
+}
+
+/// Record `message` as this thread's last error.
+fn set_last_error(message: String) {
+    // an interior NUL would truncate the message: replace it
+    let message = CString::new(message.replace('\0', " ")).ok();
+    LAST_ERROR.with_borrow_mut(|last| *last = message);
+}
+
+/// Run `f`, converting a panic into `UPKI_ERR_PANICKED`.
+///
+/// A panic must not unwind into the caller, so every exported function runs its body
+/// through this.  The panic message is recorded as this thread's last error.
+fn catch_panic(f: impl FnOnce() -> upki_result + UnwindSafe) -> upki_result {
+    catch_unwind(f).unwrap_or_else(|payload| {
+        let detail = match (
+            payload.downcast_ref::<&str>(),
+            payload.downcast_ref::<String>(),
+        ) {
+            (Some(message), _) => message,
+            (None, Some(message)) => message.as_str(),
+            (None, None) => "no message",
+        };
+
+        set_last_error(format!("panic: {detail}"));
+        upki_result::UPKI_ERR_PANICKED
+    })
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
 }
 
 /// Opaque type representing a `upki::Config`.
@@ -74,6 +810,13 @@ pub struct upki_config(Config);
 /// On success, writes the config pointer to `out` and returns `UPKI_OK`.
 /// The caller is responsible for freeing the config with `upki_config_free`.
 ///
+/// A `path` that is not valid UTF-8 is rejected with `UPKI_ERR_CONFIG_PATH`.  A file
+/// that does not exist gives `UPKI_ERR_CONFIG_NOT_FOUND`, one that cannot be read for
+/// lack of permission gives `UPKI_ERR_CONFIG_PERMISSION_DENIED`, and any other
+/// problem reading it gives `UPKI_ERR_CONFIG_READ`.  A file that is read but not a
+/// valid configuration gives `UPKI_ERR_CONFIG_DECODE`.  In every case,
+/// `upki_last_error_message` describes the problem.
+///
 /// # Safety
 ///
 /// - `out` must not be `NULL`.
@@ -83,13 +826,14 @@ pub unsafe extern "C" fn upki_config_from_file(
     path: *const c_char,
     out: *mut *mut upki_config,
 ) -> upki_result {
-    catch_unwind(|| {
+    catch_panic(|| {
         if path.is_null() || out.is_null() {
             return upki_result::UPKI_ERR_NULL_POINTER;
         }
 
         let path = unsafe { CStr::from_ptr(path) };
         let Ok(path) = path.to_str() else {
+            set_last_error(format!("config path is not valid UTF-8: {path:?}"));
             return upki_result::UPKI_ERR_CONFIG_PATH;
         };
 
@@ -98,10 +842,9 @@ pub unsafe extern "C" fn upki_config_from_file(
                 unsafe { *out = Box::into_raw(Box::new(upki_config(config))) };
                 upki_result::UPKI_OK
             }
-            Err(err) => err.into(),
+            Err(err) => failed(err),
         }
     })
-    .unwrap_or(upki_result::UPKI_ERR_PANICKED)
 }
 
 /// Create a new `upki_config` with default settings.
@@ -114,7 +857,7 @@ pub unsafe extern "C" fn upki_config_from_file(
 /// - `out` must not be `NULL`.
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn upki_config_new(out: *mut *mut upki_config) -> upki_result {
-    catch_unwind(|| {
+    catch_panic(|| {
         if out.is_null() {
             return upki_result::UPKI_ERR_NULL_POINTER;
         }
@@ -124,10 +867,9 @@ pub unsafe extern "C" fn upki_config_new(out: *mut *mut upki_config) -> upki_res
                 unsafe { *out = Box::into_raw(Box::new(upki_config(config))) };
                 upki_result::UPKI_OK
             }
-            Err(err) => err.into(),
+            Err(err) => failed(err),
         }
     })
-    .unwrap_or(upki_result::UPKI_ERR_PANICKED)
 }
 
 /// Free a `upki_config` created by `upki_config_new`.
@@ -138,9 +880,12 @@ pub unsafe extern "C" fn upki_config_new(out: *mut *mut upki_config) -> upki_res
 /// or null (in which case this is a no-op).
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn upki_config_free(config: *mut upki_config) {
-    if !config.is_null() {
-        drop(unsafe { Box::from_raw(config) });
-    }
+    catch_panic(|| {
+        if !config.is_null() {
+            drop(unsafe { Box::from_raw(config) });
+        }
+        upki_result::UPKI_OK
+    });
 }
 
 /// A DER-encoded certificate.
@@ -152,11 +897,22 @@ pub struct upki_certificate_der {
     pub len: usize,
 }
 
+/// A certificate chain, for `upki_check_batch`.
+#[repr(C)]
+pub struct upki_certificate_chain {
+    /// Pointer to the certificates, end-entity certificate first, as for
+    /// `upki_check_revocation`.
+    pub certificates: *const upki_certificate_der,
+    /// Number of certificates.
+    pub certificates_len: usize,
+}
+
 /// Result type for upki C API functions.
 ///
 /// Values 0-15 indicate success (with specific status information).
 /// Values 16 and above indicate errors.
 #[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum upki_result {
     /// Operation succeeded.
     UPKI_OK = 0,
@@ -173,11 +929,21 @@ pub enum upki_result {
     UPKI_ERR_CONFIG_PATH = 17,
     /// An unknown error variant was added to the library.
     UPKI_ERR_UNKNOWN = 18,
-    /// An unexpected panic occurred in the library.
+    /// An unexpected panic occurred in the library.  `upki_last_error_message`
+    /// describes it.
     UPKI_ERR_PANICKED = 19,
+    /// An argument was out of range, such as a `certificates_len` above
+    /// `UPKI_MAX_CERTIFICATES`.
+    UPKI_ERR_INVALID_INPUT = 20,
+    /// The revocation cache failed an integrity check.  `upki_last_error_message`
+    /// lists the problems found.
+    UPKI_ERR_INTEGRITY = 21,
+    /// An output buffer was too small.  The size needed was written to `out_written`,
+    /// or to `out_needed` where there is one.
+    UPKI_ERR_BUFFER_TOO_SMALL = 22,
 
     // Errors from upki::Error
-    /// Failed to decode configuration file.
+    /// Failed to decode configuration file, or a field in it was invalid.
     UPKI_ERR_CONFIG_DECODE = 32,
     /// Failed to read configuration file.
     UPKI_ERR_CONFIG_READ = 33,
@@ -187,6 +953,14 @@ pub enum upki_result {
     UPKI_ERR_NO_CONFIG_DIR = 35,
     /// The user's home directory could not be determined.
     UPKI_ERR_NO_HOME_DIR = 36,
+    /// An environment variable had an invalid value.
+    UPKI_ERR_INVALID_ENVIRONMENT = 37,
+    /// The configuration file does not exist.
+    UPKI_ERR_CONFIG_NOT_FOUND = 38,
+    /// The configuration file could not be read, for lack of permission.
+    UPKI_ERR_CONFIG_PERMISSION_DENIED = 39,
+    /// A setting passed to `Config::with_setting()` was unknown or had an invalid value.
+    UPKI_ERR_INVALID_SETTING = 40,
 
     // Errors from upki::revocation::Error
     /// Failed to create a directory.
@@ -231,46 +1005,143 @@ pub enum upki_result {
     UPKI_ERR_REVOCATION_REMOVE_FILE = 83,
     /// Certificate chain must contain at least 2 certificates.
     UPKI_ERR_REVOCATION_TOO_FEW_CERTS = 84,
+    /// Failed to encode a filter file.
+    UPKI_ERR_REVOCATION_FILTER_ENCODE = 85,
+    /// A manifest was older than one previously accepted.
+    UPKI_ERR_REVOCATION_DOWNGRADE = 86,
+    /// Failed to read a file.
+    UPKI_ERR_REVOCATION_FILE_READ = 87,
+    /// PEM-encoded input could not be parsed.
+    UPKI_ERR_REVOCATION_INVALID_PEM = 88,
+    /// There is no manifest file.
+    UPKI_ERR_REVOCATION_MANIFEST_MISSING = 89,
+    /// A textual check input had too few fields.
+    UPKI_ERR_REVOCATION_INVALID_CHECK_INPUT = 90,
+    /// Failed to read or write a stream.
+    UPKI_ERR_REVOCATION_STREAM_IO = 91,
+    /// Failed to decode a test vector file.
+    UPKI_ERR_REVOCATION_TEST_VECTOR_DECODE = 92,
+    /// The cache cannot be updated, because the configuration is read-only.
+    UPKI_ERR_REVOCATION_READ_ONLY = 93,
+    /// An encoded check outcome could not be decoded.
+    UPKI_ERR_REVOCATION_INVALID_OUTCOME_ENCODING = 94,
+    /// A check input was built without a required field.
+    UPKI_ERR_REVOCATION_MISSING_CHECK_INPUT_FIELD = 95,
+    /// A manifest was larger than the configured limit.
+    UPKI_ERR_REVOCATION_MANIFEST_TOO_LARGE = 96,
+    /// A fallback revocation check failed.
+    UPKI_ERR_REVOCATION_FALLBACK = 97,
+    /// An issuer certificate could not be parsed.
+    UPKI_ERR_REVOCATION_INVALID_ISSUER_CERT = 98,
+    /// A check was asked for a manifest generation other than the one loaded.
+    UPKI_ERR_REVOCATION_GENERATION_UNAVAILABLE = 99,
+    /// A check input's serial number was not canonical, and `strict-input` is set.
+    UPKI_ERR_REVOCATION_NON_CANONICAL_SERIAL = 100,
+    /// An update was cancelled.
+    UPKI_ERR_REVOCATION_CANCELLED = 101,
+    /// The manifest's digest was not the configured `pinned-manifest-digest`.
+    UPKI_ERR_REVOCATION_DIGEST_MISMATCH = 102,
+    /// The cache failed its integrity checks when opened.
+    UPKI_ERR_REVOCATION_INTEGRITY = 103,
+    /// The manifest is in a format version this version of upki does not support.
+    ///
+    /// Upgrade upki to use it.
+    UPKI_ERR_REVOCATION_UNSUPPORTED_MANIFEST_VERSION = 104,
+    /// Invalid hex encoding.
+    UPKI_ERR_REVOCATION_INVALID_HEX = 105,
+    /// Failed to start a runtime to update the cache.
+    UPKI_ERR_REVOCATION_RUNTIME = 106,
+}
+
+impl From<RevocationStatus> for upki_result {
+    fn from(status: RevocationStatus) -> Self {
+        match status {
+            RevocationStatus::NotCoveredByRevocationData => Self::UPKI_REVOCATION_NOT_COVERED,
+            RevocationStatus::CertainlyRevoked => Self::UPKI_REVOCATION_REVOKED,
+            RevocationStatus::NotRevoked => Self::UPKI_REVOCATION_NOT_REVOKED,
+        }
+    }
 }
 
 impl From<Error> for upki_result {
     fn from(err: Error) -> Self {
         match err {
-            Error::ConfigError { .. } => Self::UPKI_ERR_CONFIG_DECODE,
-            Error::FileRead { .. } => Self::UPKI_ERR_CONFIG_READ,
+            Error::ConfigError { .. } | Error::ConfigField { .. } => Self::UPKI_ERR_CONFIG_DECODE,
+            Error::FileRead { error, .. } => match error.kind() {
+                io::ErrorKind::NotFound => Self::UPKI_ERR_CONFIG_NOT_FOUND,
+                io::ErrorKind::PermissionDenied => Self::UPKI_ERR_CONFIG_PERMISSION_DENIED,
+                _ => Self::UPKI_ERR_CONFIG_READ,
+            },
+            Error::InvalidEnvironmentVariable { .. } => Self::UPKI_ERR_INVALID_ENVIRONMENT,
+            Error::InvalidSetting { .. } => Self::UPKI_ERR_INVALID_SETTING,
             Error::NoCacheDirectoryFound => Self::UPKI_ERR_NO_CACHE_DIR,
             Error::NoConfigDirectoryFound => Self::UPKI_ERR_NO_CONFIG_DIR,
             Error::NoValidHomeDirectory => Self::UPKI_ERR_NO_HOME_DIR,
+            Error::Revocation(revocation::Error::Cancelled) => Self::UPKI_ERR_REVOCATION_CANCELLED,
             Error::Revocation(revocation::Error::CreateDirectory { .. }) => {
                 Self::UPKI_ERR_REVOCATION_CREATE_DIR
             }
+            Error::Revocation(revocation::Error::DigestMismatch { .. }) => {
+                Self::UPKI_ERR_REVOCATION_DIGEST_MISMATCH
+            }
+            Error::Revocation(revocation::Error::Downgrade { .. }) => {
+                Self::UPKI_ERR_REVOCATION_DOWNGRADE
+            }
+            Error::Revocation(revocation::Error::Fallback(_)) => Self::UPKI_ERR_REVOCATION_FALLBACK,
+            Error::Revocation(revocation::Error::FileRead { .. }) => {
+                Self::UPKI_ERR_REVOCATION_FILE_READ
+            }
             Error::Revocation(revocation::Error::FileWrite { .. }) => {
                 Self::UPKI_ERR_REVOCATION_FILE_WRITE
             }
             Error::Revocation(revocation::Error::FilterDecode { .. }) => {
                 Self::UPKI_ERR_REVOCATION_FILTER_DECODE
             }
+            Error::Revocation(revocation::Error::FilterEncode { .. }) => {
+                Self::UPKI_ERR_REVOCATION_FILTER_ENCODE
+            }
             Error::Revocation(revocation::Error::FilterRead { .. }) => {
                 Self::UPKI_ERR_REVOCATION_FILTER_READ
             }
+            Error::Revocation(revocation::Error::GenerationUnavailable { .. }) => {
+                Self::UPKI_ERR_REVOCATION_GENERATION_UNAVAILABLE
+            }
             Error::Revocation(revocation::Error::HashMismatch(_)) => {
                 Self::UPKI_ERR_REVOCATION_HASH_MISMATCH
             }
             Error::Revocation(revocation::Error::HttpFetch { .. }) => {
                 Self::UPKI_ERR_REVOCATION_HTTP_FETCH
             }
+            Error::Revocation(revocation::Error::Integrity(_)) => {
+                Self::UPKI_ERR_REVOCATION_INTEGRITY
+            }
             Error::Revocation(revocation::Error::InvalidBase64 { .. }) => {
                 Self::UPKI_ERR_REVOCATION_INVALID_BASE64
             }
+            Error::Revocation(revocation::Error::InvalidCheckInput) => {
+                Self::UPKI_ERR_REVOCATION_INVALID_CHECK_INPUT
+            }
             Error::Revocation(revocation::Error::InvalidEndEntityCertificate(_)) => {
                 Self::UPKI_ERR_REVOCATION_INVALID_END_ENTITY_CERT
             }
+            Error::Revocation(revocation::Error::InvalidHex { .. }) => {
+                Self::UPKI_ERR_REVOCATION_INVALID_HEX
+            }
             Error::Revocation(revocation::Error::InvalidIntermediateCertificate { .. }) => {
                 Self::UPKI_ERR_REVOCATION_INVALID_INTERMEDIATE_CERT
             }
+            Error::Revocation(revocation::Error::InvalidIssuerCertificate(_)) => {
+                Self::UPKI_ERR_REVOCATION_INVALID_ISSUER_CERT
+            }
             Error::Revocation(revocation::Error::InvalidLength { .. }) => {
                 Self::UPKI_ERR_REVOCATION_INVALID_LENGTH
             }
+            Error::Revocation(revocation::Error::InvalidOutcomeEncoding(_)) => {
+                Self::UPKI_ERR_REVOCATION_INVALID_OUTCOME_ENCODING
+            }
+            Error::Revocation(revocation::Error::InvalidPem(_)) => {
+                Self::UPKI_ERR_REVOCATION_INVALID_PEM
+            }
             Error::Revocation(revocation::Error::InvalidSctEncoding) => {
                 Self::UPKI_ERR_REVOCATION_INVALID_SCT_ENCODING
             }
@@ -286,21 +1157,801 @@ impl From<Error> for upki_result {
             Error::Revocation(revocation::Error::ManifestEncode { .. }) => {
                 Self::UPKI_ERR_REVOCATION_MANIFEST_ENCODE
             }
+            Error::Revocation(revocation::Error::ManifestMissing(_)) => {
+                Self::UPKI_ERR_REVOCATION_MANIFEST_MISSING
+            }
             Error::Revocation(revocation::Error::ManifestRead { .. }) => {
                 Self::UPKI_ERR_REVOCATION_MANIFEST_READ
             }
+            Error::Revocation(revocation::Error::ManifestTooLarge { .. }) => {
+                Self::UPKI_ERR_REVOCATION_MANIFEST_TOO_LARGE
+            }
             Error::Revocation(revocation::Error::ManifestWrite { .. }) => {
                 Self::UPKI_ERR_REVOCATION_MANIFEST_WRITE
             }
+            Error::Revocation(revocation::Error::MissingCheckInputField(_)) => {
+                Self::UPKI_ERR_REVOCATION_MISSING_CHECK_INPUT_FIELD
+            }
             Error::Revocation(revocation::Error::NoIssuer) => Self::UPKI_ERR_REVOCATION_NO_ISSUER,
+            Error::Revocation(revocation::Error::NonCanonicalSerial { .. }) => {
+                Self::UPKI_ERR_REVOCATION_NON_CANONICAL_SERIAL
+            }
             Error::Revocation(revocation::Error::Outdated(_)) => Self::UPKI_ERR_REVOCATION_OUTDATED,
+            Error::Revocation(revocation::Error::ReadOnly) => Self::UPKI_ERR_REVOCATION_READ_ONLY,
             Error::Revocation(revocation::Error::RemoveFile { .. }) => {
                 Self::UPKI_ERR_REVOCATION_REMOVE_FILE
             }
+            Error::Revocation(revocation::Error::Runtime(_)) => Self::UPKI_ERR_REVOCATION_RUNTIME,
+            Error::Revocation(revocation::Error::StreamIo(_)) => {
+                Self::UPKI_ERR_REVOCATION_STREAM_IO
+            }
+            Error::Revocation(revocation::Error::TestVectorDecode { .. }) => {
+                Self::UPKI_ERR_REVOCATION_TEST_VECTOR_DECODE
+            }
             Error::Revocation(revocation::Error::TooFewCertificates) => {
                 Self::UPKI_ERR_REVOCATION_TOO_FEW_CERTS
             }
+            Error::Revocation(revocation::Error::UnsupportedManifestVersion { .. }) => {
+                Self::UPKI_ERR_REVOCATION_UNSUPPORTED_MANIFEST_VERSION
+            }
             _ => Self::UPKI_ERR_UNKNOWN,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use core::mem::MaybeUninit;
+    use std::fs;
+    use std::path::Path;
+
+    use rustls_pki_types::pem::PemObject;
+    use upki::revocation::{CertSerial, CtLogId, CtTimestamp, ManifestWriter};
+
+    use super::*;
+
+    #[test]
+    fn manifest_from_bytes() {
+        let crlite = Path::new(env!("CARGO_MANIFEST_DIR")).join("../upki/tests/data/crlite");
+        let bytes = fs::read(crlite.join("revocation/manifest.json")).unwrap();
+
+        let mut manifest = ptr::null_mut();
+        let result =
+            unsafe { upki_manifest_from_bytes(bytes.as_ptr(), bytes.len(), &mut manifest) };
+        assert_eq!(result, upki_result::UPKI_OK);
+        assert!(!manifest.is_null());
+
+        let config = crlite_config(&crlite);
+        let input = RevocationCheckInput {
+            cert_serial: CertSerial(vec![0x01]),
+            issuer_spki_hash: IssuerSpkiHash([0xaa; 32]),
+            sct_timestamps: vec![CtTimestamp {
+                log_id: CtLogId([0x01; 32]),
+                timestamp: 1_750_000_000_000,
+            }],
+        };
+        assert_eq!(
+            unsafe { &(*manifest).0 }
+                .check(&input, &config)
+                .unwrap(),
+            RevocationStatus::CertainlyRevoked
+        );
+        unsafe { upki_manifest_free(manifest) };
+
+        let mut manifest = ptr::null_mut();
+        let result = unsafe { upki_manifest_from_bytes(bytes.as_ptr(), 20, &mut manifest) };
+        assert_eq!(result, upki_result::UPKI_ERR_REVOCATION_MANIFEST_DECODE);
+        assert!(manifest.is_null());
+        let message = unsafe { CStr::from_ptr(upki_last_error_message()) };
+        assert!(
+            message.to_str().unwrap().starts_with(
+                "revocation error: cannot decode manifest file at None (at byte offset 20)"
+            ),
+            "{message:?}"
+        );
+    }
+
+    #[test]
+    fn manifest_from_bytes_unsupported_version() {
+        let bytes = br#"{"format_version": 99, "generated_at": 1765000000, "filters": []}"#;
+        let mut manifest = ptr::null_mut();
+        let result =
+            unsafe { upki_manifest_from_bytes(bytes.as_ptr(), bytes.len(), &mut manifest) };
+        assert_eq!(
+            result,
+            upki_result::UPKI_ERR_REVOCATION_UNSUPPORTED_MANIFEST_VERSION
+        );
+        assert!(manifest.is_null());
+        let message = unsafe { CStr::from_ptr(upki_last_error_message()) };
+        assert!(
+            message
+                .to_str()
+                .unwrap()
+                .contains("upgrade upki"),
+            "{message:?}"
+        );
+    }
+
+    #[test]
+    fn manifest_version() {
+        let crlite = Path::new(env!("CARGO_MANIFEST_DIR")).join("../upki/tests/data/crlite");
+        let bytes = fs::read(crlite.join("revocation/manifest.json")).unwrap();
+        let mut manifest = ptr::null_mut();
+        let result =
+            unsafe { upki_manifest_from_bytes(bytes.as_ptr(), bytes.len(), &mut manifest) };
+        assert_eq!(result, upki_result::UPKI_OK);
+        let expected = unsafe { &(*manifest).0 }
+            .version()
+            .to_string();
+
+        // query the length needed
+        let mut written = 0;
+        let result = unsafe { upki_manifest_version(manifest, ptr::null_mut(), 0, &mut written) };
+        assert_eq!(result, upki_result::UPKI_OK);
+        assert_eq!(written, expected.len() + 1);
+
+        let mut buf: Vec<c_char> = vec![0x7f; written];
+        let result =
+            unsafe { upki_manifest_version(manifest, buf.as_mut_ptr(), written - 1, &mut written) };
+        assert_eq!(result, upki_result::UPKI_ERR_BUFFER_TOO_SMALL);
+        assert_eq!(written, buf.len());
+        assert!(buf.iter().all(|&c| c == 0x7f));
+
+        let result =
+            unsafe { upki_manifest_version(manifest, buf.as_mut_ptr(), buf.len(), &mut written) };
+        assert_eq!(result, upki_result::UPKI_OK);
+        let version = unsafe { CStr::from_ptr(buf.as_ptr()) };
+        assert_eq!(version.to_str().unwrap(), expected);
+
+        // the version is the one that checks report
+        let config = crlite_config(&crlite);
+        let input = RevocationCheckInput {
+            cert_serial: CertSerial(vec![0x01]),
+            issuer_spki_hash: IssuerSpkiHash([0xaa; 32]),
+            sct_timestamps: vec![],
+        };
+        let outcome = unsafe { &(*manifest).0 }
+            .check_detailed(&input, &config)
+            .unwrap();
+        assert_eq!(expected, "1765445031");
+        assert_eq!(
+            upki_check_result::from(&outcome)
+                .manifest_generated_at
+                .to_string(),
+            expected
+        );
+
+        let result =
+            unsafe { upki_manifest_version(ptr::null(), buf.as_mut_ptr(), 0, &mut written) };
+        assert_eq!(result, upki_result::UPKI_ERR_NULL_POINTER);
+        unsafe { upki_manifest_free(manifest) };
+    }
+
+    #[test]
+    fn manifest_covers_issuer() {
+        let crlite = Path::new(env!("CARGO_MANIFEST_DIR")).join("../upki/tests/data/crlite");
+        let bytes = fs::read(crlite.join("revocation/manifest.json")).unwrap();
+        let mut manifest = ptr::null_mut();
+        let result =
+            unsafe { upki_manifest_from_bytes(bytes.as_ptr(), bytes.len(), &mut manifest) };
+        assert_eq!(result, upki_result::UPKI_OK);
+        let config = upki_config(crlite_config(&crlite));
+
+        let covers = |issuer: u8| {
+            let mut out = MaybeUninit::uninit();
+            let result = unsafe {
+                upki_manifest_covers_issuer(
+                    manifest,
+                    &config,
+                    [issuer; 32].as_ptr(),
+                    out.as_mut_ptr(),
+                )
+            };
+            assert_eq!(result, upki_result::UPKI_OK);
+            unsafe { out.assume_init() }
+        };
+        assert!(covers(0xaa));
+        assert!(!covers(0xcc));
+
+        let mut out = false;
+        let result =
+            unsafe { upki_manifest_covers_issuer(manifest, &config, ptr::null(), &mut out) };
+        assert_eq!(result, upki_result::UPKI_ERR_NULL_POINTER);
+        unsafe { upki_manifest_free(manifest) };
+    }
+
+    #[test]
+    fn check_result_fields() {
+        let crlite = Path::new(env!("CARGO_MANIFEST_DIR")).join("../upki/tests/data/crlite");
+        let config = crlite_config(&crlite);
+        let manifest = Manifest::from_config(&config).unwrap();
+
+        let check = |serial: u8, issuer: u8, sct: Option<u8>| {
+            let input = RevocationCheckInput {
+                cert_serial: CertSerial(vec![serial]),
+                issuer_spki_hash: IssuerSpkiHash([issuer; 32]),
+                sct_timestamps: sct
+                    .map(|log| CtTimestamp {
+                        log_id: CtLogId([log; 32]),
+                        timestamp: 1_750_000_000_000,
+                    })
+                    .into_iter()
+                    .collect(),
+            };
+            upki_check_result::from(
+                &manifest
+                    .check_detailed(&input, &config)
+                    .unwrap(),
+            )
+        };
+
+        let result = check(0x01, 0xaa, Some(0x01));
+        assert_eq!(result.status, upki_result::UPKI_REVOCATION_REVOKED);
+        assert_eq!(
+            result.not_covered_reason,
+            upki_not_covered_reason::UPKI_NOT_COVERED_REASON_NONE
+        );
+        assert_eq!(result.match_kind, upki_match_kind::UPKI_MATCH_EXACT);
+        assert_eq!(result.manifest_generated_at, 1_765_445_031);
+
+        let result = check(0x04, 0xaa, Some(0x01));
+        assert_eq!(result.status, upki_result::UPKI_REVOCATION_NOT_REVOKED);
+        assert_eq!(
+            result.not_covered_reason,
+            upki_not_covered_reason::UPKI_NOT_COVERED_REASON_NONE
+        );
+        assert_eq!(result.match_kind, upki_match_kind::UPKI_MATCH_EXACT);
+
+        let result = check(0x04, 0xaa, Some(0x02));
+        assert_eq!(result.status, upki_result::UPKI_REVOCATION_NOT_COVERED);
+        assert_eq!(
+            result.not_covered_reason,
+            upki_not_covered_reason::UPKI_NOT_COVERED_REASON_SCTS_NOT_COVERED
+        );
+        assert_eq!(result.match_kind, upki_match_kind::UPKI_MATCH_EXACT);
+
+        let result = check(0x04, 0xcc, Some(0x01));
+        assert_eq!(result.status, upki_result::UPKI_REVOCATION_NOT_COVERED);
+        assert_eq!(
+            result.not_covered_reason,
+            upki_not_covered_reason::UPKI_NOT_COVERED_REASON_ISSUER_NOT_ENROLLED
+        );
+
+        let result = check(0x01, 0xaa, None);
+        assert_eq!(result.status, upki_result::UPKI_REVOCATION_REVOKED);
+        assert_eq!(result.match_kind, upki_match_kind::UPKI_MATCH_PROBABILISTIC);
+
+        let result = check(0x04, 0xaa, None);
+        assert_eq!(result.status, upki_result::UPKI_REVOCATION_NOT_COVERED);
+        assert_eq!(
+            result.not_covered_reason,
+            upki_not_covered_reason::UPKI_NOT_COVERED_REASON_NO_SCTS
+        );
+        assert_eq!(result.match_kind, upki_match_kind::UPKI_MATCH_PROBABILISTIC);
+    }
+
+    #[test]
+    fn legacy_check_revocation() {
+        let data = Path::new(env!("CARGO_MANIFEST_DIR")).join("../upki/tests/data/precert");
+        let read =
+            |name: &str| CertificateDer::from_pem_file(data.join(format!("{name}.pem"))).unwrap();
+        let (leaf, ca) = (read("final"), read("ca"));
+
+        let temp = tempfile::TempDir::new().unwrap();
+        let config = upki_config(crlite_config(temp.path()));
+        let der = |cert: &CertificateDer<'_>| upki_certificate_der {
+            data: cert.as_ptr(),
+            len: cert.len(),
+        };
+        let chain = [der(&leaf), der(&ca)];
+        let check = |config: *const upki_config, len| unsafe {
+            upki_check_revocation(config, chain.as_ptr(), len)
+        };
+
+        // no manifest in the cache yet
+        assert_eq!(
+            check(&config, chain.len()),
+            upki_result::UPKI_ERR_REVOCATION_MANIFEST_MISSING
+        );
+
+        let input = RevocationCheckInput::from_certificates(&[leaf.clone(), ca.clone()]).unwrap();
+        let dir = temp.path().join("revocation");
+        fs::create_dir(&dir).unwrap();
+        let mut writer = ManifestWriter::new(1_765_445_031, "legacy check test");
+        writer.cover_log(CtLogId([0x01; 32]), 1_700_000_000_000, 1_760_000_000_000);
+        writer.revoked(&input.issuer_spki_hash, &input.cert_serial);
+        writer.write(&dir).unwrap();
+
+        assert_eq!(
+            check(&config, chain.len()),
+            upki_result::UPKI_REVOCATION_REVOKED
+        );
+        assert_eq!(
+            check(&config, 1),
+            upki_result::UPKI_ERR_REVOCATION_NO_ISSUER
+        );
+        assert_eq!(
+            check(ptr::null(), chain.len()),
+            upki_result::UPKI_ERR_NULL_POINTER
+        );
+
+        let mut writer = ManifestWriter::new(1_765_445_032, "legacy check test");
+        writer.cover_log(CtLogId([0x01; 32]), 1_700_000_000_000, 1_760_000_000_000);
+        writer.not_revoked(&input.issuer_spki_hash, &input.cert_serial);
+        writer.write(&dir).unwrap();
+
+        // the manifest is read again on each call
+        assert_eq!(
+            check(&config, chain.len()),
+            upki_result::UPKI_REVOCATION_NOT_REVOKED
+        );
+    }
+
+    #[test]
+    fn is_revoked() {
+        let data = Path::new(env!("CARGO_MANIFEST_DIR")).join("../upki/tests/data/precert");
+        let read =
+            |name: &str| CertificateDer::from_pem_file(data.join(format!("{name}.pem"))).unwrap();
+        let (leaf, ca) = (read("final"), read("ca"));
+        let chain = [&leaf, &ca].map(|cert| upki_certificate_der {
+            data: cert.as_ptr(),
+            len: cert.len(),
+        });
+
+        // the certificate's SCT is outside the coverage of the revocation data
+        let temp = tempfile::TempDir::new().unwrap();
+        let dir = temp.path().join("revocation");
+        fs::create_dir(&dir).unwrap();
+        let input = RevocationCheckInput::from_certificates(&[leaf.clone(), ca.clone()]).unwrap();
+        let mut writer = ManifestWriter::new(1_765_445_031, "is revoked test");
+        writer.cover_log(CtLogId([0x01; 32]), 1_700_000_000_000, 1_710_000_000_000);
+        writer.revoked(&input.issuer_spki_hash, &input.cert_serial);
+        writer.write(&dir).unwrap();
+
+        for (policy, expected) in [("return-uncovered", 0), ("fail-closed", 1)] {
+            let config_file = temp.path().join("config.toml");
+            fs::write(
+                &config_file,
+                format!(
+                    "cache-dir=\"{}\"\n[revocation]\nfetch-url=\"\"\nuncovered-policy=\"{policy}\"\n",
+                    temp.path().display()
+                ),
+            )
+            .unwrap();
+            let config = upki_config(Config::from_file(&config_file).unwrap());
+            assert_eq!(
+                unsafe { upki_is_revoked(&config, chain.as_ptr(), chain.len()) },
+                expected,
+                "{policy}"
+            );
+        }
+
+        let config = upki_config(crlite_config(temp.path()));
+        assert_eq!(unsafe { upki_is_revoked(&config, chain.as_ptr(), 1) }, -1);
+        assert_eq!(
+            unsafe { upki_is_revoked(ptr::null(), chain.as_ptr(), chain.len()) },
+            -1
+        );
+    }
+
+    #[test]
+    fn check_stream() {
+        struct Stream {
+            chains: Vec<Vec<upki_certificate_der>>,
+            next: usize,
+            results: Vec<(upki_result, Option<upki_check_result>)>,
+        }
+
+        unsafe extern "C" fn next_input(
+            user_data: *mut c_void,
+            certificates: *mut *const upki_certificate_der,
+            certificates_len: *mut usize,
+        ) -> bool {
+            let stream = unsafe { &mut *user_data.cast::<Stream>() };
+            let Some(chain) = stream.chains.get(stream.next) else {
+                return false;
+            };
+            stream.next += 1;
+            unsafe {
+                *certificates = chain.as_ptr();
+                *certificates_len = chain.len();
+            }
+            true
+        }
+
+        unsafe extern "C" fn emit_result(
+            user_data: *mut c_void,
+            result: upki_result,
+            check_result: *const upki_check_result,
+        ) {
+            let stream = unsafe { &mut *user_data.cast::<Stream>() };
+            stream
+                .results
+                .push((result, unsafe { check_result.as_ref() }.copied()));
+        }
+
+        let data = Path::new(env!("CARGO_MANIFEST_DIR")).join("../upki/tests/data/precert");
+        let read =
+            |name: &str| CertificateDer::from_pem_file(data.join(format!("{name}.pem"))).unwrap();
+        let (revoked, good, ca) = (read("final"), read("signer"), read("ca"));
+
+        let temp = tempfile::TempDir::new().unwrap();
+        let dir = temp.path().join("revocation");
+        fs::create_dir(&dir).unwrap();
+        let mut writer = ManifestWriter::new(1_765_445_031, "stream test");
+        writer.cover_log(CtLogId([0x01; 32]), 1_700_000_000_000, 1_760_000_000_000);
+        for (leaf, is_revoked) in [(&revoked, true), (&good, false)] {
+            let input =
+                RevocationCheckInput::from_certificates(&[leaf.clone(), ca.clone()]).unwrap();
+            match is_revoked {
+                true => writer.revoked(&input.issuer_spki_hash, &input.cert_serial),
+                false => writer.not_revoked(&input.issuer_spki_hash, &input.cert_serial),
+            }
+        }
+        writer.write(&dir).unwrap();
+        let config = upki_config(crlite_config(temp.path()));
+
+        let der = |cert: &CertificateDer<'_>| upki_certificate_der {
+            data: cert.as_ptr(),
+            len: cert.len(),
+        };
+        let chains = (0..300)
+            .map(|i| match i % 3 {
+                0 => vec![der(&revoked), der(&ca)],
+                1 => vec![der(&good), der(&ca)],
+                _ => vec![der(&revoked)],
+            })
+            .collect::<Vec<_>>();
+        let expected = chains
+            .iter()
+            .map(|chain| {
+                let mut out = MaybeUninit::<upki_check_result>::uninit();
+                let result = unsafe {
+                    upki_check_revocation_full(
+                        &config,
+                        chain.as_ptr(),
+                        chain.len(),
+                        out.as_mut_ptr(),
+                    )
+                };
+                let check_result = match result {
+                    upki_result::UPKI_REVOCATION_REVOKED
+                    | upki_result::UPKI_REVOCATION_NOT_REVOKED
+                    | upki_result::UPKI_REVOCATION_NOT_COVERED => {
+                        Some(unsafe { out.assume_init() })
+                    }
+                    _ => None,
+                };
+                (result, check_result)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(expected[0].0, upki_result::UPKI_REVOCATION_REVOKED);
+        assert_eq!(expected[2].0, upki_result::UPKI_ERR_REVOCATION_NO_ISSUER);
+
+        let mut stream = Stream {
+            chains,
+            next: 0,
+            results: Vec::new(),
+        };
+        let result = unsafe {
+            upki_check_stream(
+                &config,
+                ptr::null(),
+                Some(next_input),
+                Some(emit_result),
+                (&mut stream as *mut Stream).cast(),
+            )
+        };
+        assert_eq!(result, upki_result::UPKI_OK);
+        assert_eq!(stream.results.len(), expected.len());
+        for ((result, check_result), (expected, expected_check_result)) in
+            stream.results.iter().zip(&expected)
+        {
+            assert_eq!(result, expected);
+            assert_eq!(
+                check_result.map(|r| (r.status, r.not_covered_reason, r.match_kind)),
+                expected_check_result.map(|r| (r.status, r.not_covered_reason, r.match_kind))
+            );
+        }
+
+        let result = unsafe {
+            upki_check_stream(
+                &config,
+                ptr::null(),
+                Some(next_input),
+                None,
+                ptr::null_mut(),
+            )
+        };
+        assert_eq!(result, upki_result::UPKI_ERR_NULL_POINTER);
+    }
+
+    #[test]
+    fn check_batch() {
+        let data = Path::new(env!("CARGO_MANIFEST_DIR")).join("../upki/tests/data/precert");
+        let read =
+            |name: &str| CertificateDer::from_pem_file(data.join(format!("{name}.pem"))).unwrap();
+        let (revoked, good, ca) = (read("final"), read("signer"), read("ca"));
+
+        let temp = tempfile::TempDir::new().unwrap();
+        let dir = temp.path().join("revocation");
+        fs::create_dir(&dir).unwrap();
+        let mut writer = ManifestWriter::new(1_765_445_031, "batch test");
+        writer.cover_log(CtLogId([0x01; 32]), 1_700_000_000_000, 1_760_000_000_000);
+        for (leaf, is_revoked) in [(&revoked, true), (&good, false)] {
+            let input =
+                RevocationCheckInput::from_certificates(&[leaf.clone(), ca.clone()]).unwrap();
+            match is_revoked {
+                true => writer.revoked(&input.issuer_spki_hash, &input.cert_serial),
+                false => writer.not_revoked(&input.issuer_spki_hash, &input.cert_serial),
+            }
+        }
+        writer.write(&dir).unwrap();
+        let config = upki_config(crlite_config(temp.path()));
+
+        let der = |cert: &CertificateDer<'_>| upki_certificate_der {
+            data: cert.as_ptr(),
+            len: cert.len(),
+        };
+        let certs = [
+            vec![der(&revoked), der(&ca)],
+            vec![der(&revoked)],
+            vec![der(&good), der(&ca)],
+        ];
+        let chains = certs
+            .iter()
+            .map(|chain| upki_certificate_chain {
+                certificates: chain.as_ptr(),
+                certificates_len: chain.len(),
+            })
+            .collect::<Vec<_>>();
+        let batch = |results: &mut [upki_check_result]| {
+            let (mut written, mut needed) = (usize::MAX, 0);
+            let result = unsafe {
+                upki_check_batch(
+                    &config,
+                    ptr::null(),
+                    chains.as_ptr(),
+                    chains.len(),
+                    results.as_mut_ptr(),
+                    results.len(),
+                    &mut written,
+                    &mut needed,
+                )
+            };
+            (result, written, needed)
+        };
+        let unwritten = upki_check_result::failed(upki_result::UPKI_ERR_UNKNOWN);
+
+        // undersized: what fits is written, and the size needed is reported
+        let mut results = [unwritten; 3];
+        assert_eq!(
+            batch(&mut results[..2]),
+            (upki_result::UPKI_ERR_BUFFER_TOO_SMALL, 2, 3)
+        );
+        assert_eq!(
+            results.map(|r| r.status),
+            [
+                upki_result::UPKI_REVOCATION_REVOKED,
+                upki_result::UPKI_ERR_REVOCATION_NO_ISSUER,
+                upki_result::UPKI_ERR_UNKNOWN,
+            ]
+        );
+
+        // size query
+        assert_eq!(
+            batch(&mut []),
+            (upki_result::UPKI_ERR_BUFFER_TOO_SMALL, 0, 3)
+        );
+
+        let mut results = [unwritten; 3];
+        assert_eq!(batch(&mut results), (upki_result::UPKI_OK, 3, 3));
+        assert_eq!(
+            results.map(|r| r.status),
+            [
+                upki_result::UPKI_REVOCATION_REVOKED,
+                upki_result::UPKI_ERR_REVOCATION_NO_ISSUER,
+                upki_result::UPKI_REVOCATION_NOT_COVERED,
+            ]
+        );
+        assert_eq!(results[0].manifest_generated_at, 1_765_445_031);
+        assert_eq!(results[1].manifest_generated_at, 0);
+    }
+
+    #[test]
+    fn issuer_spki_hash() {
+        let data = Path::new(env!("CARGO_MANIFEST_DIR")).join("../upki/tests/data/precert");
+        let ca = CertificateDer::from_pem_file(data.join("ca.pem")).unwrap();
+        let expected = revocation::issuer_spki_hash_from_cert(&ca).unwrap();
+
+        let mut out = [0u8; 32];
+        let result = unsafe { upki_issuer_spki_hash(ca.as_ptr(), ca.len(), out.as_mut_ptr()) };
+        assert_eq!(result, upki_result::UPKI_OK);
+        assert_eq!(out, expected.0);
+
+        let mut out = [0u8; 32];
+        let result = unsafe { upki_issuer_spki_hash(ca.as_ptr(), 10, out.as_mut_ptr()) };
+        assert_eq!(result, upki_result::UPKI_ERR_REVOCATION_INVALID_ISSUER_CERT);
+        assert_eq!(out, [0; 32]);
+
+        let result = unsafe { upki_issuer_spki_hash(ca.as_ptr(), ca.len(), ptr::null_mut()) };
+        assert_eq!(result, upki_result::UPKI_ERR_NULL_POINTER);
+    }
+
+    #[test]
+    fn verify_integrity() {
+        let crlite = Path::new(env!("CARGO_MANIFEST_DIR")).join("../upki/tests/data/crlite");
+        let config = upki_config(crlite_config(&crlite));
+        assert_eq!(
+            unsafe { upki_verify_integrity(&config) },
+            upki_result::UPKI_OK
+        );
+
+        let temp = tempfile::TempDir::new().unwrap();
+        let dir = temp.path().join("revocation");
+        fs::create_dir(&dir).unwrap();
+        fs::copy(
+            crlite.join("revocation/manifest.json"),
+            dir.join("manifest.json"),
+        )
+        .unwrap();
+        let config = upki_config(crlite_config(temp.path()));
+        assert_eq!(
+            unsafe { upki_verify_integrity(&config) },
+            upki_result::UPKI_ERR_INTEGRITY
+        );
+        let message = unsafe { CStr::from_ptr(upki_last_error_message()) };
+        assert!(
+            message
+                .to_str()
+                .unwrap()
+                .starts_with("integrity problems: filter "),
+            "{message:?}"
+        );
+        assert!(
+            message
+                .to_str()
+                .unwrap()
+                .ends_with(" is missing"),
+            "{message:?}"
+        );
+    }
+
+    #[test]
+    fn too_many_certificates() {
+        let crlite = Path::new(env!("CARGO_MANIFEST_DIR")).join("../upki/tests/data/crlite");
+        let config = upki_config(crlite_config(&crlite));
+
+        // only the length is inspected
+        let certificate = upki_certificate_der {
+            data: ptr::null(),
+            len: 0,
+        };
+        let result =
+            unsafe { upki_check_revocation(&config, &certificate, UPKI_MAX_CERTIFICATES + 1) };
+        assert_eq!(result, upki_result::UPKI_ERR_INVALID_INPUT);
+
+        let mut out = MaybeUninit::uninit();
+        let result = unsafe {
+            upki_check_revocation_full(
+                &config,
+                &certificate,
+                UPKI_MAX_CERTIFICATES + 1,
+                out.as_mut_ptr(),
+            )
+        };
+        assert_eq!(result, upki_result::UPKI_ERR_INVALID_INPUT);
+    }
+
+    #[test]
+    fn config_file_errors() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let load = |path: &CStr| {
+            let mut config = ptr::null_mut();
+            let result = unsafe { upki_config_from_file(path.as_ptr(), &mut config) };
+            assert!(config.is_null());
+            let message = unsafe { CStr::from_ptr(upki_last_error_message()) };
+            (result, message.to_str().unwrap().to_owned())
+        };
+        let path = |name: &str| CString::new(temp.path().join(name).to_str().unwrap()).unwrap();
+
+        let (result, message) = load(c"config-\xff.toml");
+        assert_eq!(result, upki_result::UPKI_ERR_CONFIG_PATH);
+        assert!(
+            message.starts_with("config path is not valid UTF-8"),
+            "{message}"
+        );
+
+        let (result, message) = load(&path("absent.toml"));
+        assert_eq!(result, upki_result::UPKI_ERR_CONFIG_NOT_FOUND);
+        assert!(
+            message.starts_with("failed to read config file at "),
+            "{message}"
+        );
+        assert!(message.contains("No such file or directory"), "{message}");
+
+        // a directory exists, but cannot be read as a file
+        let (result, _) = load(&path(""));
+        assert_eq!(result, upki_result::UPKI_ERR_CONFIG_READ);
+
+        let config_file = temp.path().join("config.toml");
+        fs::write(&config_file, "[revocation\n").unwrap();
+        let (result, message) = load(&path("config.toml"));
+        assert_eq!(result, upki_result::UPKI_ERR_CONFIG_DECODE);
+        assert!(
+            message.starts_with("failed to parse config file at "),
+            "{message}"
+        );
+
+        // permissions do not stop root, so only test them where they apply
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            fs::set_permissions(&config_file, fs::Permissions::from_mode(0o000)).unwrap();
+            if fs::read(&config_file).is_err() {
+                let (result, _) = load(&path("config.toml"));
+                assert_eq!(result, upki_result::UPKI_ERR_CONFIG_PERMISSION_DENIED);
+            }
+        }
+    }
+
+    #[test]
+    fn config_field_error_message() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let config_file = temp.path().join("config.toml");
+        fs::write(
+            &config_file,
+            "cache-dir=\"x\"\n[revocation]\nfetch-url=\"\"\nmin-covered-scts=\"two\"\n",
+        )
+        .unwrap();
+        let path = CString::new(config_file.to_str().unwrap()).unwrap();
+
+        let mut config = ptr::null_mut();
+        let result = unsafe { upki_config_from_file(path.as_ptr(), &mut config) };
+        assert_eq!(result, upki_result::UPKI_ERR_CONFIG_DECODE);
+        assert!(config.is_null());
+        let message = unsafe { CStr::from_ptr(upki_last_error_message()) };
+        assert!(
+            message
+                .to_str()
+                .unwrap()
+                .starts_with("invalid field `revocation.min-covered-scts` in config file at "),
+            "{message:?}"
+        );
+        assert!(
+            message
+                .to_str()
+                .unwrap()
+                .contains(", line 4 column 18: invalid type: string \"two\""),
+            "{message:?}"
+        );
+    }
+
+    #[test]
+    fn panic_is_caught() {
+        let result = catch_panic(|| panic!("deliberate {}", 42));
+        assert_eq!(result, upki_result::UPKI_ERR_PANICKED);
+        let message = unsafe { CStr::from_ptr(upki_last_error_message()) };
+        assert_eq!(message.to_str().unwrap(), "panic: deliberate 42");
+
+        let result = catch_panic(|| panic!("static"));
+        assert_eq!(result, upki_result::UPKI_ERR_PANICKED);
+        let message = unsafe { CStr::from_ptr(upki_last_error_message()) };
+        assert_eq!(message.to_str().unwrap(), "panic: static");
+    }
+
+    fn crlite_config(cache_dir: &Path) -> Config {
+        let temp = tempfile::TempDir::new().unwrap();
+        let config_file = temp.path().join("config.toml");
+        fs::write(
+            &config_file,
+            format!(
+                "cache-dir=\"{}\"\n[revocation]\nfetch-url=\"\"\nno-sct-policy=\"use-serial-only\"\n",
+                cache_dir.display()
+            ),
+        )
+        .unwrap();
+        Config::from_file(&config_file).unwrap()
+    }
+}
@@ -3,6 +3,15 @@
 #include <stdint.h>
 #include <stdlib.h>
 
+/**
+ * The largest `certificates_len` accepted by the `upki_check_revocation*` functions.
+ *
+ * The length of `certificates` cannot be validated, so this catches callers passing
+ * a nonsensical length rather than reading past the end of their array.  Real
+ * certificate chains are far shorter than this.
+ */
+#define UPKI_MAX_CERTIFICATES 64
+
 /**
  * Result type for upki C API functions.
  *
@@ -39,11 +48,27 @@ typedef enum upki_result {
    */
   UPKI_ERR_UNKNOWN = 18,
   /**
-   * An unexpected panic occurred in the library.
+   * An unexpected panic occurred in the library.  `upki_last_error_message`
+   * describes it.
    */
   UPKI_ERR_PANICKED = 19,
   /**
-   * Failed to decode configuration file.
+   * An argument was out of range, such as a `certificates_len` above
+   * `UPKI_MAX_CERTIFICATES`.
+   */
+  UPKI_ERR_INVALID_INPUT = 20,
+  /**
+   * The revocation cache failed an integrity check.  `upki_last_error_message`
+   * lists the problems found.
+   */
+  UPKI_ERR_INTEGRITY = 21,
+  /**
+   * An output buffer was too small.  The size needed was written to `out_written`,
+   * or to `out_needed` where there is one.
+   */
+  UPKI_ERR_BUFFER_TOO_SMALL = 22,
+  /**
+   * Failed to decode configuration file, or a field in it was invalid.
    */
   UPKI_ERR_CONFIG_DECODE = 32,
   /**
@@ -62,6 +87,22 @@ typedef enum upki_result {
    * The user's home directory could not be determined.
    */
   UPKI_ERR_NO_HOME_DIR = 36,
+  /**
+   * An environment variable had an invalid value.
+   */
+  UPKI_ERR_INVALID_ENVIRONMENT = 37,
+  /**
+   * The configuration file does not exist.
+   */
+  UPKI_ERR_CONFIG_NOT_FOUND = 38,
+  /**
+   * The configuration file could not be read, for lack of permission.
+   */
+  UPKI_ERR_CONFIG_PERMISSION_DENIED = 39,
+  /**
+   * A setting passed to `Config::with_setting()` was unknown or had an invalid value.
+   */
+  UPKI_ERR_INVALID_SETTING = 40,
   /**
    * Failed to create a directory.
    */
@@ -146,13 +187,171 @@ typedef enum upki_result {
    * Certificate chain must contain at least 2 certificates.
    */
   UPKI_ERR_REVOCATION_TOO_FEW_CERTS = 84,
+  /**
+   * Failed to encode a filter file.
+   */
+  UPKI_ERR_REVOCATION_FILTER_ENCODE = 85,
+  /**
+   * A manifest was older than one previously accepted.
+   */
+  UPKI_ERR_REVOCATION_DOWNGRADE = 86,
+  /**
+   * Failed to read a file.
+   */
+  UPKI_ERR_REVOCATION_FILE_READ = 87,
+  /**
+   * PEM-encoded input could not be parsed.
+   */
+  UPKI_ERR_REVOCATION_INVALID_PEM = 88,
+  /**
+   * There is no manifest file.
+   */
+  UPKI_ERR_REVOCATION_MANIFEST_MISSING = 89,
+  /**
+   * A textual check input had too few fields.
+   */
+  UPKI_ERR_REVOCATION_INVALID_CHECK_INPUT = 90,
+  /**
+   * Failed to read or write a stream.
+   */
+  UPKI_ERR_REVOCATION_STREAM_IO = 91,
+  /**
+   * Failed to decode a test vector file.
+   */
+  UPKI_ERR_REVOCATION_TEST_VECTOR_DECODE = 92,
+  /**
+   * The cache cannot be updated, because the configuration is read-only.
+   */
+  UPKI_ERR_REVOCATION_READ_ONLY = 93,
+  /**
+   * An encoded check outcome could not be decoded.
+   */
+  UPKI_ERR_REVOCATION_INVALID_OUTCOME_ENCODING = 94,
+  /**
+   * A check input was built without a required field.
+   */
+  UPKI_ERR_REVOCATION_MISSING_CHECK_INPUT_FIELD = 95,
+  /**
+   * A manifest was larger than the configured limit.
+   */
+  UPKI_ERR_REVOCATION_MANIFEST_TOO_LARGE = 96,
+  /**
+   * A fallback revocation check failed.
+   */
+  UPKI_ERR_REVOCATION_FALLBACK = 97,
+  /**
+   * An issuer certificate could not be parsed.
+   */
+  UPKI_ERR_REVOCATION_INVALID_ISSUER_CERT = 98,
+  /**
+   * A check was asked for a manifest generation other than the one loaded.
+   */
+  UPKI_ERR_REVOCATION_GENERATION_UNAVAILABLE = 99,
+  /**
+   * A check input's serial number was not canonical, and `strict-input` is set.
+   */
+  UPKI_ERR_REVOCATION_NON_CANONICAL_SERIAL = 100,
+  /**
+   * An update was cancelled.
+   */
+  UPKI_ERR_REVOCATION_CANCELLED = 101,
+  /**
+   * The manifest's digest was not the configured `pinned-manifest-digest`.
+   */
+  UPKI_ERR_REVOCATION_DIGEST_MISMATCH = 102,
+  /**
+   * The cache failed its integrity checks when opened.
+   */
+  UPKI_ERR_REVOCATION_INTEGRITY = 103,
+  /**
+   * The manifest is in a format version this version of upki does not support.
+   *
+   * Upgrade upki to use it.
+   */
+  UPKI_ERR_REVOCATION_UNSUPPORTED_MANIFEST_VERSION = 104,
+  /**
+   * Invalid hex encoding.
+   */
+  UPKI_ERR_REVOCATION_INVALID_HEX = 105,
+  /**
+   * Failed to start a runtime to update the cache.
+   */
+  UPKI_ERR_REVOCATION_RUNTIME = 106,
 } upki_result;
 
+/**
+ * Why a certificate was not covered by revocation data.
+ */
+typedef enum upki_not_covered_reason {
+  /**
+   * The certificate was covered.
+   */
+  UPKI_NOT_COVERED_REASON_NONE = 0,
+  /**
+   * No filter has data for the certificate's issuer.
+   */
+  UPKI_NOT_COVERED_REASON_ISSUER_NOT_ENROLLED = 1,
+  /**
+   * The certificate has no SCTs, and the configured policy does not allow
+   * deciding its status without them.
+   */
+  UPKI_NOT_COVERED_REASON_NO_SCTS = 2,
+  /**
+   * None of the certificate's SCTs are within the coverage of the revocation data.
+   */
+  UPKI_NOT_COVERED_REASON_SCTS_NOT_COVERED = 3,
+  /**
+   * The certificate's issuer is configured to bypass the revocation data.
+   */
+  UPKI_NOT_COVERED_REASON_BYPASSED = 4,
+  /**
+   * The certificate's issuer is not among those the configuration is concerned with.
+   */
+  UPKI_NOT_COVERED_REASON_OUT_OF_SCOPE = 5,
+  /**
+   * The certificate may be revoked, but the configured policy requires that to be
+   * confirmed by another revocation mechanism.
+   */
+  UPKI_NOT_COVERED_REASON_NEEDS_CONFIRMATION = 6,
+  /**
+   * The revocation data could not be read.
+   */
+  UPKI_NOT_COVERED_REASON_CHECK_ERROR = 7,
+  /**
+   * The certificate has fewer SCTs than the configuration requires.
+   */
+  UPKI_NOT_COVERED_REASON_INSUFFICIENT_SCTS = 8,
+  /**
+   * An unknown reason was added to the library.
+   */
+  UPKI_NOT_COVERED_REASON_UNKNOWN = 255,
+} upki_not_covered_reason;
+
+/**
+ * How definitive a revocation check result is.
+ */
+typedef enum upki_match_kind {
+  /**
+   * The result is exact.
+   */
+  UPKI_MATCH_EXACT = 0,
+  /**
+   * The result comes from a lookup outside the known coverage of the revocation
+   * data, and may be wrong.
+   */
+  UPKI_MATCH_PROBABILISTIC = 1,
+} upki_match_kind;
+
 /**
  * Opaque type representing a `upki::Config`.
  */
 typedef struct upki_config upki_config;
 
+/**
+ * Opaque type representing a `upki::revocation::Manifest`.
+ */
+typedef struct upki_manifest upki_manifest;
+
 /**
  * A DER-encoded certificate.
  */
@@ -167,6 +366,49 @@ typedef struct upki_certificate_der {
   uintptr_t len;
 } upki_certificate_der;
 
+/**
+ * The full result of a revocation check, written by `upki_check_revocation_full`.
+ *
+ * All fields are plain values: there is nothing to free.
+ */
+typedef struct upki_check_result {
+  /**
+   * One of `UPKI_REVOCATION_NOT_COVERED`, `UPKI_REVOCATION_REVOKED` or
+   * `UPKI_REVOCATION_NOT_REVOKED`.
+   */
+  enum upki_result status;
+  /**
+   * Why the certificate was not covered.
+   *
+   * This is `UPKI_NOT_COVERED_REASON_NONE` unless `status` is
+   * `UPKI_REVOCATION_NOT_COVERED`.
+   */
+  enum upki_not_covered_reason not_covered_reason;
+  /**
+   * Whether `status` is definitive.
+   */
+  enum upki_match_kind match_kind;
+  /**
+   * When the manifest used for the check was generated, as a UNIX timestamp in seconds.
+   */
+  uint64_t manifest_generated_at;
+} upki_check_result;
+
+/**
+ * A certificate chain, for `upki_check_batch`.
+ */
+typedef struct upki_certificate_chain {
+  /**
+   * Pointer to the certificates, end-entity certificate first, as for
+   * `upki_check_revocation`.
+   */
+  const struct upki_certificate_der *certificates;
+  /**
+   * Number of certificates.
+   */
+  uintptr_t certificates_len;
+} upki_certificate_chain;
+
 /**
  * Check the revocation status of a certificate.
  *
@@ -175,6 +417,14 @@ typedef struct upki_certificate_der {
  *
  * Returns a `upki_result` indicating success (with revocation status) or an error.
  *
+ * `certificates_len` may be at most `UPKI_MAX_CERTIFICATES`; larger values are
+ * rejected with `UPKI_ERR_INVALID_INPUT` before `certificates` is read.
+ *
+ * The manifest is read from the cache directory on every call.  Callers making
+ * many checks should load it once with `upki_manifest_from_bytes` and use
+ * `upki_manifest_check_revocation` instead.  The signature and behaviour of this
+ * function will not change as others are added.
+ *
  * # Safety
  *
  * - `config` must be a valid pointer returned by `upki_config_new`.
@@ -185,12 +435,264 @@ enum upki_result upki_check_revocation(const struct upki_config *config,
                                        const struct upki_certificate_der *certificates,
                                        uintptr_t certificates_len);
 
+/**
+ * Check the revocation status of a certificate, and describe how it was determined.
+ *
+ * This is like `upki_check_revocation`, and returns the same values.  In addition,
+ * on success the full result is written to `out`.  `out` is not written on error.
+ *
+ * # Safety
+ *
+ * - `config` must be a valid pointer returned by `upki_config_new`.
+ * - `certificates` must point to `certificates_len` `upki_certificate` values.
+ * - Each `upki_certificate` must have a valid `data` pointer to `len` bytes.
+ * - `out` must not be `NULL`.
+ */
+enum upki_result upki_check_revocation_full(const struct upki_config *config,
+                                            const struct upki_certificate_der *certificates,
+                                            uintptr_t certificates_len,
+                                            struct upki_check_result *out);
+
+/**
+ * Check whether a certificate is revoked, as a plain yes or no.
+ *
+ * Returns 1 if the certificate is revoked, 0 if not, or -1 on error.  After an
+ * error, `upki_last_error_message` describes it, if it has a description.
+ *
+ * Certificates not covered by the revocation data are treated according to the
+ * configured `uncovered-policy`: they are reported as revoked under `fail-closed`,
+ * and as not revoked otherwise.  Use `upki_check_revocation` to tell them apart.
+ *
+ * # Safety
+ *
+ * - `config` must be a valid pointer returned by `upki_config_new`.
+ * - `certificates` must point to `certificates_len` `upki_certificate` values.
+ * - Each `upki_certificate` must have a valid `data` pointer to `len` bytes.
+ */
+int upki_is_revoked(const struct upki_config *config,
+                    const struct upki_certificate_der *certificates,
+                    uintptr_t certificates_len);
+
+/**
+ * Check the revocation status of a stream of certificates, one at a time.
+ *
+ * `next_input` is called to get each certificate chain to check.  It should set
+ * `*certificates` and `*certificates_len` as for `upki_check_revocation` and return
+ * `true`, or return `false` when there are no more.  `emit_result` is then called
+ * with the outcome: on success, `result` is the status and `check_result` points to
+ * the full result; on error, `result` is the error and `check_result` is `NULL`.
+ * An error for one chain does not stop the stream.  Both are passed `user_data`.
+ *
+ * `manifest` is used if it is not `NULL`; otherwise the manifest is loaded from the
+ * cache directory described by `config`.  Either way, the filters are read once,
+ * before `next_input` is first called.  Returns `UPKI_OK` once `next_input` returns
+ * `false`, or an error if the manifest or filters cannot be loaded.
+ *
+ * The callbacks are called on the calling thread, strictly alternating, and never
+ * after this returns.  The certificates set by `next_input` need only stay valid
+ * until the next call to `next_input`, and `check_result` only until `emit_result`
+ * returns.  Callbacks may call other functions in this library, except to free
+ * `config` or `manifest`, but should not call `upki_check_stream` recursively.
+ *
+ * # Safety
+ *
+ * - `config` must be a valid pointer returned by `upki_config_new`.
+ * - `manifest` must be `NULL` or a valid pointer returned by `upki_manifest_from_bytes`.
+ * - `next_input` and `emit_result` must not be `NULL`.
+ * - The certificates set by `next_input` must meet the requirements of
+ *   `upki_check_revocation`.
+ */
+enum upki_result upki_check_stream(const struct upki_config *config,
+                                   const struct upki_manifest *manifest,
+                                   bool (*next_input)(void *user_data,
+                                                      const struct upki_certificate_der **certificates,
+                                                      uintptr_t *certificates_len),
+                                   void (*emit_result)(void *user_data,
+                                                       enum upki_result result,
+                                                       const struct upki_check_result *check_result),
+                                   void *user_data);
+
+/**
+ * Check the revocation status of many certificate chains, into a caller-provided array.
+ *
+ * `chains` points to `chains_len` chains, each as for `upki_check_revocation`.  The
+ * result for `chains[i]` is written to `out_results[i]`, so `out_results` needs
+ * exactly `chains_len` elements.  If a chain cannot be checked, the `status` of its
+ * result is the error and the other fields are zero.  Nothing is allocated for the
+ * results.
+ *
+ * `*out_needed` is always set to `chains_len`.  If `results_len` is smaller, only
+ * the first `results_len` chains are checked and written, and this returns
+ * `UPKI_ERR_BUFFER_TOO_SMALL`; otherwise it returns `UPKI_OK`.  Either way,
+ * `*out_written` is set to the number of results written.  `out_results` may be
+ * `NULL` if `results_len` is 0, to query the size needed.
+ *
+ * `manifest` is used if it is not `NULL`; otherwise the manifest is loaded from the
+ * cache directory described by `config`.  Either way, the filters are read once.  If
+ * the manifest or filters cannot be loaded, the error is returned and nothing is
+ * written to `out_results`.
+ *
+ * # Safety
+ *
+ * - `config` must be a valid pointer returned by `upki_config_new`.
+ * - `manifest` must be `NULL` or a valid pointer returned by `upki_manifest_from_bytes`.
+ * - `chains` must point to `chains_len` `upki_certificate_chain` values, each
+ *   meeting the requirements of `upki_check_revocation`.
+ * - `out_results` must point to `results_len` writable `upki_check_result` values,
+ *   or be `NULL` if `results_len` is 0.
+ * - `out_written` and `out_needed` must not be `NULL`.
+ */
+enum upki_result upki_check_batch(const struct upki_config *config,
+                                  const struct upki_manifest *manifest,
+                                  const struct upki_certificate_chain *chains,
+                                  uintptr_t chains_len,
+                                  struct upki_check_result *out_results,
+                                  uintptr_t results_len,
+                                  uintptr_t *out_written,
+                                  uintptr_t *out_needed);
+
+/**
+ * Check the revocation status of a certificate against a specific manifest.
+ *
+ * This is like `upki_check_revocation`, but uses `manifest` rather than loading
+ * the manifest from the cache directory described by `config`.  The filters
+ * referenced by `manifest` are still read from that cache directory.
+ *
+ * # Safety
+ *
+ * - `config` must be a valid pointer returned by `upki_config_new`.
+ * - `manifest` must be a valid pointer returned by `upki_manifest_from_bytes`.
+ * - `certificates` must point to `certificates_len` `upki_certificate` values.
+ * - Each `upki_certificate` must have a valid `data` pointer to `len` bytes.
+ */
+enum upki_result upki_manifest_check_revocation(const struct upki_manifest *manifest,
+                                                const struct upki_config *config,
+                                                const struct upki_certificate_der *certificates,
+                                                uintptr_t certificates_len);
+
+/**
+ * Find out whether `manifest` has revocation data for an issuer.
+ *
+ * `issuer_spki_hash` is the 32-byte hash computed by `upki_issuer_spki_hash`.  On
+ * success, writes whether the issuer is covered, by a filter or by a revoked range,
+ * to `out` and returns `UPKI_OK`.  This does no revocation lookup, but reads the
+ * filters referenced by `manifest` from the cache directory described by `config`.
+ * `out` is not written on error.
+ *
+ * # Safety
+ *
+ * - `manifest` must be a valid pointer returned by `upki_manifest_from_bytes`.
+ * - `config` must be a valid pointer returned by `upki_config_new`.
+ * - `issuer_spki_hash` must point to 32 bytes.
+ * - `out` must not be `NULL`.
+ */
+enum upki_result upki_manifest_covers_issuer(const struct upki_manifest *manifest,
+                                             const struct upki_config *config,
+                                             const uint8_t *issuer_spki_hash,
+                                             bool *out);
+
+/**
+ * Create a new `upki_manifest` by parsing the `data_len` bytes at `data`.
+ *
+ * `data` is the contents of a `manifest.json` file.  On success, writes the manifest
+ * pointer to `out` and returns `UPKI_OK`.  The caller is responsible for freeing the
+ * manifest with `upki_manifest_free`.
+ *
+ * # Safety
+ *
+ * - `out` must not be `NULL`.
+ * - `data` must point to `data_len` bytes.
+ */
+enum upki_result upki_manifest_from_bytes(const uint8_t *data,
+                                          uintptr_t data_len,
+                                          struct upki_manifest **out);
+
+/**
+ * Free a `upki_manifest` created by `upki_manifest_from_bytes`.
+ *
+ * # Safety
+ *
+ * `manifest` must be a valid pointer returned by `upki_manifest_from_bytes`,
+ * or null (in which case this is a no-op).
+ */
+void upki_manifest_free(struct upki_manifest *manifest);
+
+/**
+ * Write the version of `manifest` to `out_buf`, as a null-terminated string.
+ *
+ * This is the version that `manifest_generated_at` in `upki_check_result` reports for
+ * checks against `manifest`, so it can be logged alongside a decision.
+ *
+ * `*out_written` is always set to the length of the version string in bytes,
+ * including the terminating null.  If `out_buf` is `NULL` this returns `UPKI_OK` and
+ * writes nothing else, so callers can query the size needed.  If `buf_len` is less
+ * than that size, `out_buf` is not written and this returns
+ * `UPKI_ERR_BUFFER_TOO_SMALL`.
+ *
+ * # Safety
+ *
+ * - `manifest` must be a valid pointer returned by `upki_manifest_from_bytes`.
+ * - `out_buf` must point to `buf_len` writable bytes, or be `NULL`.
+ * - `out_written` must not be `NULL`.
+ */
+enum upki_result upki_manifest_version(const struct upki_manifest *manifest,
+                                       char *out_buf,
+                                       uintptr_t buf_len,
+                                       uintptr_t *out_written);
+
+/**
+ * Audit the revocation cache described by `config`.
+ *
+ * This loads the manifest, then reads and decodes every filter it refers to.  Returns
+ * `UPKI_OK` if nothing is wrong, or `UPKI_ERR_INTEGRITY` if any problem is found: a
+ * filter that is missing, corrupt or undecodable, or a manifest or coverage dated in
+ * the future.  Errors loading the manifest are returned as usual.
+ *
+ * # Safety
+ *
+ * `config` must be a valid pointer returned by `upki_config_new`.
+ */
+enum upki_result upki_verify_integrity(const struct upki_config *config);
+
+/**
+ * Compute the issuer SPKI hash of the DER-encoded issuer certificate at `cert`.
+ *
+ * This is the SHA256 hash of the certificate's `SubjectPublicKeyInfo`, as used to
+ * identify the issuer in revocation data.  On success, writes the 32-byte hash to
+ * `out` and returns `UPKI_OK`.  `out` is not written on error.
+ *
+ * # Safety
+ *
+ * - `cert` must point to `cert_len` bytes.
+ * - `out` must point to 32 writable bytes.
+ */
+enum upki_result upki_issuer_spki_hash(const uint8_t *cert, uintptr_t cert_len, uint8_t *out);
+
+/**
+ * Describe the most recent error returned by a function on this thread.
+ *
+ * Returns a null-terminated UTF-8 string, or `NULL` if no error has occurred on
+ * this thread.  Not every error has a description: `UPKI_ERR_NULL_POINTER`, for
+ * example, does not change what this returns.
+ *
+ * The returned pointer is owned by the library, and is valid until the next call
+ * into the library on this thread.  It must not be freed.
+ */
+const char *upki_last_error_message(void);
+
 /**
  * Create a new `upki_config` by loading it from the file at `path`.
  *
  * On success, writes the config pointer to `out` and returns `UPKI_OK`.
  * The caller is responsible for freeing the config with `upki_config_free`.
  *
+ * A `path` that is not valid UTF-8 is rejected with `UPKI_ERR_CONFIG_PATH`.  A file
+ * that does not exist gives `UPKI_ERR_CONFIG_NOT_FOUND`, one that cannot be read for
+ * lack of permission gives `UPKI_ERR_CONFIG_PERMISSION_DENIED`, and any other
+ * problem reading it gives `UPKI_ERR_CONFIG_READ`.  A file that is read but not a
+ * valid configuration gives `UPKI_ERR_CONFIG_DECODE`.  In every case,
+ * `upki_last_error_message` describes the problem.
+ *
  * # Safety
  *
  * - `out` must not be `NULL`.
@@ -7,7 +7,7 @@ use std::time::SystemTime;
 use aws_lc_rs::digest::{SHA256, digest};
 use clap::{Parser, ValueEnum};
 use eyre::{Context, Report, anyhow};
-use upki::revocation::{Filter, Manifest};
+use upki::revocation::{Filter, Manifest, Provenance};
 
 mod mozilla;
 
@@ -104,6 +104,7 @@ async fn main() -> Result<(), Report> {
             filename: p.attachment.filename.clone(),
             size: p.attachment.size,
             hash: p.attachment.hash.clone(),
+            issuers: Vec::new(),
         });
     }
 
@@ -114,6 +115,18 @@ async fn main() -> Result<(), Report> {
             .as_secs(),
         comment: opts.manifest_comment.clone(),
         filters,
+        provenance: Some(Provenance {
+            builder: Some(format!(
+                "{} v{}",
+                env!("CARGO_PKG_NAME"),
+                env!("CARGO_PKG_VERSION")
+            )),
+            build_id: None,
+        }),
+        next_update: None,
+        epoch: None,
+        format_version: None,
+        revoked_ranges: Vec::new(),
     };
     let output_filename = opts.output_dir.join("manifest.json");
     fs::write(
@@ -0,0 +1,147 @@
+//! Reading and writing revocation snapshots bundled into a tar file.
+//!
+//! An archive holds the same files as a cache directory, at the same relative
+//! paths: `config.toml`, `revocation/manifest.json`, and each filter under
+//! `revocation/`.  It is an uncompressed tar file, as made by
+//! `tar -cf bundle.tar -C <cache-dir> config.toml revocation`.
+//!
+//! Only regular files are read.  Directories, links and extended headers are
+//! skipped, so member names are limited to the 255 bytes of a ustar header.
+
+use core::str;
+use std::collections::HashMap;
+use std::path::Path;
+use std::{fs, io};
+
+/// The files in an archive, read into memory.
+pub(crate) struct Archive {
+    members: HashMap<String, Vec<u8>>,
+}
+
+impl Archive {
+    /// Read the whole archive at `path`.
+    pub(crate) fn read(path: &Path) -> io::Result<Self> {
+        Self::parse(&fs::read(path)?)
+    }
+
+    /// The contents of the member named `name`.
+    ///
+    /// This fails with [`io::ErrorKind::NotFound`] if there is no such member.
+    pub(crate) fn get(&self, name: &str) -> io::Result<&[u8]> {
+        match self.members.get(name) {
+            Some(contents) => Ok(contents),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no member {name:?} in archive"),
+            )),
+        }
+    }
+
+    /// Encode `members` as an archive, in order.
+    ///
+    /// Names are limited to 99 bytes, since no ustar prefix is written.
+    #[cfg(all(unix, feature = "archive-dir"))]
+    pub(crate) fn encode<'a>(
+        members: impl IntoIterator<Item = (&'a str, &'a [u8])>,
+    ) -> io::Result<Vec<u8>> {
+        let mut out = Vec::new();
+        for (name, contents) in members {
+            if name.len() >= 100 {
+                return Err(invalid("member name too long"));
+            }
+
+            let mut header = [0u8; BLOCK_LEN];
+            header[..name.len()].copy_from_slice(name.as_bytes());
+            header[100..107].copy_from_slice(b"0000644");
+            header[108..115].copy_from_slice(b"0000000");
+            header[116..123].copy_from_slice(b"0000000");
+            header[124..135].copy_from_slice(format!("{:011o}", contents.len()).as_bytes());
+            header[136..147].copy_from_slice(b"00000000000");
+            header[156] = b'0';
+            header[257..263].copy_from_slice(b"ustar\0");
+            header[263..265].copy_from_slice(b"00");
+            header[148..156].copy_from_slice(b"        ");
+            let checksum = header
+                .iter()
+                .map(|&b| u64::from(b))
+                .sum::<u64>();
+            header[148..155].copy_from_slice(format!("{checksum:06o}\0").as_bytes());
+
+            out.extend_from_slice(&header);
+            out.extend_from_slice(contents);
+            out.resize(out.len().next_multiple_of(BLOCK_LEN), 0);
+        }
+
+        out.resize(out.len() + 2 * BLOCK_LEN, 0);
+        Ok(out)
+    }
+
+    fn parse(mut bytes: &[u8]) -> io::Result<Self> {
+        let mut members = HashMap::new();
+        loop {
+            let Some((header, rest)) = bytes.split_first_chunk::<BLOCK_LEN>() else {
+                return Err(invalid("truncated header"));
+            };
+            if header.iter().all(|&b| b == 0) {
+                break;
+            }
+
+            let checksum = header[..148]
+                .iter()
+                .chain(&[b' '; 8])
+                .chain(&header[156..])
+                .map(|&b| u64::from(b))
+                .sum::<u64>();
+            if octal(&header[148..156]) != Some(checksum) {
+                return Err(invalid("bad header checksum"));
+            }
+
+            let size = octal(&header[124..136])
+                .and_then(|size| usize::try_from(size).ok())
+                .ok_or_else(|| invalid("bad member size"))?;
+            let padded = size
+                .checked_next_multiple_of(BLOCK_LEN)
+                .filter(|&padded| padded <= rest.len())
+                .ok_or_else(|| invalid("truncated member"))?;
+
+            if matches!(header[156], b'0' | 0) {
+                let mut name = field(&header[..100]).to_owned();
+                if &header[257..263] == b"ustar\0" && header[345] != 0 {
+                    name = format!("{}/{name}", field(&header[345..500]));
+                }
+                let name = name.trim_start_matches("./").to_owned();
+                members.insert(name, rest[..size].to_vec());
+            }
+
+            bytes = &rest[padded..];
+        }
+
+        Ok(Self { members })
+    }
+}
+
+/// A string field of a tar header, which ends at the first nul.
+fn field(bytes: &[u8]) -> &str {
+    let len = bytes
+        .iter()
+        .position(|&b| b == 0)
+        .unwrap_or(bytes.len());
+    str::from_utf8(&bytes[..len]).unwrap_or_default()
+}
+
+/// A numeric field of a tar header, in octal padded with spaces or nuls.
+fn octal(bytes: &[u8]) -> Option<u64> {
+    let digits = str::from_utf8(bytes)
+        .ok()?
+        .trim_matches(|c| c == ' ' || c == '\0');
+    u64::from_str_radix(digits, 8).ok()
+}
+
+fn invalid(reason: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid tar archive: {reason}"),
+    )
+}
+
+const BLOCK_LEN: usize = 512;
@@ -0,0 +1,58 @@
+//! Replacing the system clock, for tests.
+//!
+//! Everything in upki that depends on the current time, such as a manifest's age in
+//! [`CheckOutcome::explain()`][crate::revocation::CheckOutcome::explain] or the future-dated checks of
+//! [`Manifest::verify_integrity()`][crate::revocation::Manifest::verify_integrity], reads it
+//! through this module.  A test can install its own [`Clock`] with [`set_clock()`]
+//! to control that time without passing it to every call.
+//!
+//! The clock is global to the process and shared by every thread, so tests that set
+//! it should not run alongside tests that expect the real time.  Deadlines, such as
+//! the one given to [`Manifest::check_batch_deadline()`][crate::revocation::Manifest::check_batch_deadline],
+//! use the monotonic clock and are not affected.
+
+use std::sync::{PoisonError, RwLock};
+use std::time::SystemTime;
+
+/// A source of the current time.
+pub trait Clock: Send + Sync + 'static {
+    /// The current time.
+    fn now(&self) -> SystemTime;
+}
+
+/// The real system clock, which is used unless [`set_clock()`] is called.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// Use `clock` for the current time, everywhere in upki, until it is replaced.
+pub fn set_clock(clock: impl Clock) {
+    *CLOCK
+        .write()
+        .unwrap_or_else(PoisonError::into_inner) = Some(Box::new(clock));
+}
+
+/// Go back to using the [`SystemClock`].
+pub fn reset_clock() {
+    *CLOCK
+        .write()
+        .unwrap_or_else(PoisonError::into_inner) = None;
+}
+
+/// The current time, according to the clock in use.
+pub(crate) fn now() -> SystemTime {
+    match &*CLOCK
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+    {
+        Some(clock) => clock.now(),
+        None => SystemTime::now(),
+    }
+}
+
+static CLOCK: RwLock<Option<Box<dyn Clock>>> = RwLock::new(None);
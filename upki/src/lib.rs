@@ -2,22 +2,42 @@
 #![warn(missing_docs)]
 
 use core::error::Error as StdError;
+use core::str;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
-use std::{fmt, fs, io};
+use std::{env, fmt, fs, io};
 
+use aws_lc_rs::digest;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
+use crate::archive::Archive;
 use crate::revocation::RevocationConfig;
 
 /// `upki` configuration.
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub struct Config {
     /// Where to store cache files.
     cache_dir: PathBuf,
 
+    /// Never write to the cache directory.
+    ///
+    /// Loading and checking against the cache never write to it in any case.  This
+    /// additionally makes [`revocation::fetch()`] fail with
+    /// [`revocation::Error::ReadOnly`] before doing anything, so the cache cannot be
+    /// updated in place: the same goes for [`revocation::spawn_auto_refresh()`],
+    /// [`revocation::SharedManifest::update()`] and `upki fetch`.  The cache must
+    /// instead be populated in advance, for example when building a container image.
+    #[serde(default)]
+    pub read_only: bool,
+
     /// Configuration for crlite-style revocation.
     pub revocation: RevocationConfig,
+
+    /// Where each setting not at its default came from, by key.
+    #[serde(skip)]
+    sources: BTreeMap<String, FieldSource>,
 }
 
 impl Config {
@@ -34,30 +54,292 @@ impl Config {
 
     /// Load the configuration data from a file at `path`.
     pub fn from_file(path: &Path) -> Result<Self, Error> {
-        let config_content = fs::read_to_string(path).map_err(|error| Error::FileRead {
+        decode_with_sources(&read_file(path)?, path)
+    }
+
+    /// Load the configuration data from `config.toml` in the archive at `path`.
+    ///
+    /// An archive bundles a configuration with the revocation data it uses, for
+    /// shipping a self-contained snapshot: see [`revocation::SharedManifest::from_archive()`].
+    /// Errors are reported against `path` joined with `config.toml`.
+    pub fn from_archive(path: &Path) -> Result<Self, Error> {
+        let archive = Archive::read(path).map_err(|error| Error::FileRead {
             error,
             path: path.to_owned(),
         })?;
 
-        toml::from_str(&config_content).map_err(|error| Error::ConfigError {
-            error: Box::new(error),
-            path: path.to_owned(),
+        let path = path.join(CONFIG_FILE);
+        let content = archive
+            .get(CONFIG_FILE)
+            .map_err(|error| Error::FileRead {
+                error,
+                path: path.clone(),
+            })?;
+        match str::from_utf8(content) {
+            Ok(content) => decode_with_sources(content, &path),
+            Err(error) => Err(Error::ConfigError {
+                error: Box::new(error),
+                path,
+            }),
+        }
+    }
+
+    /// Load the configuration data from several files, each overriding the ones before.
+    ///
+    /// The first of `paths` is the base configuration, and must exist.  Later files
+    /// are overlays: any that do not exist are skipped, and each needs to contain only
+    /// the settings it changes.  Files are merged one field at a time, so tables such
+    /// as `[revocation]` are combined, while any other value (including a list, such
+    /// as `bypass-issuers`) replaces the earlier value as a whole.
+    ///
+    /// Each file must be valid TOML by itself, but only the merged result needs to be
+    /// a valid configuration.  Problems with the merged result are reported against
+    /// the last file loaded.  If `paths` is empty, this returns [`Config::try_default()`].
+    pub fn from_files(paths: &[&Path]) -> Result<Self, Error> {
+        let Some((base, overlays)) = paths.split_first() else {
+            return Self::try_default();
+        };
+
+        let merged = decode::<toml::Table>(&read_file(base)?, base)?;
+        let mut sources = BTreeMap::new();
+        mark_sources(&mut sources, &merged, "", FieldSource::File);
+        layer_files(merged, sources, base, overlays)
+    }
+
+    /// Like [`Config::from_files()`], but layering every one of `paths` as an overlay
+    /// over [`Config::from_env()`].
+    ///
+    /// So a setting in a file takes precedence over one in the environment, and any
+    /// setting in neither takes its default value.  Files that do not exist are skipped.
+    pub fn from_env_and_files(paths: &[&Path]) -> Result<Self, Error> {
+        let base = Self::from_env()?;
+        let mut overlays = paths.iter();
+        let (first, table) = loop {
+            let Some(&path) = overlays.next() else {
+                return Ok(base);
+            };
+            if let Some(table) = read_overlay(path)? {
+                break (path, table);
+            }
+        };
+
+        let mut merged = toml::Table::try_from(&base).expect("config is a table");
+        let mut sources = base.sources;
+        mark_sources(&mut sources, &table, "", FieldSource::File);
+        merge_tables(&mut merged, table);
+        layer_files(merged, sources, first, overlays.as_slice())
+    }
+
+    /// Build a configuration from environment variables.
+    ///
+    /// Anything not set in the environment takes its value from [`Config::try_default()`].
+    /// The variables are:
+    ///
+    /// - `UPKI_CACHE_DIR`: the `cache-dir` setting.
+    /// - `UPKI_FETCH_URL`: the `revocation.fetch-url` setting.
+    /// - `UPKI_NO_SCT_POLICY`: the `revocation.no-sct-policy` setting, for example `use-serial-only`.
+    /// - `UPKI_ALLOW_DOWNGRADE`: the `revocation.allow-downgrade` setting, `true` or `false`.
+    ///
+    /// A variable that is set to an invalid value is an error, rather than being ignored.
+    pub fn from_env() -> Result<Self, Error> {
+        let cache_dir = match env_var("UPKI_CACHE_DIR")? {
+            Some(dir) => PathBuf::from(dir),
+            None => platform::default_cache_dir()?,
+        };
+
+        Ok(Self {
+            cache_dir,
+            read_only: false,
+            revocation: RevocationConfig::from_env()?,
+            sources: ENV_FIELDS
+                .iter()
+                .filter(|(name, _)| env::var_os(name).is_some())
+                .map(|(_, key)| (key.to_string(), FieldSource::Env))
+                .collect(),
         })
     }
 
+    /// Change the setting `key` to `value`, given in TOML syntax.
+    ///
+    /// `key` is qualified by its table, as in `revocation.fetch-url`, so
+    /// `value` for that is a quoted string.  This is for adjusting a configuration
+    /// loaded from elsewhere, for example from command-line flags, and the setting is
+    /// then reported by [`Config::field_sources()`] as [`FieldSource::Builder`].
+    pub fn with_setting(mut self, key: &str, value: &str) -> Result<Self, Error> {
+        let invalid = |error: Box<dyn StdError + Send + Sync>| Error::InvalidSetting {
+            key: key.to_owned(),
+            error,
+        };
+        let value = value
+            .parse::<toml::Value>()
+            .map_err(|error| invalid(Box::new(error)))?;
+
+        let mut table = toml::Table::try_from(&self).expect("config is a table");
+        let mut parent = &mut table;
+        let mut names = key.split('.').peekable();
+        while let Some(name) = names.next() {
+            if names.peek().is_none() {
+                parent.insert(name.to_owned(), value);
+                break;
+            }
+            parent = match parent.get_mut(name) {
+                Some(toml::Value::Table(table)) => table,
+                _ => return Err(invalid("not a setting".into())),
+            };
+        }
+
+        let mut sources = self.sources;
+        sources.insert(key.to_owned(), FieldSource::Builder);
+        self = table
+            .try_into()
+            .map_err(|error| invalid(Box::new(error)))?;
+        self.sources = sources;
+        Ok(self)
+    }
+
+    /// Where each setting came from, by key, sorted by key.
+    ///
+    /// Keys are qualified by their table, as in `revocation.fetch-url`.  Every setting
+    /// with a value is listed, including those at their default, so this explains the
+    /// output of `upki show-config`.  When a setting comes from several places, such as
+    /// the environment and then a file, the last one is reported.
+    pub fn field_sources(&self) -> Vec<(String, FieldSource)> {
+        let table = toml::Table::try_from(self).expect("config is a table");
+        let mut sources = BTreeMap::new();
+        mark_sources(&mut sources, &table, "", FieldSource::Default);
+        for (key, source) in &self.sources {
+            if let Some(entry) = sources.get_mut(key) {
+                *entry = *source;
+            }
+        }
+
+        sources.into_iter().collect()
+    }
+
     /// Return a sensible default configuration.
     pub fn try_default() -> Result<Self, Error> {
         Ok(Self {
             cache_dir: platform::default_cache_dir()?,
+            read_only: false,
             revocation: RevocationConfig::default(),
+            sources: BTreeMap::new(),
         })
     }
 
-    pub(crate) fn revocation_cache_dir(&self) -> PathBuf {
+    /// A SHA256 digest of this configuration's effective settings.
+    ///
+    /// Two configurations have the same digest if they have the same settings,
+    /// however they were built: from files in any field order, layered with
+    /// [`Config::from_files()`], or from defaults and the environment.  The lists
+    /// `revocation.bypass-issuers`, `revocation.approved-logs`,
+    /// `revocation.sct-exempt-issuers` and `revocation.in-scope-issuers` are sets, so
+    /// their order does not matter either.
+    ///
+    /// `revocation.refresh-splay-seed` is excluded, because it is meant to differ
+    /// between hosts that are otherwise configured alike.  The digest may change
+    /// between versions of this crate, for example when settings are added.
+    pub fn digest(&self) -> [u8; 32] {
+        let mut table = toml::Table::try_from(self).expect("config is a table");
+        if let Some(toml::Value::Table(revocation)) = table.get_mut("revocation") {
+            revocation.remove("refresh-splay-seed");
+            for key in [
+                "bypass-issuers",
+                "approved-logs",
+                "sct-exempt-issuers",
+                "in-scope-issuers",
+            ] {
+                if let Some(toml::Value::Array(values)) = revocation.get_mut(key) {
+                    values.sort_by(|a, b| a.as_str().cmp(&b.as_str()));
+                    values.dedup();
+                }
+            }
+        }
+
+        let normalized = toml::to_string(&table).expect("config is a table");
+        digest::digest(&digest::SHA256, normalized.as_bytes())
+            .as_ref()
+            .try_into()
+            .expect("sha256 output must be [u8;32]")
+    }
+
+    /// The directory under which all cache files are stored.
+    pub fn cache_dir(&self) -> &Path {
+        &self.cache_dir
+    }
+
+    /// The directory holding revocation data.
+    ///
+    /// This is `revocation` under [`Config::cache_dir()`].  Its layout is stable, so
+    /// that other tools can back it up, mirror it, or populate it in advance:
+    ///
+    /// - `manifest.json`: the current manifest (see [`Config::manifest_path()`]).
+    /// - The filter files listed in the manifest, each named by its
+    ///   [`Filter::filename`][revocation::Filter::filename].
+    /// - `highest-accepted`: the `generated_at` of the newest manifest accepted,
+    ///   used to refuse downgrades.  This is optional.
+    /// - `validators.json`: HTTP cache validators for the manifest.  This is optional.
+    /// - `epochs`: a directory of the manifests of earlier epochs, kept according to
+    ///   `revocation.retain-epochs` (see [`Config::epoch_manifest_path()`]), whose
+    ///   filters are kept too.  This is optional.
+    /// - `update.lock`: locked by a [`revocation::CacheWriteLock`] while an updater
+    ///   other than [`revocation::fetch()`] changes the cache.  This is optional, and
+    ///   only used on Unix.
+    ///
+    /// A directory containing only `manifest.json` and its filters is complete.
+    pub fn revocation_cache_dir(&self) -> PathBuf {
         self.cache_dir.join("revocation")
     }
+
+    /// The path of the current revocation manifest.
+    pub fn manifest_path(&self) -> PathBuf {
+        self.revocation_cache_dir()
+            .join("manifest.json")
+    }
+
+    /// The path of the manifest retained for `epoch`.
+    ///
+    /// This is `epochs/{epoch}.json` under [`Config::revocation_cache_dir()`].
+    pub fn epoch_manifest_path(&self, epoch: u64) -> PathBuf {
+        self.revocation_cache_dir()
+            .join(EPOCHS_DIR)
+            .join(format!("{epoch}.json"))
+    }
+}
+
+/// Read the environment variable `name`, if it is set.
+pub(crate) fn env_var(name: &'static str) -> Result<Option<String>, Error> {
+    match env::var(name) {
+        Ok(value) => Ok(Some(value)),
+        Err(env::VarError::NotPresent) => Ok(None),
+        Err(env::VarError::NotUnicode(value)) => Err(Error::InvalidEnvironmentVariable {
+            name,
+            value: value.to_string_lossy().into_owned(),
+        }),
+    }
+}
+
+/// Where a setting's value came from, as reported by [`Config::field_sources()`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum FieldSource {
+    /// The setting was not given anywhere, so it has its default value.
+    Default,
+    /// The setting was given in a configuration file.
+    File,
+    /// The setting was given in an environment variable (see [`Config::from_env()`]).
+    Env,
+    /// The setting was given to [`Config::with_setting()`].
+    Builder,
 }
 
+/// The environment variables read by [`Config::from_env()`], and the keys they set.
+const ENV_FIELDS: [(&str, &str); 4] = [
+    ("UPKI_CACHE_DIR", "cache-dir"),
+    ("UPKI_FETCH_URL", "revocation.fetch-url"),
+    ("UPKI_NO_SCT_POLICY", "revocation.no-sct-policy"),
+    ("UPKI_ALLOW_DOWNGRADE", "revocation.allow-downgrade"),
+];
+
 /// How the path to a configuration file was decided upon.
 pub enum ConfigPath {
     /// The path was directly specified by a user.
@@ -136,6 +418,158 @@ mod platform {
     }
 }
 
+/// Read the configuration file at `path`.
+fn read_file(path: &Path) -> Result<String, Error> {
+    fs::read_to_string(path).map_err(|error| Error::FileRead {
+        error,
+        path: path.to_owned(),
+    })
+}
+
+/// Decode `content`, read from the file at `path`, as TOML.
+fn decode<T: DeserializeOwned>(content: &str, path: &Path) -> Result<T, Error> {
+    toml::from_str(content).map_err(|error| {
+        match error
+            .span()
+            .and_then(|span| field_at(content, span.start))
+        {
+            Some((key, line, column)) => Error::ConfigField {
+                key,
+                message: error.message().to_owned(),
+                line,
+                column,
+                path: path.to_owned(),
+            },
+            None => Error::ConfigError {
+                error: Box::new(error),
+                path: path.to_owned(),
+            },
+        }
+    })
+}
+
+/// Decode `content`, read from the file at `path`, as a configuration whose settings
+/// came from that file.
+fn decode_with_sources(content: &str, path: &Path) -> Result<Config, Error> {
+    let mut config = decode::<Config>(content, path)?;
+    mark_sources(
+        &mut config.sources,
+        &decode(content, path)?,
+        "",
+        FieldSource::File,
+    );
+    Ok(config)
+}
+
+/// Read the configuration file at `path` as an overlay, or `None` if it does not exist.
+fn read_overlay(path: &Path) -> Result<Option<toml::Table>, Error> {
+    match read_file(path) {
+        Ok(content) => decode(&content, path).map(Some),
+        Err(Error::FileRead { error, .. }) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+/// Merge the files at `overlays` over `merged`, which with `sources` came from the file
+/// at `base`, and decode the result.
+fn layer_files(
+    mut merged: toml::Table,
+    mut sources: BTreeMap<String, FieldSource>,
+    base: &Path,
+    overlays: &[&Path],
+) -> Result<Config, Error> {
+    let mut last = base;
+    for &path in overlays {
+        let Some(overlay) = read_overlay(path)? else {
+            continue;
+        };
+        mark_sources(&mut sources, &overlay, "", FieldSource::File);
+        merge_tables(&mut merged, overlay);
+        last = path;
+    }
+
+    let mut config: Config = merged
+        .try_into()
+        .map_err(|error| Error::ConfigError {
+            error: Box::new(error),
+            path: last.to_owned(),
+        })?;
+    config.sources = sources;
+    Ok(config)
+}
+
+/// Record `source` in `sources` for each setting in `table`, whose keys are qualified
+/// by `prefix`.
+fn mark_sources(
+    sources: &mut BTreeMap<String, FieldSource>,
+    table: &toml::Table,
+    prefix: &str,
+    source: FieldSource,
+) {
+    for (key, value) in table {
+        let key = format!("{prefix}{key}");
+        match value {
+            toml::Value::Table(table) => mark_sources(sources, table, &format!("{key}."), source),
+            _ => {
+                sources.insert(key, source);
+            }
+        }
+    }
+}
+
+/// Merge `overlay` into `base`, for [`Config::from_files()`].
+///
+/// Tables present in both are merged recursively; any other value in `overlay`
+/// replaces the one in `base`.
+fn merge_tables(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overlay)) => {
+                merge_tables(base, overlay)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Find the key of the `key = value` line in `toml` containing byte `offset`.
+///
+/// Returns the key, qualified by the name of the table it is in, and the 1-based line
+/// and column of `offset`.  Returns `None` if that line is not a key/value pair, for
+/// example because it is a table header or part of a multi-line value.
+fn field_at(toml: &str, offset: usize) -> Option<(String, usize, usize)> {
+    let before = toml.get(..offset)?;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let line = toml[line_start..]
+        .lines()
+        .next()
+        .unwrap_or_default();
+    let (key, _) = line.split_once('=')?;
+    let key = key.trim().trim_matches('"');
+    if key.is_empty() || key.starts_with(['[', '#']) {
+        return None;
+    }
+
+    let table = before[..line_start]
+        .lines()
+        .rev()
+        .map(str::trim)
+        .find(|line| line.starts_with('['))
+        .map(|header| header.trim_matches(['[', ']']).trim());
+    let key = match table {
+        Some(table) => format!("{table}.{key}"),
+        None => key.to_owned(),
+    };
+
+    Some((
+        key,
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    ))
+}
+
 /// Errors for the upki library API.
 #[non_exhaustive]
 #[derive(Debug)]
@@ -147,6 +581,19 @@ pub enum Error {
         /// Path to the configuration file.
         path: PathBuf,
     },
+    /// A field in the configuration file at `path` was unknown or had an invalid value.
+    ConfigField {
+        /// The field's key, qualified by its table: for example `revocation.fetch-url`.
+        key: String,
+        /// Description of the problem.
+        message: String,
+        /// Line number of the problem, starting from 1.
+        line: usize,
+        /// Column number of the problem, in characters and starting from 1.
+        column: usize,
+        /// Path to the configuration file.
+        path: PathBuf,
+    },
     /// Failed to read configuration file at `path`.
     FileRead {
         /// Underlying error.
@@ -154,6 +601,20 @@ pub enum Error {
         /// Path to the configuration file.
         path: PathBuf,
     },
+    /// An environment variable had an invalid value.
+    InvalidEnvironmentVariable {
+        /// Name of the variable.
+        name: &'static str,
+        /// The invalid value.
+        value: String,
+    },
+    /// A setting given to [`Config::with_setting()`] was unknown or had an invalid value.
+    InvalidSetting {
+        /// The setting's key.
+        key: String,
+        /// Underlying error.
+        error: Box<dyn StdError + Send + Sync>,
+    },
     /// No cache directory could be found.
     NoCacheDirectoryFound,
     /// No configuration directory could be found.
@@ -169,7 +630,10 @@ impl StdError for Error {
         match self {
             Self::ConfigError { error, .. } => Some(error.as_ref()),
             Self::FileRead { error, .. } => Some(error),
-            Self::NoCacheDirectoryFound
+            Self::InvalidSetting { error, .. } => Some(error.as_ref()),
+            Self::ConfigField { .. }
+            | Self::InvalidEnvironmentVariable { .. }
+            | Self::NoCacheDirectoryFound
             | Self::NoConfigDirectoryFound
             | Self::NoValidHomeDirectory => None,
             Self::Revocation(err) => Some(err),
@@ -183,9 +647,27 @@ impl fmt::Display for Error {
             Self::ConfigError { path, .. } => {
                 write!(f, "failed to parse config file at {}", path.display())
             }
+            Self::ConfigField {
+                key,
+                message,
+                line,
+                column,
+                path,
+            } => write!(
+                f,
+                "invalid field `{key}` in config file at {}, line {line} column {column}: {message}",
+                path.display()
+            ),
             Self::FileRead { path, .. } => {
                 write!(f, "failed to read config file at {}", path.display())
             }
+            Self::InvalidEnvironmentVariable { name, value } => {
+                write!(
+                    f,
+                    "invalid value for environment variable {name}: '{value}'"
+                )
+            }
+            Self::InvalidSetting { key, .. } => write!(f, "invalid setting `{key}`"),
             Self::NoCacheDirectoryFound => write!(f, "no cache directory could be found"),
             Self::NoConfigDirectoryFound => write!(f, "no configuration directory could be found"),
             Self::NoValidHomeDirectory => write!(f, "could not determine user's home directory"),
@@ -194,8 +676,27 @@ impl fmt::Display for Error {
     }
 }
 
+impl From<revocation::Error> for Error {
+    fn from(err: revocation::Error) -> Self {
+        Self::Revocation(err)
+    }
+}
+
 const PREFIX: &str = "upki";
 const CONFIG_FILE: &str = "config.toml";
+const EPOCHS_DIR: &str = "epochs";
+
+mod archive;
+#[cfg(feature = "test-util")]
+pub mod clock;
+#[cfg(not(feature = "test-util"))]
+mod clock {
+    use std::time::SystemTime;
+
+    pub(crate) fn now() -> SystemTime {
+        SystemTime::now()
+    }
+}
 
 /// Determining revocation status of publicly trusted certificates.
 pub mod revocation;
@@ -30,12 +30,32 @@ async fn main() -> Result<ExitCode, Report> {
         return Ok(ExitCode::SUCCESS);
     }
 
+    if let Command::Diff { older, newer } = &args.command {
+        let diff = Manifest::from_file(older)?.diff(&Manifest::from_file(newer)?);
+        print!("{diff}");
+        return Ok(ExitCode::SUCCESS);
+    }
+
     let config = Config::from_file_or_default(&config_path)?;
 
     Ok(match args.command {
         Command::Fetch { dry_run } => fetch(dry_run, &config).await?,
-        Command::Verify => Manifest::from_config(&config)?.verify(&config)?,
-        Command::ShowConfigPath => unreachable!(),
+        Command::Verify { deep } => {
+            let manifest = Manifest::from_config(&config)?;
+            if manifest.is_empty() {
+                eprintln!("warning: manifest references no filters, so no certificate is covered");
+            }
+            if deep {
+                if let Err(problems) = manifest.verify_integrity(&config) {
+                    for problem in &problems {
+                        eprintln!("problem: {problem}");
+                    }
+                    eyre::bail!("found {} integrity problems", problems.len());
+                }
+            }
+            manifest.verify(&config)?
+        }
+        Command::ShowConfigPath | Command::Diff { .. } => unreachable!(),
         Command::ShowConfig => {
             print!(
                 "{}",
@@ -96,7 +116,23 @@ enum Command {
     /// Exits non-zero if the manifest if any filter file is missing or corrupt.
     ///
     /// This command does no network I/O.  It does not say anything whether the files are up-to-date or recent.
-    Verify,
+    Verify {
+        /// Also decode every filter, and check that neither the manifest nor the
+        /// filters' coverage is dated in the future.  Every problem found is listed.
+        #[arg(long)]
+        deep: bool,
+    },
+
+    /// Compares two manifest files.
+    ///
+    /// This lists the filters that were added and removed between `older` and `newer`.
+    /// This command does no network I/O, and does not read any filter files.
+    Diff {
+        /// Path to the older manifest file.
+        older: PathBuf,
+        /// Path to the newer manifest file.
+        newer: PathBuf,
+    },
 
     /// Checks the revocation status of a certificate.
     #[clap(subcommand)]
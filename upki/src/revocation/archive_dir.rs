@@ -0,0 +1,210 @@
+//! Revocation data shared between processes through a directory of archives.
+
+use std::fs::{self, Permissions};
+use std::io::{self, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+
+use super::{Error, Manifest, SharedManifest};
+use crate::Config;
+use crate::archive::Archive;
+
+/// A [`SharedManifest`] loaded from revocation data published in a directory.
+///
+/// This lets many co-located worker processes check against data that one updater
+/// process maintains, so only the updater needs network access and a cache
+/// directory.  Nothing is mapped or shared in memory: each process reads a segment
+/// and decodes the filters into its own memory, since `clubcard-crlite` cannot check
+/// against the encoded bytes directly.  Placing the directory on a memory-backed
+/// filesystem avoids disk IO when reading.
+///
+/// Workers open a handle with [`ArchiveDirBackend::open()`], check with
+/// [`ArchiveDirBackend::manifest()`], and call [`ArchiveDirBackend::reload_if_changed()`]
+/// periodically.  The updater calls [`ArchiveDirBackend::publish()`] after each
+/// [`fetch()`][super::fetch()].
+///
+/// The updater publishes the cache as segments in a directory, such as one on
+/// `/dev/shm` on Linux.  Each segment is an archive (see [`Manifest::from_archive()`])
+/// named `<name>.<version>`, and a pointer file `<name>.current` holds the version
+/// of the newest one.
+///
+/// The synchronization protocol is:
+///
+/// 1. The updater writes a new segment under a temporary name, and renames it to
+///    `<name>.<version>`, where `version` is one more than the pointer's.  Renaming is
+///    atomic, so a segment is never seen partially written.
+/// 2. The updater replaces the pointer in the same way, so readers see either the old
+///    or the new version.
+/// 3. The updater removes the segment before the previous one.  A reader that read
+///    the pointer just before it was flipped can still open the previous segment.
+/// 4. Readers read the pointer, and then read the segment it names whole.  Once it is
+///    read, its removal does not matter.
+///
+/// There must be only one updater at a time for each name.
+#[derive(Debug)]
+pub struct ArchiveDirBackend {
+    dir: PathBuf,
+    name: String,
+    /// The version of the segment loaded, held while reloading.
+    loaded: Mutex<u64>,
+    manifest: SharedManifest,
+}
+
+impl ArchiveDirBackend {
+    /// Load the newest segment named `name` in `dir`.
+    ///
+    /// This fails with [`Error::ManifestMissing`] if nothing has been published.
+    pub fn open(dir: &Path, name: &str) -> Result<Self, Error> {
+        let version = current_version(dir, name)?
+            .ok_or_else(|| Error::ManifestMissing(pointer_path(dir, name)))?;
+        Ok(Self {
+            dir: dir.to_owned(),
+            name: name.to_owned(),
+            loaded: Mutex::new(version),
+            manifest: SharedManifest::from_archive(&segment_path(dir, name, version))?,
+        })
+    }
+
+    /// Switch to the newest segment, if it is not the one loaded.
+    ///
+    /// Returns whether it switched.  If loading the new segment fails, the current
+    /// manifest stays in use.
+    pub fn reload_if_changed(&self) -> Result<bool, Error> {
+        let mut loaded = self
+            .loaded
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let version = match current_version(&self.dir, &self.name)? {
+            Some(version) if version != *loaded => version,
+            _ => return Ok(false),
+        };
+
+        self.manifest
+            .replace_from_archive(&segment_path(&self.dir, &self.name, version))?;
+        *loaded = version;
+        Ok(true)
+    }
+
+    /// The manifest and filters currently loaded.
+    pub fn manifest(&self) -> &SharedManifest {
+        &self.manifest
+    }
+
+    /// The version of the segment currently loaded.
+    ///
+    /// This counts publications, and is unrelated to [`SharedManifest::version()`].
+    pub fn segment_version(&self) -> u64 {
+        *self
+            .loaded
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Publish the cache described by `config` as a new segment named `name` in `dir`.
+    ///
+    /// This is for the updater process.  The cache is verified first, so a missing
+    /// or corrupt filter is never published.  Returns the version of the new segment.
+    pub fn publish(dir: &Path, name: &str, config: &Config) -> Result<u64, Error> {
+        let manifest = Manifest::from_config(config)?;
+        manifest.verify(config)?;
+
+        let manifest_path = config.manifest_path();
+        let mut members = vec![(
+            "revocation/manifest.json".to_owned(),
+            fs::read(&manifest_path).map_err(|error| Error::ManifestRead {
+                error,
+                path: manifest_path,
+            })?,
+        )];
+        let cache_dir = config.revocation_cache_dir();
+        for filter in &manifest.filters {
+            let path = cache_dir.join(&filter.filename);
+            let bytes = fs::read(&path).map_err(|error| Error::FilterRead {
+                error,
+                path: Some(path),
+            })?;
+            members.push((format!("revocation/{}", filter.filename), bytes));
+        }
+
+        let archive = Archive::encode(
+            members
+                .iter()
+                .map(|(name, bytes)| (name.as_str(), bytes.as_slice())),
+        )
+        .map_err(|error| Error::FileWrite {
+            error,
+            path: dir.to_owned(),
+        })?;
+
+        let previous = current_version(dir, name)?.unwrap_or(0);
+        let version = previous + 1;
+        write_atomically(dir, &segment_path(dir, name, version), &archive)?;
+        write_atomically(
+            dir,
+            &pointer_path(dir, name),
+            version.to_string().as_bytes(),
+        )?;
+
+        // earlier ones were removed by earlier publications
+        if previous > 1 {
+            let path = segment_path(dir, name, previous - 1);
+            match fs::remove_file(&path) {
+                Err(error) if error.kind() != io::ErrorKind::NotFound => {
+                    return Err(Error::RemoveFile { error, path });
+                }
+                _ => {}
+            }
+        }
+
+        Ok(version)
+    }
+}
+
+/// The version in the pointer for `name`, or `None` if nothing has been published.
+fn current_version(dir: &Path, name: &str) -> Result<Option<u64>, Error> {
+    let path = pointer_path(dir, name);
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(Error::FileRead { error, path }),
+    };
+
+    match contents.trim().parse() {
+        Ok(version) => Ok(Some(version)),
+        Err(_) => Err(Error::FileRead {
+            error: io::Error::new(io::ErrorKind::InvalidData, "invalid segment version"),
+            path,
+        }),
+    }
+}
+
+/// Write `contents` to a temporary file in `dir`, and rename it into place at `path`.
+fn write_atomically(dir: &Path, path: &Path, contents: &[u8]) -> Result<(), Error> {
+    let temp = tempfile::Builder::new()
+        .permissions(Permissions::from_mode(0o644))
+        .suffix(".new")
+        .tempfile_in(dir)
+        .and_then(|mut temp| {
+            temp.write_all(contents)?;
+            Ok(temp)
+        })
+        .map_err(|error| Error::FileWrite {
+            error,
+            path: path.to_owned(),
+        })?;
+    temp.persist(path)
+        .map_err(|error| Error::FileWrite {
+            error: error.error,
+            path: path.to_owned(),
+        })?;
+    Ok(())
+}
+
+fn pointer_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{name}.current"))
+}
+
+fn segment_path(dir: &Path, name: &str, version: u64) -> PathBuf {
+    dir.join(format!("{name}.{version}"))
+}
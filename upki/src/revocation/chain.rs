@@ -0,0 +1,122 @@
+//! Several fallbacks, consulted in turn.
+
+use core::error::Error as StdError;
+use core::fmt;
+use core::time::Duration;
+use std::sync::{Arc, mpsc};
+use std::thread;
+
+use tracing::warn;
+
+use super::{CheckOutcome, RevocationCheckInput, RevocationFallback, RevocationStatus};
+
+/// An ordered list of [`RevocationFallback`]s, each given its own timeout.
+///
+/// This is for layering revocation sources behind the revocation data: for example,
+/// a local CRL cache, and then OCSP.  Pass one to [`Manifest::check_with_chain()`],
+/// or anywhere else a [`RevocationFallback`] is accepted.
+///
+/// Steps are consulted in the order they were added, and the first definite answer
+/// is returned.  A step that answers [`RevocationStatus::NotCoveredByRevocationData`],
+/// fails, or does not answer within its timeout is passed over for the next.  If
+/// every step is passed over, the chain answers
+/// [`RevocationStatus::NotCoveredByRevocationData`]; it never fails.
+///
+/// Each step runs on a thread of its own, so that it can be abandoned when it times
+/// out.  An abandoned step keeps running in the background until it returns, and its
+/// answer is then discarded.
+///
+/// [`Manifest::check_with_chain()`]: super::Manifest::check_with_chain()
+#[derive(Clone, Default)]
+pub struct FallbackChain {
+    steps: Vec<Step>,
+}
+
+impl FallbackChain {
+    /// A chain with no steps, which answers
+    /// [`RevocationStatus::NotCoveredByRevocationData`] for everything.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `fallback` as the last step, to be abandoned if it takes longer than `timeout`.
+    pub fn step(
+        mut self,
+        fallback: impl RevocationFallback + Send + Sync + 'static,
+        timeout: Duration,
+    ) -> Self {
+        self.steps.push(Step {
+            fallback: Arc::new(fallback),
+            timeout,
+        });
+        self
+    }
+
+    /// The number of steps.
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Returns true if there are no steps.
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+}
+
+impl RevocationFallback for FallbackChain {
+    fn check(
+        &self,
+        input: &RevocationCheckInput,
+        outcome: &CheckOutcome,
+    ) -> Result<RevocationStatus, Box<dyn StdError + Send + Sync>> {
+        for (index, step) in self.steps.iter().enumerate() {
+            match step.check(input, outcome) {
+                Ok(RevocationStatus::NotCoveredByRevocationData) => {}
+                Ok(status) => return Ok(status),
+                Err(error) => warn!(index, %error, "revocation fallback step passed over"),
+            }
+        }
+
+        Ok(RevocationStatus::NotCoveredByRevocationData)
+    }
+}
+
+impl fmt::Debug for FallbackChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(
+                self.steps
+                    .iter()
+                    .map(|step| step.timeout),
+            )
+            .finish()
+    }
+}
+
+#[derive(Clone)]
+struct Step {
+    fallback: Arc<dyn RevocationFallback + Send + Sync>,
+    timeout: Duration,
+}
+
+impl Step {
+    fn check(
+        &self,
+        input: &RevocationCheckInput,
+        outcome: &CheckOutcome,
+    ) -> Result<RevocationStatus, Box<dyn StdError + Send + Sync>> {
+        let (sender, receiver) = mpsc::channel();
+        let fallback = Arc::clone(&self.fallback);
+        let (input, outcome) = (input.clone(), outcome.clone());
+        thread::Builder::new()
+            .name("upki-fallback".to_owned())
+            .spawn(move || {
+                // the receiver is gone if the step timed out
+                let _ = sender.send(fallback.check(&input, &outcome));
+            })?;
+
+        receiver
+            .recv_timeout(self.timeout)
+            .map_err(|_| format!("no answer within {:?}", self.timeout))?
+    }
+}
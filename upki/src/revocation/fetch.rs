@@ -2,13 +2,15 @@
 //!
 //! This program synchronises a local directory with the crlite files contained on a
 //! remote server.  There is a manifest file that gives the names, sizes and hashes of
-//! all valid files; this is fetched first. Then a plan is formed by comparing this against
+//! all valid files; this is fetched first, conditionally if the server previously sent cache
+//! validators for it. Then a plan is formed by comparing this against
 //! the local filesystem contents. Finally, the plan is executed. If that succeeds
 //! the remote server contents matches the local filesystem.
 
 use core::fmt;
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use core::time::Duration;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::fs::{self, File, Permissions};
 use std::io::{self, Read};
@@ -16,8 +18,12 @@ use std::io::{self, Read};
 use std::os::unix::fs::PermissionsExt;
 use std::path::{Path, PathBuf};
 use std::process::ExitCode;
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use std::time::{Instant, SystemTime};
 
 use aws_lc_rs::digest;
+use reqwest::{StatusCode, header};
+use serde::{Deserialize, Serialize};
 use tracing::{debug, info};
 
 use super::{Error, Filter, Manifest};
@@ -28,7 +34,151 @@ use crate::Config;
 /// `dry_run` means this call fetches the new manifest, but does not fetch any
 /// required files; but the necessary files are printed to stdout.  Therefore
 /// such a call is not completely "dry" -- perhaps "moist".
+///
+/// Updates of the same cache directory are coalesced: while one is in progress in
+/// this process, another waits for it and, if it succeeds, returns the same result
+/// without downloading anything.  If it fails, the waiting update tries again
+/// itself.  Dry runs are never coalesced.
+///
+/// This fails with [`Error::ReadOnly`] if the configuration is read-only.
 pub async fn fetch(dry_run: bool, config: &Config) -> Result<ExitCode, Error> {
+    update(dry_run, config)
+        .await
+        .map(|_| ExitCode::SUCCESS)
+}
+
+/// Like [`fetch()`], but also describe what was downloaded and how long it took.
+pub async fn fetch_with_report(config: &Config) -> Result<UpdateReport, Error> {
+    update(false, config).await
+}
+
+/// Signals an update to stop, from another thread.
+///
+/// Clones share the same signal.  See [`Manifest::update_blocking_with_cancel()`].
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<CancelState>);
+
+impl CancelToken {
+    /// Make a token that has not been signalled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Signal updates using this token to stop.
+    ///
+    /// This cannot be undone: updates started with this token afterwards stop
+    /// immediately.
+    pub fn cancel(&self) {
+        self.0
+            .cancelled
+            .store(true, Ordering::Release);
+        self.0.notify.notify_waiters();
+    }
+
+    /// Whether [`CancelToken::cancel()`] has been called.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::Acquire)
+    }
+
+    /// Wait until [`CancelToken::cancel()`] is called.
+    pub(super) async fn cancelled(&self) {
+        let notified = self.0.notify.notified();
+        if !self.is_cancelled() {
+            notified.await;
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct CancelState {
+    cancelled: AtomicBool,
+    notify: tokio::sync::Notify,
+}
+
+/// What a call to [`fetch_with_report()`] did.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct UpdateReport {
+    /// Whether the server sent a new manifest, rather than saying ours was current.
+    pub manifest_modified: bool,
+
+    /// Total size of the response bodies downloaded, including the manifest.
+    pub bytes_downloaded: usize,
+
+    /// How many filters were downloaded.
+    ///
+    /// If this is every filter in the manifest, the cache was populated from scratch;
+    /// otherwise it was updated incrementally.
+    pub filters_downloaded: usize,
+
+    /// How many filters the manifest lists that were already present in the cache.
+    pub filters_reused: usize,
+
+    /// How long the whole update took.
+    pub duration: Duration,
+
+    /// The manifest's [`Manifest::next_update()`] hint.
+    pub next_update: Option<SystemTime>,
+
+    /// The manifest's [`Manifest::generated_at`].
+    pub generated_at: u64,
+}
+
+async fn update(dry_run: bool, config: &Config) -> Result<UpdateReport, Error> {
+    if config.read_only {
+        return Err(Error::ReadOnly);
+    } else if dry_run {
+        return update_now(true, config).await;
+    }
+
+    let slot = InFlight::for_dir(config.revocation_cache_dir());
+    let seen = slot.finished.load(Ordering::Acquire);
+    let mut last = slot.last.lock().await;
+    match &*last {
+        Some((finished, report)) if *finished > seen => {
+            info!("joined an update that was already in progress");
+            return Ok(report.clone());
+        }
+        _ => {}
+    }
+
+    let result = update_now(false, config).await;
+    let finished = slot
+        .finished
+        .fetch_add(1, Ordering::AcqRel)
+        + 1;
+    *last = result
+        .as_ref()
+        .ok()
+        .map(|report| (finished, report.clone()));
+    result
+}
+
+/// Coordinates the updates of one cache directory within this process.
+#[derive(Default)]
+struct InFlight {
+    /// How many updates have finished, successfully or not.
+    finished: AtomicU64,
+    /// Held for the duration of an update, with the number and result of the last
+    /// one if it succeeded.
+    last: tokio::sync::Mutex<Option<(u64, UpdateReport)>>,
+}
+
+impl InFlight {
+    fn for_dir(cache_dir: PathBuf) -> Arc<Self> {
+        static ALL: OnceLock<Mutex<HashMap<PathBuf, Arc<InFlight>>>> = OnceLock::new();
+        ALL.get_or_init(Mutex::default)
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(cache_dir)
+            .or_default()
+            .clone()
+    }
+}
+
+async fn update_now(dry_run: bool, config: &Config) -> Result<UpdateReport, Error> {
+    let start = Instant::now();
+
     let cache_dir = config.revocation_cache_dir();
     info!(
         "fetching {} into {:?}...",
@@ -51,8 +201,16 @@ pub async fn fetch(dry_run: bool, config: &Config) -> Result<ExitCode, Error> {
             url: manifest_url.clone(),
         })?;
 
-    let response = client
-        .get(&manifest_url)
+    let validators = Validators::load(&cache_dir);
+    let mut request = client.get(&manifest_url);
+    if let Some(etag) = &validators.etag {
+        request = request.header(header::IF_NONE_MATCH, etag);
+    }
+    if let Some(last_modified) = &validators.last_modified {
+        request = request.header(header::IF_MODIFIED_SINCE, last_modified);
+    }
+
+    let response = request
         .send()
         .await
         .map_err(|error| Error::HttpFetch {
@@ -65,17 +223,55 @@ pub async fn fetch(dry_run: bool, config: &Config) -> Result<ExitCode, Error> {
             url: manifest_url.clone(),
         })?;
 
-    let manifest = response
-        .json::<Manifest>()
-        .await
-        .map_err(|error| Error::ManifestDecode {
-            error: Box::new(error),
-            path: None,
-        })?;
+    let (manifest, validators, manifest_bytes) = match response.status() {
+        // our copy of the manifest is current, but may still need its filters repaired
+        StatusCode::NOT_MODIFIED => {
+            info!("manifest not modified");
+            (
+                Manifest::from_file_limited(
+                    &cache_dir.join(MANIFEST_JSON),
+                    config.revocation.max_manifest_bytes,
+                )?,
+                validators,
+                None,
+            )
+        }
+        _ => {
+            let validators = Validators::from_response(&response);
+            let bytes = manifest_body(
+                response,
+                config.revocation.max_manifest_bytes,
+                &manifest_url,
+            )
+            .await?;
+            (Manifest::from_bytes(&bytes)?, validators, Some(bytes.len()))
+        }
+    };
 
     manifest.introduce()?;
-
-    let plan = Plan::construct(&manifest, &config.revocation.fetch_url, &cache_dir)?;
+    manifest.check_downgrade(config)?;
+    manifest.check_pinned_digest(config)?;
+
+    let epochs = RetainedEpochs::new(&manifest, config)?;
+    let plan = Plan::construct(
+        &manifest,
+        &config.revocation.fetch_url,
+        &cache_dir,
+        config
+            .revocation
+            .manifest_temp_dir
+            .as_deref(),
+        &epochs.filters,
+    )?;
+    let mut report = UpdateReport {
+        manifest_modified: manifest_bytes.is_some(),
+        bytes_downloaded: manifest_bytes.unwrap_or(0),
+        filters_downloaded: 0,
+        filters_reused: manifest.filters.len(),
+        duration: Duration::ZERO,
+        next_update: manifest.next_update(),
+        generated_at: manifest.generated_at,
+    };
 
     if dry_run {
         println!(
@@ -86,7 +282,8 @@ pub async fn fetch(dry_run: bool, config: &Config) -> Result<ExitCode, Error> {
         for step in plan.steps {
             println!("- {step}");
         }
-        return Ok(ExitCode::SUCCESS);
+        report.duration = start.elapsed();
+        return Ok(report);
     }
 
     info!(
@@ -96,11 +293,20 @@ pub async fn fetch(dry_run: bool, config: &Config) -> Result<ExitCode, Error> {
     );
 
     for step in plan.steps {
-        step.execute(&client).await?;
+        if let Some(bytes) = step.execute(&client).await? {
+            report.bytes_downloaded += bytes;
+            report.filters_downloaded += 1;
+            report.filters_reused -= 1;
+        }
     }
 
+    validators.save(&cache_dir)?;
+    manifest.record_accepted(config)?;
+    epochs.save(&manifest, config)?;
+
     info!("success");
-    Ok(ExitCode::SUCCESS)
+    report.duration = start.elapsed();
+    Ok(report)
 }
 
 pub(crate) struct Plan {
@@ -113,10 +319,15 @@ impl Plan {
     /// - `manifest` describes the contents of the remote server.
     /// - `remote_url` is the base URL.
     /// - `local` is the path into which files are downloaded.  The caller ensures this exists.
+    /// - `temp_dir` is where the new manifest is written before it replaces the old one,
+    ///   if not `local`.
+    /// - `keep` names files in `local` to leave alone, despite `manifest` not listing them.
     pub(crate) fn construct(
         manifest: &Manifest,
         remote_url: &str,
         local: &Path,
+        temp_dir: Option<&Path>,
+        keep: &HashSet<PathBuf>,
     ) -> Result<Self, Error> {
         let mut steps = Vec::new();
 
@@ -145,6 +356,7 @@ impl Plan {
             steps.push(PlanStep::CreateDir(local.to_owned()));
         }
 
+        unwanted_files.retain(|path| !keep.contains(path));
         for filter in &manifest.filters {
             unwanted_files.remove(Path::new(&filter.filename));
 
@@ -160,6 +372,7 @@ impl Plan {
         steps.push(PlanStep::SaveManifest {
             manifest: manifest.clone(),
             local_dir: local.to_owned(),
+            temp_dir: temp_dir.map(Path::to_owned),
         });
 
         for filename in unwanted_files {
@@ -201,11 +414,14 @@ enum PlanStep {
     SaveManifest {
         manifest: Manifest,
         local_dir: PathBuf,
+        /// Where to write it first, if not `local_dir`.
+        temp_dir: Option<PathBuf>,
     },
 }
 
 impl PlanStep {
-    async fn execute(self, client: &reqwest::Client) -> Result<(), Error> {
+    /// Returns the number of bytes downloaded, for a download step.
+    async fn execute(self, client: &reqwest::Client) -> Result<Option<usize>, Error> {
         match self {
             Self::CreateDir(path) => {
                 fs::create_dir_all(&path).map_err(|error| Error::CreateDirectory { error, path })?
@@ -231,17 +447,14 @@ impl PlanStep {
                         url: remote_url.clone(),
                     })?;
 
-                fs::write(
-                    &local,
-                    response
-                        .bytes()
-                        .await
-                        .map_err(|error| Error::HttpFetch {
-                            error: Box::new(error),
-                            url: remote_url.clone(),
-                        })?,
-                )
-                .map_err(|error| Error::FileWrite {
+                let bytes = response
+                    .bytes()
+                    .await
+                    .map_err(|error| Error::HttpFetch {
+                        error: Box::new(error),
+                        url: remote_url.clone(),
+                    })?;
+                fs::write(&local, &bytes).map_err(|error| Error::FileWrite {
                     error,
                     path: local.clone(),
                 })?;
@@ -258,6 +471,7 @@ impl PlanStep {
                 }
 
                 debug!("download successful");
+                return Ok(Some(bytes.len()));
             }
             Self::Delete(target) => {
                 debug!("deleting unreferenced file {target:?}");
@@ -269,22 +483,14 @@ impl PlanStep {
             Self::SaveManifest {
                 manifest,
                 local_dir,
+                temp_dir,
             } => {
                 debug!("saving manifest");
-                #[cfg(target_family = "unix")]
-                let temp = tempfile::Builder::new()
-                    .permissions(Permissions::from_mode(0o644))
-                    .suffix(".new")
-                    .tempfile_in(&local_dir);
-                #[cfg(not(target_family = "unix"))]
-                let temp = tempfile::Builder::new()
-                    .suffix(".new")
-                    .tempfile_in(&local_dir);
-
-                let mut local_temp = temp.map_err(|error| Error::ManifestWrite {
-                    error,
-                    path: local_dir.clone(),
-                })?;
+                let mut local_temp = manifest_temp_file(
+                    temp_dir
+                        .as_deref()
+                        .unwrap_or(&local_dir),
+                )?;
 
                 serde_json::to_writer(local_temp.as_file_mut(), &manifest).map_err(|error| {
                     Error::ManifestEncode {
@@ -293,17 +499,13 @@ impl PlanStep {
                     }
                 })?;
 
-                let path = local_dir.join(MANIFEST_JSON);
-                local_temp
-                    .persist(&path)
-                    .map_err(|error| Error::ManifestWrite {
-                        error: error.error,
-                        path,
-                    })?;
+                persist_manifest(local_temp, &local_dir, |file, path| {
+                    file.persist(path).map(drop)
+                })?;
             }
         }
 
-        Ok(())
+        Ok(None)
     }
 
     fn download(filter: &Filter, remote_url: &str, local: &Path) -> Self {
@@ -336,6 +538,261 @@ impl fmt::Display for PlanStep {
     }
 }
 
+/// The earlier epochs kept in the cache, according to `retain-epochs`.
+struct RetainedEpochs {
+    /// Filters of retained epochs, which must not be deleted.
+    filters: HashSet<PathBuf>,
+    /// Manifests of epochs no longer retained.
+    pruned: Vec<PathBuf>,
+}
+
+impl RetainedEpochs {
+    /// Decide which epochs to keep once `manifest` is current.
+    ///
+    /// A retained manifest that cannot be loaded is pruned rather than failing the update.
+    fn new(manifest: &Manifest, config: &Config) -> Result<Self, Error> {
+        let dir = &config
+            .revocation_cache_dir()
+            .join(crate::EPOCHS_DIR);
+        let mut stored = Vec::new();
+        if dir.exists() {
+            let read_error = |error| Error::FileRead {
+                error,
+                path: dir.to_owned(),
+            };
+            for entry in fs::read_dir(dir).map_err(read_error)? {
+                let entry = entry.map_err(read_error)?;
+                let name = entry.file_name();
+                if let Some(epoch) = name
+                    .to_str()
+                    .and_then(|name| name.strip_suffix(".json"))
+                    .and_then(|epoch| epoch.parse::<u64>().ok())
+                {
+                    stored.push(epoch);
+                }
+            }
+        }
+
+        let mut epochs = stored.clone();
+        epochs.extend(manifest.epoch);
+        epochs.sort_unstable_by(|a, b| b.cmp(a));
+        epochs.dedup();
+        epochs.truncate(config.revocation.retain_epochs);
+
+        let mut filters = HashSet::new();
+        let mut pruned = Vec::new();
+        for epoch in stored {
+            let path = config.epoch_manifest_path(epoch);
+            if !epochs.contains(&epoch) {
+                pruned.push(path);
+                continue;
+            }
+
+            if Some(epoch) == manifest.epoch {
+                continue;
+            }
+
+            match Manifest::from_file_limited(&path, config.revocation.max_manifest_bytes) {
+                Ok(retained) => filters.extend(
+                    retained
+                        .filters
+                        .iter()
+                        .map(|filter| PathBuf::from(&filter.filename)),
+                ),
+                Err(error) => {
+                    debug!("pruning unreadable epoch manifest {path:?}: {error}");
+                    pruned.push(path);
+                }
+            }
+        }
+
+        Ok(Self { filters, pruned })
+    }
+
+    /// Retain `manifest` as its epoch, and remove the manifests of pruned epochs.
+    fn save(self, manifest: &Manifest, config: &Config) -> Result<(), Error> {
+        if let (Some(epoch), 1..) = (manifest.epoch, config.revocation.retain_epochs) {
+            let path = config.epoch_manifest_path(epoch);
+            let dir = path.parent().unwrap();
+            fs::create_dir_all(dir).map_err(|error| Error::CreateDirectory {
+                error,
+                path: dir.to_owned(),
+            })?;
+            let json = serde_json::to_vec(manifest).map_err(|error| Error::ManifestEncode {
+                error: Box::new(error),
+                path: path.clone(),
+            })?;
+            fs::write(&path, json).map_err(|error| Error::ManifestWrite { error, path })?;
+        }
+
+        for path in self.pruned {
+            debug!("deleting manifest of pruned epoch {path:?}");
+            fs::remove_file(&path).map_err(|error| Error::RemoveFile { error, path })?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Rename `temp` into place as the manifest in `local_dir`, using `rename`.
+///
+/// If `temp` is on another filesystem, so cannot be renamed there, it is copied
+/// alongside the manifest first and that copy is renamed instead.
+fn persist_manifest(
+    temp: tempfile::NamedTempFile,
+    local_dir: &Path,
+    rename: impl Fn(tempfile::NamedTempFile, &Path) -> Result<(), tempfile::PersistError>,
+) -> Result<(), Error> {
+    let path = local_dir.join(MANIFEST_JSON);
+    let Err(error) = rename(temp, &path) else {
+        return Ok(());
+    };
+    if error.error.kind() != io::ErrorKind::CrossesDevices {
+        return Err(Error::ManifestWrite {
+            error: error.error,
+            path,
+        });
+    }
+
+    debug!("copying manifest from {:?}", error.file.path());
+    let mut copy = manifest_temp_file(local_dir)?;
+    error
+        .file
+        .reopen()
+        .and_then(|mut source| io::copy(&mut source, copy.as_file_mut()))
+        .and_then(|_| copy.as_file().sync_all())
+        .map_err(|error| Error::ManifestWrite {
+            error,
+            path: copy.path().to_owned(),
+        })?;
+    rename(copy, &path).map_err(|error| Error::ManifestWrite {
+        error: error.error,
+        path,
+    })
+}
+
+/// Create a temporary file in `dir`, for writing a manifest that will replace `manifest.json`.
+fn manifest_temp_file(dir: &Path) -> Result<tempfile::NamedTempFile, Error> {
+    #[cfg(target_family = "unix")]
+    let temp = tempfile::Builder::new()
+        .permissions(Permissions::from_mode(0o644))
+        .suffix(".new")
+        .tempfile_in(dir);
+    #[cfg(not(target_family = "unix"))]
+    let temp = tempfile::Builder::new()
+        .suffix(".new")
+        .tempfile_in(dir);
+
+    temp.map_err(|error| Error::ManifestWrite {
+        error,
+        path: dir.to_owned(),
+    })
+}
+
+/// Read the manifest from `response`, stopping as soon as it is more than `limit` bytes.
+async fn manifest_body(
+    mut response: reqwest::Response,
+    limit: u64,
+    url: &str,
+) -> Result<Vec<u8>, Error> {
+    if response
+        .content_length()
+        .is_some_and(|len| len > limit)
+    {
+        return Err(Error::ManifestTooLarge { limit });
+    }
+
+    let mut body = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|error| Error::HttpFetch {
+            error: Box::new(error),
+            url: url.to_owned(),
+        })?
+    {
+        if (body.len() + chunk.len()) as u64 > limit {
+            return Err(Error::ManifestTooLarge { limit });
+        }
+        body.extend_from_slice(&chunk);
+    }
+
+    Ok(body)
+}
+
+/// HTTP cache validators for the manifest, as last sent by the server.
+///
+/// These are stored alongside the manifest, and allow the next fetch to be a
+/// conditional request.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct Validators {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    etag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_modified: Option<String>,
+}
+
+impl Validators {
+    /// Load the validators stored in `local`.
+    ///
+    /// These are only meaningful alongside the manifest they were received with,
+    /// so none are returned if there is no local manifest.  Errors are not fatal:
+    /// they just mean the next fetch is unconditional.
+    fn load(local: &Path) -> Self {
+        if !local.join(MANIFEST_JSON).exists() {
+            return Self::default();
+        }
+
+        let bytes = match fs::read(local.join(VALIDATORS_JSON)) {
+            Ok(bytes) => bytes,
+            Err(error) => {
+                debug!("no stored validators: {error}");
+                return Self::default();
+            }
+        };
+
+        serde_json::from_slice(&bytes).unwrap_or_else(|error| {
+            debug!("cannot decode stored validators: {error}");
+            Self::default()
+        })
+    }
+
+    fn from_response(response: &reqwest::Response) -> Self {
+        let value = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_owned)
+        };
+
+        Self {
+            etag: value(header::ETAG),
+            last_modified: value(header::LAST_MODIFIED),
+        }
+    }
+
+    /// Store these validators in `local`, or remove stale ones if there are none.
+    fn save(&self, local: &Path) -> Result<(), Error> {
+        let path = local.join(VALIDATORS_JSON);
+        if self.etag.is_none() && self.last_modified.is_none() {
+            return match fs::remove_file(&path) {
+                Err(error) if error.kind() != io::ErrorKind::NotFound => {
+                    Err(Error::RemoveFile { error, path })
+                }
+                _ => Ok(()),
+            };
+        }
+
+        let json = serde_json::to_vec(self).map_err(|error| Error::ManifestEncode {
+            error: Box::new(error),
+            path: path.clone(),
+        })?;
+        fs::write(&path, json).map_err(|error| Error::FileWrite { error, path })
+    }
+}
+
 fn hash_file(path: &Path) -> Result<digest::Digest, io::Error> {
     let mut file = File::open(path)?;
     let mut hasher = digest::Context::new(&digest::SHA256);
@@ -353,4 +810,55 @@ fn hash_file(path: &Path) -> Result<digest::Digest, io::Error> {
 }
 
 const MANIFEST_JSON: &str = "manifest.json";
+const VALIDATORS_JSON: &str = "validators.json";
 const REQUEST_TIMEOUT: u64 = 30;
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    #[test]
+    fn persist_manifest_across_filesystems() {
+        let local = tempfile::TempDir::new().unwrap();
+        let other = tempfile::TempDir::new().unwrap();
+        let mut temp = manifest_temp_file(other.path()).unwrap();
+        temp.write_all(b"{}").unwrap();
+
+        // renames within `local` work, and anything else acts as if across filesystems
+        let renamed = Mutex::new(Vec::new());
+        persist_manifest(temp, local.path(), |file, path| {
+            renamed
+                .lock()
+                .unwrap()
+                .push(file.path().to_owned());
+            match file.path().parent() == path.parent() {
+                true => file.persist(path).map(drop),
+                false => Err(tempfile::PersistError {
+                    error: io::ErrorKind::CrossesDevices.into(),
+                    file,
+                }),
+            }
+        })
+        .unwrap();
+
+        let renamed = renamed.into_inner().unwrap();
+        assert_eq!(renamed.len(), 2);
+        assert_eq!(renamed[0].parent(), Some(other.path()));
+        assert_eq!(renamed[1].parent(), Some(local.path()));
+        assert_eq!(fs::read(local.path().join(MANIFEST_JSON)).unwrap(), b"{}");
+        assert_eq!(
+            fs::read_dir(local.path())
+                .unwrap()
+                .count(),
+            1
+        );
+        assert_eq!(
+            fs::read_dir(other.path())
+                .unwrap()
+                .count(),
+            0
+        );
+    }
+}
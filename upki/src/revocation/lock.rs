@@ -0,0 +1,99 @@
+//! Advisory locking of the cache, for updaters that cannot replace it atomically.
+
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::path::PathBuf;
+
+use rustix::fs::{FlockOperation, flock};
+
+use super::{Error, Manifest};
+use crate::Config;
+
+/// An exclusive lock on the cache described by a [`Config`], held until dropped.
+///
+/// [`fetch()`][super::fetch()] replaces the manifest atomically, by renaming a
+/// complete file into place, so readers never see a partial update and it does not
+/// take this lock.  Other updaters may not be able to do that: for example, a tool
+/// that syncs the cache from shared storage file by file.  Such an updater should
+/// hold this lock while the cache is inconsistent, and readers that would rather
+/// carry on with the manifest they have than wait can load with
+/// [`Manifest::try_from_config()`].
+///
+/// The lock is an advisory `flock(2)` lock on `update.lock` in
+/// [`Config::revocation_cache_dir()`].  It is released when the lock is dropped, or
+/// when the process holding it exits, so a crashed updater cannot leave the cache
+/// locked.  Processes that do not use it, including [`Manifest::from_config()`], are
+/// not affected.  `flock` locks may not work on some network filesystems.
+///
+/// Readers only take the lock while loading the manifest.  Checks read the filters it
+/// references later, without the lock, so an updater should still write new filters
+/// before the manifest that references them, and not change a filter in place.
+#[derive(Debug)]
+pub struct CacheWriteLock {
+    _file: File,
+}
+
+impl CacheWriteLock {
+    /// Lock the cache described by `config`, waiting for any other holder of the lock,
+    /// or any reader in [`Manifest::try_from_config()`], to finish.
+    ///
+    /// The lock file is created if needed, so the cache directory must exist.
+    pub fn acquire(config: &Config) -> Result<Self, Error> {
+        let path = lock_path(config);
+        let lock = |file: &File| loop {
+            match flock(file, FlockOperation::LockExclusive) {
+                Err(rustix::io::Errno::INTR) => continue,
+                result => break result,
+            }
+        };
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .and_then(|file| {
+                lock(&file)?;
+                Ok(file)
+            })
+            .map_err(|error| Error::FileWrite { error, path })?;
+        Ok(Self { _file: file })
+    }
+}
+
+impl Manifest {
+    /// Like [`Manifest::from_config()`], but returns `Ok(None)` rather than waiting if
+    /// the cache is locked by a [`CacheWriteLock`].
+    ///
+    /// This is for request threads that hold a manifest already, and can keep using it
+    /// and try again later.  A shared lock is held while `manifest.json` is read, so an
+    /// updater cannot start while that is happening.  The lock covers only loading the
+    /// manifest: the filters are read by each check, after it is released.  If nothing
+    /// has ever locked the cache, this is the same as [`Manifest::from_config()`].
+    pub fn try_from_config(config: &Config) -> Result<Option<Self>, Error> {
+        let path = lock_path(config);
+        let _file = match File::open(&path) {
+            Ok(file) => match flock(&file, FlockOperation::NonBlockingLockShared) {
+                Ok(()) => Some(file),
+                Err(rustix::io::Errno::WOULDBLOCK) => return Ok(None),
+                Err(error) => {
+                    return Err(Error::FileRead {
+                        error: error.into(),
+                        path,
+                    });
+                }
+            },
+            Err(error) if error.kind() == io::ErrorKind::NotFound => None,
+            Err(error) => return Err(Error::FileRead { error, path }),
+        };
+
+        Self::from_config(config).map(Some)
+    }
+}
+
+fn lock_path(config: &Config) -> PathBuf {
+    config
+        .revocation_cache_dir()
+        .join(LOCK_FILE)
+}
+
+const LOCK_FILE: &str = "update.lock";
@@ -1,25 +1,98 @@
+use core::cmp::Ordering;
 use core::error::Error as StdError;
-use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::ops::RangeInclusive;
 use core::str::FromStr;
-use std::fs::{self, File};
-use std::io::{self, BufReader};
-use std::path::PathBuf;
+use core::time::Duration;
+use core::{fmt, iter};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, Read, Write};
+use std::path::{Path, PathBuf};
 use std::process::ExitCode;
+#[cfg(feature = "stream")]
+use std::sync::Arc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use std::{fs, io, panic, thread};
 
-use aws_lc_rs::digest;
+use aws_lc_rs::{digest, hmac};
 use base64::Engine;
 use base64::prelude::BASE64_STANDARD;
 use chrono::{DateTime, Utc};
-use clubcard_crlite::{CRLiteClubcard, CRLiteKey, CRLiteStatus};
+use clubcard_crlite::{CRLiteClubcard, CRLiteKey, CRLiteQuery, CRLiteStatus};
+#[cfg(feature = "stream")]
+use futures_util::Stream;
+#[cfg(feature = "stream")]
+use futures_util::stream::FuturesUnordered;
+use hex::FromHex;
+use rustls_pki_types::pem::PemObject;
 use rustls_pki_types::{CertificateDer, TrustAnchor};
-use serde::{Deserialize, Serialize};
-use tracing::info;
+use serde::de::value::StrDeserializer;
+use serde::{Deserialize, Serialize, de};
+use tracing::{info, warn};
+#[cfg(feature = "x509-parser")]
+use x509_parser::certificate::{TbsCertificate, X509Certificate};
+#[cfg(feature = "x509-parser")]
+use x509_parser::extensions::ParsedExtension;
+#[cfg(feature = "x509-parser")]
+use x509_parser::oid_registry::OID_CT_LIST_SCT;
+#[cfg(feature = "x509-parser")]
+use x509_parser::prelude::FromDer;
 
 use crate::Config;
+use crate::archive::Archive;
+
+mod chain;
+pub use chain::FallbackChain;
 
 mod fetch;
 use fetch::Plan;
-pub use fetch::fetch;
+pub use fetch::{CancelToken, UpdateReport, fetch, fetch_with_report};
+
+#[cfg(unix)]
+mod lock;
+#[cfg(unix)]
+pub use lock::CacheWriteLock;
+
+mod refresh;
+pub use refresh::{RefreshHandle, spawn_auto_refresh};
+
+mod set;
+pub use set::{ManifestSet, ManifestSetBuilder, ManifestSource};
+
+mod shared;
+pub use shared::{ManifestVersion, SharedManifest};
+
+#[cfg(all(unix, feature = "archive-dir"))]
+mod archive_dir;
+#[cfg(all(unix, feature = "archive-dir"))]
+pub use archive_dir::ArchiveDirBackend;
+
+mod trace;
+pub use trace::{CheckTrace, ProbeResult, TraceStep};
+
+#[cfg(feature = "test-vectors")]
+mod vectors;
+#[cfg(feature = "test-vectors")]
+pub use vectors::{TestVectorMismatch, TestVectorReport, run_test_vectors};
+
+#[cfg(feature = "builder")]
+mod writer;
+#[cfg(feature = "builder")]
+pub use writer::{Conflict, ManifestWriter};
+
+// Callers share these between threads, for example behind the FFI's pointers or in
+// an `Arc`: adding a field that is not `Send + Sync` must not silently change that.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Manifest>();
+    assert_send_sync::<SharedManifest>();
+    assert_send_sync::<ManifestSet>();
+    assert_send_sync::<FastAllowSet>();
+    assert_send_sync::<RevocationCheckInput>();
+    assert_send_sync::<CheckOutcome>();
+    assert_send_sync::<Config>();
+};
 
 /// The structure contained in a manifest.json
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -34,28 +107,290 @@ pub struct Manifest {
 
     /// List of filter files.
     pub filters: Vec<Filter>,
+
+    /// Where this manifest came from, if recorded by the tool that built it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+
+    /// When the next manifest is expected to be published, if the publisher says.
+    ///
+    /// UNIX timestamp in seconds.  See [`Manifest::next_update()`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_update: Option<u64>,
+
+    /// Which build of the revocation data this is, if the publisher numbers them.
+    ///
+    /// See [`Manifest::epoch()`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub epoch: Option<u64>,
+
+    /// The version of the manifest format, if not the first.
+    ///
+    /// See [`Manifest::format_version()`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format_version: Option<u32>,
+
+    /// Ranges of serial numbers revoked wholesale, if the publisher lists any.
+    ///
+    /// This needs format version 2: see [`Manifest::is_serial_in_revoked_range()`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub revoked_ranges: Vec<RevokedRange>,
 }
 
 impl Manifest {
     /// Load the revocation manifest from the cache directory specified in the configuration.
+    ///
+    /// This fails with [`Error::Downgrade`] if the manifest is older than one previously
+    /// accepted by [`fetch()`], unless the configuration allows downgrades.
+    ///
+    /// This fails with [`Error::ManifestTooLarge`], without reading the whole file, if
+    /// it is larger than the configured `max-manifest-bytes`.
     pub fn from_config(config: &Config) -> Result<Self, Error> {
-        let mut file_name = config.revocation_cache_dir();
-        file_name.push("manifest.json");
+        let manifest = Self::from_file_limited(
+            &config.manifest_path(),
+            config.revocation.max_manifest_bytes,
+        )?;
+        manifest.check_downgrade(config)?;
+        manifest.check_pinned_digest(config)?;
+        Ok(manifest)
+    }
+
+    /// Load the manifest of an earlier `epoch`, retained in the cache directory
+    /// specified in the configuration.
+    ///
+    /// A manifest's epoch is kept by [`fetch()`] when `retain-epochs` is set, along with
+    /// its filters, so that checks can be repeated as that epoch's data would have
+    /// decided them.  This fails with [`Error::ManifestMissing`] if `epoch` was not
+    /// retained.  There is no downgrade check, as an older manifest is what is wanted.
+    pub fn from_config_at_epoch(config: &Config, epoch: u64) -> Result<Self, Error> {
+        Self::from_file_limited(
+            &config.epoch_manifest_path(epoch),
+            config.revocation.max_manifest_bytes,
+        )
+    }
+
+    /// Like [`Manifest::from_config()`], but also report problems that did not prevent
+    /// loading the manifest.
+    pub fn from_config_with_report(config: &Config) -> Result<(Self, LoadReport), Error> {
+        let manifest = Self::from_config(config)?;
+        let mut report = LoadReport::default();
+        let age = crate::clock::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |now| {
+                now.as_secs()
+                    .saturating_sub(manifest.generated_at)
+            });
+        if age >= STALE_MANIFEST_AGE {
+            report
+                .warnings
+                .push(Warning::StaleManifest {
+                    generated_at: manifest.generated_at,
+                });
+        }
+
+        Ok((manifest, report))
+    }
+
+    /// Load the manifest from the cache described by `config`, and make sure it is
+    /// ready to check against, as a server would at startup.
+    ///
+    /// This loads the manifest as [`Manifest::from_config()`] does, and then audits the
+    /// cache with [`Manifest::verify_integrity()`], failing with [`Error::Integrity`] if
+    /// anything is wrong.  That reads and decodes every filter, so they are in the page
+    /// cache for the first checks.  Problems that do not make the data unusable are
+    /// returned alongside the manifest, for the caller to log or expose in a health
+    /// check: see [`OpenOptions`] for what is looked for.  Every problem found is
+    /// returned, so a healthy manifest comes with none.
+    pub fn open(
+        config: &Config,
+        options: OpenOptions,
+    ) -> Result<(Self, Vec<HealthProblem>), Error> {
+        let manifest = Self::from_config(config)?;
+        manifest
+            .verify_integrity(config)
+            .map_err(Error::Integrity)?;
+
+        let mut problems = Vec::new();
+        let age = crate::clock::now()
+            .duration_since(UNIX_EPOCH + Duration::from_secs(manifest.generated_at))
+            .unwrap_or_default();
+        if age > options.max_age {
+            problems.push(HealthProblem::Stale { age });
+        }
+
+        if !options.expected_issuers.is_empty() {
+            let filters = manifest.load_filters_in_scope(config)?;
+            let uncovered = options
+                .expected_issuers
+                .into_iter()
+                .filter(|issuer| {
+                    !filters.filters.iter().any(|filter| {
+                        filter
+                            .index()
+                            .contains_key(issuer.0.as_slice())
+                    })
+                })
+                .collect::<Vec<_>>();
+            if !uncovered.is_empty() {
+                problems.push(HealthProblem::IssuersNotCovered(uncovered));
+            }
+        }
+
+        Ok((manifest, problems))
+    }
+
+    /// Update the cache described by `config`, and then load the new manifest.
+    ///
+    /// This is a synchronous version of [`fetch()`], for programs without an async
+    /// runtime.  The fetch runs on a separate thread with its own single-threaded tokio
+    /// runtime, so this may also be called from within a runtime, although it then
+    /// blocks the calling task.
+    pub fn update_blocking(config: &Config) -> Result<Self, Error> {
+        Self::update_blocking_with_cancel(config, &CancelToken::new())
+    }
+
+    /// Like [`Manifest::update_blocking()`], but stops early if `cancel` is signalled.
+    ///
+    /// The download is abandoned promptly when [`CancelToken::cancel()`] is called,
+    /// even while waiting on a stalled server, and this then fails with
+    /// [`Error::Cancelled`].  As when a [`RefreshHandle`] is shut down mid-fetch, the
+    /// cache stays usable: the manifest is only replaced once all its filters are
+    /// present.
+    pub fn update_blocking_with_cancel(
+        config: &Config,
+        cancel: &CancelToken,
+    ) -> Result<Self, Error> {
+        let fetched = thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    tokio::runtime::Builder::new_current_thread()
+                        .enable_all()
+                        .build()
+                        .map_err(Error::Runtime)?
+                        .block_on(async {
+                            tokio::select! {
+                                result = fetch(false, config) => result,
+                                _ = cancel.cancelled() => Err(Error::Cancelled),
+                            }
+                        })
+                })
+                .join()
+                .unwrap_or_else(|panic| panic::resume_unwind(panic))
+        });
+
+        fetched?;
+        Self::from_config(config)
+    }
+
+    /// Load a revocation manifest from the file at `path`.
+    ///
+    /// This fails with [`Error::ManifestMissing`] if there is no file at `path`.
+    pub fn from_file(path: &Path) -> Result<Self, Error> {
+        Self::from_file_limited(path, u64::MAX)
+    }
 
-        let file = match File::open(&file_name) {
-            Ok(f) => f,
+    /// Like [`Manifest::from_file()`], but fails with [`Error::ManifestTooLarge`] if
+    /// the file is larger than `limit` bytes.
+    ///
+    /// At most `limit` bytes are read, so an oversized file is refused without
+    /// allocating room for all of it.
+    pub(crate) fn from_file_limited(path: &Path, limit: u64) -> Result<Self, Error> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                return Err(Error::ManifestMissing(path.to_owned()));
+            }
             Err(error) => {
                 return Err(Error::ManifestRead {
                     error,
-                    path: file_name,
+                    path: path.to_owned(),
                 });
             }
         };
 
-        serde_json::from_reader(BufReader::new(file)).map_err(|error| Error::ManifestDecode {
-            error: Box::new(error),
-            path: Some(file_name),
-        })
+        let mut bytes = Vec::new();
+        file.take(limit.saturating_add(1))
+            .read_to_end(&mut bytes)
+            .map_err(|error| Error::ManifestRead {
+                error,
+                path: path.to_owned(),
+            })?;
+        if bytes.len() as u64 > limit {
+            return Err(Error::ManifestTooLarge { limit });
+        }
+
+        Self::decode(&bytes, Some(path.to_owned()))
+    }
+
+    /// Load the revocation manifest from `revocation/manifest.json` in the archive at `path`.
+    ///
+    /// This reads only the manifest; [`SharedManifest::from_archive()`] also loads its
+    /// filters from the archive, for checking without a cache directory.
+    pub fn from_archive(path: &Path) -> Result<Self, Error> {
+        Self::from_archived(&read_archive(path)?, path)
+    }
+
+    fn from_archived(archive: &Archive, path: &Path) -> Result<Self, Error> {
+        let member = path.join(ARCHIVED_MANIFEST);
+        match archive.get(ARCHIVED_MANIFEST) {
+            Ok(bytes) => Self::decode(bytes, Some(member)),
+            Err(_) => Err(Error::ManifestMissing(member)),
+        }
+    }
+
+    /// Parse a revocation manifest from its JSON encoding in `bytes`.
+    ///
+    /// `bytes` is treated as untrusted: any malformed input results in an error.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Self::decode(bytes, None)
+    }
+
+    /// Decode a manifest, refusing with [`Error::UnsupportedManifestVersion`] one whose
+    /// format this crate does not understand.
+    ///
+    /// The version is read first, so such a manifest is refused whatever else it
+    /// contains.  If it cannot be read, the manifest is malformed, and decoding it
+    /// fails in the usual way.  A manifest listing [`Manifest::revoked_ranges`] without
+    /// declaring format version 2 is malformed too.
+    fn decode(bytes: &[u8], path: Option<PathBuf>) -> Result<Self, Error> {
+        #[derive(Deserialize)]
+        struct Versioned {
+            #[serde(default)]
+            format_version: Option<u32>,
+        }
+
+        if let Ok(Versioned {
+            format_version: Some(found),
+        }) = serde_json::from_slice(bytes)
+        {
+            if !SUPPORTED_FORMAT_VERSIONS.contains(&found) {
+                return Err(Error::UnsupportedManifestVersion {
+                    found,
+                    supported: SUPPORTED_FORMAT_VERSIONS,
+                });
+            }
+        }
+
+        let manifest: Self =
+            serde_json::from_slice(bytes).map_err(|error| Error::ManifestDecode {
+                offset: byte_offset(bytes, error.line(), error.column()),
+                error: Box::new(error),
+                path: path.clone(),
+            })?;
+
+        // older clients would ignore the ranges, so the publisher must declare them
+        if !manifest.revoked_ranges.is_empty() && manifest.format_version() < 2 {
+            return Err(Error::ManifestDecode {
+                error: Box::new(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "revoked ranges need format version 2",
+                )),
+                path,
+                offset: None,
+            });
+        }
+
+        Ok(manifest)
     }
 
     /// This function does a low-level revocation check.
@@ -70,170 +405,2572 @@ impl Manifest {
         input: &RevocationCheckInput,
         config: &Config,
     ) -> Result<RevocationStatus, Error> {
-        let key = input.key();
-        let cache_dir = config.revocation_cache_dir();
-        for f in &self.filters {
-            let path = cache_dir.join(&f.filename);
-            let bytes = match fs::read(&path) {
-                Ok(bytes) => bytes,
-                Err(error) => {
-                    return Err(Error::FilterRead {
-                        error,
-                        path: Some(path),
-                    });
-                }
-            };
-
-            let filter =
-                CRLiteClubcard::from_bytes(&bytes).map_err(|error| Error::FilterDecode {
-                    error: format!("cannot decode crlite filter: {error:?}").into(),
-                    path,
-                })?;
+        Ok(self
+            .check_detailed(input, config)?
+            .status)
+    }
 
-            match filter.contains(
-                &key,
-                input
-                    .sct_timestamps
-                    .iter()
-                    .map(|ct_ts| (&ct_ts.log_id, ct_ts.timestamp)),
-            ) {
-                CRLiteStatus::Revoked => return Ok(RevocationStatus::CertainlyRevoked),
-                CRLiteStatus::Good => return Ok(RevocationStatus::NotRevoked),
-                CRLiteStatus::NotEnrolled | CRLiteStatus::NotCovered => continue,
-            }
-        }
+    /// Like [`Manifest::check()`], but applies the configured [`UncoveredPolicy`].
+    ///
+    /// Under [`UncoveredPolicy::FailOpen`] or [`UncoveredPolicy::FailClosed`], this
+    /// never returns [`RevocationStatus::NotCoveredByRevocationData`].
+    pub fn check_with_policy(
+        &self,
+        input: &RevocationCheckInput,
+        config: &Config,
+    ) -> Result<RevocationStatus, Error> {
+        Ok(config
+            .revocation
+            .uncovered_policy
+            .apply(self.check(input, config)?))
+    }
 
-        Ok(RevocationStatus::NotCoveredByRevocationData)
+    /// Whether the certificate described by `input` is revoked, as a plain yes or no.
+    ///
+    /// This is `true` when [`Manifest::check_with_policy()`] returns
+    /// [`RevocationStatus::CertainlyRevoked`].  So the answer for a certificate the
+    /// data does not cover depends on the configured [`UncoveredPolicy`]: it is `true`
+    /// under [`UncoveredPolicy::FailClosed`], and `false` otherwise.
+    pub fn is_revoked(&self, input: &RevocationCheckInput, config: &Config) -> Result<bool, Error> {
+        Ok(self.check_with_policy(input, config)? == RevocationStatus::CertainlyRevoked)
     }
 
-    /// Verify the current contents of the cache against this manifest.
+    /// Like [`Manifest::check()`], but asks `fallback` about certificates that the
+    /// revocation data does not cover.
     ///
-    /// This performs disk IO but does not perform network IO.
-    pub fn verify(&self, config: &Config) -> Result<ExitCode, Error> {
-        self.introduce()?;
-        let plan = Plan::construct(self, "https://.../", &config.revocation_cache_dir())?;
-        match plan.download_bytes() {
-            0 => Ok(ExitCode::SUCCESS),
-            bytes => Err(Error::Outdated(bytes)),
-        }
+    /// `fallback` is only consulted when the outcome is
+    /// [`RevocationStatus::NotCoveredByRevocationData`], and its answer is returned
+    /// as-is.  An error from `fallback` is returned as [`Error::Fallback`].
+    pub fn check_with_fallback(
+        &self,
+        input: &RevocationCheckInput,
+        fallback: &dyn RevocationFallback,
+        config: &Config,
+    ) -> Result<RevocationStatus, Error> {
+        Ok(self
+            .check_detailed_with_fallback(input, fallback, config)?
+            .status)
     }
 
-    /// Logs metadata fields in this manifest.
-    pub fn introduce(&self) -> Result<(), Error> {
-        let dt = match DateTime::<Utc>::from_timestamp(self.generated_at as i64, 0) {
-            Some(dt) => dt.to_rfc3339(),
-            None => {
-                return Err(Error::InvalidTimestamp {
-                    input: self.generated_at.to_string(),
-                    context: "manifest generated (in s)",
-                });
-            }
-        };
+    /// Like [`Manifest::check_with_fallback()`], but returns how the status was
+    /// determined as well.
+    ///
+    /// When `fallback` decides the status, the outcome's [`CheckOutcome::source`] is
+    /// [`DecisionSource::Fallback`].  When it cannot either, the outcome is the one from
+    /// the revocation data.
+    pub fn check_detailed_with_fallback(
+        &self,
+        input: &RevocationCheckInput,
+        fallback: &dyn RevocationFallback,
+        config: &Config,
+    ) -> Result<CheckOutcome, Error> {
+        let outcome = self.check_detailed(input, config)?;
+        if outcome.status != RevocationStatus::NotCoveredByRevocationData {
+            return Ok(outcome);
+        }
 
-        info!(comment = self.comment, date = dt, "parsed manifest");
-        Ok(())
+        match fallback
+            .check(input, &outcome)
+            .map_err(Error::Fallback)?
+        {
+            RevocationStatus::NotCoveredByRevocationData => Ok(outcome),
+            status => Ok(CheckOutcome {
+                status,
+                not_covered_reason: None,
+                source: DecisionSource::Fallback,
+                ..outcome
+            }),
+        }
     }
-}
 
-/// Manifest data for a single crlite filter file.
-#[derive(Clone, Debug, Deserialize, Serialize)]
-pub struct Filter {
-    /// Relative filename.
+    /// Like [`Manifest::check_detailed_with_fallback()`], but asks each step of `chain`
+    /// in turn about certificates that the revocation data does not cover.
     ///
-    /// This is also the suggested local filename.
-    pub filename: String,
+    /// The first definite answer is returned, with [`DecisionSource::Fallback`].  Steps
+    /// that fail or time out are passed over, so unlike a single fallback, this does
+    /// not fail with [`Error::Fallback`]: if no step answers, the outcome is the one
+    /// from the revocation data.
+    pub fn check_with_chain(
+        &self,
+        input: &RevocationCheckInput,
+        chain: &FallbackChain,
+        config: &Config,
+    ) -> Result<CheckOutcome, Error> {
+        self.check_detailed_with_fallback(input, chain, config)
+    }
 
-    /// File size, indicative.  Allows a fetcher to predict data usage.
-    pub size: usize,
+    /// Check `serial` under each of several candidate issuers.
+    ///
+    /// A cross-signed CA has several keys, and which of them issued a certificate
+    /// depends on the chain built.  This loads the filters once, and checks `serial`
+    /// and `scts` under each of `issuer_hashes`.  The result is
+    /// [`RevocationStatus::CertainlyRevoked`] if the certificate is revoked under any
+    /// candidate, or [`RevocationStatus::NotRevoked`] if any candidate covers it.
+    /// Otherwise (including when `issuer_hashes` is empty) it is
+    /// [`RevocationStatus::NotCoveredByRevocationData`].
+    pub fn check_any_issuer(
+        &self,
+        serial: &CertSerial,
+        issuer_hashes: &[IssuerSpkiHash],
+        scts: &[CtTimestamp],
+        config: &Config,
+    ) -> Result<RevocationStatus, Error> {
+        config
+            .revocation
+            .require_canonical(serial)?;
+        let filters = self.load_filters_in_scope(config)?;
+        let mut status = RevocationStatus::NotCoveredByRevocationData;
+        for issuer_spki_hash in issuer_hashes {
+            let input = RevocationCheckInput {
+                cert_serial: serial.clone(),
+                issuer_spki_hash: issuer_spki_hash.clone(),
+                sct_timestamps: scts.to_vec(),
+            };
 
-    /// SHA256 hash of file contents.
-    #[serde(with = "hex::serde")]
-    pub hash: Vec<u8>,
-}
+            status = match self
+                .check_loaded(&filters, &input, config)
+                .status
+            {
+                RevocationStatus::CertainlyRevoked => {
+                    return Ok(RevocationStatus::CertainlyRevoked);
+                }
+                RevocationStatus::NotRevoked => RevocationStatus::NotRevoked,
+                RevocationStatus::NotCoveredByRevocationData => status,
+            };
+        }
 
-/// Input parameters for a revocation check.
-#[derive(Debug)]
-pub struct RevocationCheckInput {
-    /// Big-endian bytes encoding of the end-entity certificate serial number.
-    pub cert_serial: CertSerial,
-    /// SHA256 hash of the `SubjectPublicKeyInfo` of the issuer of the end-entity certificate.
-    pub issuer_spki_hash: IssuerSpkiHash,
-    /// CT log IDs and inclusion timestamps present in the end-entity certificate.
-    pub sct_timestamps: Vec<CtTimestamp>,
-}
+        Ok(status)
+    }
 
-impl RevocationCheckInput {
-    /// Construct a `RevocationCheckInput` from a sequence of DER-encoded certificates.
-    ///
-    /// The first element in `certificates` **must** be the end-entity certificate.  The
-    /// end-entity certificate's issuer **must** be present in the other certificates
-    /// (but does not need to be in any specific position).
+    /// Like [`Manifest::check()`], but only if this is the manifest `generation`.
     ///
-    /// Note this interface **only** checks the end-entity certificate for revocation.  It does
-    /// **not** check any of the certificates for validity: it assumes the caller has done any
-    /// required checks before calling this interface (path building, naming validation,
-    /// expiry checking, etc.).
-    pub fn from_certificates(certificates: &[CertificateDer<'_>]) -> Result<Self, Error> {
-        let (end_entity, rest) = certificates
-            .split_first()
-            .ok_or(Error::TooFewCertificates)?;
-        let end_entity = webpki::EndEntityCert::try_from(end_entity)
-            .map_err(|error| Error::InvalidEndEntityCertificate(Box::new(error)))?;
+    /// A caller that pins a generation per request, such as one obtained from
+    /// [`Manifest::version()`] when the request began, can use this to ensure its
+    /// decisions all come from that data.  If this manifest has a different version,
+    /// this fails with [`Error::GenerationUnavailable`] without checking anything.
+    pub fn check_at_generation(
+        &self,
+        input: &RevocationCheckInput,
+        generation: ManifestVersion,
+        config: &Config,
+    ) -> Result<RevocationStatus, Error> {
+        self.require_generation(generation)?;
+        self.check(input, config)
+    }
 
-        let issuer = find_issuer(end_entity.issuer(), rest.iter())?;
-        let issuer_spki_hash = IssuerSpkiHash(
-            digest::digest(&digest::SHA256, &webpki::spki_for_anchor(&issuer))
-                .as_ref()
-                .try_into()
-                .expect("sha256 output must be [u8;32]"),
-        );
+    fn require_generation(&self, requested: ManifestVersion) -> Result<(), Error> {
+        match self.version() {
+            loaded if loaded == requested => Ok(()),
+            loaded => Err(Error::GenerationUnavailable { requested, loaded }),
+        }
+    }
 
-        let mut sct_timestamps = vec![];
-        let iter = end_entity
-            .sct_log_timestamps()
-            .map_err(|e| Error::InvalidEndEntityCertificate(Box::new(e)))?;
+    /// Like [`Manifest::check()`], but returns how the status was determined as well.
+    pub fn check_detailed(
+        &self,
+        input: &RevocationCheckInput,
+        config: &Config,
+    ) -> Result<CheckOutcome, Error> {
+        config
+            .revocation
+            .require_canonical(&input.cert_serial)?;
+        let filters = self.load_filters_in_scope(config)?;
+        Ok(self.check_loaded(&filters, input, config))
+    }
 
-        for ts in iter {
-            let ts = ts.map_err(|e| Error::InvalidSctInCertificate(Box::new(e)))?;
-            sct_timestamps.push(CtTimestamp {
-                log_id: ts.log_id,
-                timestamp: ts.timestamp_ms,
-            });
-        }
+    /// Like [`Manifest::check_detailed()`], but evaluated as if the current time were
+    /// `now`.
+    ///
+    /// This is for reproducing a historical decision: SCTs are judged future-dated,
+    /// and coverage stale, relative to `now`, and the outcome's
+    /// [`CheckOutcome::evaluated_at`] is `now`.  The revocation data is still this
+    /// manifest's.
+    pub fn check_at(
+        &self,
+        input: &RevocationCheckInput,
+        now: SystemTime,
+        config: &Config,
+    ) -> Result<CheckOutcome, Error> {
+        config
+            .revocation
+            .require_canonical(&input.cert_serial)?;
+        let filters = self.load_filters_in_scope(config)?;
+        Ok(self.check_loaded_at(&filters, input, now, config))
+    }
 
-        Ok(Self {
-            cert_serial: CertSerial(end_entity.serial().into()),
-            issuer_spki_hash,
-            sct_timestamps,
-        })
+    /// Like [`Manifest::check_detailed()`], but treats a failure to check as the
+    /// certificate not being covered.
+    ///
+    /// Where [`Manifest::check_detailed()`] would fail, for example because a filter
+    /// is corrupt or cannot be read, this returns
+    /// [`RevocationStatus::NotCoveredByRevocationData`] with the reason
+    /// [`NotCoveredReason::CheckError`], and logs the error.  Otherwise the outcome is
+    /// the same, so a certificate that can be found revoked still is.
+    ///
+    /// This trades strictness for availability: a broken cache makes every
+    /// certificate uncovered, rather than every check fail.  Callers using it should
+    /// monitor for [`NotCoveredReason::CheckError`], which needs an operator to repair
+    /// the cache.
+    pub fn check_lenient(&self, input: &RevocationCheckInput, config: &Config) -> CheckOutcome {
+        self.check_detailed(input, config)
+            .unwrap_or_else(|error| {
+                warn!("revocation check failed, treating as not covered: {error}");
+                self.outcome(
+                    RevocationStatus::NotCoveredByRevocationData,
+                    Some(NotCoveredReason::CheckError),
+                    MatchKind::Exact,
+                )
+            })
     }
 
-    fn key(&self) -> CRLiteKey<'_> {
-        CRLiteKey::new(&self.issuer_spki_hash.0, &self.cert_serial.0)
+    /// Like [`Manifest::check_detailed()`], but also records each step of the check.
+    ///
+    /// This is for support tooling and tests: it does extra work to describe the
+    /// issuer lookup, bypass check, each SCT and filter query, and the coverage
+    /// decision.  The outcome is the same as [`Manifest::check_detailed()`] returns.
+    pub fn check_trace(
+        &self,
+        input: &RevocationCheckInput,
+        config: &Config,
+    ) -> Result<CheckTrace, Error> {
+        config
+            .revocation
+            .require_canonical(&input.cert_serial)?;
+        let filters = self.load_filters_in_scope(config)?;
+        Ok(CheckTrace::new(self, &filters, input, config))
     }
-}
 
-/// A certificate serial number.
-#[derive(Clone, Debug)]
-pub struct CertSerial(pub Vec<u8>);
+    /// Check many inputs, loading the filters only once.
+    ///
+    /// The returned statuses correspond to `inputs`, in the same order.
+    pub fn check_batch(
+        &self,
+        inputs: &[RevocationCheckInput],
+        config: &Config,
+    ) -> Result<Vec<RevocationStatus>, Error> {
+        config
+            .revocation
+            .require_all_canonical(inputs)?;
+        let filters = self.load_filters_in_scope(config)?;
+        Ok(inputs
+            .iter()
+            .map(|input| {
+                self.check_loaded(&filters, input, config)
+                    .status
+            })
+            .collect())
+    }
 
-impl FromStr for CertSerial {
-    type Err = Error;
+    /// Like [`Manifest::check_batch()`], but yields each status as an async stream, as
+    /// soon as it is determined.
+    ///
+    /// Each input is checked on tokio's blocking thread pool, so this must be polled
+    /// within a tokio runtime.  Statuses may arrive in any order, so each comes with the
+    /// index of its input in `inputs`.  The filters are loaded before this returns, and
+    /// this fails if they cannot be.
+    #[cfg(feature = "stream")]
+    pub fn check_batch_stream(
+        &self,
+        inputs: Vec<RevocationCheckInput>,
+        config: &Config,
+    ) -> Result<impl Stream<Item = (usize, RevocationStatus)> + Send + 'static, Error> {
+        config
+            .revocation
+            .require_all_canonical(&inputs)?;
+        let shared = Arc::new((
+            self.clone(),
+            self.load_filters_in_scope(config)?,
+            config.clone(),
+        ));
+        Ok(inputs
+            .into_iter()
+            .enumerate()
+            .map(|(index, input)| {
+                let shared = shared.clone();
+                async move {
+                    let status = tokio::task::spawn_blocking(move || {
+                        let (manifest, filters, config) = &*shared;
+                        manifest
+                            .check_loaded(filters, &input, config)
+                            .status
+                    })
+                    .await
+                    .unwrap_or_else(|error| panic::resume_unwind(error.into_panic()));
+                    (index, status)
+                }
+            })
+            .collect::<FuturesUnordered<_>>())
+    }
 
-    fn from_str(value: &str) -> Result<Self, Self::Err> {
-        match BASE64_STANDARD.decode(value) {
-            Ok(bytes) => Ok(Self(bytes)),
-            Err(e) => Err(Error::InvalidBase64 {
-                error: Box::new(e),
-                context: "certificate serial",
-            }),
+    /// Like [`Manifest::check_batch()`], but separates out the inputs that are not covered.
+    ///
+    /// Inputs that are [`RevocationStatus::NotCoveredByRevocationData`] are listed in
+    /// [`BatchPartition::uncovered`], so that a caller can consult another revocation
+    /// mechanism, such as OCSP, for all of them at once.
+    pub fn check_batch_collect_uncovered(
+        &self,
+        inputs: &[RevocationCheckInput],
+        config: &Config,
+    ) -> Result<BatchPartition, Error> {
+        let mut decided = Vec::with_capacity(inputs.len());
+        let mut uncovered = Vec::new();
+        for (i, status) in self
+            .check_batch(inputs, config)?
+            .into_iter()
+            .enumerate()
+        {
+            match status {
+                RevocationStatus::NotCoveredByRevocationData => uncovered.push(i),
+                status => decided.push((i, status)),
+            }
         }
+
+        Ok(BatchPartition { decided, uncovered })
     }
-}
 
-/// The SHA256 hash of a `SubjectPublicKeyInfoDer` belonging to a certificate's issuer.
-#[derive(Clone, Debug)]
-pub struct IssuerSpkiHash(pub [u8; 32]);
+    /// Check many inputs, and count how many had each status.
+    ///
+    /// This is for reporting on a whole set of certificates, such as an inventory of
+    /// a deployment: uncovered inputs are counted by their [`NotCoveredReason`].  The
+    /// report records the manifest version used.  This fails as
+    /// [`Manifest::check_batch()`] does.
+    pub fn coverage_report(
+        &self,
+        inputs: &[RevocationCheckInput],
+        config: &Config,
+    ) -> Result<CoverageReport, Error> {
+        config
+            .revocation
+            .require_all_canonical(inputs)?;
+        let filters = self.load_filters_in_scope(config)?;
+
+        let mut report = CoverageReport {
+            version: self.version(),
+            total: inputs.len(),
+            revoked: 0,
+            not_revoked: 0,
+            not_covered: HashMap::new(),
+        };
+        for input in inputs {
+            let outcome = self.check_loaded(&filters, input, config);
+            match (outcome.status, outcome.not_covered_reason) {
+                (RevocationStatus::CertainlyRevoked, _) => report.revoked += 1,
+                (RevocationStatus::NotRevoked, _) => report.not_revoked += 1,
+                (RevocationStatus::NotCoveredByRevocationData, reason) => {
+                    *report
+                        .not_covered
+                        .entry(reason)
+                        .or_default() += 1;
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Like [`Manifest::check_batch()`], but stops checking inputs once `deadline` passes.
+    ///
+    /// The deadline is tested before each input is checked.  Most of the work done
+    /// here is loading the filters, which happens once before any inputs are
+    /// checked: so individual checks are very unlikely to be what exceeds the deadline.
+    pub fn check_batch_deadline(
+        &self,
+        inputs: &[RevocationCheckInput],
+        config: &Config,
+        deadline: Instant,
+    ) -> Result<BatchResult, Error> {
+        config
+            .revocation
+            .require_all_canonical(inputs)?;
+        let filters = self.load_filters_in_scope(config)?;
+        let mut statuses = Vec::with_capacity(inputs.len());
+        for input in inputs {
+            if Instant::now() >= deadline {
+                break;
+            }
+
+            statuses.push(
+                self.check_loaded(&filters, input, config)
+                    .status,
+            );
+        }
+
+        Ok(BatchResult {
+            remaining: inputs.len() - statuses.len(),
+            statuses,
+        })
+    }
+
+    /// Check each of `inputs` as it is produced, returning an iterator of outcomes.
+    ///
+    /// The filters are loaded once, before this returns; inputs are then pulled from
+    /// `inputs` only as the returned iterator is advanced, so memory use does not
+    /// depend on how many there are.  An input that is an error produces that error
+    /// in its place, and checking continues: so this accepts the output of
+    /// [`RevocationCheckInput::from_pem_bundle()`] directly.  Likewise, an input whose
+    /// serial number `strict-input` rejects produces [`Error::NonCanonicalSerial`],
+    /// with the input's index.
+    pub fn check_iter<'a, I, E>(
+        &'a self,
+        inputs: I,
+        config: &'a Config,
+    ) -> Result<impl Iterator<Item = Result<CheckOutcome, E>> + 'a, Error>
+    where
+        I: IntoIterator<Item = Result<RevocationCheckInput, E>>,
+        I::IntoIter: 'a,
+        E: From<Error>,
+    {
+        let filters = self.load_filters_in_scope(config)?;
+        Ok(inputs
+            .into_iter()
+            .enumerate()
+            .map(move |(index, input)| {
+                let input = input?;
+                config
+                    .revocation
+                    .require_canonical_at(&input.cert_serial, Some(index))?;
+                Ok(self.check_loaded(&filters, &input, config))
+            }))
+    }
+
+    /// Like [`Manifest::check()`], but first consults `fast_allow`.
+    ///
+    /// If `fast_allow` was built for this manifest (that is, from a manifest with the
+    /// same [`Manifest::generated_at`]) and contains the input's issuer and serial,
+    /// this returns [`RevocationStatus::NotRevoked`] without loading any filters.
+    /// Otherwise it does a normal check: so a set built for an older manifest never
+    /// hides a revocation added since.
+    pub fn check_with_fast_path(
+        &self,
+        input: &RevocationCheckInput,
+        fast_allow: &FastAllowSet,
+        config: &Config,
+    ) -> Result<RevocationStatus, Error> {
+        Ok(self
+            .check_detailed_with_fast_path(input, fast_allow, config)?
+            .status)
+    }
+
+    /// Like [`Manifest::check_with_fast_path()`], but returns how the status was
+    /// determined as well.
+    ///
+    /// A certificate found in `fast_allow` has the [`CheckOutcome::source`]
+    /// [`DecisionSource::Allowlist`].
+    pub fn check_detailed_with_fast_path(
+        &self,
+        input: &RevocationCheckInput,
+        fast_allow: &FastAllowSet,
+        config: &Config,
+    ) -> Result<CheckOutcome, Error> {
+        config
+            .revocation
+            .require_canonical(&input.cert_serial)?;
+        if fast_allow.generated_at == self.generated_at
+            && fast_allow.entries.contains(&(
+                input.issuer_spki_hash.0,
+                input.cert_serial.canonical().to_vec(),
+            ))
+        {
+            return Ok(CheckOutcome {
+                source: DecisionSource::Allowlist,
+                ..self.outcome(RevocationStatus::NotRevoked, None, MatchKind::Exact)
+            });
+        }
+
+        self.check_detailed(input, config)
+    }
+
+    /// Check each line of `input`, writing a result record to `output` for each.
+    ///
+    /// Each line of `input` holds whitespace-separated fields: the issuer SPKI hash and
+    /// the certificate serial, both in hex, and then any number of SCTs in the encoding
+    /// of [`CtTimestamp`].  Blank lines are skipped.  Lines are read, checked
+    /// and reported one at a time, so memory use does not depend on the size of
+    /// `input`.  The filters are loaded once, before any line is read.
+    ///
+    /// A line that cannot be parsed produces an error record, and checking continues.
+    /// Records identify lines by their number, starting from 1.  This fails only if
+    /// the filters cannot be loaded, or `input` or `output` fail.
+    pub fn check_stream<R: BufRead, W: Write>(
+        &self,
+        mut input: R,
+        mut output: W,
+        format: StreamFormat,
+        config: &Config,
+    ) -> Result<(), Error> {
+        let filters = self.load_filters_in_scope(config)?;
+        if format == StreamFormat::Csv {
+            writeln!(output, "line,status,error").map_err(Error::StreamIo)?;
+        }
+
+        let mut line = String::new();
+        let mut number = 0;
+        loop {
+            line.clear();
+            if input
+                .read_line(&mut line)
+                .map_err(Error::StreamIo)?
+                == 0
+            {
+                break;
+            }
+
+            number += 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let result = RevocationCheckInput::from_hex_line(&line).and_then(|input| {
+                config
+                    .revocation
+                    .require_canonical(&input.cert_serial)?;
+                Ok(self
+                    .check_loaded(&filters, &input, config)
+                    .status)
+            });
+            match (format, result) {
+                (StreamFormat::Csv, Ok(status)) => writeln!(output, "{number},{status:?},"),
+                (StreamFormat::Csv, Err(error)) => {
+                    let error = error.to_string().replace('"', "\"\"");
+                    writeln!(output, "{number},,\"{error}\"")
+                }
+                (StreamFormat::JsonLines, result) => {
+                    let record = match result {
+                        Ok(status) => StreamRecord {
+                            line: number,
+                            status: Some(format!("{status:?}")),
+                            error: None,
+                        },
+                        Err(error) => StreamRecord {
+                            line: number,
+                            status: None,
+                            error: Some(error.to_string()),
+                        },
+                    };
+                    serde_json::to_writer(&mut output, &record)
+                        .map_err(|error| Error::StreamIo(error.into()))?;
+                    writeln!(output)
+                }
+            }
+            .map_err(Error::StreamIo)?;
+        }
+
+        output.flush().map_err(Error::StreamIo)
+    }
+
+    /// Whether `scts` place a certificate within the coverage of the revocation data.
+    ///
+    /// This needs only the SCTs, not the certificate's issuer or serial, so it can be
+    /// used as a cheap pre-check: if the result is not [`SctCoverage::sufficient`],
+    /// [`Manifest::check()`] cannot return a definitive status for the certificate.
+    /// No revocation lookup is done.
+    ///
+    /// SCTs with a timestamp after `now`, SCTs from logs not in the configured
+    /// `approved-logs`, and SCTs outside their log's `log-windows`, are not counted as
+    /// covered.
+    pub fn sct_coverage(
+        &self,
+        scts: &[CtTimestamp],
+        config: &Config,
+        now: SystemTime,
+    ) -> Result<SctCoverage, Error> {
+        let (current, future) = scts
+            .iter()
+            .partition::<Vec<_>, _>(|sct| {
+                sct.to_system_time()
+                    .is_some_and(|ts| ts <= now)
+            });
+
+        // coverage is tested before the issuer is looked up, so any key will do
+        let key = CRLiteKey::new(&[0; 32], &[]);
+        let covered = self
+            .load_filters_in_scope(config)?
+            .filters
+            .iter()
+            .map(|filter| {
+                current
+                    .iter()
+                    .filter(|sct| {
+                        config
+                            .revocation
+                            .approves_log(&sct.log_id)
+                            && config.revocation.qualifies(sct)
+                            && filter.contains(&key, iter::once((&sct.log_id.0, sct.timestamp)))
+                                != CRLiteStatus::NotCovered
+                    })
+                    .count()
+            })
+            .max()
+            .unwrap_or(0);
+
+        Ok(SctCoverage {
+            covered,
+            future: future.len(),
+            sufficient: covered
+                >= config
+                    .revocation
+                    .min_covered_scts
+                    .max(1),
+        })
+    }
+
+    fn load_filters(&self, dir: &Path) -> Result<LoadedFilters, Error> {
+        LoadedFilters::new(
+            self.filters
+                .iter()
+                .map(|filter| filter.load_with_bytes(dir)),
+        )
+    }
+
+    /// Like [`Manifest::load_filters()`], from the cache directory in `config`, but
+    /// skipping filters that only have data for issuers outside `in-scope-issuers`.
+    ///
+    /// Only filters listing their [`Filter::issuers`] can be skipped, and they are not
+    /// read at all.
+    fn load_filters_in_scope(&self, config: &Config) -> Result<LoadedFilters, Error> {
+        let dir = config.revocation_cache_dir();
+        LoadedFilters::new(
+            self.filters
+                .iter()
+                .filter(|filter| filter.in_scope(config))
+                .map(|filter| filter.load_with_bytes(&dir)),
+        )
+    }
+
+    /// Like [`Manifest::load_filters()`], but from `archive`, which was read from `path`.
+    ///
+    /// Filters are checked against their hashes, as there is no cache to verify first.
+    fn load_archived_filters(
+        &self,
+        archive: &Archive,
+        path: &Path,
+    ) -> Result<LoadedFilters, Error> {
+        LoadedFilters::new(self.filters.iter().map(|filter| {
+            let name = format!("revocation/{}", filter.filename);
+            let member = path.join(&name);
+            let bytes = archive
+                .get(&name)
+                .map_err(|error| Error::FilterRead {
+                    error,
+                    path: Some(member.clone()),
+                })?;
+            if digest::digest(&digest::SHA256, bytes).as_ref() != filter.hash {
+                return Err(Error::HashMismatch(member));
+            }
+
+            Ok((filter.decode(bytes, member)?, bytes))
+        }))
+    }
+
+    fn check_loaded(
+        &self,
+        filters: &LoadedFilters,
+        input: &RevocationCheckInput,
+        config: &Config,
+    ) -> CheckOutcome {
+        self.check_loaded_at(filters, input, crate::clock::now(), config)
+    }
+
+    /// Like [`Manifest::check_loaded()`], but evaluated at `now`.
+    fn check_loaded_at(
+        &self,
+        filters: &LoadedFilters,
+        input: &RevocationCheckInput,
+        now: SystemTime,
+        config: &Config,
+    ) -> CheckOutcome {
+        let (resolved, warnings) = config
+            .revocation
+            .duplicate_sct_policy
+            .resolve(&input.sct_timestamps);
+        let mut rejected_scts = Vec::new();
+        if let (true, Some(resolved)) = (config.revocation.report_rejected_scts, &resolved) {
+            let mut kept = resolved.iter().collect::<Vec<_>>();
+            for sct in &input.sct_timestamps {
+                match kept.iter().position(|k| *k == sct) {
+                    Some(i) => {
+                        kept.swap_remove(i);
+                    }
+                    None => rejected_scts.push(RejectedSct {
+                        sct: sct.clone(),
+                        reason: SctRejection::Conflicting,
+                    }),
+                }
+            }
+        }
+
+        let resolved_input;
+        let input = match resolved {
+            Some(sct_timestamps) => {
+                resolved_input = RevocationCheckInput {
+                    sct_timestamps,
+                    ..input.clone()
+                };
+                &resolved_input
+            }
+            None => input,
+        };
+
+        let latest_sct = config.revocation.latest_sct(now);
+        let stale_coverage = config
+            .revocation
+            .stale_coverage_end(filters, now);
+        let mut outcome = self.check_filters(
+            &filters.filters,
+            input,
+            latest_sct,
+            stale_coverage.is_some()
+                && config.revocation.stale_coverage_policy == StaleCoveragePolicy::SerialOnly,
+            config,
+        );
+        outcome.evaluated_at = now;
+        outcome.warnings = warnings;
+        if let Some(coverage_end) = stale_coverage {
+            outcome
+                .warnings
+                .push(Warning::StaleCoverage { coverage_end });
+        }
+        if config.revocation.report_rejected_scts {
+            rejected_scts.extend(
+                input
+                    .sct_timestamps
+                    .iter()
+                    .filter_map(|sct| {
+                        let reason = if sct.timestamp > latest_sct {
+                            SctRejection::Future
+                        } else if !config
+                            .revocation
+                            .approves_log(&sct.log_id)
+                        {
+                            SctRejection::UnapprovedLog
+                        } else if !config.revocation.qualifies(sct) {
+                            SctRejection::Disqualified
+                        } else if filters
+                            .coverage_ends
+                            .as_ref()
+                            .is_some_and(|ends| !ends.contains_key(&sct.log_id.0))
+                        {
+                            SctRejection::UnknownLog
+                        } else {
+                            return None;
+                        };
+                        Some(RejectedSct {
+                            sct: sct.clone(),
+                            reason,
+                        })
+                    }),
+            );
+            outcome.rejected_scts = rejected_scts;
+        }
+
+        outcome.warnings.extend(
+            input
+                .sct_timestamps
+                .iter()
+                .filter(|sct| sct.timestamp > latest_sct)
+                .map(|sct| Warning::FutureSct {
+                    log_id: sct.log_id,
+                    timestamp: sct.timestamp,
+                }),
+        );
+        outcome.warnings.extend(
+            input
+                .sct_timestamps
+                .iter()
+                .filter(|sct| !config.revocation.qualifies(sct))
+                .map(|sct| Warning::DisqualifiedSct {
+                    log_id: sct.log_id,
+                    timestamp: sct.timestamp,
+                }),
+        );
+
+        let Some(coverage_ends) = &filters.coverage_ends else {
+            return outcome;
+        };
+
+        outcome.warnings.extend(
+            input
+                .sct_log_ids()
+                .filter(|log_id| !coverage_ends.contains_key(&log_id.0))
+                .map(|log_id| Warning::UnknownLog { log_id }),
+        );
+
+        if outcome.not_covered_reason == Some(NotCoveredReason::SctsNotCovered) {
+            outcome.sct_gap = input
+                .sct_timestamps
+                .iter()
+                .map(|sct| sct.timestamp)
+                .min()
+                .map(|earliest_sct| SctGap {
+                    issuer_covered: filters.filters.iter().any(|filter| {
+                        filter
+                            .index()
+                            .contains_key(input.issuer_spki_hash.0.as_slice())
+                    }),
+                    coverage_end: input
+                        .sct_log_ids()
+                        .filter_map(|log_id| coverage_ends.get(&log_id.0).copied())
+                        .max(),
+                    earliest_sct,
+                });
+        }
+
+        outcome
+    }
+
+    /// Check `input` against `filters`, ignoring SCTs dated after `latest_sct`.
+    ///
+    /// `serial_only` means the coverage of `filters` is too stale to use, according to
+    /// the configured [`StaleCoveragePolicy`].
+    fn check_filters(
+        &self,
+        filters: &[CRLiteClubcard],
+        input: &RevocationCheckInput,
+        latest_sct: u64,
+        serial_only: bool,
+        config: &Config,
+    ) -> CheckOutcome {
+        if !config
+            .revocation
+            .in_scope(&input.issuer_spki_hash)
+        {
+            return self.outcome(
+                RevocationStatus::NotCoveredByRevocationData,
+                Some(NotCoveredReason::OutOfScope),
+                MatchKind::Exact,
+            );
+        }
+
+        if config
+            .revocation
+            .bypass_issuers
+            .contains(&input.issuer_spki_hash)
+        {
+            return self.outcome(
+                RevocationStatus::NotCoveredByRevocationData,
+                Some(NotCoveredReason::Bypassed),
+                MatchKind::Exact,
+            );
+        }
+
+        if self.is_serial_in_revoked_range(&input.issuer_spki_hash, &input.cert_serial) {
+            return self.outcome(RevocationStatus::CertainlyRevoked, None, MatchKind::Exact);
+        }
+
+        if config
+            .revocation
+            .sct_exempt_issuers
+            .contains(&input.issuer_spki_hash)
+        {
+            return self.check_by_serial(filters, input, NoSctPolicy::TreatAsCovered, config);
+        }
+
+        if input.sct_timestamps.len() < config.revocation.min_sct_count {
+            return self.outcome(
+                RevocationStatus::NotCoveredByRevocationData,
+                Some(NotCoveredReason::InsufficientScts),
+                MatchKind::Exact,
+            );
+        }
+
+        if serial_only {
+            return self.check_by_serial(filters, input, NoSctPolicy::TreatAsCovered, config);
+        }
+
+        if input.sct_timestamps.is_empty() {
+            return self.check_by_serial(filters, input, config.revocation.no_sct_policy, config);
+        }
+
+        let key = input.key();
+        let min_covered = config
+            .revocation
+            .min_covered_scts
+            .max(1);
+        let mut reason = NotCoveredReason::IssuerNotEnrolled;
+        let mut most_covered = 0;
+        for filter in filters {
+            // each SCT is queried separately, so that those within coverage can be counted
+            let mut status = None;
+            let mut covered = 0;
+            for ct_ts in &input.sct_timestamps {
+                if ct_ts.timestamp > latest_sct
+                    || !config
+                        .revocation
+                        .approves_log(&ct_ts.log_id)
+                    || !config.revocation.qualifies(ct_ts)
+                {
+                    reason = NotCoveredReason::SctsNotCovered;
+                    continue;
+                }
+
+                match filter.contains(&key, iter::once((&ct_ts.log_id.0, ct_ts.timestamp))) {
+                    CRLiteStatus::Revoked => status = Some(RevocationStatus::CertainlyRevoked),
+                    CRLiteStatus::Good => status = Some(RevocationStatus::NotRevoked),
+                    CRLiteStatus::NotEnrolled => break,
+                    CRLiteStatus::NotCovered => {
+                        reason = NotCoveredReason::SctsNotCovered;
+                        continue;
+                    }
+                }
+                covered += 1;
+            }
+
+            most_covered = most_covered.max(covered);
+            match status {
+                Some(status) if covered >= min_covered => {
+                    return CheckOutcome {
+                        covered_sct_count: covered,
+                        ..self.outcome(status, None, MatchKind::Exact)
+                    };
+                }
+                Some(_) => reason = NotCoveredReason::SctsNotCovered,
+                None => {}
+            }
+        }
+
+        CheckOutcome {
+            covered_sct_count: most_covered,
+            ..self.outcome(
+                RevocationStatus::NotCoveredByRevocationData,
+                Some(reason),
+                MatchKind::Exact,
+            )
+        }
+    }
+
+    /// Check an input by its serial number alone, ignoring any SCTs.
+    ///
+    /// `policy` is the configured [`NoSctPolicy`] for an input with no SCTs.
+    fn check_by_serial(
+        &self,
+        filters: &[CRLiteClubcard],
+        input: &RevocationCheckInput,
+        policy: NoSctPolicy,
+        config: &Config,
+    ) -> CheckOutcome {
+        if policy == NoSctPolicy::TreatAsNotCovered {
+            return self.outcome(
+                RevocationStatus::NotCoveredByRevocationData,
+                Some(NotCoveredReason::NoScts),
+                MatchKind::Exact,
+            );
+        }
+
+        let key = input.key();
+        let mut enrolled = false;
+        for filter in filters {
+            if !filter
+                .index()
+                .contains_key(input.issuer_spki_hash.0.as_slice())
+            {
+                continue;
+            }
+
+            enrolled = true;
+            if filter
+                .as_ref()
+                .unchecked_contains(&CRLiteQuery::new(&key, None))
+            {
+                return match config.revocation.probabilistic_policy {
+                    ProbabilisticPolicy::Lenient => self.outcome(
+                        RevocationStatus::CertainlyRevoked,
+                        None,
+                        MatchKind::Probabilistic,
+                    ),
+                    ProbabilisticPolicy::Strict => self.outcome(
+                        RevocationStatus::NotCoveredByRevocationData,
+                        Some(NotCoveredReason::NeedsConfirmation),
+                        MatchKind::Probabilistic,
+                    ),
+                };
+            }
+        }
+
+        match (policy, enrolled) {
+            (NoSctPolicy::TreatAsCovered, true) => {
+                self.outcome(RevocationStatus::NotRevoked, None, MatchKind::Probabilistic)
+            }
+            (_, true) => self.outcome(
+                RevocationStatus::NotCoveredByRevocationData,
+                Some(NotCoveredReason::NoScts),
+                MatchKind::Probabilistic,
+            ),
+            (_, false) => self.outcome(
+                RevocationStatus::NotCoveredByRevocationData,
+                Some(NotCoveredReason::IssuerNotEnrolled),
+                MatchKind::Exact,
+            ),
+        }
+    }
+
+    fn outcome(
+        &self,
+        status: RevocationStatus,
+        not_covered_reason: Option<NotCoveredReason>,
+        match_kind: MatchKind,
+    ) -> CheckOutcome {
+        CheckOutcome {
+            status,
+            not_covered_reason,
+            match_kind,
+            covered_sct_count: 0,
+            generated_at: self.generated_at,
+            sct_gap: None,
+            warnings: Vec::new(),
+            rejected_scts: Vec::new(),
+            source: DecisionSource::of(not_covered_reason),
+            evaluated_at: crate::clock::now(),
+        }
+    }
+
+    /// Fail if this manifest is older than the newest one accepted into the cache.
+    ///
+    /// This prevents an attacker rolling back the revocation data to a time before
+    /// a certificate was revoked.
+    fn check_downgrade(&self, config: &Config) -> Result<(), Error> {
+        if config.revocation.allow_downgrade {
+            return Ok(());
+        }
+
+        match highest_accepted(&config.revocation_cache_dir())? {
+            Some(accepted) if self.generated_at < accepted => Err(Error::Downgrade {
+                accepted,
+                offered: self.generated_at,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Refuse this manifest if the configuration pins a different digest.
+    fn check_pinned_digest(&self, config: &Config) -> Result<(), Error> {
+        match config.revocation.pinned_manifest_digest {
+            Some(expected) if expected != self.digest() => Err(Error::DigestMismatch {
+                expected,
+                actual: self.digest(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Record that this manifest has been accepted into the cache.
+    ///
+    /// When downgrades are allowed, this manifest becomes the baseline for future
+    /// checks even if it is older than the previous one.
+    fn record_accepted(&self, config: &Config) -> Result<(), Error> {
+        let dir = config.revocation_cache_dir();
+        if !config.revocation.allow_downgrade
+            && highest_accepted(&dir)?.is_some_and(|accepted| accepted >= self.generated_at)
+        {
+            return Ok(());
+        }
+
+        let path = dir.join(HIGHEST_ACCEPTED);
+        fs::write(&path, self.generated_at.to_string())
+            .map_err(|error| Error::FileWrite { error, path })
+    }
+
+    /// Compare this manifest with a `newer` one.
+    ///
+    /// Filters are identified by their filename and hash, so a filter whose
+    /// contents changed appears as both removed and added.
+    pub fn diff(&self, newer: &Self) -> ManifestDiff {
+        ManifestDiff {
+            generated_at: (self.generated_at, newer.generated_at),
+            added: newer
+                .filters
+                .iter()
+                .filter(|f| !self.contains_filter(f))
+                .cloned()
+                .collect(),
+            removed: self
+                .filters
+                .iter()
+                .filter(|f| !newer.contains_filter(f))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Whether this manifest has `filter`, with the same filename and hash.
+    fn contains_filter(&self, filter: &Filter) -> bool {
+        self.filters
+            .iter()
+            .any(|f| f.filename == filter.filename && f.hash == filter.hash)
+    }
+
+    /// Compare the issuers this manifest has data for with those of an older one,
+    /// `since`.
+    ///
+    /// This is lighter than [`Manifest::diff()`] for a cache of per-issuer results: only
+    /// the issuers added, removed or changed need invalidating.  Issuers are those listed by
+    /// each [`Filter::issuers`], and those with [`Manifest::revoked_ranges`].  A filter
+    /// that does not list its issuers cannot be accounted for without reading it, so
+    /// then [`IssuerDelta::complete`] is false, and the whole cache should be flushed
+    /// if any such filter differs between the manifests.
+    pub fn issuer_delta(&self, since: &Self) -> IssuerDelta {
+        let (issuers, older) = (self.issuers(), since.issuers());
+        IssuerDelta {
+            added: issuers
+                .difference(&older)
+                .map(|&issuer| issuer.clone())
+                .collect(),
+            removed: older
+                .difference(&issuers)
+                .map(|&issuer| issuer.clone())
+                .collect(),
+            changed: self
+                .issuers_changed_since(since)
+                .into_iter()
+                .filter(|issuer| issuers.contains(issuer) && older.contains(issuer))
+                .cloned()
+                .collect(),
+            complete: [self, since].iter().all(|manifest| {
+                manifest
+                    .filters
+                    .iter()
+                    .all(|filter| !filter.issuers.is_empty())
+            }),
+        }
+    }
+
+    /// The issuers listed by filters or ranges that differ between this manifest and
+    /// `since`, for [`Manifest::issuer_delta()`].
+    fn issuers_changed_since<'a>(&'a self, since: &'a Self) -> BTreeSet<&'a IssuerSpkiHash> {
+        let mut changed = BTreeSet::new();
+        for (manifest, other) in [(self, since), (since, self)] {
+            changed.extend(
+                manifest
+                    .filters
+                    .iter()
+                    .filter(|filter| !other.contains_filter(filter))
+                    .flat_map(|filter| &filter.issuers),
+            );
+            changed.extend(
+                manifest
+                    .revoked_ranges
+                    .iter()
+                    .filter(|range| !other.revoked_ranges.contains(range))
+                    .map(|range| &range.issuer),
+            );
+        }
+        changed
+    }
+
+    /// The issuers listed in this manifest, for [`Manifest::issuer_delta()`].
+    fn issuers(&self) -> BTreeSet<&IssuerSpkiHash> {
+        self.filters
+            .iter()
+            .flat_map(|filter| &filter.issuers)
+            .chain(
+                self.revoked_ranges
+                    .iter()
+                    .map(|range| &range.issuer),
+            )
+            .collect()
+    }
+
+    /// Verify the current contents of the cache against this manifest.
+    ///
+    /// This performs disk IO but does not perform network IO.
+    pub fn verify(&self, config: &Config) -> Result<ExitCode, Error> {
+        self.introduce()?;
+        let plan = Plan::construct(
+            self,
+            "https://.../",
+            &config.revocation_cache_dir(),
+            None,
+            &HashSet::new(),
+        )?;
+        match plan.download_bytes() {
+            0 => Ok(ExitCode::SUCCESS),
+            bytes => Err(Error::Outdated(bytes)),
+        }
+    }
+
+    /// Audit this manifest and the filters it refers to in the cache described by `config`.
+    ///
+    /// Unlike [`Manifest::verify()`], this does not stop at the first problem: it reads
+    /// and decodes every filter, and returns everything it finds wrong.  As well as
+    /// filters that are missing, corrupt or undecodable, it reports a manifest
+    /// generated in the future, and coverage extending into the future, to within an
+    /// hour of clock skew.  Either suggests the data was produced with a bad clock.
+    ///
+    /// This performs disk IO but does not perform network IO.
+    pub fn verify_integrity(&self, config: &Config) -> Result<(), Vec<IntegrityProblem>> {
+        let mut problems = Vec::new();
+        let now = crate::clock::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let limit = now.as_secs() + CLOCK_SKEW_TOLERANCE;
+        if self.generated_at > limit {
+            problems.push(IntegrityProblem::FutureManifest {
+                generated_at: self.generated_at,
+            });
+        }
+
+        let dir = config.revocation_cache_dir();
+        for filter in &self.filters {
+            let path = dir.join(&filter.filename);
+            let bytes = match fs::read(&path) {
+                Ok(bytes) => bytes,
+                Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                    problems.push(IntegrityProblem::MissingFilter(path));
+                    continue;
+                }
+                Err(error) => {
+                    problems.push(IntegrityProblem::UnreadableFilter { path, error });
+                    continue;
+                }
+            };
+
+            if digest::digest(&digest::SHA256, &bytes).as_ref() != filter.hash {
+                problems.push(IntegrityProblem::HashMismatch(path));
+                continue;
+            }
+
+            let (Ok(_), Some(table)) = (CRLiteClubcard::from_bytes(&bytes), coverage_table(&bytes))
+            else {
+                problems.push(IntegrityProblem::UndecodableFilter(path));
+                continue;
+            };
+            for (log_id, _, max_timestamp) in table {
+                if max_timestamp / 1000 > limit {
+                    problems.push(IntegrityProblem::FutureCoverage {
+                        path: path.clone(),
+                        log_id: CtLogId(log_id),
+                        max_timestamp,
+                    });
+                }
+            }
+        }
+
+        match problems.is_empty() {
+            true => Ok(()),
+            false => Err(problems),
+        }
+    }
+
+    /// List the files this manifest refers to, with their expected sizes and hashes.
+    ///
+    /// Paths are relative to the directory holding the manifest.  A manifest refers only
+    /// to filter files, so there is one entry per [`Filter`], in manifest order.
+    pub fn artifacts(&self) -> Vec<ArtifactInfo> {
+        self.filters
+            .iter()
+            .map(|filter| ArtifactInfo {
+                path: PathBuf::from(&filter.filename),
+                size: filter.size,
+                hash: filter.hash.clone(),
+            })
+            .collect()
+    }
+
+    /// Estimate how much memory checking against the manifest in the cache described by
+    /// `config` would use, without loading its filters.
+    ///
+    /// Only the manifest is read, so this is cheap enough to do at startup, before
+    /// deciding how to load the revocation data.  The figures are the encoded filter
+    /// sizes recorded in the manifest, not measurements of decoded filters: a decoded
+    /// filter takes about as much memory as its encoding, but not exactly.  Filters are
+    /// not split by issuer, so the memory used for any one issuer cannot be estimated.
+    pub fn estimate_footprint(config: &Config) -> Result<FootprintEstimate, Error> {
+        let manifest = Self::from_config(config)?;
+        let filters = manifest
+            .filters
+            .iter()
+            .map(|filter| FilterFootprint {
+                filename: filter.filename.clone(),
+                bytes: filter.size,
+            })
+            .collect::<Vec<_>>();
+
+        let total = filters
+            .iter()
+            .map(|filter| filter.bytes)
+            .sum::<usize>();
+        let largest = filters
+            .iter()
+            .map(|filter| filter.bytes)
+            .max()
+            .unwrap_or(0);
+        Ok(FootprintEstimate {
+            filters,
+            total,
+            // each filter's encoding is held only while it is decoded
+            peak: total + largest,
+        })
+    }
+
+    /// Summarize this manifest and its filters as JSON, for display on dashboards.
+    ///
+    /// The filters are read from the cache described by `config`.  The summary is an
+    /// object with these fields, and new fields may be added:
+    ///
+    /// - `schema`: 1, the version of this layout.
+    /// - `generated_at`: [`Manifest::generated_at`], which identifies the manifest.
+    /// - `stale_after`: when (in UNIX seconds) the manifest becomes stale, and should
+    ///   have been replaced.
+    /// - `filters`, `filter_bytes`: the number of filters and their total size.
+    /// - `coverage`: for each CT log, the earliest and latest SCT timestamp covered
+    ///   by any filter, as `log_id` (base64), `min_timestamp` and `max_timestamp`
+    ///   (UNIX milliseconds).  Sorted by log ID.
+    /// - `issuers`: `count`, the number of enrolled issuers, and `sample`, the first
+    ///   few of their SPKI hashes (base64, sorted).
+    ///
+    /// The filters do not record how many certificates are revoked, so that is not
+    /// included.
+    pub fn summary_json(&self, config: &Config) -> Result<String, Error> {
+        let dir = config.revocation_cache_dir();
+        let mut coverage = HashMap::<[u8; 32], (u64, u64)>::new();
+        let mut issuers = BTreeSet::new();
+        for filter in &self.filters {
+            let (clubcard, bytes) = filter.load_with_bytes(&dir)?;
+            let Some(table) = coverage_table(&bytes) else {
+                return Err(Error::FilterDecode {
+                    error: "cannot decode crlite filter coverage".into(),
+                    path: dir.join(&filter.filename),
+                });
+            };
+            for (log_id, min, max) in table {
+                let entry = coverage
+                    .entry(log_id)
+                    .or_insert((min, max));
+                *entry = (entry.0.min(min), entry.1.max(max));
+            }
+            issuers.extend(clubcard.index().keys().cloned());
+        }
+
+        let mut coverage = coverage
+            .into_iter()
+            .map(|(log_id, (min_timestamp, max_timestamp))| LogCoverage {
+                log_id: BASE64_STANDARD.encode(log_id),
+                min_timestamp,
+                max_timestamp,
+            })
+            .collect::<Vec<_>>();
+        coverage.sort_by(|a, b| a.log_id.cmp(&b.log_id));
+
+        let summary = ManifestSummary {
+            schema: 1,
+            generated_at: self.generated_at,
+            stale_after: self
+                .generated_at
+                .saturating_add(STALE_MANIFEST_AGE),
+            filters: self.filters.len(),
+            filter_bytes: self
+                .filters
+                .iter()
+                .map(|f| f.size)
+                .sum(),
+            coverage,
+            issuers: IssuerSummary {
+                count: issuers.len(),
+                sample: issuers
+                    .iter()
+                    .take(ISSUER_SAMPLE_LEN)
+                    .map(|issuer| BASE64_STANDARD.encode(issuer))
+                    .collect(),
+            },
+        };
+        Ok(serde_json::to_string_pretty(&summary).expect("summary has only string map keys"))
+    }
+
+    /// Render metrics about this manifest in the OpenMetrics text format.
+    ///
+    /// This is for simple deployments, that can serve it directly from a `/metrics`
+    /// endpoint rather than running an exporter.  It is also accepted by Prometheus.
+    /// The metrics are:
+    ///
+    /// - `upki_manifest_age_seconds`: a gauge, the time since [`Manifest::generated_at`].
+    /// - `upki_manifest_info`: an info metric, with the manifest's `generated_at` and,
+    ///   if it has one, `epoch` as labels.
+    /// - `upki_manifest_filters` and `upki_manifest_filter_bytes`: gauges, the number
+    ///   of filters and their total size.
+    ///
+    /// As with [`Manifest::summary_json()`], the number of revoked certificates is not
+    /// included, since the filters do not record it.
+    pub fn openmetrics_text(&self) -> String {
+        let age = crate::clock::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |now| {
+                now.as_secs()
+                    .saturating_sub(self.generated_at)
+            });
+        let mut labels = format!("generated_at=\"{}\"", self.generated_at);
+        if let Some(epoch) = self.epoch {
+            labels.push_str(&format!(",epoch=\"{epoch}\""));
+        }
+        let filter_bytes = self
+            .filters
+            .iter()
+            .map(|f| f.size)
+            .sum::<usize>();
+
+        let mut text = String::new();
+        for (name, kind, help, sample) in [
+            (
+                "upki_manifest_age_seconds",
+                "gauge",
+                "Time since the revocation manifest was generated.",
+                format!("upki_manifest_age_seconds {age}"),
+            ),
+            (
+                "upki_manifest",
+                "info",
+                "Which revocation manifest is loaded.",
+                format!("upki_manifest_info{{{labels}}} 1"),
+            ),
+            (
+                "upki_manifest_filters",
+                "gauge",
+                "Number of filters in the revocation manifest.",
+                format!("upki_manifest_filters {}", self.filters.len()),
+            ),
+            (
+                "upki_manifest_filter_bytes",
+                "gauge",
+                "Total size of the filters in the revocation manifest.",
+                format!("upki_manifest_filter_bytes {filter_bytes}"),
+            ),
+        ] {
+            text.push_str(&format!(
+                "# TYPE {name} {kind}\n# HELP {name} {help}\n{sample}\n"
+            ));
+        }
+        text.push_str("# EOF\n");
+        text
+    }
+
+    /// Read the encoded filters that contain data for `issuer`, from the cache described
+    /// by `config`.
+    ///
+    /// This is for tooling that analyses the filters themselves.  Filters are not split
+    /// by issuer: each one returned may contain data for other issuers too.  The bytes
+    /// are in the internal `clubcard-crlite` encoding, which may change between
+    /// versions of this crate, so they are not suitable for storing or exchanging.
+    ///
+    /// Filters are returned in manifest order.  The result is empty if the issuer is
+    /// not enrolled.
+    pub fn filter_bytes(
+        &self,
+        issuer: &IssuerSpkiHash,
+        config: &Config,
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        let dir = config.revocation_cache_dir();
+        let mut found = Vec::new();
+        for filter in &self.filters {
+            let (clubcard, bytes) = filter.load_with_bytes(&dir)?;
+            if clubcard
+                .index()
+                .contains_key(issuer.0.as_slice())
+            {
+                found.push(bytes);
+            }
+        }
+
+        Ok(found)
+    }
+
+    /// Whether the revocation data has entries for `issuer`, in its filters or in
+    /// [`Manifest::revoked_ranges`].
+    ///
+    /// This reads the filters from the cache described by `config`, but does no
+    /// revocation lookup, so it can be used to decide whether upki is the right
+    /// revocation source for a certificate chain.  A certificate from an issuer that is
+    /// not covered is always [`RevocationStatus::NotCoveredByRevocationData`]; one from
+    /// an issuer that is covered still needs its serial number to be in a revoked range,
+    /// or its SCTs to be within the coverage of a filter.
+    pub fn covers_issuer(&self, issuer: &IssuerSpkiHash, config: &Config) -> Result<bool, Error> {
+        if self
+            .revoked_ranges
+            .iter()
+            .any(|range| range.issuer == *issuer)
+        {
+            return Ok(true);
+        }
+
+        Ok(self
+            .load_filters_in_scope(config)?
+            .filters
+            .iter()
+            .any(|filter| {
+                filter
+                    .index()
+                    .contains_key(issuer.0.as_slice())
+            }))
+    }
+
+    /// When the next manifest is expected to be published, if this manifest says.
+    ///
+    /// This is only a hint, for scheduling the next [`fetch()`]: a new manifest may
+    /// appear earlier or later.  [`spawn_auto_refresh()`] uses it when present.
+    pub fn next_update(&self) -> Option<SystemTime> {
+        UNIX_EPOCH.checked_add(Duration::from_secs(self.next_update?))
+    }
+
+    /// The version of the manifest format.
+    ///
+    /// A manifest that does not say is version 1.  Publishers increase the version
+    /// when a change to the format would be misunderstood by older clients, which then
+    /// refuse such a manifest with [`Error::UnsupportedManifestVersion`].
+    pub fn format_version(&self) -> u32 {
+        self.format_version.unwrap_or(1)
+    }
+
+    /// Which build of the revocation data this is, if the publisher numbers them.
+    ///
+    /// Publishers that build the data periodically, such as weekly, may number each
+    /// build as an epoch.  Epochs increase with [`Manifest::generated_at`].  Earlier
+    /// epochs can be kept in the cache with `retain-epochs`, and loaded with
+    /// [`Manifest::from_config_at_epoch()`].
+    pub fn epoch(&self) -> Option<u64> {
+        self.epoch
+    }
+
+    /// The SHA-256 digest of this manifest, for pinning with `pinned-manifest-digest`.
+    ///
+    /// This is the digest of its compact JSON encoding, which is how [`fetch()`] writes
+    /// it to the cache.  So it is the `sha256sum` of a fetched `manifest.json`, but
+    /// not necessarily of the publisher's copy.
+    pub fn digest(&self) -> [u8; 32] {
+        let json = serde_json::to_vec(self).expect("manifest has only string map keys");
+        digest::digest(&digest::SHA256, &json)
+            .as_ref()
+            .try_into()
+            .expect("SHA-256 digests are 32 bytes")
+    }
+
+    /// Identifies this manifest, for tying a decision to the data that produced it.
+    pub fn version(&self) -> ManifestVersion {
+        ManifestVersion {
+            generated_at: self.generated_at,
+        }
+    }
+
+    /// Whether `serial`, from `issuer`, is in one of [`Manifest::revoked_ranges`].
+    ///
+    /// A CA that revokes a batch of certificates, for example everything issued during
+    /// a compromise, can be listed as a range rather than serial by serial.  Checks
+    /// consult the ranges as well as the filters, and report a certificate in a range
+    /// as [`RevocationStatus::CertainlyRevoked`] whatever its SCTs.  Serial numbers are
+    /// compared as integers, and ranges include both ends.
+    ///
+    /// crlite filters have no ranges, so this is always false for manifests without
+    /// them.  Publishers listing ranges must declare format version 2, so that older
+    /// clients refuse the manifest rather than missing the revocations.
+    pub fn is_serial_in_revoked_range(&self, issuer: &IssuerSpkiHash, serial: &CertSerial) -> bool {
+        self.revoked_ranges
+            .iter()
+            .any(|range| range.contains(issuer, serial))
+    }
+
+    /// Returns true if this manifest references no filters.
+    ///
+    /// Every check against an empty manifest is [`RevocationStatus::NotCoveredByRevocationData`],
+    /// unless it lists [`Manifest::revoked_ranges`].
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    /// Logs metadata fields in this manifest.
+    pub fn introduce(&self) -> Result<(), Error> {
+        let dt = match i64::try_from(self.generated_at)
+            .ok()
+            .and_then(|secs| DateTime::<Utc>::from_timestamp(secs, 0))
+        {
+            Some(dt) => dt.to_rfc3339(),
+            None => {
+                return Err(Error::InvalidTimestamp {
+                    input: self.generated_at.to_string(),
+                    context: "manifest generated (in s)",
+                });
+            }
+        };
+
+        info!(comment = self.comment, date = dt, "parsed manifest");
+        Ok(())
+    }
+}
+
+/// What [`Manifest::open()`] looks for, beyond the manifest being usable.
+#[derive(Clone, Debug)]
+pub struct OpenOptions {
+    max_age: Duration,
+    expected_issuers: Vec<IssuerSpkiHash>,
+}
+
+impl OpenOptions {
+    /// Options that report a manifest older than two days as stale, and expect no
+    /// particular issuers.
+    pub fn new() -> Self {
+        Self {
+            max_age: Duration::from_secs(STALE_MANIFEST_AGE),
+            expected_issuers: Vec::new(),
+        }
+    }
+
+    /// Report the manifest as [`HealthProblem::Stale`] if it is older than `max_age`.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    /// Report the manifest as [`HealthProblem::IssuersNotCovered`] unless the revocation
+    /// data has entries for each of `issuers`.
+    ///
+    /// These are typically the issuers of the certificates a server expects to see,
+    /// so that a publisher dropping one is noticed at startup rather than as a stream
+    /// of uncovered checks.
+    pub fn expected_issuers(mut self, issuers: impl IntoIterator<Item = IssuerSpkiHash>) -> Self {
+        self.expected_issuers.extend(issuers);
+        self
+    }
+}
+
+impl Default for OpenOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A problem with the health of a manifest opened by [`Manifest::open()`].
+///
+/// The manifest can be checked against despite these, but checks may be
+/// [`RevocationStatus::NotCoveredByRevocationData`] more often than expected.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum HealthProblem {
+    /// The manifest is older than [`OpenOptions::max_age()`].
+    ///
+    /// `upki fetch` is probably not running as often as it should.
+    Stale {
+        /// How old the manifest is.
+        age: Duration,
+    },
+    /// The revocation data has no entries for these [`OpenOptions::expected_issuers()`].
+    IssuersNotCovered(Vec<IssuerSpkiHash>),
+}
+
+/// A problem found by [`Manifest::verify_integrity()`].
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum IntegrityProblem {
+    /// A filter covers SCTs from a CT log up to a time in the future.
+    FutureCoverage {
+        /// Path to the filter file.
+        path: PathBuf,
+        /// The CT log.
+        log_id: CtLogId,
+        /// The latest SCT timestamp covered, in UNIX milliseconds.
+        max_timestamp: u64,
+    },
+    /// The manifest's `generated_at` is in the future.
+    FutureManifest {
+        /// The manifest's [`Manifest::generated_at`].
+        generated_at: u64,
+    },
+    /// A filter file's contents do not match the hash in the manifest.
+    HashMismatch(PathBuf),
+    /// A filter file is missing.
+    MissingFilter(PathBuf),
+    /// A filter file matches its hash, but could not be decoded.
+    UndecodableFilter(PathBuf),
+    /// A filter file exists, but could not be read.
+    UnreadableFilter {
+        /// Underlying error.
+        error: io::Error,
+        /// Path to the filter file.
+        path: PathBuf,
+    },
+}
+
+impl fmt::Display for IntegrityProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FutureCoverage {
+                path,
+                log_id,
+                max_timestamp,
+            } => write!(
+                f,
+                "filter {path:?} covers log {} into the future, until {max_timestamp}",
+                BASE64_STANDARD.encode(log_id.0)
+            ),
+            Self::FutureManifest { generated_at } => {
+                write!(f, "manifest generated in the future, at {generated_at}")
+            }
+            Self::HashMismatch(path) => write!(f, "filter {path:?} does not match its hash"),
+            Self::MissingFilter(path) => write!(f, "filter {path:?} is missing"),
+            Self::UndecodableFilter(path) => write!(f, "filter {path:?} cannot be decoded"),
+            Self::UnreadableFilter { error, path } => {
+                write!(f, "filter {path:?} cannot be read: {error}")
+            }
+        }
+    }
+}
+
+/// How far in the future, in seconds, [`Manifest::verify_integrity()`] allows
+/// timestamps to be.
+const CLOCK_SKEW_TOLERANCE: u64 = 3600;
+
+/// A file referred to by a manifest, produced by [`Manifest::artifacts()`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ArtifactInfo {
+    /// Path of the file, relative to the manifest's directory.
+    pub path: PathBuf,
+
+    /// Expected size of the file, in bytes.
+    pub size: usize,
+
+    /// Expected SHA256 hash of the file contents.
+    pub hash: Vec<u8>,
+}
+
+/// How much memory loading a manifest's filters would use, from
+/// [`Manifest::estimate_footprint()`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct FootprintEstimate {
+    /// The estimate for each filter, in manifest order.
+    pub filters: Vec<FilterFootprint>,
+
+    /// The estimated memory used by all the loaded filters, in bytes.
+    ///
+    /// This is the total encoded size of the filters.
+    pub total: usize,
+
+    /// The estimated memory used while loading the filters, in bytes.
+    ///
+    /// This is `total` plus the encoded size of the largest filter, because each
+    /// filter's encoding is read into memory to be decoded.
+    pub peak: usize,
+}
+
+/// How much memory one loaded filter would use, in a [`FootprintEstimate`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct FilterFootprint {
+    /// The filter's [`Filter::filename`].
+    pub filename: String,
+
+    /// The estimated memory used by the loaded filter, in bytes: its encoded size.
+    pub bytes: usize,
+}
+
+/// The differences between two manifests, produced by [`Manifest::diff()`].
+///
+/// The contents of crlite filters cannot be enumerated, so this describes
+/// changes in terms of filter files rather than individual certificates.
+#[derive(Clone, Debug)]
+pub struct ManifestDiff {
+    /// [`Manifest::generated_at`] of the older and newer manifest.
+    pub generated_at: (u64, u64),
+
+    /// Filters only present in the newer manifest.
+    pub added: Vec<Filter>,
+
+    /// Filters only present in the older manifest.
+    pub removed: Vec<Filter>,
+}
+
+impl ManifestDiff {
+    /// Returns true if the manifests reference the same filters.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+impl fmt::Display for ManifestDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (older, newer) = self.generated_at;
+        writeln!(f, "generated at {older} -> {newer}")?;
+        for filter in &self.removed {
+            writeln!(f, "- {} ({} bytes)", filter.filename, filter.size)?;
+        }
+        for filter in &self.added {
+            writeln!(f, "+ {} ({} bytes)", filter.filename, filter.size)?;
+        }
+        Ok(())
+    }
+}
+
+/// A range of serial numbers revoked wholesale, listed in [`Manifest::revoked_ranges`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RevokedRange {
+    /// The issuer of the revoked certificates.
+    pub issuer: IssuerSpkiHash,
+
+    /// The lowest serial number revoked.
+    pub first: CertSerial,
+
+    /// The highest serial number revoked.
+    pub last: CertSerial,
+}
+
+impl RevokedRange {
+    fn contains(&self, issuer: &IssuerSpkiHash, serial: &CertSerial) -> bool {
+        self.issuer == *issuer
+            && self.first.value_cmp(serial).is_le()
+            && serial.value_cmp(&self.last).is_le()
+    }
+}
+
+/// The issuers added, removed and changed between two manifests, produced by
+/// [`Manifest::issuer_delta()`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct IssuerDelta {
+    /// Issuers only the newer manifest has data for, in order.
+    pub added: Vec<IssuerSpkiHash>,
+
+    /// Issuers only the older manifest has data for, in order.
+    pub removed: Vec<IssuerSpkiHash>,
+
+    /// Issuers both manifests have data for, but listed by a filter or range that
+    /// differs between them, in order.  Their revocations may have changed.
+    pub changed: Vec<IssuerSpkiHash>,
+
+    /// Whether every filter in both manifests lists its issuers.
+    ///
+    /// If not, issuers may have been added, removed or changed without appearing here.
+    pub complete: bool,
+}
+
+impl IssuerDelta {
+    /// Returns true if no issuers were added, removed or changed.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Information about how a [`Manifest`] was produced.
+///
+/// This is informational: it is recorded by the producer, and not authenticated.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Provenance {
+    /// The name and version of the tool that built the manifest.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub builder: Option<String>,
+
+    /// An identifier for the build or pipeline run that produced the manifest.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub build_id: Option<String>,
+}
+
+/// Manifest data for a single crlite filter file.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Filter {
+    /// Relative filename.
+    ///
+    /// This is also the suggested local filename.
+    pub filename: String,
+
+    /// File size, indicative.  Allows a fetcher to predict data usage.
+    pub size: usize,
+
+    /// SHA256 hash of file contents.
+    #[serde(with = "hex::serde")]
+    pub hash: Vec<u8>,
+
+    /// The issuers this filter has data for, if the publisher lists them.
+    ///
+    /// When this is not empty, a filter with none of the configured
+    /// `in-scope-issuers` is never loaded.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub issuers: Vec<IssuerSpkiHash>,
+}
+
+impl Filter {
+    /// Whether this filter may have data for an issuer in `in-scope-issuers`.
+    fn in_scope(&self, config: &Config) -> bool {
+        self.issuers.is_empty()
+            || self
+                .issuers
+                .iter()
+                .any(|issuer| config.revocation.in_scope(issuer))
+    }
+
+    /// Read and decode this filter from the directory `dir`, also returning the file's
+    /// contents.
+    fn load_with_bytes(&self, dir: &Path) -> Result<(CRLiteClubcard, Vec<u8>), Error> {
+        let path = dir.join(&self.filename);
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(error) => {
+                return Err(Error::FilterRead {
+                    error,
+                    path: Some(path),
+                });
+            }
+        };
+
+        Ok((self.decode(&bytes, path)?, bytes))
+    }
+
+    /// Decode this filter from `bytes`, which were read from `path`.
+    fn decode(&self, bytes: &[u8], path: PathBuf) -> Result<CRLiteClubcard, Error> {
+        CRLiteClubcard::from_bytes(bytes).map_err(|error| Error::FilterDecode {
+            error: format!("cannot decode crlite filter: {error:?}").into(),
+            path,
+        })
+    }
+}
+
+/// The decoded filters of a manifest.
+struct LoadedFilters {
+    filters: Vec<CRLiteClubcard>,
+
+    /// For each CT log covered by any of `filters`, the latest SCT timestamp covered.
+    ///
+    /// This is `None` if the coverage of any filter could not be read.
+    coverage_ends: Option<HashMap<[u8; 32], u64>>,
+}
+
+impl LoadedFilters {
+    /// Collect decoded filters, each with its encoding.
+    fn new<B: AsRef<[u8]>>(
+        loaded: impl Iterator<Item = Result<(CRLiteClubcard, B), Error>>,
+    ) -> Result<Self, Error> {
+        let mut filters = Vec::new();
+        let mut coverage_ends = Some(HashMap::new());
+        for result in loaded {
+            let (filter, bytes) = result?;
+            match (coverage_table(bytes.as_ref()), &mut coverage_ends) {
+                (Some(table), Some(coverage_ends)) => {
+                    for (log_id, _, max_timestamp) in table {
+                        let end = coverage_ends
+                            .entry(log_id)
+                            .or_insert(max_timestamp);
+                        *end = max_timestamp.max(*end);
+                    }
+                }
+                _ => coverage_ends = None,
+            }
+            filters.push(filter);
+        }
+
+        Ok(Self {
+            filters,
+            coverage_ends,
+        })
+    }
+}
+
+/// Read the archive at `path` into memory.
+fn read_archive(path: &Path) -> Result<Archive, Error> {
+    Archive::read(path).map_err(|error| Error::FileRead {
+        error,
+        path: path.to_owned(),
+    })
+}
+
+/// Where the manifest is in an archive.
+const ARCHIVED_MANIFEST: &str = "revocation/manifest.json";
+
+/// The versions of the manifest format that [`Manifest`] understands.
+///
+/// Version 2 added [`Manifest::revoked_ranges`].
+const SUPPORTED_FORMAT_VERSIONS: RangeInclusive<u32> = 1..=2;
+
+/// Read the coverage table from the start of an encoded crlite filter.
+///
+/// `clubcard-crlite` does not expose the coverage of a decoded filter, so this reads
+/// it from the encoding instead.  After a two-byte version comes the coverage map,
+/// in `bincode` form: a little-endian `u64` entry count, and then for each entry the
+/// 32-byte log ID and little-endian `u64` minimum and maximum timestamps.
+fn coverage_table(bytes: &[u8]) -> Option<Vec<([u8; 32], u64, u64)>> {
+    let u64_at = |offset: usize| {
+        let value = bytes.get(offset..offset + 8)?;
+        Some(u64::from_le_bytes(value.try_into().ok()?))
+    };
+
+    const ENTRY_LEN: usize = 32 + 8 + 8;
+    let count = usize::try_from(u64_at(2)?).ok()?;
+    if bytes.len() < 10 + count.checked_mul(ENTRY_LEN)? {
+        return None;
+    }
+
+    (0..count)
+        .map(|i| {
+            let offset = 10 + i * ENTRY_LEN;
+            Some((
+                bytes[offset..offset + 32]
+                    .try_into()
+                    .ok()?,
+                u64_at(offset + 32)?,
+                u64_at(offset + 40)?,
+            ))
+        })
+        .collect()
+}
+
+/// The output of [`Manifest::summary_json()`].
+#[derive(Serialize)]
+struct ManifestSummary {
+    schema: u32,
+    generated_at: u64,
+    stale_after: u64,
+    filters: usize,
+    filter_bytes: usize,
+    coverage: Vec<LogCoverage>,
+    issuers: IssuerSummary,
+}
+
+#[derive(Serialize)]
+struct LogCoverage {
+    log_id: String,
+    min_timestamp: u64,
+    max_timestamp: u64,
+}
+
+#[derive(Serialize)]
+struct IssuerSummary {
+    count: usize,
+    sample: Vec<String>,
+}
+
+/// How many issuers [`Manifest::summary_json()`] lists.
+const ISSUER_SAMPLE_LEN: usize = 10;
+
+/// Input parameters for a revocation check.
+///
+/// Two inputs are equal if they have the same serial, issuer and SCTs, whatever
+/// order the SCTs are in: the order does not affect the outcome of a check.  So an
+/// input can be used directly as a cache key.
+#[derive(Clone, Debug)]
+pub struct RevocationCheckInput {
+    /// Big-endian bytes encoding of the end-entity certificate serial number.
+    pub cert_serial: CertSerial,
+    /// SHA256 hash of the `SubjectPublicKeyInfo` of the issuer of the end-entity certificate.
+    pub issuer_spki_hash: IssuerSpkiHash,
+    /// CT log IDs and inclusion timestamps present in the end-entity certificate.
+    pub sct_timestamps: Vec<CtTimestamp>,
+}
+
+impl RevocationCheckInput {
+    /// Construct a `RevocationCheckInput` from a sequence of DER-encoded certificates.
+    ///
+    /// The first element in `certificates` **must** be the end-entity certificate.  The
+    /// end-entity certificate's issuer **must** be present in the other certificates
+    /// (but does not need to be in any specific position).
+    ///
+    /// Note this interface **only** checks the end-entity certificate for revocation.  It does
+    /// **not** check any of the certificates for validity: it assumes the caller has done any
+    /// required checks before calling this interface (path building, naming validation,
+    /// expiry checking, etc.).
+    pub fn from_certificates(certificates: &[CertificateDer<'_>]) -> Result<Self, Error> {
+        let (end_entity, rest) = certificates
+            .split_first()
+            .ok_or(Error::TooFewCertificates)?;
+        let end_entity = webpki::EndEntityCert::try_from(end_entity)
+            .map_err(|error| Error::InvalidEndEntityCertificate(Box::new(error)))?;
+
+        let issuer = find_issuer(end_entity.issuer(), rest.iter())?;
+        let issuer_spki = webpki::spki_for_anchor(&issuer);
+
+        let mut sct_timestamps = vec![];
+        let iter = end_entity
+            .sct_log_timestamps()
+            .map_err(|e| Error::InvalidEndEntityCertificate(Box::new(e)))?;
+
+        for ts in iter {
+            let ts = ts.map_err(|e| Error::InvalidSctInCertificate(Box::new(e)))?;
+            sct_timestamps.push(CtTimestamp {
+                log_id: CtLogId(ts.log_id),
+                timestamp: ts.timestamp_ms,
+            });
+        }
+
+        Ok(Self::from_fields(CertificateFields {
+            serial: end_entity.serial(),
+            issuer_spki: &issuer_spki,
+            sct_timestamps,
+        }))
+    }
+
+    /// Construct a `RevocationCheckInput` from values already extracted from the certificates.
+    ///
+    /// This avoids parsing the certificates again when the caller already has them in a
+    /// structured form.  See [`CertificateFields`] for what each field must contain.
+    pub fn from_fields(fields: CertificateFields<'_>) -> Self {
+        Self {
+            cert_serial: CertSerial(fields.serial.to_vec()),
+            issuer_spki_hash: issuer_spki_hash(fields.issuer_spki),
+            sct_timestamps: fields.sct_timestamps,
+        }
+    }
+
+    /// Construct a `RevocationCheckInput` from certificates parsed by `x509-parser`.
+    ///
+    /// `issuer` **must** be the issuer of `end_entity`; this fails with [`Error::NoIssuer`]
+    /// if its subject does not match the end-entity certificate's issuer name.  As with
+    /// [`RevocationCheckInput::from_certificates()`], neither certificate is otherwise
+    /// checked for validity.
+    #[cfg(feature = "x509-parser")]
+    pub fn from_x509_parser(
+        end_entity: &X509Certificate<'_>,
+        issuer: &X509Certificate<'_>,
+    ) -> Result<Self, Error> {
+        Self::from_parsed_tbs(&end_entity.tbs_certificate, issuer)
+    }
+
+    /// Construct a `RevocationCheckInput` from the DER-encoded `TBSCertificate` of the
+    /// end-entity certificate, and its DER-encoded issuer certificate.
+    ///
+    /// This is for callers that have the certificate's contents but not the signed
+    /// certificate, such as before it is signed.  A `TBSCertificate` is the part of
+    /// a certificate that is signed: unlike the input to
+    /// [`RevocationCheckInput::from_certificates()`], it has no outer wrapper with the
+    /// signature algorithm and signature.  The serial number and SCTs are the same as
+    /// in the signed certificate, so the result is the same input.
+    ///
+    /// This fails with [`Error::InvalidEndEntityCertificate`] if `tbs_der` is malformed
+    /// or has an empty serial number, [`Error::InvalidIssuerCertificate`] if
+    /// `issuer_der` is malformed, and [`Error::NoIssuer`] if `issuer_der` is not the
+    /// issuer.  As with [`RevocationCheckInput::from_x509_parser()`], neither is
+    /// otherwise checked for validity.
+    #[cfg(feature = "x509-parser")]
+    pub fn from_tbs_certificate(tbs_der: &[u8], issuer_der: &[u8]) -> Result<Self, Error> {
+        let tbs = match TbsCertificate::from_der(tbs_der) {
+            Ok(([], tbs)) => tbs,
+            Ok(_) => {
+                return Err(Error::InvalidEndEntityCertificate(
+                    "trailing data after TBSCertificate".into(),
+                ));
+            }
+            Err(error) => return Err(Error::InvalidEndEntityCertificate(Box::new(error))),
+        };
+        if tbs.raw_serial().is_empty() {
+            return Err(Error::InvalidEndEntityCertificate(
+                "TBSCertificate has an empty serial number".into(),
+            ));
+        }
+
+        let (_, issuer) = X509Certificate::from_der(issuer_der)
+            .map_err(|error| Error::InvalidIssuerCertificate(Box::new(error)))?;
+        Self::from_parsed_tbs(&tbs, &issuer)
+    }
+
+    #[cfg(feature = "x509-parser")]
+    fn from_parsed_tbs(
+        end_entity: &TbsCertificate<'_>,
+        issuer: &X509Certificate<'_>,
+    ) -> Result<Self, Error> {
+        if end_entity.issuer().as_raw() != issuer.subject().as_raw() {
+            return Err(Error::NoIssuer);
+        }
+
+        let mut sct_timestamps = vec![];
+        for extension in end_entity.extensions() {
+            match extension.parsed_extension() {
+                ParsedExtension::SCT(scts) => {
+                    sct_timestamps.extend(scts.iter().map(|sct| CtTimestamp {
+                        log_id: CtLogId(*sct.id.key_id),
+                        timestamp: sct.timestamp,
+                    }))
+                }
+                ParsedExtension::ParseError { error } if extension.oid == OID_CT_LIST_SCT => {
+                    return Err(Error::InvalidSctInCertificate(Box::new(error.clone())));
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Self::from_fields(CertificateFields {
+            serial: end_entity.raw_serial(),
+            issuer_spki: issuer.public_key().raw,
+            sct_timestamps,
+        }))
+    }
+
+    /// Construct a `RevocationCheckInput` for a CT precertificate parsed by `x509-parser`.
+    ///
+    /// A precertificate carries the critical CT poison extension, and has the same serial
+    /// number as the final certificate that is later issued from it.  It contains no SCTs:
+    /// logs issue those for the precertificate, and they are embedded in the final
+    /// certificate.  So pass them in `sct_timestamps`.  The result is the same input that
+    /// [`RevocationCheckInput::from_x509_parser()`] produces for the final certificate.
+    ///
+    /// `chain` must contain the certificate that signed `precert`.  If that is a
+    /// precertificate signing certificate (one with the CT precertificate signing extended
+    /// key usage), then the final certificate is issued by the CA that issued the signing
+    /// certificate, and that CA must be in `chain` too: its SPKI is the one used.
+    ///
+    /// This fails with [`Error::InvalidEndEntityCertificate`] if `precert` has no poison
+    /// extension.  Precertificates cannot be used with
+    /// [`RevocationCheckInput::from_certificates()`], which rejects the poison extension.
+    #[cfg(feature = "x509-parser")]
+    pub fn from_precertificate(
+        precert: &X509Certificate<'_>,
+        chain: &[X509Certificate<'_>],
+        sct_timestamps: Vec<CtTimestamp>,
+    ) -> Result<Self, Error> {
+        if !precert
+            .extensions()
+            .iter()
+            .any(|ext| ext.oid.as_bytes() == OID_CT_PRECERT_POISON)
+        {
+            return Err(Error::InvalidEndEntityCertificate(
+                "certificate is not a precertificate: it has no CT poison extension".into(),
+            ));
+        }
+
+        let issuer_of = |cert: &X509Certificate<'_>| {
+            chain
+                .iter()
+                .position(|c| c.subject().as_raw() == cert.issuer().as_raw())
+                .ok_or(Error::NoIssuer)
+        };
+        let mut index = issuer_of(precert)?;
+        let is_precert_signer = chain[index]
+            .extended_key_usage()
+            .map_err(|error| Error::InvalidIntermediateCertificate {
+                error: Box::new(error),
+                index,
+            })?
+            .is_some_and(|eku| {
+                eku.value
+                    .other
+                    .iter()
+                    .any(|oid| oid.as_bytes() == OID_CT_PRECERT_SIGNING)
+            });
+        if is_precert_signer {
+            index = issuer_of(&chain[index])?;
+        }
+
+        Ok(Self::from_fields(CertificateFields {
+            serial: precert.raw_serial(),
+            issuer_spki: chain[index].public_key().raw,
+            sct_timestamps,
+        }))
+    }
+
+    /// Construct a `RevocationCheckInput` for each certificate in a PEM bundle.
+    ///
+    /// `pem` must contain a certificate chain in order: each certificate must be
+    /// followed by its issuer.  One item is yielded for each certificate that is
+    /// followed by another; an item is an error if the following certificate is
+    /// not its issuer.
+    ///
+    /// The final certificate in the bundle has no issuer to check it against, and
+    /// is skipped.  This is usually a root certificate.
+    ///
+    /// If `pem` cannot be parsed, the only item yielded is an error.
+    pub fn from_pem_bundle(pem: &str) -> impl Iterator<Item = Result<Self, Error>> {
+        let (certs, error) =
+            match CertificateDer::pem_slice_iter(pem.as_bytes()).collect::<Result<Vec<_>, _>>() {
+                Ok(certs) => (certs, None),
+                Err(error) => (Vec::new(), Some(Error::InvalidPem(Box::new(error)))),
+            };
+
+        let issued = certs.len().saturating_sub(1);
+        error
+            .map(Err)
+            .into_iter()
+            .chain((0..issued).map(move |i| Self::from_certificates(&certs[i..i + 2])))
+    }
+
+    /// Start building a `RevocationCheckInput` field by field.
+    ///
+    /// The serial and issuer SPKI hash must be set before
+    /// [`RevocationCheckInputBuilder::build()`].  SCTs are optional.
+    pub fn builder() -> RevocationCheckInputBuilder {
+        RevocationCheckInputBuilder::default()
+    }
+
+    /// The distinct CT logs that issued this input's SCTs, in the order they first appear.
+    pub fn sct_log_ids(&self) -> impl Iterator<Item = CtLogId> + '_ {
+        let mut seen = HashSet::new();
+        self.sct_timestamps
+            .iter()
+            .map(|ts| ts.log_id)
+            .filter(move |log_id| seen.insert(*log_id))
+    }
+
+    /// An identifier for this input's certificate that does not reveal it, for telemetry.
+    ///
+    /// This is the HMAC-SHA256 of the issuer SPKI hash and canonical serial number,
+    /// keyed with `salt`.  It is the same for every check of the same certificate with
+    /// the same salt, so repeated checks can be correlated, but cannot be linked to the
+    /// certificate without the salt.  SCTs are not included.  Use a secret salt per
+    /// deployment: with a known salt, a candidate certificate can be confirmed.
+    pub fn telemetry_id(&self, salt: &[u8]) -> [u8; 32] {
+        let mut context = hmac::Context::with_key(&hmac::Key::new(hmac::HMAC_SHA256, salt));
+        context.update(&self.issuer_spki_hash.0);
+        context.update(self.cert_serial.canonical());
+        context
+            .sign()
+            .as_ref()
+            .try_into()
+            .expect("HMAC-SHA256 tags are 32 bytes")
+    }
+
+    /// Parse a line of [`Manifest::check_stream()`] input.
+    fn from_hex_line(line: &str) -> Result<Self, Error> {
+        let mut fields = line.split_whitespace();
+        let (Some(issuer), Some(serial)) = (fields.next(), fields.next()) else {
+            return Err(Error::InvalidCheckInput);
+        };
+
+        Ok(Self {
+            issuer_spki_hash: IssuerSpkiHash(<[u8; 32]>::from_hex(issuer).map_err(|e| {
+                Error::InvalidHex {
+                    error: Box::new(e),
+                    context: "issuer SPKI hash",
+                }
+            })?),
+            cert_serial: CertSerial(Vec::from_hex(serial).map_err(|e| Error::InvalidHex {
+                error: Box::new(e),
+                context: "certificate serial",
+            })?),
+            sct_timestamps: fields
+                .map(CtTimestamp::from_str)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    fn sorted_sct_timestamps(&self) -> Vec<&CtTimestamp> {
+        let mut sorted = self
+            .sct_timestamps
+            .iter()
+            .collect::<Vec<_>>();
+        sorted.sort();
+        sorted
+    }
+
+    fn key(&self) -> CRLiteKey<'_> {
+        CRLiteKey::new(&self.issuer_spki_hash.0, self.cert_serial.canonical())
+    }
+}
+
+/// DER encoding of the CT precertificate poison extension OID, 1.3.6.1.4.1.11129.2.4.3.
+#[cfg(feature = "x509-parser")]
+const OID_CT_PRECERT_POISON: &[u8] = &[0x2b, 0x06, 0x01, 0x04, 0x01, 0xd6, 0x79, 0x02, 0x04, 0x03];
+
+/// DER encoding of the CT precertificate signing extended key usage OID,
+/// 1.3.6.1.4.1.11129.2.4.4.
+#[cfg(feature = "x509-parser")]
+const OID_CT_PRECERT_SIGNING: &[u8] = &[0x2b, 0x06, 0x01, 0x04, 0x01, 0xd6, 0x79, 0x02, 0x04, 0x04];
+
+/// Builds a [`RevocationCheckInput`], from [`RevocationCheckInput::builder()`].
+#[derive(Clone, Debug, Default)]
+pub struct RevocationCheckInputBuilder {
+    cert_serial: Option<CertSerial>,
+    issuer_spki_hash: Option<IssuerSpkiHash>,
+    sct_timestamps: Vec<CtTimestamp>,
+}
+
+impl RevocationCheckInputBuilder {
+    /// Set the end-entity certificate serial number.  Required.
+    pub fn serial(mut self, serial: impl Into<CertSerial>) -> Self {
+        self.cert_serial = Some(serial.into());
+        self
+    }
+
+    /// Set the hash of the issuer's `SubjectPublicKeyInfo`.  Required.
+    pub fn issuer_spki_hash(mut self, hash: impl Into<IssuerSpkiHash>) -> Self {
+        self.issuer_spki_hash = Some(hash.into());
+        self
+    }
+
+    /// Add an SCT from the end-entity certificate.
+    ///
+    /// Call this once for each SCT.
+    pub fn sct(mut self, sct: CtTimestamp) -> Self {
+        self.sct_timestamps.push(sct);
+        self
+    }
+
+    /// Finish building the input.
+    ///
+    /// This fails with [`Error::MissingCheckInputField`] if the serial or issuer
+    /// SPKI hash was not set.
+    pub fn build(self) -> Result<RevocationCheckInput, Error> {
+        Ok(RevocationCheckInput {
+            cert_serial: self
+                .cert_serial
+                .ok_or(Error::MissingCheckInputField("serial"))?,
+            issuer_spki_hash: self
+                .issuer_spki_hash
+                .ok_or(Error::MissingCheckInputField("issuer SPKI hash"))?,
+            sct_timestamps: self.sct_timestamps,
+        })
+    }
+}
+
+/// Values extracted from an end-entity certificate and its issuer.
+///
+/// Pass these to [`RevocationCheckInput::from_fields()`].
+#[derive(Clone, Debug)]
+pub struct CertificateFields<'a> {
+    /// The end-entity certificate's serial number.
+    ///
+    /// Required.  These are the content bytes of the DER `INTEGER`, including any
+    /// leading zero byte, as returned by most X.509 parsers as the "raw" serial.
+    pub serial: &'a [u8],
+
+    /// The DER-encoded `SubjectPublicKeyInfo` of the end-entity certificate's issuer.
+    ///
+    /// Required.  This is the complete `SEQUENCE`, including its tag and length.
+    pub issuer_spki: &'a [u8],
+
+    /// CT log IDs and inclusion timestamps from the SCTs embedded in the end-entity
+    /// certificate.
+    ///
+    /// This may be empty, but a certificate without SCTs is never covered by
+    /// revocation data.
+    pub sct_timestamps: Vec<CtTimestamp>,
+}
+
+impl PartialEq for RevocationCheckInput {
+    fn eq(&self, other: &Self) -> bool {
+        self.cert_serial == other.cert_serial
+            && self.issuer_spki_hash == other.issuer_spki_hash
+            && self.sorted_sct_timestamps() == other.sorted_sct_timestamps()
+    }
+}
+
+impl Eq for RevocationCheckInput {}
+
+impl Hash for RevocationCheckInput {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.cert_serial.hash(state);
+        self.issuer_spki_hash.hash(state);
+        self.sorted_sct_timestamps().hash(state);
+    }
+}
+
+impl FromStr for RevocationCheckInput {
+    type Err = Error;
+
+    /// Parse whitespace-separated fields: the issuer SPKI hash, the certificate serial,
+    /// and then any number of SCTs.
+    ///
+    /// These use the encodings of [`IssuerSpkiHash`], [`CertSerial`] and [`CtTimestamp`].
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut fields = value.split_whitespace();
+        let (Some(issuer), Some(serial)) = (fields.next(), fields.next()) else {
+            return Err(Error::InvalidCheckInput);
+        };
+
+        Ok(Self {
+            issuer_spki_hash: IssuerSpkiHash::from_str(issuer)?,
+            cert_serial: CertSerial::from_str(serial)?,
+            sct_timestamps: fields
+                .map(CtTimestamp::from_str)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+/// A certificate serial number.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct CertSerial(pub Vec<u8>);
+
+impl CertSerial {
+    /// Whether this is the minimal DER encoding of a certificate serial number.
+    ///
+    /// That is, it is not empty, and has no leading zero byte unless the next byte has
+    /// its top bit set.  Upstream bugs can produce other encodings of the same number,
+    /// which checks normalize unless `strict-input` is set.
+    pub fn is_canonical(&self) -> bool {
+        match self.0.as_slice() {
+            [] => false,
+            [0, next, ..] => *next >= 0x80,
+            _ => true,
+        }
+    }
+
+    /// Compare serial numbers as unsigned big-endian integers.
+    fn value_cmp(&self, other: &Self) -> Ordering {
+        let magnitude = |bytes: &[u8]| {
+            let zeros = bytes
+                .iter()
+                .take_while(|&&byte| byte == 0)
+                .count();
+            bytes.len() - zeros
+        };
+        let (len, other_len) = (magnitude(&self.0), magnitude(&other.0));
+        len.cmp(&other_len)
+            .then_with(|| self.0[self.0.len() - len..].cmp(&other.0[other.0.len() - other_len..]))
+    }
+
+    /// These bytes without redundant leading zero bytes.
+    fn canonical(&self) -> &[u8] {
+        let mut bytes = self.0.as_slice();
+        while let [0, next, ..] = bytes {
+            if *next >= 0x80 {
+                break;
+            }
+            bytes = &bytes[1..];
+        }
+        bytes
+    }
+}
+
+impl From<Vec<u8>> for CertSerial {
+    fn from(value: Vec<u8>) -> Self {
+        Self(value)
+    }
+}
+
+impl From<&[u8]> for CertSerial {
+    fn from(value: &[u8]) -> Self {
+        Self(value.to_vec())
+    }
+}
+
+impl Serialize for CertSerial {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&BASE64_STANDARD.encode(&self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for CertSerial {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Self::from_str(&value).map_err(de::Error::custom)
+    }
+}
+
+impl FromStr for CertSerial {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match BASE64_STANDARD.decode(value) {
+            Ok(bytes) => Ok(Self(bytes)),
+            Err(e) => Err(Error::InvalidBase64 {
+                error: Box::new(e),
+                context: "certificate serial",
+            }),
+        }
+    }
+}
+
+/// The SHA256 hash of a `SubjectPublicKeyInfoDer` belonging to a certificate's issuer.
+///
+/// In configuration files this is written in base64.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct IssuerSpkiHash(pub [u8; 32]);
+
+impl From<[u8; 32]> for IssuerSpkiHash {
+    fn from(value: [u8; 32]) -> Self {
+        Self(value)
+    }
+}
+
+impl Serialize for IssuerSpkiHash {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&BASE64_STANDARD.encode(self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for IssuerSpkiHash {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Self::from_str(&value).map_err(de::Error::custom)
+    }
+}
 
 impl FromStr for IssuerSpkiHash {
     type Err = Error;
@@ -256,15 +2993,82 @@ impl FromStr for IssuerSpkiHash {
     }
 }
 
+/// The ID of a certificate transparency log: the SHA256 hash of the log's public key.
+///
+/// The string representation is base64, as used in published CT log lists.  This is
+/// also how it is written in configuration files.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct CtLogId(pub [u8; 32]);
+
+impl Serialize for CtLogId {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for CtLogId {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Self::from_str(&value).map_err(de::Error::custom)
+    }
+}
+
+impl From<[u8; 32]> for CtLogId {
+    fn from(value: [u8; 32]) -> Self {
+        Self(value)
+    }
+}
+
+impl FromStr for CtLogId {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Ok(Self(
+            BASE64_STANDARD
+                .decode(value)
+                .map_err(|e| Error::InvalidBase64 {
+                    error: Box::new(e),
+                    context: "CT log ID",
+                })?
+                .try_into()
+                .map_err(|wrong: Vec<u8>| Error::InvalidLength {
+                    expected: 32,
+                    actual: wrong.len(),
+                    context: "CT log ID",
+                })?,
+        ))
+    }
+}
+
+impl fmt::Display for CtLogId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&BASE64_STANDARD.encode(self.0))
+    }
+}
+
 /// An issuance timestamp established in certificate transparency.
-#[derive(Clone, Debug)]
+///
+/// The string representation is `{log_id}:{timestamp}`.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct CtTimestamp {
     /// CT log ID
-    pub log_id: [u8; 32],
+    pub log_id: CtLogId,
     /// Issuance timestamp
+    ///
+    /// This is in milliseconds since the UNIX epoch, as in the SCT itself.
     pub timestamp: u64,
 }
 
+impl CtTimestamp {
+    /// Convert [`CtTimestamp::timestamp`] to a [`SystemTime`].
+    ///
+    /// Returns `None` if the timestamp cannot be represented as a `SystemTime` on
+    /// this platform.
+    pub fn to_system_time(&self) -> Option<SystemTime> {
+        UNIX_EPOCH.checked_add(Duration::from_millis(self.timestamp))
+    }
+}
+
 impl FromStr for CtTimestamp {
     type Err = Error;
 
@@ -274,18 +3078,7 @@ impl FromStr for CtTimestamp {
         };
 
         Ok(Self {
-            log_id: BASE64_STANDARD
-                .decode(log_id)
-                .map_err(|e| Error::InvalidBase64 {
-                    error: Box::new(e),
-                    context: "CT log ID",
-                })?
-                .try_into()
-                .map_err(|wrong: Vec<u8>| Error::InvalidLength {
-                    expected: 32,
-                    actual: wrong.len(),
-                    context: "CT log ID",
-                })?,
+            log_id: CtLogId::from_str(log_id)?,
             timestamp: u64::from_str(issuance_timestamp).map_err(|_| Error::InvalidTimestamp {
                 input: issuance_timestamp.to_string(),
                 context: "CT timestamp (in ms)",
@@ -294,10 +3087,857 @@ impl FromStr for CtTimestamp {
     }
 }
 
+impl fmt::Display for CtTimestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.log_id, self.timestamp)
+    }
+}
+
+/// The results of [`Manifest::check_batch_deadline()`].
+#[derive(Clone, Debug)]
+pub struct BatchResult {
+    /// Statuses for the inputs that were checked before the deadline.
+    ///
+    /// These correspond to the first `statuses.len()` inputs.
+    pub statuses: Vec<RevocationStatus>,
+
+    /// How many inputs were not checked because the deadline passed.
+    pub remaining: usize,
+}
+
+/// The results of [`Manifest::check_batch_collect_uncovered()`].
+///
+/// Inputs are identified by their index, and listed in the order they were given.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BatchPartition {
+    /// The inputs whose status was decided, with their status.
+    pub decided: Vec<(usize, RevocationStatus)>,
+
+    /// The inputs that are not covered by the revocation data.
+    pub uncovered: Vec<usize>,
+}
+
+/// The results of [`Manifest::coverage_report()`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct CoverageReport {
+    /// The manifest the inputs were checked against.
+    pub version: ManifestVersion,
+
+    /// How many inputs were checked.
+    pub total: usize,
+
+    /// How many inputs were [`RevocationStatus::CertainlyRevoked`].
+    pub revoked: usize,
+
+    /// How many inputs were [`RevocationStatus::NotRevoked`].
+    pub not_revoked: usize,
+
+    /// How many inputs were [`RevocationStatus::NotCoveredByRevocationData`], by
+    /// reason.
+    ///
+    /// The key is `None` for inputs whose outcome had no reason.  Reasons that no
+    /// input had are absent.
+    pub not_covered: HashMap<Option<NotCoveredReason>, usize>,
+}
+
+impl CoverageReport {
+    /// How many inputs the revocation data covered, whether revoked or not.
+    pub fn covered(&self) -> usize {
+        self.revoked + self.not_revoked
+    }
+}
+
+/// Another source of revocation information, for certificates the revocation data
+/// does not cover.
+///
+/// Used by [`Manifest::check_with_fallback()`].  Implementations might query OCSP, a
+/// CRL, or a deployment's own revocation service.
+pub trait RevocationFallback {
+    /// Determine the revocation status of the certificate described by `input`.
+    ///
+    /// `outcome` is the result of checking `input` against the revocation data, and
+    /// says why it was not covered.
+    fn check(
+        &self,
+        input: &RevocationCheckInput,
+        outcome: &CheckOutcome,
+    ) -> Result<RevocationStatus, Box<dyn StdError + Send + Sync>>;
+}
+
+/// The results of [`Manifest::sct_coverage()`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SctCoverage {
+    /// How many SCTs fall within the coverage of the revocation data.
+    ///
+    /// As for a check, SCTs are counted against each filter separately, and this is
+    /// the largest count.
+    pub covered: usize,
+
+    /// How many SCTs were not counted because their timestamp is in the future.
+    pub future: usize,
+
+    /// Whether `covered` is at least the configured `min-covered-scts`.
+    pub sufficient: bool,
+}
+
+/// The format of the records written by [`Manifest::check_stream()`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StreamFormat {
+    /// Comma-separated values, with a header line.
+    ///
+    /// The columns are `line`, `status` and `error`; exactly one of `status` and
+    /// `error` is non-empty.
+    Csv,
+
+    /// One JSON object per line.
+    ///
+    /// Each object has a `line` field, and either a `status` or an `error` field.
+    JsonLines,
+}
+
+/// A JSON lines record written by [`Manifest::check_stream()`].
+#[derive(Serialize)]
+struct StreamRecord {
+    line: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// A set of certificates known not to be revoked, for [`Manifest::check_with_fast_path()`].
+///
+/// The set is tied to the manifest it was built for.  Entries should be certificates
+/// that [`Manifest::check()`] found to be [`RevocationStatus::NotRevoked`] against
+/// that manifest; they are not checked again.
+#[derive(Clone, Debug)]
+pub struct FastAllowSet {
+    generated_at: u64,
+    entries: HashSet<([u8; 32], Vec<u8>)>,
+}
+
+impl FastAllowSet {
+    /// Start an empty set for `manifest`.
+    pub fn new(manifest: &Manifest) -> Self {
+        Self {
+            generated_at: manifest.generated_at,
+            entries: HashSet::new(),
+        }
+    }
+
+    /// Add the certificate with `serial`, issued by `issuer`.
+    pub fn insert(&mut self, issuer: &IssuerSpkiHash, serial: &CertSerial) {
+        self.entries
+            .insert((issuer.0, serial.canonical().to_vec()));
+    }
+}
+
+/// A [`RevocationStatus`], along with how it was determined.
+///
+/// Returned by [`Manifest::check_detailed()`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct CheckOutcome {
+    /// The revocation status.
+    pub status: RevocationStatus,
+
+    /// Why the revocation status could not be determined.
+    ///
+    /// This is `Some` exactly when `status` is [`RevocationStatus::NotCoveredByRevocationData`].
+    pub not_covered_reason: Option<NotCoveredReason>,
+
+    /// Whether `status` is definitive.
+    pub match_kind: MatchKind,
+
+    /// How many of the certificate's SCTs fall within the coverage of the revocation data.
+    ///
+    /// When the certificate is not covered because it has fewer such SCTs than
+    /// the configured `min-covered-scts`, this is the number it does have.
+    pub covered_sct_count: usize,
+
+    /// [`Manifest::generated_at`] of the manifest used for the check.
+    pub generated_at: u64,
+
+    /// How the certificate's SCTs relate to the coverage of the revocation data.
+    ///
+    /// This is `Some` when `not_covered_reason` is [`NotCoveredReason::SctsNotCovered`],
+    /// and is not included in [`CheckOutcome::encode()`].
+    pub sct_gap: Option<SctGap>,
+
+    /// Problems noticed during the check that did not affect `status`.
+    ///
+    /// These are not included in [`CheckOutcome::encode()`].
+    pub warnings: Vec<Warning>,
+
+    /// The certificate's SCTs that could not count towards its coverage, and why.
+    ///
+    /// This is only filled in when the `report-rejected-scts` setting is enabled, for
+    /// debugging CT policy, and is not included in [`CheckOutcome::encode()`].  SCTs
+    /// are in the order the certificate has them, except that those dropped by the
+    /// `duplicate-sct-policy` come first.
+    pub rejected_scts: Vec<RejectedSct>,
+
+    /// What decided `status`, for attributing decisions in audit logs.
+    ///
+    /// This is not included in [`CheckOutcome::encode()`]: [`CheckOutcome::decode()`]
+    /// gives the source a check of the revocation data alone would have.
+    pub source: DecisionSource,
+
+    /// The time the check was evaluated at, for audit logs.
+    ///
+    /// This is the current time when the check was made, or the time passed to
+    /// [`Manifest::check_at()`].  SCTs are judged future-dated, and coverage stale,
+    /// relative to it.  It is not included in [`CheckOutcome::encode()`], so
+    /// [`CheckOutcome::decode()`] gives [`UNIX_EPOCH`], and outcomes compare equal
+    /// whatever it is: it records when a decision was made, not what it was.
+    pub evaluated_at: SystemTime,
+}
+
+impl PartialEq for CheckOutcome {
+    fn eq(&self, other: &Self) -> bool {
+        let Self {
+            status,
+            not_covered_reason,
+            match_kind,
+            covered_sct_count,
+            generated_at,
+            sct_gap,
+            warnings,
+            rejected_scts,
+            source,
+            evaluated_at: _,
+        } = self;
+        (
+            status,
+            not_covered_reason,
+            match_kind,
+            covered_sct_count,
+            generated_at,
+            sct_gap,
+            warnings,
+            rejected_scts,
+            source,
+        ) == (
+            &other.status,
+            &other.not_covered_reason,
+            &other.match_kind,
+            &other.covered_sct_count,
+            &other.generated_at,
+            &other.sct_gap,
+            &other.warnings,
+            &other.rejected_scts,
+            &other.source,
+        )
+    }
+}
+
+impl CheckOutcome {
+    /// Describe this outcome in several lines of plain text, for people.
+    ///
+    /// This covers the decision, why it was reached, the manifest that was used and
+    /// its age, and what the caller could do next.  The wording may change between
+    /// versions, so it should not be parsed.
+    pub fn explain(&self) -> String {
+        let decision = match self.status {
+            RevocationStatus::CertainlyRevoked => "the certificate is revoked",
+            RevocationStatus::NotRevoked => "the certificate is not revoked",
+            RevocationStatus::NotCoveredByRevocationData => {
+                "the certificate is not covered by revocation data"
+            }
+        };
+
+        let reason = match self.not_covered_reason {
+            None => "the issuer is enrolled, and the certificate is within the data's coverage",
+            Some(NotCoveredReason::Bypassed) => {
+                "bypassed: the issuer is configured to skip revocation data"
+            }
+            Some(NotCoveredReason::IssuerNotEnrolled) => {
+                "issuer not enrolled: no filter has data for the certificate's issuer"
+            }
+            Some(NotCoveredReason::NoScts) => {
+                "no SCTs: the certificate has no SCTs, and the no-sct-policy requires them"
+            }
+            Some(NotCoveredReason::SctsNotCovered) => {
+                "SCTs not covered: none of the certificate's SCTs fall within the data's coverage"
+            }
+            Some(NotCoveredReason::OutOfScope) => {
+                "out of scope: the issuer is not in the configured in-scope-issuers"
+            }
+            Some(NotCoveredReason::NeedsConfirmation) => {
+                "needs confirmation: the serial number may be revoked, and the probabilistic-policy is strict"
+            }
+            Some(NotCoveredReason::CheckError) => {
+                "check error: the revocation data could not be read"
+            }
+            Some(NotCoveredReason::InsufficientScts) => {
+                "insufficient SCTs: the certificate has fewer SCTs than the configured min-sct-count"
+            }
+        };
+
+        let match_kind = match self.match_kind {
+            MatchKind::Exact => "exact",
+            MatchKind::Probabilistic => {
+                "probabilistic (serial number lookup outside the data's coverage; may be wrong)"
+            }
+        };
+
+        let date = i64::try_from(self.generated_at)
+            .ok()
+            .and_then(|secs| DateTime::<Utc>::from_timestamp(secs, 0));
+        let age = crate::clock::now()
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .and_then(|now| {
+                now.as_secs()
+                    .checked_sub(self.generated_at)
+            });
+        let manifest = match (date, age) {
+            (Some(date), Some(age)) => format!(
+                "generated at {} ({}), {} hours old",
+                self.generated_at,
+                date.to_rfc3339(),
+                age / 3600
+            ),
+            (Some(date), None) => format!(
+                "generated at {} ({}), which is in the future",
+                self.generated_at,
+                date.to_rfc3339(),
+            ),
+            (None, _) => format!("generated at {}", self.generated_at),
+        };
+
+        let next = match (self.status, self.match_kind) {
+            (RevocationStatus::CertainlyRevoked, MatchKind::Exact) => "reject the certificate",
+            (RevocationStatus::NotRevoked, MatchKind::Exact) => {
+                "accept the certificate, as far as revocation is concerned"
+            }
+            (RevocationStatus::NotCoveredByRevocationData, _) => {
+                "fall back to another revocation mechanism, such as OCSP or CRLs; \
+                 if the certificate is new, running `upki fetch` later may cover it"
+            }
+            (_, MatchKind::Probabilistic) => {
+                "confirm the decision with another revocation mechanism, such as OCSP or CRLs"
+            }
+        };
+
+        format!(
+            "decision: {decision}\n\
+             reason: {reason}\n\
+             match: {match_kind}\n\
+             covered SCTs: {}\n\
+             manifest: {manifest}\n\
+             next step: {next}\n",
+            self.covered_sct_count
+        )
+    }
+
+    /// Suggest why a certificate was not covered, and what might be done about it.
+    ///
+    /// This is a heuristic intended for support staff: it looks at the reason the
+    /// certificate was not covered and at the age of the manifest at `now`, and
+    /// guesses the most likely cause.  The guess can be wrong.
+    ///
+    /// Returns `None` if the certificate was covered.
+    pub fn coverage_advice(&self, now: SystemTime) -> Option<CoverageAdvice> {
+        let reason = self.not_covered_reason?;
+        let age = now
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .and_then(|now| {
+                now.as_secs()
+                    .checked_sub(self.generated_at)
+            })
+            .unwrap_or(0);
+
+        Some(match reason {
+            NotCoveredReason::Bypassed => CoverageAdvice::Bypassed,
+            NotCoveredReason::NoScts => CoverageAdvice::NoScts,
+            NotCoveredReason::OutOfScope => CoverageAdvice::OutOfScope,
+            NotCoveredReason::NeedsConfirmation => CoverageAdvice::NeedsConfirmation,
+            NotCoveredReason::CheckError => CoverageAdvice::CheckError,
+            NotCoveredReason::InsufficientScts => CoverageAdvice::InsufficientScts,
+            NotCoveredReason::IssuerNotEnrolled | NotCoveredReason::SctsNotCovered
+                if age >= STALE_MANIFEST_AGE =>
+            {
+                CoverageAdvice::StaleManifest {
+                    age_days: age / 86_400,
+                }
+            }
+            NotCoveredReason::IssuerNotEnrolled => CoverageAdvice::PrivateIssuer,
+            NotCoveredReason::SctsNotCovered => CoverageAdvice::TooNew,
+        })
+    }
+
+    /// Encode this outcome in a compact binary form, for passing between processes.
+    ///
+    /// The encoding is [`Self::ENCODED_LEN`] bytes:
+    ///
+    /// | Offset | Size | Contents |
+    /// |--------|------|----------|
+    /// | 0      | 1    | Format version: 1 |
+    /// | 1      | 1    | `status`: 0 not covered, 1 revoked, 2 not revoked |
+    /// | 2      | 1    | `not_covered_reason`: 0 none, 1 bypassed, 2 issuer not enrolled, 3 no SCTs, 4 SCTs not covered, 5 out of scope, 6 needs confirmation, 7 check error, 8 insufficient SCTs |
+    /// | 3      | 1    | `match_kind`: 0 exact, 1 probabilistic |
+    /// | 4      | 8    | `covered_sct_count`, big-endian |
+    /// | 12     | 8    | `generated_at`, big-endian |
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(Self::ENCODED_LEN);
+        out.push(OUTCOME_ENCODING_VERSION);
+        out.push(match self.status {
+            RevocationStatus::NotCoveredByRevocationData => 0,
+            RevocationStatus::CertainlyRevoked => 1,
+            RevocationStatus::NotRevoked => 2,
+        });
+        out.push(match self.not_covered_reason {
+            None => 0,
+            Some(NotCoveredReason::Bypassed) => 1,
+            Some(NotCoveredReason::IssuerNotEnrolled) => 2,
+            Some(NotCoveredReason::NoScts) => 3,
+            Some(NotCoveredReason::SctsNotCovered) => 4,
+            Some(NotCoveredReason::OutOfScope) => 5,
+            Some(NotCoveredReason::NeedsConfirmation) => 6,
+            Some(NotCoveredReason::CheckError) => 7,
+            Some(NotCoveredReason::InsufficientScts) => 8,
+        });
+        out.push(match self.match_kind {
+            MatchKind::Exact => 0,
+            MatchKind::Probabilistic => 1,
+        });
+        out.extend_from_slice(&(self.covered_sct_count as u64).to_be_bytes());
+        out.extend_from_slice(&self.generated_at.to_be_bytes());
+        out
+    }
+
+    /// Decode an outcome produced by [`CheckOutcome::encode()`].
+    ///
+    /// This fails with [`Error::InvalidOutcomeEncoding`] if `bytes` has the wrong
+    /// length or version, contains an unknown value, or describes an impossible
+    /// outcome (such as a revoked certificate with a not-covered reason).
+    pub fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        let Ok(bytes) = <&[u8; Self::ENCODED_LEN]>::try_from(bytes) else {
+            return Err(Error::InvalidOutcomeEncoding("wrong length"));
+        };
+        if bytes[0] != OUTCOME_ENCODING_VERSION {
+            return Err(Error::InvalidOutcomeEncoding("unsupported version"));
+        }
+
+        let status = match bytes[1] {
+            0 => RevocationStatus::NotCoveredByRevocationData,
+            1 => RevocationStatus::CertainlyRevoked,
+            2 => RevocationStatus::NotRevoked,
+            _ => return Err(Error::InvalidOutcomeEncoding("unknown status")),
+        };
+        let not_covered_reason = match bytes[2] {
+            0 => None,
+            1 => Some(NotCoveredReason::Bypassed),
+            2 => Some(NotCoveredReason::IssuerNotEnrolled),
+            3 => Some(NotCoveredReason::NoScts),
+            4 => Some(NotCoveredReason::SctsNotCovered),
+            5 => Some(NotCoveredReason::OutOfScope),
+            6 => Some(NotCoveredReason::NeedsConfirmation),
+            7 => Some(NotCoveredReason::CheckError),
+            8 => Some(NotCoveredReason::InsufficientScts),
+            _ => return Err(Error::InvalidOutcomeEncoding("unknown not-covered reason")),
+        };
+        if not_covered_reason.is_some() != (status == RevocationStatus::NotCoveredByRevocationData)
+        {
+            return Err(Error::InvalidOutcomeEncoding(
+                "not-covered reason does not match status",
+            ));
+        }
+        let match_kind = match bytes[3] {
+            0 => MatchKind::Exact,
+            1 => MatchKind::Probabilistic,
+            _ => return Err(Error::InvalidOutcomeEncoding("unknown match kind")),
+        };
+
+        let u64_at = |offset: usize| {
+            let mut value = [0; 8];
+            value.copy_from_slice(&bytes[offset..offset + 8]);
+            u64::from_be_bytes(value)
+        };
+        Ok(Self {
+            status,
+            not_covered_reason,
+            match_kind,
+            covered_sct_count: usize::try_from(u64_at(4))
+                .map_err(|_| Error::InvalidOutcomeEncoding("covered SCT count too large"))?,
+            generated_at: u64_at(12),
+            sct_gap: None,
+            warnings: Vec::new(),
+            rejected_scts: Vec::new(),
+            source: DecisionSource::of(not_covered_reason),
+            evaluated_at: UNIX_EPOCH,
+        })
+    }
+
+    /// The length of the output of [`CheckOutcome::encode()`].
+    pub const ENCODED_LEN: usize = 20;
+}
+
+const OUTCOME_ENCODING_VERSION: u8 = 1;
+
+/// What decided a [`CheckOutcome`].
+///
+/// See [`CheckOutcome::source`].
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum DecisionSource {
+    /// The revocation data, as the configuration says to use it.
+    ///
+    /// This includes decisions that the data does not cover the certificate, and
+    /// that its issuer is not in `in-scope-issuers`.
+    Filter,
+
+    /// An emergency override of the revocation data.
+    ///
+    /// Nothing in this crate makes such overrides yet.  This is for callers that
+    /// layer their own over a [`CheckOutcome`], so that they can record it.
+    Override,
+
+    /// The [`FastAllowSet`] passed to [`Manifest::check_detailed_with_fast_path()`].
+    Allowlist,
+
+    /// The issuer is in `bypass-issuers`, so the revocation data was not consulted.
+    Bypass,
+
+    /// The [`RevocationFallback`] passed to [`Manifest::check_detailed_with_fallback()`].
+    Fallback,
+}
+
+impl DecisionSource {
+    /// The source of an outcome of checking the revocation data with `reason`.
+    fn of(reason: Option<NotCoveredReason>) -> Self {
+        match reason {
+            Some(NotCoveredReason::Bypassed) => Self::Bypass,
+            _ => Self::Filter,
+        }
+    }
+}
+
+/// An SCT that could not count towards a certificate's coverage.
+///
+/// See [`CheckOutcome::rejected_scts`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct RejectedSct {
+    /// The SCT.
+    pub sct: CtTimestamp,
+
+    /// Why it was rejected.
+    pub reason: SctRejection,
+}
+
+/// Why an SCT could not count towards a certificate's coverage, in a [`RejectedSct`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum SctRejection {
+    /// The certificate has another SCT from the same log with a different timestamp,
+    /// and the `duplicate-sct-policy` chose not to use this one.
+    Conflicting,
+
+    /// The SCT is dated beyond the `sct-skew-tolerance`.
+    Future,
+
+    /// The SCT's log is not in the configured `approved-logs`.
+    UnapprovedLog,
+
+    /// The SCT is dated outside its log's configured `log-windows`.
+    Disqualified,
+
+    /// No filter covers the SCT's log.
+    UnknownLog,
+}
+
+/// Why a certificate's SCTs were outside the coverage of the revocation data.
+///
+/// See [`CheckOutcome::sct_gap`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct SctGap {
+    /// Whether the revocation data includes the certificate's issuer.
+    ///
+    /// If so, the certificate is most likely newer than the data, and will be
+    /// covered by a later manifest.  If not, the issuer is not tracked at all.
+    pub issuer_covered: bool,
+
+    /// The latest SCT timestamp covered, among the logs the certificate has SCTs from.
+    ///
+    /// UNIX timestamp in milliseconds.  This is `None` if the data covers none of
+    /// those logs.
+    pub coverage_end: Option<u64>,
+
+    /// The earliest of the certificate's SCT timestamps.
+    ///
+    /// UNIX timestamp in milliseconds.
+    pub earliest_sct: u64,
+}
+
+impl SctGap {
+    /// How much later the certificate's earliest SCT is than `coverage_end`.
+    ///
+    /// Returns `None` if `coverage_end` is `None`, or is not before `earliest_sct`.
+    pub fn gap(&self) -> Option<Duration> {
+        let gap = self
+            .earliest_sct
+            .checked_sub(self.coverage_end?)?;
+        (gap > 0).then(|| Duration::from_millis(gap))
+    }
+}
+
+/// What [`Manifest::from_config_with_report()`] noticed while loading a manifest.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct LoadReport {
+    /// Problems that did not prevent loading the manifest.
+    pub warnings: Vec<Warning>,
+}
+
+/// A problem that did not cause an operation to fail, but deserves attention.
+///
+/// See [`CheckOutcome::warnings`] and [`LoadReport::warnings`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Warning {
+    /// The certificate had SCTs from one log with different timestamps.
+    ///
+    /// See [`DuplicateSctPolicy`].
+    ConflictingScts {
+        /// The log concerned.
+        log_id: CtLogId,
+        /// The timestamp of the SCT that was used, or `None` if they were all ignored.
+        used: Option<u64>,
+    },
+
+    /// An SCT was dated outside its log's configured `log-windows`, and so was ignored.
+    DisqualifiedSct {
+        /// The log that the SCT claims to be from.
+        log_id: CtLogId,
+        /// The SCT's timestamp, in UNIX milliseconds.
+        timestamp: u64,
+    },
+
+    /// An SCT was dated too far in the future, and so was ignored.
+    ///
+    /// See the `sct-skew-tolerance` setting.
+    FutureSct {
+        /// The log that the SCT claims to be from.
+        log_id: CtLogId,
+        /// The SCT's timestamp, in UNIX milliseconds.
+        timestamp: u64,
+    },
+
+    /// The coverage of the revocation data ends longer ago than the configured
+    /// `stale-coverage-threshold`.
+    ///
+    /// See [`StaleCoveragePolicy`] for how checks treat this.
+    StaleCoverage {
+        /// The latest SCT timestamp any filter covers, in UNIX milliseconds.
+        coverage_end: u64,
+    },
+
+    /// The manifest is more than two days old.
+    ///
+    /// Checks still use it, but certificates issued since it was generated are not
+    /// covered.  Run `upki fetch`.
+    StaleManifest {
+        /// The manifest's [`Manifest::generated_at`].
+        generated_at: u64,
+    },
+
+    /// An SCT was issued by a CT log that no filter covers.
+    ///
+    /// The SCT cannot contribute to coverage.  The log may be new, or not yet
+    /// included in the revocation data.
+    UnknownLog {
+        /// The log's ID.
+        log_id: CtLogId,
+    },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ConflictingScts { log_id, used } => match used {
+                Some(used) => write!(f, "conflicting SCTs from CT log {log_id}, used {used}"),
+                None => write!(f, "conflicting SCTs from CT log {log_id}, ignored them all"),
+            },
+            Self::DisqualifiedSct { log_id, timestamp } => {
+                write!(
+                    f,
+                    "ignored SCT from CT log {log_id} dated outside its qualification window ({timestamp})"
+                )
+            }
+            Self::FutureSct { log_id, timestamp } => {
+                write!(
+                    f,
+                    "ignored SCT from CT log {log_id} dated in the future ({timestamp})"
+                )
+            }
+            Self::StaleCoverage { coverage_end } => {
+                write!(
+                    f,
+                    "revocation data coverage ending at {coverage_end} is stale"
+                )
+            }
+            Self::StaleManifest { generated_at } => {
+                write!(f, "manifest generated at {generated_at} is stale")
+            }
+            Self::UnknownLog { log_id } => {
+                write!(f, "no filter covers CT log {log_id}")
+            }
+        }
+    }
+}
+
+/// A manifest older than this, in seconds, is considered stale by [`CheckOutcome::coverage_advice()`].
+const STALE_MANIFEST_AGE: u64 = 2 * 86_400;
+
+/// A guess at why a certificate was not covered by revocation data.
+///
+/// Returned by [`CheckOutcome::coverage_advice()`].  The `Display` implementation
+/// gives a short hint suitable for showing to an operator.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CoverageAdvice {
+    /// The issuer is configured in `bypass-issuers`.  This is deliberate.
+    Bypassed,
+
+    /// The revocation data could not be read, so the cache needs repairing.
+    CheckError,
+
+    /// The certificate has fewer SCTs than `min-sct-count`.  It may not be CT
+    /// compliant.
+    InsufficientScts,
+
+    /// The certificate may be revoked, but the `probabilistic-policy` requires
+    /// confirmation from another revocation mechanism.
+    NeedsConfirmation,
+
+    /// The certificate has no SCTs.  It may not be publicly trusted, or the
+    /// `no-sct-policy` could allow a serial-only check.
+    NoScts,
+
+    /// The issuer is not in `in-scope-issuers`.  This is deliberate.
+    OutOfScope,
+
+    /// The issuer has no data in a fresh manifest, so it is probably not a public CA.
+    PrivateIssuer,
+
+    /// The manifest is at least two days old, and a newer one may well cover the
+    /// certificate.
+    StaleManifest {
+        /// Age of the manifest, in whole days.
+        age_days: u64,
+    },
+
+    /// The issuer has data in a fresh manifest, but the certificate's SCTs are
+    /// outside its coverage: it was probably issued after the data was generated.
+    TooNew,
+}
+
+impl fmt::Display for CoverageAdvice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bypassed => write!(
+                f,
+                "issuer is listed in bypass-issuers -- remove it to use revocation data"
+            ),
+            Self::CheckError => write!(
+                f,
+                "revocation data could not be read -- check the logs and run `upki fetch`"
+            ),
+            Self::InsufficientScts => write!(
+                f,
+                "certificate has fewer SCTs than min-sct-count -- is it CT compliant?"
+            ),
+            Self::NeedsConfirmation => {
+                write!(f, "certificate may be revoked -- confirm with OCSP or CRLs")
+            }
+            Self::NoScts => write!(
+                f,
+                "certificate has no SCTs -- is it publicly trusted? consider no-sct-policy"
+            ),
+            Self::OutOfScope => write!(
+                f,
+                "issuer is not in in-scope-issuers -- add it to use revocation data"
+            ),
+            Self::PrivateIssuer => {
+                write!(f, "issuer not in this manifest -- is it a private CA?")
+            }
+            Self::StaleManifest { age_days } => {
+                write!(f, "manifest is {age_days} days stale -- run `upki fetch`")
+            }
+            Self::TooNew => write!(
+                f,
+                "certificate is newer than the data's coverage -- too new, try again later"
+            ),
+        }
+    }
+}
+
+/// Why a certificate was not covered by revocation data.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum NotCoveredReason {
+    /// The certificate's issuer is listed in the `bypass-issuers` configuration,
+    /// so the revocation data was not consulted.
+    Bypassed,
+
+    /// No filter has data for the certificate's issuer.
+    IssuerNotEnrolled,
+
+    /// The certificate has no SCTs, and the [`NoSctPolicy`] in effect did not
+    /// allow its status to be decided without them.
+    NoScts,
+
+    /// The certificate's issuer has data, but none of the certificate's SCTs
+    /// fall within the time ranges the data covers.
+    SctsNotCovered,
+
+    /// The certificate's issuer is not listed in the `in-scope-issuers` configuration,
+    /// so the revocation data was not consulted.
+    ///
+    /// Unlike [`NotCoveredReason::Bypassed`], this does not mean the issuer's
+    /// revocation is checked some other way: just that the deployment does not care
+    /// about certificates from it.
+    OutOfScope,
+
+    /// The certificate's serial number was found by a probabilistic lookup, and the
+    /// [`ProbabilisticPolicy`] in effect requires that to be confirmed some other way.
+    NeedsConfirmation,
+
+    /// The revocation data could not be consulted because of an error.
+    ///
+    /// This is only returned by [`Manifest::check_lenient()`], in place of the error.
+    CheckError,
+
+    /// The certificate has fewer SCTs than the configured `min-sct-count`, so the
+    /// revocation data was not consulted.
+    InsufficientScts,
+}
+
+/// How definitive a [`CheckOutcome`] is.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MatchKind {
+    /// The status is exact.
+    ///
+    /// This is the case for certificates whose SCTs put them within the coverage
+    /// of the revocation data.
+    Exact,
+
+    /// The status comes from a serial number lookup outside the known coverage of
+    /// the revocation data, and may be wrong.
+    ///
+    /// This only happens for certificates without SCTs, according to the [`NoSctPolicy`].
+    Probabilistic,
+}
+
 /// The successful outcome of a revocation check.
 ///
 /// Look at a value of this type to determine whether a certificate was revoked or not.
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 #[must_use]
 pub enum RevocationStatus {
     /// We couldn't determine the revocation status.
@@ -313,6 +3953,28 @@ pub enum RevocationStatus {
 }
 
 impl RevocationStatus {
+    /// Returns whichever of `self` and `other` is worse for the certificate(s) concerned.
+    ///
+    /// This is for combining statuses, like those of each certificate in a chain.
+    /// [`RevocationStatus::CertainlyRevoked`] is worst: one revoked certificate must
+    /// not be masked by any other status.  Next is [`RevocationStatus::NotCoveredByRevocationData`],
+    /// because missing information cannot vouch for the certificate.  Finally,
+    /// [`RevocationStatus::NotRevoked`] is only the result if both are not revoked.
+    pub fn worse_of(self, other: Self) -> Self {
+        match self.severity() >= other.severity() {
+            true => self,
+            false => other,
+        }
+    }
+
+    fn severity(&self) -> u8 {
+        match self {
+            Self::NotRevoked => 0,
+            Self::NotCoveredByRevocationData => 1,
+            Self::CertainlyRevoked => 2,
+        }
+    }
+
     /// Convert this revocation status to an exit code for the CLI.
     ///
     /// Also print the status to stdout.
@@ -328,21 +3990,741 @@ impl RevocationStatus {
 }
 
 /// Details about crlite-style revocation.
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub struct RevocationConfig {
     /// Where to fetch revocation data files.
     fetch_url: String,
+
+    /// What to do when checking a certificate that has no SCTs.
+    #[serde(default)]
+    no_sct_policy: NoSctPolicy,
+
+    /// Accept manifests older than one previously accepted.
+    ///
+    /// This is for intentionally rolling back to older revocation data.
+    #[serde(default)]
+    allow_downgrade: bool,
+
+    /// How many of a certificate's SCTs must fall within the coverage of the
+    /// revocation data for its status to be decided.
+    ///
+    /// Raising this from the default of 1 requires, for example, that two
+    /// independent logs place the certificate within coverage.  Values below 1
+    /// are treated as 1.
+    #[serde(default = "default_min_covered_scts")]
+    min_covered_scts: usize,
+
+    /// How many SCTs a certificate must have for the revocation data to be consulted.
+    ///
+    /// Certificates with fewer are `NotCoveredByRevocationData`, with the reason
+    /// [`NotCoveredReason::InsufficientScts`], whether or not the data covers them.
+    /// This makes CT presence a precondition, whatever the `no-sct-policy`.  SCTs are
+    /// counted after applying the `duplicate-sct-policy`, but before checking their
+    /// logs or timestamps.  The issuers in `sct-exempt-issuers` are exempt.  The
+    /// default of 0 requires none.
+    #[serde(default)]
+    min_sct_count: usize,
+
+    /// Issuers whose certificates are never checked against the revocation data.
+    ///
+    /// Certificates from these issuers are always `NotCoveredByRevocationData`,
+    /// with the reason [`NotCoveredReason::Bypassed`], so upki will never report
+    /// them as revoked.  Only list issuers whose revocation is checked some other
+    /// way.
+    #[serde(default)]
+    bypass_issuers: Vec<IssuerSpkiHash>,
+
+    /// The CT logs whose SCTs count towards a certificate's coverage.
+    ///
+    /// SCTs from other logs are ignored when deciding whether a certificate is
+    /// covered, as if the revocation data did not cover those logs.  When this is
+    /// empty, the default, every log the revocation data covers counts.  Combine
+    /// with `min-covered-scts` to require SCTs from several approved logs.
+    #[serde(default)]
+    approved_logs: Vec<CtLogId>,
+
+    /// When each CT log's SCTs count towards a certificate's coverage.
+    ///
+    /// A log is qualified in a browser's CT policy from some date, and may later be
+    /// retired; SCTs it issued outside that window do not show the certificate was
+    /// logged.  SCTs from a log with windows here only count if their timestamp is
+    /// within one of them, and are otherwise reported with [`Warning::DisqualifiedSct`].
+    /// Logs not listed here are not restricted.
+    #[serde(default)]
+    log_windows: Vec<LogWindow>,
+
+    /// How [`Manifest::check_with_policy()`] reports certificates that are not
+    /// covered by the revocation data.
+    #[serde(default)]
+    uncovered_policy: UncoveredPolicy,
+
+    /// The largest manifest, in bytes, that will be downloaded or loaded.
+    ///
+    /// Larger manifests are refused with [`Error::ManifestTooLarge`].  Real manifests
+    /// are a few kilobytes, so the default of 1 MiB leaves plenty of room.
+    #[serde(default = "default_max_manifest_bytes")]
+    max_manifest_bytes: u64,
+
+    /// The only issuers whose certificates are checked against the revocation data.
+    ///
+    /// Certificates from other issuers are `NotCoveredByRevocationData`, with the
+    /// reason [`NotCoveredReason::OutOfScope`], without any filter lookup.  When this
+    /// is empty, the default, every issuer is in scope.
+    ///
+    /// This is for deployments that only care about certificates chaining to some
+    /// trust anchors.  It differs from `bypass-issuers` in intent: a bypassed issuer
+    /// is trusted to be checked some other way, while an out of scope one is simply
+    /// of no interest.  An issuer in both is reported as out of scope.
+    ///
+    /// Filters whose manifest entry lists only other issuers (see [`Filter::issuers`])
+    /// are not loaded at all, saving the memory and time they would take.
+    #[serde(default)]
+    in_scope_issuers: Vec<IssuerSpkiHash>,
+
+    /// The longest random delay, in seconds, that [`spawn_auto_refresh()`] adds before
+    /// each scheduled fetch.
+    ///
+    /// A fleet of clients refreshing on the same schedule would otherwise all fetch at
+    /// the same moment.  The default of 0 adds no delay.
+    #[serde(default)]
+    refresh_splay: u64,
+
+    /// Makes the `refresh-splay` delay the same for every fetch, rather than random.
+    ///
+    /// Give each host a different seed (such as its hostname) to spread a fleet's
+    /// fetches out, while keeping each host's schedule reproducible.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    refresh_splay_seed: Option<String>,
+
+    /// What to do when a probabilistic lookup finds a certificate's serial number.
+    #[serde(default)]
+    probabilistic_policy: ProbabilisticPolicy,
+
+    /// How far ahead of the current time, in seconds, an SCT's timestamp may be.
+    ///
+    /// SCTs dated further in the future are ignored by checks, and reported as a
+    /// [`Warning::FutureSct`]: a log cannot have issued them yet, so they may be an
+    /// attempt to claim coverage the certificate does not have.  The default of 3600
+    /// allows for an hour of clock skew.
+    #[serde(default = "default_sct_skew_tolerance")]
+    sct_skew_tolerance: u64,
+
+    /// What to do with a certificate that has SCTs from one log with different
+    /// timestamps.
+    #[serde(default)]
+    duplicate_sct_policy: DuplicateSctPolicy,
+
+    /// Where `upki fetch` writes a new manifest before renaming it over the old one.
+    ///
+    /// The default is the revocation cache directory.  If this is on a different
+    /// filesystem, the file is copied into the cache directory to be renamed there,
+    /// so replacing the manifest is still atomic.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    manifest_temp_dir: Option<PathBuf>,
+
+    /// Issuers whose certificates are covered without SCTs.
+    ///
+    /// Private CAs do not log their certificates to CT, so their certificates
+    /// would otherwise never be covered.  A certificate from one of these issuers
+    /// is looked up by serial number alone, like one without SCTs under
+    /// `no-sct-policy = "treat-as-covered"`, and is covered if the revocation data
+    /// has any entries for its issuer.  This only decides coverage: revoked
+    /// certificates are still found, subject to `probabilistic-policy`.
+    #[serde(default)]
+    sct_exempt_issuers: Vec<IssuerSpkiHash>,
+
+    /// Record each SCT that could not count towards a certificate's coverage.
+    ///
+    /// They are listed, with the reason, in [`CheckOutcome::rejected_scts`].  This is
+    /// for finding out why certificates are unexpectedly not covered under a strict
+    /// `approved-logs` policy, and is off by default.
+    #[serde(default)]
+    report_rejected_scts: bool,
+
+    /// How many epochs of revocation data [`fetch()`] keeps in the cache.
+    ///
+    /// When this is more than 0, the manifest of each epoch fetched (see
+    /// [`Manifest::epoch()`]) is kept, along with its filters, until this many newer
+    /// epochs have been fetched; the current epoch counts as one.  Use
+    /// [`Manifest::from_config_at_epoch()`] to check against them.  Filters must have
+    /// different names in each epoch.  The default of 0 keeps only the current
+    /// manifest.
+    #[serde(default)]
+    retain_epochs: usize,
+
+    /// How long ago, in seconds, the coverage of the revocation data may end before it
+    /// is considered stale.
+    ///
+    /// Coverage ends at the latest SCT timestamp any filter covers.  When that lags
+    /// the current time by more than this, recently issued certificates cannot be
+    /// covered, and checks apply the `stale-coverage-policy`.  The default of 0 never
+    /// considers coverage stale.
+    #[serde(default)]
+    stale_coverage_threshold: u64,
+
+    /// What checks do when the coverage of the revocation data is stale, according to
+    /// `stale-coverage-threshold`.
+    #[serde(default)]
+    stale_coverage_policy: StaleCoveragePolicy,
+
+    /// Reject check inputs whose serial number is not canonically encoded, rather than
+    /// normalizing them.
+    ///
+    /// A serial number is canonical if it is the minimal DER encoding: see
+    /// [`CertSerial::is_canonical()`].  By default, redundant leading zero bytes are
+    /// removed before the lookup.  When this is set, checks that can fail do so with
+    /// [`Error::NonCanonicalSerial`] instead, which catches upstream bugs rather than
+    /// masking them.  Checks of many inputs fail for the first such input, giving its
+    /// index, except [`Manifest::check_iter()`], which reports the error in place of
+    /// that input's outcome.  Checks that cannot fail, such as
+    /// [`SharedManifest::check_detailed()`], still normalize.  Issuer SPKI hashes are
+    /// always checked for their length, so are not affected.
+    #[serde(default)]
+    strict_input: bool,
+
+    /// Refuse any manifest whose [`Manifest::digest()`] is not this, in hex.
+    ///
+    /// This is for deployments that pin a specific build of the revocation data, out
+    /// of band.  A different manifest fails to load, and [`fetch()`] rejects it before
+    /// changing the cache, with [`Error::DigestMismatch`].  Manifests of earlier epochs
+    /// are not checked.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "optional_hex_digest"
+    )]
+    pinned_manifest_digest: Option<[u8; 32]>,
+}
+
+/// (De)serializes an optional SHA-256 digest in hex.
+mod optional_hex_digest {
+    use hex::FromHex;
+    use serde::{Deserialize, Deserializer, Serializer, de};
+
+    pub(super) fn serialize<S: Serializer>(
+        digest: &Option<[u8; 32]>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match digest {
+            Some(digest) => serializer.serialize_some(&hex::encode(digest)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<[u8; 32]>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|value| <[u8; 32]>::from_hex(value).map_err(de::Error::custom))
+            .transpose()
+    }
+}
+
+fn default_min_covered_scts() -> usize {
+    1
+}
+
+fn default_sct_skew_tolerance() -> u64 {
+    3600
+}
+
+fn default_max_manifest_bytes() -> u64 {
+    1024 * 1024
 }
 
 impl Default for RevocationConfig {
     fn default() -> Self {
         Self {
             fetch_url: "https://upki.rustls.dev/".into(),
+            no_sct_policy: NoSctPolicy::default(),
+            allow_downgrade: false,
+            min_covered_scts: default_min_covered_scts(),
+            min_sct_count: 0,
+            bypass_issuers: Vec::new(),
+            approved_logs: Vec::new(),
+            log_windows: Vec::new(),
+            uncovered_policy: UncoveredPolicy::default(),
+            max_manifest_bytes: default_max_manifest_bytes(),
+            in_scope_issuers: Vec::new(),
+            refresh_splay: 0,
+            refresh_splay_seed: None,
+            probabilistic_policy: ProbabilisticPolicy::default(),
+            sct_skew_tolerance: default_sct_skew_tolerance(),
+            duplicate_sct_policy: DuplicateSctPolicy::default(),
+            manifest_temp_dir: None,
+            sct_exempt_issuers: Vec::new(),
+            report_rejected_scts: false,
+            retain_epochs: 0,
+            stale_coverage_threshold: 0,
+            stale_coverage_policy: StaleCoveragePolicy::default(),
+            strict_input: false,
+            pinned_manifest_digest: None,
+        }
+    }
+}
+
+impl RevocationConfig {
+    /// Fail with [`Error::NonCanonicalSerial`] if `serial` is not canonical and
+    /// `strict-input` is set.
+    fn require_canonical(&self, serial: &CertSerial) -> Result<(), Error> {
+        self.require_canonical_at(serial, None)
+    }
+
+    /// Like [`RevocationConfig::require_canonical()`], for each of `inputs`, reporting
+    /// the index of the first that is not canonical.
+    fn require_all_canonical(&self, inputs: &[RevocationCheckInput]) -> Result<(), Error> {
+        inputs
+            .iter()
+            .enumerate()
+            .try_for_each(|(index, input)| {
+                self.require_canonical_at(&input.cert_serial, Some(index))
+            })
+    }
+
+    fn require_canonical_at(&self, serial: &CertSerial, index: Option<usize>) -> Result<(), Error> {
+        match self.strict_input && !serial.is_canonical() {
+            true => Err(Error::NonCanonicalSerial {
+                serial: serial.clone(),
+                index,
+            }),
+            false => Ok(()),
+        }
+    }
+
+    /// The latest SCT timestamp accepted at `now`, in UNIX milliseconds.
+    fn latest_sct(&self, now: SystemTime) -> u64 {
+        let latest = now
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis()
+            .saturating_add(u128::from(self.sct_skew_tolerance) * 1000);
+        u64::try_from(latest).unwrap_or(u64::MAX)
+    }
+
+    /// When the coverage of `filters` ends, if that is stale at `now` according to
+    /// `stale-coverage-threshold`.
+    fn stale_coverage_end(&self, filters: &LoadedFilters, now: SystemTime) -> Option<u64> {
+        if self.stale_coverage_threshold == 0 {
+            return None;
+        }
+
+        let coverage_end = filters
+            .coverage_ends
+            .as_ref()?
+            .values()
+            .max()
+            .copied()?;
+        let now = now
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let lag = now.saturating_sub(u128::from(coverage_end));
+        (lag > u128::from(self.stale_coverage_threshold) * 1000).then_some(coverage_end)
+    }
+
+    /// The delay to add before a scheduled fetch, according to `refresh-splay`.
+    ///
+    /// This is at most `refresh-splay` seconds.  If `refresh-splay-seed` is set, it is
+    /// derived from the seed and so the same every time; otherwise it is random.
+    pub fn refresh_splay(&self) -> Duration {
+        if self.refresh_splay == 0 {
+            return Duration::ZERO;
+        }
+
+        let mut value = [0; 8];
+        match &self.refresh_splay_seed {
+            Some(seed) => value
+                .copy_from_slice(&digest::digest(&digest::SHA256, seed.as_bytes()).as_ref()[..8]),
+            None => {
+                if aws_lc_rs::rand::fill(&mut value).is_err() {
+                    return Duration::ZERO;
+                }
+            }
+        }
+
+        // millisecond resolution, so small splays still spread fetches out
+        let max = self.refresh_splay.saturating_mul(1000);
+        Duration::from_millis(u64::from_be_bytes(value) % max.saturating_add(1))
+    }
+
+    /// Whether certificates from `issuer` are checked, according to `in-scope-issuers`.
+    fn in_scope(&self, issuer: &IssuerSpkiHash) -> bool {
+        self.in_scope_issuers.is_empty() || self.in_scope_issuers.contains(issuer)
+    }
+
+    /// Whether SCTs from `log` count towards coverage, according to `approved-logs`.
+    fn approves_log(&self, log: &CtLogId) -> bool {
+        self.approved_logs.is_empty() || self.approved_logs.contains(log)
+    }
+
+    /// Whether `sct` is within its log's qualification window, according to
+    /// `log-windows`.
+    fn qualifies(&self, sct: &CtTimestamp) -> bool {
+        let mut windows = self
+            .log_windows
+            .iter()
+            .filter(|window| window.log_id == sct.log_id)
+            .peekable();
+        windows.peek().is_none() || windows.any(|window| window.contains(sct.timestamp))
+    }
+
+    /// Build a configuration from environment variables: see [`Config::from_env()`].
+    pub(crate) fn from_env() -> Result<Self, crate::Error> {
+        let mut config = Self::default();
+        if let Some(url) = crate::env_var("UPKI_FETCH_URL")? {
+            config.fetch_url = url;
+        }
+
+        if let Some(value) = crate::env_var("UPKI_NO_SCT_POLICY")? {
+            config.no_sct_policy =
+                NoSctPolicy::deserialize(StrDeserializer::<de::value::Error>::new(&value))
+                    .map_err(|_| crate::Error::InvalidEnvironmentVariable {
+                        name: "UPKI_NO_SCT_POLICY",
+                        value,
+                    })?;
+        }
+
+        if let Some(value) = crate::env_var("UPKI_ALLOW_DOWNGRADE")? {
+            config.allow_downgrade =
+                value
+                    .parse()
+                    .map_err(|_| crate::Error::InvalidEnvironmentVariable {
+                        name: "UPKI_ALLOW_DOWNGRADE",
+                        value,
+                    })?;
+        }
+
+        Ok(config)
+    }
+}
+
+/// A period during which a CT log's SCTs count towards coverage, in the configured
+/// `log-windows`.
+///
+/// Both ends are inclusive, and in milliseconds since the UNIX epoch, as in SCTs.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct LogWindow {
+    /// The log this applies to.
+    pub log_id: CtLogId,
+    /// The earliest SCT timestamp that counts, or `None` for no lower limit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usable_from: Option<u64>,
+    /// The latest SCT timestamp that counts, or `None` if the log is not retired.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retired_after: Option<u64>,
+}
+
+impl LogWindow {
+    /// Whether an SCT dated `timestamp` is within this window.
+    pub fn contains(&self, timestamp: u64) -> bool {
+        self.usable_from
+            .is_none_or(|from| timestamp >= from)
+            && self
+                .retired_after
+                .is_none_or(|after| timestamp <= after)
+    }
+}
+
+/// How to check a certificate which has no embedded SCTs.
+///
+/// The crlite filters only cover certificates logged in certificate transparency
+/// during a known time window; the SCTs in a certificate are what place it within
+/// that window.  Without SCTs, the filters can still be consulted for the certificate's
+/// serial number, but the answer is no longer exact: a serial number that was never
+/// part of the data set may (rarely) be reported as revoked.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NoSctPolicy {
+    /// Report the certificate as not covered by revocation data, without consulting the filters.
+    ///
+    /// This is the default.
+    #[default]
+    TreatAsNotCovered,
+
+    /// Look up the certificate's serial number, ignoring coverage.
+    ///
+    /// Found serial numbers are reported as revoked, but absence does not establish
+    /// that the certificate is not revoked: it is reported as not covered.
+    UseSerialOnly,
+
+    /// Look up the certificate's serial number, assuming it is covered by the data
+    /// for its issuer.
+    ///
+    /// This is like [`NoSctPolicy::UseSerialOnly`], but absence of the serial number
+    /// is reported as not revoked if the filters contain data for the certificate's
+    /// issuer.  Suitable for deployments that do not use certificate transparency.
+    TreatAsCovered,
+}
+
+/// What checks do when the coverage of the revocation data is stale.
+///
+/// Coverage is stale when it ends longer ago than the configured
+/// `stale-coverage-threshold`.  Then nearly every recently issued certificate is
+/// outside it, which can be worse than not checking CT coverage at all.  Either way,
+/// outcomes carry a [`Warning::StaleCoverage`].
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StaleCoveragePolicy {
+    /// Check as usual, only marking the outcome with the warning.
+    ///
+    /// This is the default.
+    #[default]
+    Degraded,
+
+    /// Ignore coverage, and look up certificates by serial number alone.
+    ///
+    /// This is like `no-sct-policy = "treat-as-covered"` for every certificate: it is
+    /// reported as not revoked if the filters have data for its issuer and its serial
+    /// number is not found.  Found serial numbers are subject to the
+    /// `probabilistic-policy`.
+    SerialOnly,
+}
+
+/// How to report a certificate whose serial number is found by a probabilistic lookup.
+///
+/// Such lookups happen for certificates without SCTs, according to the [`NoSctPolicy`],
+/// and can (rarely) find a serial number that was never revoked.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProbabilisticPolicy {
+    /// Report the certificate as revoked, with [`MatchKind::Probabilistic`].
+    ///
+    /// This is the default.
+    #[default]
+    Lenient,
+
+    /// Report the certificate as not covered, with the reason
+    /// [`NotCoveredReason::NeedsConfirmation`].
+    ///
+    /// A false positive then cannot block a valid certificate, but unless the caller
+    /// confirms the result with another revocation mechanism (such as a
+    /// [`RevocationFallback`]), revoked certificates without SCTs will be missed.
+    Strict,
+}
+
+/// What to do with a certificate that has SCTs from one log with different timestamps.
+///
+/// A log issues one SCT per certificate, so such a certificate is malformed or
+/// malicious.  Each log with conflicting SCTs is reported as a
+/// [`Warning::ConflictingScts`].
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DuplicateSctPolicy {
+    /// Use the SCT with the latest timestamp.
+    ///
+    /// This is the default.  Later timestamps are less likely to be within the
+    /// coverage of the revocation data, so an extra SCT cannot make a certificate
+    /// look covered when it is not.
+    #[default]
+    UseLatest,
+
+    /// Use the SCT with the earliest timestamp.
+    UseEarliest,
+
+    /// Ignore all the SCTs from that log.
+    ///
+    /// If this leaves the certificate without SCTs, it is checked according to the
+    /// [`NoSctPolicy`].
+    Ignore,
+}
+
+impl DuplicateSctPolicy {
+    /// Apply this policy to `scts`.
+    ///
+    /// If any log has SCTs with different timestamps, returns the SCTs to use instead
+    /// of `scts`.  Also returns a warning for each such log.
+    fn resolve(self, scts: &[CtTimestamp]) -> (Option<Vec<CtTimestamp>>, Vec<Warning>) {
+        let mut warnings = Vec::new();
+        let mut used = HashMap::new();
+        let mut seen = HashSet::new();
+        for sct in scts {
+            if !seen.insert(sct.log_id) {
+                continue;
+            }
+
+            let timestamps = scts
+                .iter()
+                .filter(|other| other.log_id == sct.log_id)
+                .map(|other| other.timestamp);
+            let (Some(earliest), Some(latest)) = (timestamps.clone().min(), timestamps.max())
+            else {
+                continue;
+            };
+            if earliest == latest {
+                continue;
+            }
+
+            let timestamp = match self {
+                Self::UseLatest => Some(latest),
+                Self::UseEarliest => Some(earliest),
+                Self::Ignore => None,
+            };
+            warnings.push(Warning::ConflictingScts {
+                log_id: sct.log_id,
+                used: timestamp,
+            });
+            used.insert(sct.log_id, timestamp);
+        }
+
+        if used.is_empty() {
+            return (None, warnings);
+        }
+
+        let mut kept = HashSet::new();
+        let resolved = scts
+            .iter()
+            .filter(|sct| match used.get(&sct.log_id) {
+                None => true,
+                Some(timestamp) => *timestamp == Some(sct.timestamp) && kept.insert(sct.log_id),
+            })
+            .cloned()
+            .collect();
+        (Some(resolved), warnings)
+    }
+}
+
+/// How to report a certificate which is not covered by the revocation data.
+///
+/// Used by [`Manifest::check_with_policy()`], for callers that need a yes-or-no
+/// answer.  [`Manifest::check()`] always reports such certificates as
+/// [`RevocationStatus::NotCoveredByRevocationData`].
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum UncoveredPolicy {
+    /// Report the certificate as not revoked.
+    ///
+    /// Connections proceed whenever upki cannot decide, so a revoked certificate
+    /// outside the data set (for example, one from a new issuer, or issued
+    /// after the manifest was generated) will be accepted.
+    FailOpen,
+
+    /// Report the certificate as revoked.
+    ///
+    /// This never accepts a revoked certificate, but rejects every certificate the
+    /// data does not cover: including all certificates from private CAs, and all
+    /// certificates when the cache is stale, empty, or bypassed for their issuer.
+    FailClosed,
+
+    /// Report the certificate as not covered, leaving the decision to the caller.
+    ///
+    /// This is the default.
+    #[default]
+    ReturnUncovered,
+}
+
+impl UncoveredPolicy {
+    /// Apply this policy to `status`.
+    ///
+    /// Only [`RevocationStatus::NotCoveredByRevocationData`] is changed.
+    pub fn apply(self, status: RevocationStatus) -> RevocationStatus {
+        match (self, status) {
+            (Self::FailOpen, RevocationStatus::NotCoveredByRevocationData) => {
+                RevocationStatus::NotRevoked
+            }
+            (Self::FailClosed, RevocationStatus::NotCoveredByRevocationData) => {
+                RevocationStatus::CertainlyRevoked
+            }
+            (_, status) => status,
         }
     }
 }
 
+/// Convert a 1-based `line` and `column` position in `bytes` into a byte offset.
+///
+/// `line` is zero if the position is not known.
+fn byte_offset(bytes: &[u8], line: usize, column: usize) -> Option<usize> {
+    if line == 0 {
+        return None;
+    }
+
+    let line_start = bytes
+        .split_inclusive(|&b| b == b'\n')
+        .take(line - 1)
+        .map(<[u8]>::len)
+        .sum::<usize>();
+    Some(Ord::min(line_start + column, bytes.len()))
+}
+
+/// Read the `generated_at` of the newest manifest accepted into the cache at `dir`.
+fn highest_accepted(dir: &Path) -> Result<Option<u64>, Error> {
+    let path = dir.join(HIGHEST_ACCEPTED);
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(Error::FileRead { error, path }),
+    };
+
+    match text.trim().parse() {
+        Ok(generated_at) => Ok(Some(generated_at)),
+        Err(_) => Err(Error::InvalidTimestamp {
+            input: text,
+            context: "highest accepted manifest (in s)",
+        }),
+    }
+}
+
+const HIGHEST_ACCEPTED: &str = "highest-accepted";
+
+/// Hash the `SubjectPublicKeyInfo` of the DER-encoded issuer certificate `cert_der`.
+///
+/// This gives the same value as [`issuer_spki_hash()`] on the certificate's public
+/// key.  The certificate is not checked for validity.
+pub fn issuer_spki_hash_from_cert(cert_der: &[u8]) -> Result<IssuerSpkiHash, Error> {
+    let cert = CertificateDer::from(cert_der);
+    let anchor = webpki::anchor_from_trusted_cert(&cert)
+        .map_err(|error| Error::InvalidIssuerCertificate(Box::new(error)))?;
+    Ok(issuer_spki_hash(&webpki::spki_for_anchor(&anchor)))
+}
+
+/// Find the issuer of the certificate at `target_index` in `chain`, and hash its SPKI.
+///
+/// The issuer is found by matching its subject against the target's issuer name, so
+/// `chain` may be in any order.  The target itself is not considered as a candidate.
+///
+/// This fails with [`Error::NoIssuer`] if no other certificate in `chain` is the
+/// issuer, or [`Error::TooFewCertificates`] if `target_index` is out of range.  As
+/// with [`RevocationCheckInput::from_certificates()`], no certificate is checked for
+/// validity.
+pub fn resolve_issuer_spki_hash(
+    chain: &[&[u8]],
+    target_index: usize,
+) -> Result<IssuerSpkiHash, Error> {
+    let chain = chain
+        .iter()
+        .map(|der| CertificateDer::from(*der))
+        .collect::<Vec<_>>();
+    let target = chain
+        .get(target_index)
+        .ok_or(Error::TooFewCertificates)?;
+    let target = webpki::EndEntityCert::try_from(target)
+        .map_err(|error| Error::InvalidEndEntityCertificate(Box::new(error)))?;
+
+    let candidates = chain
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != target_index)
+        .map(|(_, der)| der);
+    let issuer = find_issuer(target.issuer(), candidates)?;
+    Ok(issuer_spki_hash(&webpki::spki_for_anchor(&issuer)))
+}
+
+/// Hash the DER-encoded `SubjectPublicKeyInfo` of an issuer.
+///
+/// This is how the [`IssuerSpkiHash`] in a [`RevocationCheckInput`] is computed: the
+/// SHA256 hash of the complete DER encoding, including the outer `SEQUENCE`.  To hash
+/// the key of an issuer certificate, use [`issuer_spki_hash_from_cert()`] instead;
+/// hashing the whole certificate gives a different value.
+pub fn issuer_spki_hash(spki_der: &[u8]) -> IssuerSpkiHash {
+    IssuerSpkiHash(
+        digest::digest(&digest::SHA256, spki_der)
+            .as_ref()
+            .try_into()
+            .expect("sha256 output must be [u8;32]"),
+    )
+}
+
 fn find_issuer<'a>(
     name: &[u8],
     candidates: impl Iterator<Item = &'a CertificateDer<'a>>,
@@ -369,6 +4751,8 @@ fn find_issuer<'a>(
 #[non_exhaustive]
 #[derive(Debug)]
 pub enum Error {
+    /// An update was stopped by its [`CancelToken`].
+    Cancelled,
     /// Failed to create a directory.
     CreateDirectory {
         /// Underlying error.
@@ -376,6 +4760,29 @@ pub enum Error {
         /// Path to the directory being created.
         path: PathBuf,
     },
+    /// The manifest's digest is not the configured `pinned-manifest-digest`.
+    DigestMismatch {
+        /// The pinned digest.
+        expected: [u8; 32],
+        /// The manifest's [`Manifest::digest()`].
+        actual: [u8; 32],
+    },
+    /// A manifest was older than one previously accepted.
+    Downgrade {
+        /// `generated_at` of the newest manifest previously accepted.
+        accepted: u64,
+        /// `generated_at` of the rejected manifest.
+        offered: u64,
+    },
+    /// A [`RevocationFallback`] failed.
+    Fallback(Box<dyn StdError + Send + Sync>),
+    /// Failed to read a file.
+    FileRead {
+        /// Underlying error.
+        error: io::Error,
+        /// Path to the file being read.
+        path: PathBuf,
+    },
     /// Failed to write a file.
     FileWrite {
         /// Underlying error.
@@ -390,6 +4797,13 @@ pub enum Error {
         /// Path to the filter file.
         path: PathBuf,
     },
+    /// Failed to encode a filter file.
+    FilterEncode {
+        /// Underlying error.
+        error: Box<dyn StdError + Send + Sync>,
+        /// Path to the filter file.
+        path: PathBuf,
+    },
     /// Failed to read a filter file.
     FilterRead {
         /// Underlying error.
@@ -397,6 +4811,13 @@ pub enum Error {
         /// Path to the filter file.
         path: Option<PathBuf>,
     },
+    /// A check was asked for a manifest generation other than the one loaded.
+    GenerationUnavailable {
+        /// The generation asked for.
+        requested: ManifestVersion,
+        /// The generation loaded.
+        loaded: ManifestVersion,
+    },
     /// A downloaded file did not match the expected hash.
     HashMismatch(PathBuf),
     /// Failed to fetch a file over HTTP.
@@ -406,6 +4827,8 @@ pub enum Error {
         /// URL being accessed.
         url: String,
     },
+    /// [`Manifest::open()`] found problems with the cache.
+    Integrity(Vec<IntegrityProblem>),
     /// Invalid base64 encoding.
     InvalidBase64 {
         /// Underlying error.
@@ -413,6 +4836,8 @@ pub enum Error {
         /// Context in which the base64 was being parsed.
         context: &'static str,
     },
+    /// A [`RevocationCheckInput`] string representation had too few fields.
+    InvalidCheckInput,
     /// The end-entity certificate was invalid and could not be parsed.
     InvalidEndEntityCertificate(Box<dyn StdError + Send + Sync>),
     /// An intermediate certificate was invalid and could not be parsed.
@@ -422,6 +4847,15 @@ pub enum Error {
         /// Index of the intermediate certificate in the provided chain.
         index: usize,
     },
+    /// Invalid hex encoding.
+    InvalidHex {
+        /// Underlying error.
+        error: Box<dyn StdError + Send + Sync>,
+        /// Context in which the hex was being parsed.
+        context: &'static str,
+    },
+    /// An issuer certificate was invalid and could not be parsed.
+    InvalidIssuerCertificate(Box<dyn StdError + Send + Sync>),
     /// A base64-decoded value did not have the expected length.
     InvalidLength {
         /// Expected length.
@@ -431,6 +4865,10 @@ pub enum Error {
         /// Context in which the hash was being parsed.
         context: &'static str,
     },
+    /// An encoded [`CheckOutcome`] could not be decoded, for the given reason.
+    InvalidOutcomeEncoding(&'static str),
+    /// PEM-encoded input could not be parsed.
+    InvalidPem(Box<dyn StdError + Send + Sync>),
     /// No ':' found in [`CtTimestamp`] string representation.
     InvalidSctEncoding,
     /// An SCT in the end-entity certificate could not be parsed.
@@ -448,6 +4886,8 @@ pub enum Error {
         error: Box<dyn StdError + Send + Sync>,
         /// Path to the manifest file.
         path: Option<PathBuf>,
+        /// Byte offset into the manifest at which decoding failed, if known.
+        offset: Option<usize>,
     },
     /// Failed to encode a manifest file.
     ManifestEncode {
@@ -456,6 +4896,10 @@ pub enum Error {
         /// Path to the manifest file.
         path: PathBuf,
     },
+    /// There is no manifest file at the given path.
+    ///
+    /// For the cache directory, this usually means `upki fetch` has not been run.
+    ManifestMissing(PathBuf),
     /// Failed to read a manifest file.
     ManifestRead {
         /// Underlying error.
@@ -463,6 +4907,11 @@ pub enum Error {
         /// Path to the manifest file.
         path: PathBuf,
     },
+    /// A manifest was larger than the configured `max-manifest-bytes`.
+    ManifestTooLarge {
+        /// The configured limit, in bytes.
+        limit: u64,
+    },
     /// Failed to write a manifest file.
     ManifestWrite {
         /// Underlying error.
@@ -470,10 +4919,22 @@ pub enum Error {
         /// Path to the manifest file.
         path: PathBuf,
     },
+    /// A [`RevocationCheckInputBuilder`] was built without a required field.
+    MissingCheckInputField(&'static str),
     /// No issuer found for the end-entity certificate in the provided chain.
     NoIssuer,
+    /// A check input's serial number is not canonically encoded, and `strict-input`
+    /// is set.
+    NonCanonicalSerial {
+        /// The serial number as given.
+        serial: CertSerial,
+        /// The index of the input, for checks of many inputs.
+        index: Option<usize>,
+    },
     /// Number of bytes that need to be downloaded to update the local cache.
     Outdated(usize),
+    /// The cache cannot be updated, because the configuration is read-only.
+    ReadOnly,
     /// Failed to remove a file.
     RemoveFile {
         /// Underlying error.
@@ -481,35 +4942,88 @@ pub enum Error {
         /// Path to the file being removed.
         path: PathBuf,
     },
+    /// Failed to start the tokio runtime for [`Manifest::update_blocking()`].
+    Runtime(io::Error),
+    /// Failed to read or write a stream.
+    StreamIo(io::Error),
+    /// Failed to decode a test vector file.
+    TestVectorDecode {
+        /// Underlying error.
+        error: Box<dyn StdError + Send + Sync>,
+        /// Path to the test vector file.
+        path: PathBuf,
+    },
     /// Certificate chains must contain at least 2 certificates.
     TooFewCertificates,
+    /// A manifest's format is a version this crate does not understand.
+    ///
+    /// A newer version of upki is needed to use it.
+    UnsupportedManifestVersion {
+        /// The manifest's [`Manifest::format_version()`].
+        found: u32,
+        /// The format versions this crate understands.
+        supported: RangeInclusive<u32>,
+    },
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            Self::Cancelled => write!(f, "update was cancelled"),
             Self::CreateDirectory { path, .. } => {
                 write!(f, "cannot create directory {path:?}")
             }
+            Self::DigestMismatch { expected, actual } => write!(
+                f,
+                "manifest digest {} is not the pinned {}",
+                hex::encode(actual),
+                hex::encode(expected)
+            ),
+            Self::Downgrade { accepted, offered } => write!(
+                f,
+                "manifest generated at {offered} is older than previously accepted manifest generated at {accepted}"
+            ),
+            Self::Fallback(_) => write!(f, "fallback revocation check failed"),
+            Self::FileRead { path, .. } => write!(f, "cannot read file {path:?}"),
             Self::FileWrite { path, .. } => write!(f, "cannot write file {path:?}"),
             Self::FilterDecode { path, .. } => {
                 write!(f, "cannot decode filter file {path:?}")
             }
+            Self::FilterEncode { path, .. } => {
+                write!(f, "cannot encode filter file {path:?}")
+            }
             Self::FilterRead { path, .. } => match path {
                 Some(path) => write!(f, "cannot read filter file {path:?}"),
                 None => write!(f, "cannot read filter file"),
             },
+            Self::GenerationUnavailable { requested, loaded } => write!(
+                f,
+                "manifest generation {requested} is not available, {loaded} is loaded"
+            ),
             Self::HashMismatch(path) => write!(f, "hash mismatch for file {path:?}"),
             Self::HttpFetch { url, .. } => write!(f, "HTTP fetch error for URL {url}"),
+            Self::Integrity(problems) => {
+                write!(f, "revocation data failed integrity checks")?;
+                for (i, problem) in problems.iter().enumerate() {
+                    write!(f, "{} {problem}", if i == 0 { ":" } else { ";" })?;
+                }
+                Ok(())
+            }
             Self::InvalidBase64 { context, .. } => {
                 write!(f, "invalid base64 for {context}")
             }
+            Self::InvalidCheckInput => write!(
+                f,
+                "invalid check input: expected an issuer SPKI hash and a certificate serial"
+            ),
             Self::InvalidEndEntityCertificate(_) => {
                 write!(f, "invalid end-entity certificate")
             }
             Self::InvalidIntermediateCertificate { index, .. } => {
                 write!(f, "invalid intermediate certificate at index {index}")
             }
+            Self::InvalidHex { context, .. } => write!(f, "invalid hex for {context}"),
+            Self::InvalidIssuerCertificate(_) => write!(f, "invalid issuer certificate"),
             Self::InvalidLength {
                 expected,
                 actual,
@@ -518,6 +5032,10 @@ impl fmt::Display for Error {
                 f,
                 "invalid length for {context}: expected {expected}, got {actual}"
             ),
+            Self::InvalidOutcomeEncoding(reason) => {
+                write!(f, "invalid encoded check outcome: {reason}")
+            }
+            Self::InvalidPem(_) => write!(f, "invalid PEM encoding"),
             Self::InvalidSctEncoding => write!(f, "invalid SCT encoding: no ':' found"),
             Self::InvalidSctInCertificate(_) => {
                 write!(f, "invalid SCT in certificate")
@@ -525,24 +5043,54 @@ impl fmt::Display for Error {
             Self::InvalidTimestamp { input, context } => {
                 write!(f, "invalid timestamp for {context}: '{input}'")
             }
-            Self::ManifestDecode { path, .. } => {
-                write!(f, "cannot decode manifest file at {path:?}")
-            }
+            Self::ManifestDecode { path, offset, .. } => match offset {
+                Some(offset) => write!(
+                    f,
+                    "cannot decode manifest file at {path:?} (at byte offset {offset})"
+                ),
+                None => write!(f, "cannot decode manifest file at {path:?}"),
+            },
             Self::ManifestEncode { path, .. } => {
                 write!(f, "cannot encode manifest file at {path:?}")
             }
+            Self::ManifestMissing(path) => write!(f, "no manifest file at {path:?}"),
             Self::ManifestRead { path, .. } => {
                 write!(f, "cannot read manifest file at {path:?}")
             }
+            Self::ManifestTooLarge { limit } => {
+                write!(f, "manifest is larger than the limit of {limit} bytes")
+            }
             Self::ManifestWrite { path, .. } => {
                 write!(f, "cannot write manifest file at {path:?}")
             }
+            Self::MissingCheckInputField(field) => {
+                write!(f, "check input is missing its {field}")
+            }
             Self::NoIssuer => write!(f, "no issuer found for end-entity certificate"),
+            Self::NonCanonicalSerial { serial, index } => {
+                write!(f, "serial number {} ", BASE64_STANDARD.encode(&serial.0))?;
+                if let Some(index) = index {
+                    write!(f, "of input {index} ")?;
+                }
+                write!(f, "is not canonically encoded")
+            }
             Self::Outdated(bytes) => write!(f, "cache is outdated, {bytes} bytes need downloading"),
+            Self::ReadOnly => write!(f, "cannot update cache: configuration is read-only"),
             Self::RemoveFile { path, .. } => write!(f, "cannot remove file {path:?}"),
+            Self::Runtime(_) => write!(f, "cannot start runtime for update"),
+            Self::StreamIo(_) => write!(f, "cannot read or write stream"),
+            Self::TestVectorDecode { path, .. } => {
+                write!(f, "cannot decode test vector file {path:?}")
+            }
             Self::TooFewCertificates => {
                 write!(f, "certificate chain must contain at least 2 certificates")
             }
+            Self::UnsupportedManifestVersion { found, supported } => write!(
+                f,
+                "manifest format version {found} is not supported (this version of upki supports {}-{}), upgrade upki to use it",
+                supported.start(),
+                supported.end()
+            ),
         }
     }
 }
@@ -550,27 +5098,49 @@ impl fmt::Display for Error {
 impl StdError for Error {
     fn source(&self) -> Option<&(dyn StdError + 'static)> {
         match self {
+            Self::Cancelled => None,
             Self::CreateDirectory { error, .. } => Some(error),
+            Self::DigestMismatch { .. } => None,
+            Self::Downgrade { .. } => None,
+            Self::Fallback(error) => Some(&**error),
+            Self::FileRead { error, .. } => Some(error),
             Self::FileWrite { error, .. } => Some(error),
             Self::FilterDecode { error, .. } => Some(&**error),
+            Self::FilterEncode { error, .. } => Some(&**error),
             Self::FilterRead { error, .. } => Some(error),
+            Self::GenerationUnavailable { .. } => None,
             Self::HashMismatch(_) => None,
             Self::HttpFetch { error, .. } => Some(&**error),
+            Self::Integrity(_) => None,
             Self::InvalidBase64 { error, .. } => Some(&**error),
+            Self::InvalidCheckInput => None,
             Self::InvalidEndEntityCertificate(error) => Some(&**error),
             Self::InvalidIntermediateCertificate { error, .. } => Some(&**error),
+            Self::InvalidHex { error, .. } => Some(&**error),
+            Self::InvalidIssuerCertificate(error) => Some(&**error),
             Self::InvalidLength { .. } => None,
+            Self::InvalidOutcomeEncoding(_) => None,
+            Self::InvalidPem(error) => Some(&**error),
             Self::InvalidSctEncoding => None,
             Self::InvalidSctInCertificate(error) => Some(&**error),
             Self::InvalidTimestamp { .. } => None,
             Self::ManifestDecode { error, .. } => Some(&**error),
             Self::ManifestEncode { error, .. } => Some(&**error),
+            Self::ManifestMissing(_) => None,
             Self::ManifestRead { error, .. } => Some(error),
+            Self::ManifestTooLarge { .. } => None,
             Self::ManifestWrite { error, .. } => Some(error),
+            Self::MissingCheckInputField(_) => None,
             Self::NoIssuer => None,
+            Self::NonCanonicalSerial { .. } => None,
             Self::Outdated(_) => None,
+            Self::ReadOnly => None,
             Self::RemoveFile { error, .. } => Some(error),
+            Self::Runtime(error) => Some(error),
+            Self::StreamIo(error) => Some(error),
+            Self::TestVectorDecode { error, .. } => Some(&**error),
             Self::TooFewCertificates => None,
+            Self::UnsupportedManifestVersion { .. } => None,
         }
     }
 }
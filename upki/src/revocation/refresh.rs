@@ -0,0 +1,204 @@
+//! Periodic fetching in the background.
+
+use core::time::Duration;
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tokio::sync::oneshot;
+use tracing::{debug, warn};
+
+use super::{STALE_MANIFEST_AGE, fetch_with_report};
+use crate::Config;
+
+/// Start keeping the revocation cache described by `config` up to date.
+///
+/// This runs [`fetch()`] immediately, and then every `interval`, on a new thread
+/// with its own single-threaded tokio runtime.  So it can be used both from
+/// synchronous programs and from within an existing tokio runtime.  Errors from
+/// individual fetches are logged, and do not stop the refreshing.
+///
+/// If the fetched manifest has a [`Manifest::next_update()`] hint in the future,
+/// the next fetch is instead made shortly after that time: a random delay of up to a
+/// tenth of `interval` is added, so that many clients do not all fetch at once.  The
+/// hint cannot postpone the next fetch until after the fetched manifest would become
+/// stale, two days after it was generated.
+///
+/// Each fetch after the first is further delayed by [`RevocationConfig::refresh_splay()`],
+/// so that a fleet of clients do not fetch in step.
+///
+/// Refreshing continues until the returned [`RefreshHandle`] is shut down or dropped.
+///
+/// [`fetch()`]: super::fetch()
+/// [`Manifest::next_update()`]: super::Manifest::next_update()
+/// [`RevocationConfig::refresh_splay()`]: super::RevocationConfig::refresh_splay()
+pub fn spawn_auto_refresh(config: Config, interval: Duration) -> RefreshHandle {
+    let (shutdown, mut stopped) = oneshot::channel::<()>();
+
+    let thread = thread::spawn(move || {
+        let runtime = match tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+        {
+            Ok(runtime) => runtime,
+            Err(error) => {
+                warn!(%error, "cannot start refresh runtime");
+                return;
+            }
+        };
+
+        runtime.block_on(async move {
+            loop {
+                let hint = tokio::select! {
+                    result = fetch_with_report(&config) => match result {
+                        Ok(report) => {
+                            debug!("refresh complete");
+                            report.next_update.map(|at| (at, report.generated_at))
+                        }
+                        Err(error) => {
+                            warn!(%error, "refresh failed");
+                            None
+                        }
+                    },
+                    _ = &mut stopped => return,
+                };
+
+                let delay = next_delay(hint, interval, crate::clock::now(), random_fraction())
+                    + config.revocation.refresh_splay();
+                tokio::select! {
+                    _ = tokio::time::sleep(delay) => {}
+                    _ = &mut stopped => return,
+                }
+            }
+        })
+    });
+
+    RefreshHandle {
+        shutdown: Some(shutdown),
+        thread: Some(thread),
+    }
+}
+
+/// How long to wait before the next fetch.
+///
+/// `hint` is the last manifest's `next_update` hint and `generated_at`, if it had a
+/// hint.  A hint in the past, or for a manifest that is already stale, is ignored.
+/// `random` is in `0.0..=1.0`, and chooses the jitter.
+fn next_delay(
+    hint: Option<(SystemTime, u64)>,
+    interval: Duration,
+    now: SystemTime,
+    random: f64,
+) -> Duration {
+    let Some((next_update, generated_at)) = hint else {
+        return interval;
+    };
+    let stale_at =
+        UNIX_EPOCH + Duration::from_secs(generated_at.saturating_add(STALE_MANIFEST_AGE));
+    let (Ok(until), Ok(cap)) = (
+        next_update.duration_since(now),
+        stale_at.duration_since(now),
+    ) else {
+        return interval;
+    };
+
+    (until + interval.mul_f64(random / 10.0)).min(cap)
+}
+
+/// A random number in `0.0..=1.0`, or 0 if no randomness is available.
+fn random_fraction() -> f64 {
+    let mut random = [0; 4];
+    match aws_lc_rs::rand::fill(&mut random) {
+        Ok(()) => f64::from(u32::from_ne_bytes(random)) / f64::from(u32::MAX),
+        Err(_) => 0.0,
+    }
+}
+
+/// Controls the background refreshing started by [`spawn_auto_refresh()`].
+///
+/// Dropping this stops the refreshing, in the same way as [`RefreshHandle::shutdown()`].
+#[derive(Debug)]
+pub struct RefreshHandle {
+    shutdown: Option<oneshot::Sender<()>>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl RefreshHandle {
+    /// Stop refreshing, and wait for the background thread to exit.
+    ///
+    /// This takes effect promptly, even during a fetch: an interrupted fetch may
+    /// leave newly-downloaded filters in the cache directory, but the manifest is
+    /// only replaced once all its filters are present, so the cache stays usable.
+    /// The next fetch completes the work.
+    ///
+    /// Waiting for the thread blocks the caller.  That is brief, but from async code
+    /// in a tokio runtime prefer calling this (or dropping the handle) from
+    /// `tokio::task::spawn_blocking`.
+    pub fn shutdown(mut self) {
+        self.stop();
+    }
+
+    fn stop(&mut self) {
+        // dropping the sender wakes the background thread
+        self.shutdown.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for RefreshHandle {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INTERVAL: Duration = Duration::from_secs(3600);
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn next_delay_without_hint() {
+        assert_eq!(next_delay(None, INTERVAL, at(1_000_000), 0.5), INTERVAL);
+    }
+
+    #[test]
+    fn next_delay_hint_in_the_past() {
+        let now = at(1_000_000);
+        let hint = Some((at(999_000), 990_000));
+        assert_eq!(next_delay(hint, INTERVAL, now, 0.5), INTERVAL);
+    }
+
+    #[test]
+    fn next_delay_hint_in_the_future() {
+        let now = at(1_000_000);
+        let hint = Some((at(1_000_600), 990_000));
+
+        // shortly after the hint, by up to a tenth of the interval
+        assert_eq!(
+            next_delay(hint, INTERVAL, now, 0.0),
+            Duration::from_secs(600)
+        );
+        assert_eq!(
+            next_delay(hint, INTERVAL, now, 1.0),
+            Duration::from_secs(600 + 360)
+        );
+
+        // but no later than the manifest becomes stale, counted from its generation
+        let stale_in = 990_000 + STALE_MANIFEST_AGE - 1_000_000;
+        let hint = Some((at(2_000_000), 990_000));
+        assert_eq!(
+            next_delay(hint, INTERVAL, now, 0.5),
+            Duration::from_secs(stale_in)
+        );
+
+        // and a hint for an already stale manifest is ignored
+        let hint = Some((at(2_000_000), 1_000_000 - STALE_MANIFEST_AGE - 1));
+        assert_eq!(next_delay(hint, INTERVAL, now, 0.5), INTERVAL);
+    }
+}
@@ -0,0 +1,181 @@
+//! Several manifests, each responsible for some issuers.
+
+use core::cmp::Reverse;
+use core::fmt;
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use super::{
+    Error, IssuerSpkiHash, LoadedFilters, Manifest, RevocationCheckInput, RevocationStatus,
+    UncoveredPolicy,
+};
+use crate::Config;
+
+/// Several manifests and their filters, loaded into memory and checked together.
+///
+/// This is for stacking revocation data from more than one source: for example, an
+/// internal feed for an organisation's own CAs, and the public feed for everything
+/// else.  Build one with [`ManifestSet::builder()`].
+///
+/// [`ManifestSet::check()`] consults the sources whose scope includes the
+/// certificate's issuer, from the highest priority to the lowest, and the first
+/// that covers the certificate decides.  So where sources disagree, the higher
+/// priority wins, even if a lower priority source says the certificate is revoked.
+/// Sources with equal priority are consulted in the order they were added.
+#[derive(Debug)]
+pub struct ManifestSet {
+    sources: Vec<Loaded>,
+}
+
+impl ManifestSet {
+    /// Start building a `ManifestSet`, with no sources.
+    pub fn builder() -> ManifestSetBuilder {
+        ManifestSetBuilder::default()
+    }
+
+    /// Check `input` against the sources responsible for its issuer.
+    ///
+    /// A source's own [`ManifestSource::uncovered_policy()`] applies when it does not
+    /// cover the certificate: so a source with a policy other than the default
+    /// [`UncoveredPolicy::ReturnUncovered`] always decides, and lower priority sources
+    /// are not consulted.  If no source decides, this returns
+    /// [`RevocationStatus::NotCoveredByRevocationData`].  The configured
+    /// `uncovered-policy` is not applied.
+    pub fn check(&self, input: &RevocationCheckInput, config: &Config) -> RevocationStatus {
+        for source in &self.sources {
+            if source
+                .scope
+                .as_ref()
+                .is_some_and(|scope| !scope.contains(&input.issuer_spki_hash))
+            {
+                continue;
+            }
+
+            let status = source.uncovered_policy.apply(
+                source
+                    .manifest
+                    .check_loaded(&source.filters, input, config)
+                    .status,
+            );
+            if status != RevocationStatus::NotCoveredByRevocationData {
+                return status;
+            }
+        }
+
+        RevocationStatus::NotCoveredByRevocationData
+    }
+
+    /// The number of sources.
+    pub fn len(&self) -> usize {
+        self.sources.len()
+    }
+
+    /// Returns true if there are no sources.
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+}
+
+/// Builds a [`ManifestSet`], from [`ManifestSet::builder()`].
+#[derive(Debug, Default)]
+pub struct ManifestSetBuilder {
+    sources: Vec<ManifestSource>,
+}
+
+impl ManifestSetBuilder {
+    /// Add a source.
+    pub fn source(mut self, source: ManifestSource) -> Self {
+        self.sources.push(source);
+        self
+    }
+
+    /// Load each source's filters and finish building the set.
+    ///
+    /// This fails if any filter cannot be loaded.
+    pub fn build(self) -> Result<ManifestSet, Error> {
+        let mut sources = self
+            .sources
+            .into_iter()
+            .map(|source| {
+                Ok(Loaded {
+                    filters: source
+                        .manifest
+                        .load_filters(&source.dir)?,
+                    manifest: source.manifest,
+                    priority: source.priority,
+                    scope: source.scope,
+                    uncovered_policy: source.uncovered_policy,
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        // stable, so equal priorities keep the order they were added in
+        sources.sort_by_key(|source| Reverse(source.priority));
+        Ok(ManifestSet { sources })
+    }
+}
+
+/// One manifest in a [`ManifestSet`], and how it is used.
+#[derive(Debug)]
+pub struct ManifestSource {
+    manifest: Manifest,
+    dir: PathBuf,
+    priority: i32,
+    scope: Option<HashSet<IssuerSpkiHash>>,
+    uncovered_policy: UncoveredPolicy,
+}
+
+impl ManifestSource {
+    /// A source for `manifest`, whose filters are in `dir`.
+    ///
+    /// By default it has priority 0, is responsible for every issuer, and leaves
+    /// certificates it does not cover to lower priority sources.
+    pub fn new(manifest: Manifest, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            manifest,
+            dir: dir.into(),
+            priority: 0,
+            scope: None,
+            uncovered_policy: UncoveredPolicy::ReturnUncovered,
+        }
+    }
+
+    /// Set the priority.  Higher priorities are consulted first.
+    pub fn priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Only use this source for certificates from `issuers`.
+    pub fn scope(mut self, issuers: impl IntoIterator<Item = IssuerSpkiHash>) -> Self {
+        self.scope = Some(issuers.into_iter().collect());
+        self
+    }
+
+    /// Decide certificates this source does not cover according to `policy`, rather
+    /// than leaving them to lower priority sources.
+    pub fn uncovered_policy(mut self, policy: UncoveredPolicy) -> Self {
+        self.uncovered_policy = policy;
+        self
+    }
+}
+
+struct Loaded {
+    manifest: Manifest,
+    filters: LoadedFilters,
+    priority: i32,
+    scope: Option<HashSet<IssuerSpkiHash>>,
+    uncovered_policy: UncoveredPolicy,
+}
+
+impl fmt::Debug for Loaded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Loaded")
+            .field("manifest", &self.manifest)
+            .field("filters", &self.filters.filters.len())
+            .field("priority", &self.priority)
+            .field("scope", &self.scope)
+            .field("uncovered_policy", &self.uncovered_policy)
+            .finish()
+    }
+}
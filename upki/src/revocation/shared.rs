@@ -0,0 +1,173 @@
+//! A loaded manifest that can be replaced while in use.
+
+use core::fmt;
+use std::path::Path;
+use std::sync::{Arc, PoisonError, RwLock};
+
+use super::{
+    CheckOutcome, Error, LoadedFilters, Manifest, RevocationCheckInput, RevocationStatus, fetch,
+    read_archive,
+};
+use crate::Config;
+
+/// A manifest and its filters, loaded into memory and shared between threads.
+///
+/// Checks use the filters already in memory, so they do no IO and cannot fail.
+/// [`SharedManifest::update()`] fetches, verifies and loads a new manifest, and then
+/// replaces the current one in a single step: each check sees either the old manifest
+/// or the new one, never a mixture.  Checks hold a lock only for long enough to clone
+/// an [`Arc`], so they are not held up by an update in progress.
+#[derive(Debug)]
+pub struct SharedManifest {
+    current: RwLock<Arc<Loaded>>,
+}
+
+impl SharedManifest {
+    /// Load the manifest and filters from the cache described by `config`.
+    ///
+    /// The cache is verified first, so this fails if any filter is missing or corrupt.
+    pub fn from_config(config: &Config) -> Result<Self, Error> {
+        Ok(Self {
+            current: RwLock::new(Arc::new(Loaded::from_config(config)?)),
+        })
+    }
+
+    /// Load the manifest and filters from the archive at `path`.
+    ///
+    /// The archive is read into memory and nothing is unpacked, so this works without
+    /// a writable (or any) cache directory: checks need only the configuration's
+    /// policies.  Each filter is checked against its hash.  Use [`Config::from_archive()`]
+    /// to load the configuration bundled with it.
+    pub fn from_archive(path: &Path) -> Result<Self, Error> {
+        Ok(Self {
+            current: RwLock::new(Arc::new(Loaded::from_archive(path)?)),
+        })
+    }
+
+    /// Update the cache described by `config`, and then switch to the new manifest.
+    ///
+    /// This calls [`fetch()`], then loads and verifies the result.  If any step fails,
+    /// the current manifest stays in use.  Returns the version now in use.
+    ///
+    /// Loading the filters is done on the task awaiting this future.
+    pub async fn update(&self, config: &Config) -> Result<ManifestVersion, Error> {
+        fetch(false, config).await?;
+        let loaded = Arc::new(Loaded::from_config(config)?);
+        let version = loaded.version();
+
+        *self
+            .current
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = loaded;
+        Ok(version)
+    }
+
+    /// Load the manifest and filters from the archive at `path`, and then switch to
+    /// them.  If that fails, the current manifest stays in use.
+    #[cfg(all(unix, feature = "archive-dir"))]
+    pub(super) fn replace_from_archive(&self, path: &Path) -> Result<ManifestVersion, Error> {
+        let loaded = Arc::new(Loaded::from_archive(path)?);
+        let version = loaded.version();
+
+        *self
+            .current
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = loaded;
+        Ok(version)
+    }
+
+    /// The version of the manifest currently in use.
+    pub fn version(&self) -> ManifestVersion {
+        self.current().version()
+    }
+
+    /// Like [`Manifest::check()`], using the manifest currently in use.
+    pub fn check(&self, input: &RevocationCheckInput, config: &Config) -> RevocationStatus {
+        self.check_detailed(input, config)
+            .status
+    }
+
+    /// Like [`Manifest::check_at_generation()`], using the manifest currently in use.
+    ///
+    /// This fails with [`Error::GenerationUnavailable`] if an update has replaced
+    /// `generation`, or it was never loaded.
+    pub fn check_at_generation(
+        &self,
+        input: &RevocationCheckInput,
+        generation: ManifestVersion,
+        config: &Config,
+    ) -> Result<RevocationStatus, Error> {
+        let current = self.current();
+        current
+            .manifest
+            .require_generation(generation)?;
+        Ok(current
+            .manifest
+            .check_loaded(&current.filters, input, config)
+            .status)
+    }
+
+    /// Like [`Manifest::check_detailed()`], using the manifest currently in use.
+    pub fn check_detailed(&self, input: &RevocationCheckInput, config: &Config) -> CheckOutcome {
+        let current = self.current();
+        current
+            .manifest
+            .check_loaded(&current.filters, input, config)
+    }
+
+    fn current(&self) -> Arc<Loaded> {
+        self.current
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+/// Identifies a manifest, as returned by [`Manifest::version()`].
+///
+/// This displays as the decimal [`Manifest::generated_at`].
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ManifestVersion {
+    /// The manifest's [`Manifest::generated_at`].
+    pub generated_at: u64,
+}
+
+impl fmt::Display for ManifestVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.generated_at)
+    }
+}
+
+struct Loaded {
+    manifest: Manifest,
+    filters: LoadedFilters,
+}
+
+impl Loaded {
+    fn from_config(config: &Config) -> Result<Self, Error> {
+        let manifest = Manifest::from_config(config)?;
+        manifest.verify(config)?;
+        let filters = manifest.load_filters_in_scope(config)?;
+        Ok(Self { manifest, filters })
+    }
+
+    fn from_archive(path: &Path) -> Result<Self, Error> {
+        let archive = read_archive(path)?;
+        let manifest = Manifest::from_archived(&archive, path)?;
+        let filters = manifest.load_archived_filters(&archive, path)?;
+        Ok(Self { manifest, filters })
+    }
+
+    fn version(&self) -> ManifestVersion {
+        self.manifest.version()
+    }
+}
+
+impl fmt::Debug for Loaded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Loaded")
+            .field("manifest", &self.manifest)
+            .field("filters", &self.filters.filters.len())
+            .finish()
+    }
+}
@@ -0,0 +1,390 @@
+//! Step-by-step records of revocation checks, for debugging.
+
+use core::{fmt, iter};
+
+use base64::Engine;
+use base64::prelude::BASE64_STANDARD;
+use clubcard_crlite::{CRLiteClubcard, CRLiteKey, CRLiteQuery, CRLiteStatus};
+
+use super::{
+    CheckOutcome, CtTimestamp, IssuerSpkiHash, LoadedFilters, Manifest, NoSctPolicy,
+    RevocationCheckInput, StaleCoveragePolicy,
+};
+use crate::Config;
+
+/// How [`Manifest::check_trace()`] reached its outcome.
+///
+/// This displays as a numbered list of steps, followed by the outcome.  Like
+/// [`CheckOutcome::explain()`], the wording may change between versions.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct CheckTrace {
+    /// Each step of the check, in the order taken.
+    pub steps: Vec<TraceStep>,
+
+    /// The outcome, as [`Manifest::check_detailed()`] returns it.
+    pub outcome: CheckOutcome,
+}
+
+impl CheckTrace {
+    pub(super) fn new(
+        manifest: &Manifest,
+        filters: &LoadedFilters,
+        input: &RevocationCheckInput,
+        config: &Config,
+    ) -> Self {
+        let outcome = manifest.check_loaded(filters, input, config);
+        let revocation = &config.revocation;
+        let stale_coverage = revocation.stale_coverage_end(filters, outcome.evaluated_at);
+        let names = manifest
+            .filters
+            .iter()
+            .filter(|filter| filter.in_scope(config))
+            .map(|filter| filter.filename.clone());
+        let filters = names
+            .zip(&filters.filters)
+            .collect::<Vec<_>>();
+
+        let mut steps = vec![TraceStep::Issuer {
+            issuer_spki_hash: input.issuer_spki_hash.clone(),
+            enrolled_filters: filters
+                .iter()
+                .filter(|(_, filter)| {
+                    filter
+                        .index()
+                        .contains_key(input.issuer_spki_hash.0.as_slice())
+                })
+                .count(),
+        }];
+
+        if !revocation.in_scope(&input.issuer_spki_hash) {
+            steps.push(TraceStep::OutOfScope);
+            return Self { steps, outcome };
+        }
+
+        let bypassed = revocation
+            .bypass_issuers
+            .contains(&input.issuer_spki_hash);
+        steps.push(TraceStep::Bypass { bypassed });
+        if bypassed {
+            return Self { steps, outcome };
+        }
+
+        if manifest.is_serial_in_revoked_range(&input.issuer_spki_hash, &input.cert_serial) {
+            steps.push(TraceStep::RevokedRange);
+            return Self { steps, outcome };
+        }
+
+        let key = input.key();
+        if revocation
+            .sct_exempt_issuers
+            .contains(&input.issuer_spki_hash)
+        {
+            steps.push(TraceStep::SctExempt);
+            probe_by_serial(&filters, input, &key, &mut steps);
+            return Self { steps, outcome };
+        }
+
+        let (resolved, _) = revocation
+            .duplicate_sct_policy
+            .resolve(&input.sct_timestamps);
+        let scts = resolved.unwrap_or_else(|| input.sct_timestamps.clone());
+        if scts.len() < revocation.min_sct_count {
+            steps.push(TraceStep::InsufficientScts {
+                count: scts.len(),
+                required: revocation.min_sct_count,
+            });
+            return Self { steps, outcome };
+        }
+
+        if let (Some(coverage_end), StaleCoveragePolicy::SerialOnly) =
+            (stale_coverage, revocation.stale_coverage_policy)
+        {
+            steps.push(TraceStep::StaleCoverage { coverage_end });
+            probe_by_serial(&filters, input, &key, &mut steps);
+            return Self { steps, outcome };
+        }
+
+        if scts.is_empty() {
+            let policy = revocation.no_sct_policy;
+            steps.push(TraceStep::NoScts { policy });
+            if policy != NoSctPolicy::TreatAsNotCovered {
+                probe_by_serial(&filters, input, &key, &mut steps);
+            }
+            return Self { steps, outcome };
+        }
+
+        let latest_sct = revocation.latest_sct(outcome.evaluated_at);
+        let mut usable = Vec::new();
+        for sct in scts {
+            let approved = revocation.approves_log(&sct.log_id);
+            let future = sct.timestamp > latest_sct;
+            let qualified = revocation.qualifies(&sct);
+            if approved && !future && qualified {
+                usable.push(sct.clone());
+            }
+            steps.push(TraceStep::Sct {
+                sct,
+                approved,
+                future,
+                qualified,
+            });
+        }
+
+        let required = revocation.min_covered_scts.max(1);
+        for (name, filter) in &filters {
+            let mut found = false;
+            let mut covered = 0;
+            for sct in &usable {
+                let result = match filter.contains(&key, iter::once((&sct.log_id.0, sct.timestamp)))
+                {
+                    CRLiteStatus::Revoked => ProbeResult::Revoked,
+                    CRLiteStatus::Good => ProbeResult::NotRevoked,
+                    CRLiteStatus::NotEnrolled => ProbeResult::NotEnrolled,
+                    CRLiteStatus::NotCovered => ProbeResult::NotCovered,
+                };
+                steps.push(TraceStep::Probe {
+                    filter: name.clone(),
+                    sct: Some(sct.clone()),
+                    result,
+                });
+                match result {
+                    ProbeResult::Revoked | ProbeResult::NotRevoked => {
+                        found = true;
+                        covered += 1;
+                    }
+                    ProbeResult::NotEnrolled => break,
+                    ProbeResult::NotCovered => {}
+                }
+            }
+
+            steps.push(TraceStep::Coverage {
+                filter: name.clone(),
+                covered,
+                required,
+            });
+            if found && covered >= required {
+                break;
+            }
+        }
+
+        Self { steps, outcome }
+    }
+}
+
+/// Query each filter the issuer is enrolled in by serial number, until one has it.
+fn probe_by_serial(
+    filters: &[(String, &CRLiteClubcard)],
+    input: &RevocationCheckInput,
+    key: &CRLiteKey<'_>,
+    steps: &mut Vec<TraceStep>,
+) {
+    for (name, filter) in filters {
+        if !filter
+            .index()
+            .contains_key(input.issuer_spki_hash.0.as_slice())
+        {
+            continue;
+        }
+
+        let revoked = filter
+            .as_ref()
+            .unchecked_contains(&CRLiteQuery::new(key, None));
+        steps.push(TraceStep::Probe {
+            filter: name.clone(),
+            sct: None,
+            result: match revoked {
+                true => ProbeResult::Revoked,
+                false => ProbeResult::NotRevoked,
+            },
+        });
+        if revoked {
+            break;
+        }
+    }
+}
+
+impl fmt::Display for CheckTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, step) in self.steps.iter().enumerate() {
+            writeln!(f, "{}. {step}", i + 1)?;
+        }
+
+        write!(f, "outcome: {:?}", self.outcome.status)?;
+        if let Some(reason) = self.outcome.not_covered_reason {
+            write!(f, " ({reason:?})")?;
+        }
+        for warning in &self.outcome.warnings {
+            write!(f, "\nwarning: {warning}")?;
+        }
+        Ok(())
+    }
+}
+
+/// One step of a [`CheckTrace`].
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum TraceStep {
+    /// The certificate's issuer was looked up in each filter.
+    Issuer {
+        /// The issuer.
+        issuer_spki_hash: IssuerSpkiHash,
+        /// How many filters have data for it.
+        enrolled_filters: usize,
+    },
+
+    /// The issuer is not in the configured `in-scope-issuers`, which ends the check.
+    OutOfScope,
+
+    /// The issuer was looked up in the configured `bypass-issuers`.
+    Bypass {
+        /// Whether it was found, which ends the check.
+        bypassed: bool,
+    },
+
+    /// The certificate's serial number is in one of the manifest's
+    /// [`Manifest::revoked_ranges`], which ends the check.
+    RevokedRange,
+
+    /// The issuer is in the configured `sct-exempt-issuers`, so the certificate was
+    /// looked up by serial number alone.
+    SctExempt,
+
+    /// The certificate has fewer SCTs than the configured `min-sct-count`, which
+    /// ends the check.
+    InsufficientScts {
+        /// How many SCTs the certificate has.
+        count: usize,
+        /// The configured `min-sct-count`.
+        required: usize,
+    },
+
+    /// The coverage of the revocation data is stale, so the certificate was looked up
+    /// by serial number alone, according to the `stale-coverage-policy`.
+    StaleCoverage {
+        /// The latest SCT timestamp any filter covers, in UNIX milliseconds.
+        coverage_end: u64,
+    },
+
+    /// The certificate has no SCTs, so it was checked according to `policy`.
+    NoScts {
+        /// The configured `no-sct-policy`.
+        policy: NoSctPolicy,
+    },
+
+    /// An SCT was considered for use.
+    ///
+    /// It is only used if it is from an approved log, within the log's qualification
+    /// window, and not in the future.
+    Sct {
+        /// The SCT, after applying the configured `duplicate-sct-policy`.
+        sct: CtTimestamp,
+        /// Whether its log is approved by the configured `approved-logs`.
+        approved: bool,
+        /// Whether it is dated beyond the configured `sct-skew-tolerance`.
+        future: bool,
+        /// Whether it is dated within its log's configured `log-windows`.
+        qualified: bool,
+    },
+
+    /// A filter was queried for the certificate.
+    Probe {
+        /// The filter's filename.
+        filter: String,
+        /// The SCT queried with, or `None` for a lookup by serial number only.
+        sct: Option<CtTimestamp>,
+        /// What the filter said.
+        result: ProbeResult,
+    },
+
+    /// A filter's coverage of the certificate was compared with `min-covered-scts`.
+    ///
+    /// If the filter has a result for the certificate and enough SCTs are covered,
+    /// it decides the outcome.
+    Coverage {
+        /// The filter's filename.
+        filter: String,
+        /// How many of the certificate's SCTs the filter covers.
+        covered: usize,
+        /// How many are required.
+        required: usize,
+    },
+}
+
+impl fmt::Display for TraceStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Issuer {
+                issuer_spki_hash,
+                enrolled_filters,
+            } => write!(
+                f,
+                "issuer {} is enrolled in {enrolled_filters} filters",
+                BASE64_STANDARD.encode(issuer_spki_hash.0)
+            ),
+            Self::OutOfScope => write!(f, "issuer is out of scope"),
+            Self::RevokedRange => write!(f, "serial is in a revoked range"),
+            Self::Bypass { bypassed: true } => write!(f, "issuer is bypassed"),
+            Self::Bypass { bypassed: false } => write!(f, "issuer is not bypassed"),
+            Self::SctExempt => write!(f, "issuer is exempt from SCT coverage"),
+            Self::InsufficientScts { count, required } => {
+                write!(f, "{count} SCTs is fewer than the {required} required")
+            }
+            Self::StaleCoverage { coverage_end } => {
+                write!(
+                    f,
+                    "coverage ending at {coverage_end} is stale, so using serial only"
+                )
+            }
+            Self::NoScts { policy } => write!(f, "no SCTs, so applying {policy:?}"),
+            Self::Sct {
+                sct,
+                approved,
+                future,
+                qualified,
+            } => {
+                write!(f, "SCT {sct}: ")?;
+                match (approved, future, qualified) {
+                    (false, _, _) => write!(f, "ignored, log is not approved"),
+                    (true, true, _) => write!(f, "ignored, dated in the future"),
+                    (true, false, false) => {
+                        write!(f, "ignored, outside the log's qualification window")
+                    }
+                    (true, false, true) => write!(f, "used"),
+                }
+            }
+            Self::Probe {
+                filter,
+                sct: Some(sct),
+                result,
+            } => write!(f, "filter {filter} with SCT {sct}: {result:?}"),
+            Self::Probe {
+                filter,
+                sct: None,
+                result,
+            } => write!(f, "filter {filter} by serial only: {result:?}"),
+            Self::Coverage {
+                filter,
+                covered,
+                required,
+            } => write!(
+                f,
+                "filter {filter} covers {covered} SCTs, of {required} required"
+            ),
+        }
+    }
+}
+
+/// What a filter said about a certificate, in a [`TraceStep::Probe`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ProbeResult {
+    /// The certificate is in the filter.
+    Revoked,
+    /// The certificate is not in the filter.
+    NotRevoked,
+    /// The filter has no data for the certificate's issuer.
+    NotEnrolled,
+    /// The SCT is outside the filter's coverage.
+    NotCovered,
+}
@@ -0,0 +1,142 @@
+//! Conformance test vectors.
+//!
+//! A test vector file is a JSON array of cases, each naming a manifest, a check
+//! input, the time to check as of, and the expected status:
+//!
+//! ```json
+//! [
+//!   {
+//!     "description": "revoked certificate with a covered SCT",
+//!     "manifest": "crlite/revocation/manifest.json",
+//!     "input": "qqqq...qo= AQ== AQEB...AQE=:1750000000000",
+//!     "as_of": 1765445031,
+//!     "expected": "CertainlyRevoked"
+//!   }
+//! ]
+//! ```
+//!
+//! - `manifest` is relative to the test vector file.  The manifest's filters are
+//!   read from the manifest's directory.
+//! - `input` uses the [`RevocationCheckInput`] string representation.
+//! - `as_of` is the time the check is made at, in seconds since the UNIX epoch: see
+//!   [`Manifest::check_at()`].  SCTs dated more than an hour after it are ignored.
+//! - `expected` is the name of a [`RevocationStatus`] variant.
+//!
+//! Checks are made with the default revocation configuration, so results do not
+//! depend on the current time.
+
+use core::str::FromStr;
+use core::time::Duration;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use serde::Deserialize;
+
+use super::{Error, Manifest, RevocationCheckInput, RevocationConfig};
+use crate::Config;
+
+/// Run the test vectors in the file at `path`.
+///
+/// Cases whose outcome differs from what they expect are reported in the returned
+/// [`TestVectorReport`].  This fails if the file, or a manifest or filter it refers
+/// to, cannot be loaded.
+pub fn run_test_vectors(path: &Path) -> Result<TestVectorReport, Error> {
+    let text = fs::read_to_string(path).map_err(|error| Error::FileRead {
+        error,
+        path: path.to_owned(),
+    })?;
+    let cases = serde_json::from_str::<Vec<TestVector>>(&text).map_err(|error| {
+        Error::TestVectorDecode {
+            error: Box::new(error),
+            path: path.to_owned(),
+        }
+    })?;
+
+    let base = path
+        .parent()
+        .unwrap_or_else(|| Path::new(""));
+    let mut report = TestVectorReport::default();
+    for (index, case) in cases.into_iter().enumerate() {
+        let manifest_path = base.join(&case.manifest);
+        let manifest = Manifest::from_file(&manifest_path)?;
+        let dir = manifest_path
+            .parent()
+            .unwrap_or_else(|| Path::new(""));
+        let filters = manifest.load_filters(dir)?;
+        let config = Config {
+            cache_dir: PathBuf::new(),
+            read_only: true,
+            revocation: RevocationConfig::default(),
+            sources: BTreeMap::new(),
+        };
+
+        let actual = match RevocationCheckInput::from_str(&case.input) {
+            Ok(input) => format!(
+                "{:?}",
+                manifest
+                    .check_loaded_at(
+                        &filters,
+                        &input,
+                        UNIX_EPOCH + Duration::from_secs(case.as_of),
+                        &config,
+                    )
+                    .status
+            ),
+            Err(error) => format!("error: {error}"),
+        };
+
+        match actual == case.expected {
+            true => report.passed += 1,
+            false => report
+                .mismatches
+                .push(TestVectorMismatch {
+                    index,
+                    description: case.description,
+                    expected: case.expected,
+                    actual,
+                }),
+        }
+    }
+
+    Ok(report)
+}
+
+/// The results of [`run_test_vectors()`].
+#[derive(Clone, Debug, Default)]
+pub struct TestVectorReport {
+    /// How many cases produced their expected status.
+    pub passed: usize,
+
+    /// The cases that did not.
+    pub mismatches: Vec<TestVectorMismatch>,
+}
+
+/// A test vector whose outcome was not what it expected.
+#[derive(Clone, Debug)]
+pub struct TestVectorMismatch {
+    /// Position of the case in the file, starting from 0.
+    pub index: usize,
+
+    /// The case's description.
+    pub description: String,
+
+    /// The expected status.
+    pub expected: String,
+
+    /// The actual status, or `error: ` and a description of why the input could
+    /// not be parsed.
+    pub actual: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TestVector {
+    #[serde(default)]
+    description: String,
+    manifest: PathBuf,
+    input: String,
+    as_of: u64,
+    expected: String,
+}
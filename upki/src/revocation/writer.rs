@@ -0,0 +1,256 @@
+//! Production of manifests and filters.
+//!
+//! This is for tooling that runs its own revocation data pipeline.  The output of
+//! [`ManifestWriter::write()`] is a directory that can be used directly as the
+//! revocation cache directory, or served to `upki fetch` by a web server.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use aws_lc_rs::digest;
+use clubcard::builder::{ApproximateRibbon, ClubcardBuilder, ExactRibbon};
+use clubcard_crlite::builder::CRLiteBuilderItem;
+use clubcard_crlite::{CRLiteClubcard, CRLiteCoverage, CRLiteQuery};
+
+use super::{CertSerial, CtLogId, Error, Filter, IssuerSpkiHash, Manifest};
+
+/// Builds a manifest and a single crlite filter from revocation data.
+///
+/// Entries are added one at a time.  A filter is exact only for the certificates it
+/// was built from: so for each issuer, every covered certificate should be added,
+/// whether revoked (with [`ManifestWriter::revoked()`]) or not (with
+/// [`ManifestWriter::not_revoked()`]).
+#[derive(Clone, Debug)]
+pub struct ManifestWriter {
+    generated_at: u64,
+    comment: String,
+    logs: Vec<LogCoverage>,
+    issuers: BTreeMap<[u8; 32], IssuerEntries>,
+}
+
+impl ManifestWriter {
+    /// Start a new manifest.
+    ///
+    /// `generated_at` is a UNIX timestamp in seconds, and becomes
+    /// [`Manifest::generated_at`].  `comment` becomes [`Manifest::comment`].
+    pub fn new(generated_at: u64, comment: impl Into<String>) -> Self {
+        Self {
+            generated_at,
+            comment: comment.into(),
+            logs: Vec::new(),
+            issuers: BTreeMap::new(),
+        }
+    }
+
+    /// Declare that the data covers certificates logged in the CT log `log_id`
+    /// with timestamps from `min_timestamp` to `max_timestamp`.
+    ///
+    /// Timestamps are in milliseconds since the UNIX epoch, like [`super::CtTimestamp::timestamp`].
+    pub fn cover_log(&mut self, log_id: CtLogId, min_timestamp: u64, max_timestamp: u64) {
+        self.logs.push(LogCoverage {
+            log_id,
+            min_timestamp,
+            max_timestamp,
+        });
+    }
+
+    /// Add a revoked certificate.
+    pub fn revoked(&mut self, issuer: &IssuerSpkiHash, serial: &CertSerial) {
+        self.issuers
+            .entry(issuer.0)
+            .or_default()
+            .revoked
+            .push(serial.0.clone());
+    }
+
+    /// Add a certificate that is not revoked.
+    pub fn not_revoked(&mut self, issuer: &IssuerSpkiHash, serial: &CertSerial) {
+        self.issuers
+            .entry(issuer.0)
+            .or_default()
+            .not_revoked
+            .push(serial.0.clone());
+    }
+
+    /// List the certificates added more than once, or added with conflicting statuses.
+    ///
+    /// This is for pipelines that merge several sources of revocation data.  A
+    /// certificate added as both revoked and not revoked is a [`Conflict::RevokedAndNotRevoked`],
+    /// whether or not it was also added twice with the same status.  Conflicts are
+    /// listed by issuer and then serial number.
+    ///
+    /// Written manifests cannot be audited this way, as crlite filters cannot be
+    /// enumerated.
+    pub fn audit_conflicts(&self) -> Vec<Conflict> {
+        let mut conflicts = Vec::new();
+        for (issuer, entries) in &self.issuers {
+            let mut counts = BTreeMap::<&[u8], (usize, usize)>::new();
+            for serial in &entries.revoked {
+                counts.entry(serial).or_default().0 += 1;
+            }
+            for serial in &entries.not_revoked {
+                counts.entry(serial).or_default().1 += 1;
+            }
+
+            for (serial, counts) in counts {
+                let issuer = IssuerSpkiHash(*issuer);
+                let serial = CertSerial(serial.to_vec());
+                conflicts.push(match counts {
+                    (1, 0) | (0, 1) => continue,
+                    (revoked, 0) => Conflict::Duplicate {
+                        issuer,
+                        serial,
+                        revoked: true,
+                        count: revoked,
+                    },
+                    (0, not_revoked) => Conflict::Duplicate {
+                        issuer,
+                        serial,
+                        revoked: false,
+                        count: not_revoked,
+                    },
+                    _ => Conflict::RevokedAndNotRevoked { issuer, serial },
+                });
+            }
+        }
+
+        conflicts
+    }
+
+    /// Build the filter, and write it and the manifest into `dir`.
+    ///
+    /// `dir` must exist.  The filter is named after `generated_at`. Returns the
+    /// manifest that was written.
+    pub fn write(&self, dir: &Path) -> Result<Manifest, Error> {
+        let filename = format!("{}.filter", self.generated_at);
+        let path = dir.join(&filename);
+        let bytes = self
+            .build()
+            .to_bytes()
+            .map_err(|error| Error::FilterEncode {
+                error: format!("cannot encode crlite filter: {error:?}").into(),
+                path: path.clone(),
+            })?;
+
+        fs::write(&path, &bytes).map_err(|error| Error::FileWrite {
+            error,
+            path: path.clone(),
+        })?;
+
+        let manifest = Manifest {
+            generated_at: self.generated_at,
+            comment: self.comment.clone(),
+            filters: vec![Filter {
+                filename,
+                size: bytes.len(),
+                hash: digest::digest(&digest::SHA256, &bytes)
+                    .as_ref()
+                    .to_vec(),
+                issuers: Vec::new(),
+            }],
+            provenance: None,
+            next_update: None,
+            epoch: None,
+            format_version: None,
+            revoked_ranges: Vec::new(),
+        };
+
+        let path = dir.join("manifest.json");
+        let json = serde_json::to_vec(&manifest).map_err(|error| Error::ManifestEncode {
+            error: Box::new(error),
+            path: path.clone(),
+        })?;
+        fs::write(&path, json).map_err(|error| Error::ManifestWrite { error, path })?;
+
+        Ok(manifest)
+    }
+
+    fn build(&self) -> CRLiteClubcard {
+        let mut builder = ClubcardBuilder::new();
+
+        let mut approx = Vec::with_capacity(self.issuers.len());
+        for (issuer, entries) in &self.issuers {
+            let mut ribbon = builder.new_approx_builder(issuer);
+            for serial in &entries.revoked {
+                ribbon.insert(CRLiteBuilderItem::revoked(*issuer, serial.clone()));
+            }
+            ribbon.set_universe_size(entries.revoked.len() + entries.not_revoked.len());
+            approx.push(ApproximateRibbon::from(ribbon));
+        }
+        builder.collect_approx_ribbons(approx);
+
+        let mut exact = Vec::with_capacity(self.issuers.len());
+        for (issuer, entries) in &self.issuers {
+            let mut ribbon = builder.new_exact_builder(issuer);
+            for serial in &entries.revoked {
+                ribbon.insert(CRLiteBuilderItem::revoked(*issuer, serial.clone()));
+            }
+            for serial in &entries.not_revoked {
+                ribbon.insert(CRLiteBuilderItem::not_revoked(*issuer, serial.clone()));
+            }
+            exact.push(ExactRibbon::from(ribbon));
+        }
+        builder.collect_exact_ribbons(exact);
+
+        // `CRLiteCoverage` can only be constructed from Mozilla's `ct-logs.json` format.
+        let logs = self
+            .logs
+            .iter()
+            .map(|log| {
+                serde_json::json!({
+                    "LogID": log.log_id.to_string(),
+                    "MinTimestamp": log.min_timestamp,
+                    "MaxTimestamp": log.max_timestamp,
+                    "MMD": 0,
+                    "MinEntry": 0,
+                })
+            })
+            .collect::<Vec<_>>();
+        let logs = serde_json::Value::from(logs).to_string();
+        let coverage = CRLiteCoverage::from_mozilla_ct_logs_json(logs.as_bytes());
+
+        builder
+            .build::<CRLiteQuery<'_>>(coverage, ())
+            .into()
+    }
+}
+
+/// A problem with the entries added to a [`ManifestWriter`].
+///
+/// Returned by [`ManifestWriter::audit_conflicts()`].
+#[non_exhaustive]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Conflict {
+    /// A certificate was added more than once, always with the same status.
+    Duplicate {
+        /// The certificate's issuer.
+        issuer: IssuerSpkiHash,
+        /// The certificate's serial number.
+        serial: CertSerial,
+        /// Whether it was added as revoked.
+        revoked: bool,
+        /// How many times it was added.
+        count: usize,
+    },
+    /// A certificate was added as both revoked and not revoked.
+    RevokedAndNotRevoked {
+        /// The certificate's issuer.
+        issuer: IssuerSpkiHash,
+        /// The certificate's serial number.
+        serial: CertSerial,
+    },
+}
+
+#[derive(Clone, Debug)]
+struct LogCoverage {
+    log_id: CtLogId,
+    min_timestamp: u64,
+    max_timestamp: u64,
+}
+
+#[derive(Clone, Debug, Default)]
+struct IssuerEntries {
+    revoked: Vec<Vec<u8>>,
+    not_revoked: Vec<Vec<u8>>,
+}
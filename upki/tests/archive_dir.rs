@@ -0,0 +1,69 @@
+#![cfg(unix)]
+
+use core::str::FromStr;
+use std::fs;
+
+use tempfile::TempDir;
+use upki::Config;
+use upki::revocation::{
+    ArchiveDirBackend, CertSerial, CtTimestamp, IssuerSpkiHash, RevocationCheckInput,
+    RevocationStatus,
+};
+
+#[test]
+fn two_handles_read_one_segment() {
+    let segments = TempDir::new().unwrap();
+    let dir = segments.path();
+    let crlite = Config::from_file("tests/data/crlite/config.toml".as_ref()).unwrap();
+    assert_eq!(ArchiveDirBackend::publish(dir, "upki", &crlite).unwrap(), 1);
+
+    let first = ArchiveDirBackend::open(dir, "upki").unwrap();
+    let second = ArchiveDirBackend::open(dir, "upki").unwrap();
+    let input = RevocationCheckInput {
+        cert_serial: CertSerial(vec![0x01]),
+        issuer_spki_hash: IssuerSpkiHash([0xaa; 32]),
+        sct_timestamps: vec![
+            CtTimestamp::from_str("AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE=:1750000000000")
+                .unwrap(),
+        ],
+    };
+    for handle in [&first, &second] {
+        assert_eq!(handle.segment_version(), 1);
+        assert_eq!(handle.manifest().version().generated_at, 1_765_445_031);
+        assert_eq!(
+            handle.manifest().check(&input, &crlite),
+            RevocationStatus::CertainlyRevoked
+        );
+        assert!(!handle.reload_if_changed().unwrap());
+    }
+
+    // the updater publishes newer data, and each handle picks it up
+    let config_path = dir.join("config.toml");
+    fs::write(
+        &config_path,
+        "cache-dir = \"tests/data/epochs/2/\"\n[revocation]\nfetch-url = \"\"\n",
+    )
+    .unwrap();
+    let newer = Config::from_file(&config_path).unwrap();
+    assert_eq!(ArchiveDirBackend::publish(dir, "upki", &newer).unwrap(), 2);
+    for handle in [&first, &second] {
+        assert!(handle.reload_if_changed().unwrap());
+        assert_eq!(handle.segment_version(), 2);
+        assert_eq!(handle.manifest().version().generated_at, 1_765_531_431);
+        assert!(!handle.reload_if_changed().unwrap());
+    }
+
+    // only the previous segment is kept
+    ArchiveDirBackend::publish(dir, "upki", &crlite).unwrap();
+    assert!(!dir.join("upki.1").exists());
+    assert!(dir.join("upki.2").exists());
+    ArchiveDirBackend::publish(dir, "upki", &crlite).unwrap();
+    assert!(!dir.join("upki.2").exists());
+    assert!(dir.join("upki.3").exists());
+
+    // nothing published under another name
+    assert!(matches!(
+        ArchiveDirBackend::open(dir, "other"),
+        Err(upki::revocation::Error::ManifestMissing(_))
+    ));
+}
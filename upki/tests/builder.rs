@@ -0,0 +1,217 @@
+use std::fs;
+use std::path::Path;
+
+use tempfile::TempDir;
+use upki::Config;
+use upki::revocation::{
+    CertSerial, Conflict, CtLogId, CtTimestamp, IntegrityProblem, IssuerSpkiHash, Manifest,
+    ManifestSet, ManifestSource, ManifestWriter, RevocationCheckInput, RevocationStatus,
+    UncoveredPolicy,
+};
+
+#[test]
+fn written_manifest_loads_and_checks() {
+    let temp = TempDir::new().unwrap();
+    let issuer = IssuerSpkiHash([0x55; 32]);
+
+    let mut writer = ManifestWriter::new(1_765_445_031, "written by test");
+    writer.cover_log(CtLogId([0x01; 32]), 1_700_000_000_000, 1_760_000_000_000);
+    writer.revoked(&issuer, &CertSerial(vec![0x01]));
+    writer.revoked(&issuer, &CertSerial(vec![0x02, 0x03]));
+    writer.not_revoked(&issuer, &CertSerial(vec![0x04]));
+
+    fs::create_dir(temp.path().join("revocation")).unwrap();
+    let written = writer
+        .write(&temp.path().join("revocation"))
+        .unwrap();
+    assert_eq!(written.filters.len(), 1);
+
+    let config_file = temp.path().join("config.toml");
+    fs::write(
+        &config_file,
+        format!(
+            "cache-dir=\"{}\"\n\
+            [revocation]\n\
+            fetch-url=\"\"\n",
+            temp.path().display(),
+        ),
+    )
+    .unwrap();
+    let config = Config::from_file(&config_file).unwrap();
+
+    let manifest = Manifest::from_config(&config).unwrap();
+    assert_eq!(manifest.generated_at, 1_765_445_031);
+    assert_eq!(manifest.comment, "written by test");
+    assert_eq!(manifest.filters[0].hash, written.filters[0].hash);
+    assert!(manifest.verify(&config).is_ok());
+
+    let check = |serial: &[u8], log_id| {
+        let input = RevocationCheckInput {
+            cert_serial: CertSerial(serial.to_vec()),
+            issuer_spki_hash: issuer.clone(),
+            sct_timestamps: vec![CtTimestamp {
+                log_id: CtLogId(log_id),
+                timestamp: 1_750_000_000_000,
+            }],
+        };
+        manifest.check(&input, &config).unwrap()
+    };
+
+    assert_eq!(
+        check(&[0x01], [0x01; 32]),
+        RevocationStatus::CertainlyRevoked
+    );
+    assert_eq!(
+        check(&[0x02, 0x03], [0x01; 32]),
+        RevocationStatus::CertainlyRevoked
+    );
+    assert_eq!(check(&[0x04], [0x01; 32]), RevocationStatus::NotRevoked);
+    assert_eq!(
+        check(&[0x01], [0x02; 32]),
+        RevocationStatus::NotCoveredByRevocationData
+    );
+}
+
+#[test]
+fn future_coverage_fails_integrity_check() {
+    let temp = TempDir::new().unwrap();
+    let dir = temp.path().join("revocation");
+    fs::create_dir(&dir).unwrap();
+
+    let mut writer = ManifestWriter::new(1_765_445_031, "from the future");
+    writer.cover_log(CtLogId([0x01; 32]), 1_700_000_000_000, 1_760_000_000_000);
+    writer.cover_log(CtLogId([0x02; 32]), 1_700_000_000_000, u64::MAX);
+    writer.revoked(&IssuerSpkiHash([0x55; 32]), &CertSerial(vec![0x01]));
+    writer.write(&dir).unwrap();
+
+    let config_file = temp.path().join("config.toml");
+    fs::write(
+        &config_file,
+        format!(
+            "cache-dir=\"{}\"\n\
+            [revocation]\n\
+            fetch-url=\"\"\n",
+            temp.path().display(),
+        ),
+    )
+    .unwrap();
+    let config = Config::from_file(&config_file).unwrap();
+
+    let manifest = Manifest::from_config(&config).unwrap();
+    assert!(manifest.verify(&config).is_ok());
+    let problems = manifest
+        .verify_integrity(&config)
+        .unwrap_err();
+    assert!(
+        matches!(
+            &problems[..],
+            [IntegrityProblem::FutureCoverage {
+                log_id: CtLogId([0x02, ..]),
+                max_timestamp: u64::MAX,
+                ..
+            }]
+        ),
+        "{problems:?}"
+    );
+}
+
+#[test]
+fn audit_conflicts() {
+    let (a, b) = (IssuerSpkiHash([0xaa; 32]), IssuerSpkiHash([0xbb; 32]));
+    let mut writer = ManifestWriter::new(1_765_445_031, "merged feeds");
+    writer.revoked(&b, &CertSerial(vec![0x01]));
+    writer.revoked(&a, &CertSerial(vec![0x02]));
+    writer.not_revoked(&a, &CertSerial(vec![0x01]));
+    writer.revoked(&b, &CertSerial(vec![0x01]));
+    writer.revoked(&a, &CertSerial(vec![0x03]));
+    writer.not_revoked(&a, &CertSerial(vec![0x03]));
+    writer.revoked(&a, &CertSerial(vec![0x03]));
+    assert_eq!(
+        writer.audit_conflicts(),
+        [
+            Conflict::RevokedAndNotRevoked {
+                issuer: a,
+                serial: CertSerial(vec![0x03]),
+            },
+            Conflict::Duplicate {
+                issuer: b,
+                serial: CertSerial(vec![0x01]),
+                revoked: true,
+                count: 2,
+            },
+        ]
+    );
+
+    let mut clean = ManifestWriter::new(1_765_445_031, "one feed");
+    clean.revoked(&IssuerSpkiHash([0xaa; 32]), &CertSerial(vec![0x01]));
+    clean.not_revoked(&IssuerSpkiHash([0xaa; 32]), &CertSerial(vec![0x02]));
+    assert_eq!(clean.audit_conflicts(), []);
+}
+
+#[test]
+fn manifest_set_routes_by_scope() {
+    let temp = TempDir::new().unwrap();
+    let internal_ca = IssuerSpkiHash([0x55; 32]);
+    let public_ca = IssuerSpkiHash([0xaa; 32]);
+
+    // the internal feed disagrees with the public one about serial 0x01 from the
+    // public CA, but is only responsible for the internal CA
+    let mut writer = ManifestWriter::new(1_765_445_031, "internal feed");
+    writer.cover_log(CtLogId([0x01; 32]), 1_700_000_000_000, 1_760_000_000_000);
+    writer.revoked(&internal_ca, &CertSerial(vec![0x02]));
+    writer.not_revoked(&internal_ca, &CertSerial(vec![0x04]));
+    writer.not_revoked(&public_ca, &CertSerial(vec![0x01]));
+    let internal = writer.write(temp.path()).unwrap();
+
+    let public_dir = Path::new("tests/data/crlite/revocation");
+    let public = Manifest::from_file(&public_dir.join("manifest.json")).unwrap();
+    let config = Config::from_file("tests/data/crlite/config.toml".as_ref()).unwrap();
+
+    let set = ManifestSet::builder()
+        .source(ManifestSource::new(public, public_dir))
+        .source(
+            ManifestSource::new(internal, temp.path())
+                .priority(10)
+                .scope([internal_ca.clone()])
+                .uncovered_policy(UncoveredPolicy::FailClosed),
+        )
+        .build()
+        .unwrap();
+    assert_eq!(set.len(), 2);
+
+    let check = |issuer: &IssuerSpkiHash, serial: u8, log_id| {
+        let input = RevocationCheckInput {
+            cert_serial: CertSerial(vec![serial]),
+            issuer_spki_hash: issuer.clone(),
+            sct_timestamps: vec![CtTimestamp {
+                log_id: CtLogId([log_id; 32]),
+                timestamp: 1_750_000_000_000,
+            }],
+        };
+        set.check(&input, &config)
+    };
+
+    assert_eq!(
+        check(&internal_ca, 0x02, 0x01),
+        RevocationStatus::CertainlyRevoked
+    );
+    assert_eq!(
+        check(&internal_ca, 0x04, 0x01),
+        RevocationStatus::NotRevoked
+    );
+    assert_eq!(
+        check(&public_ca, 0x01, 0x01),
+        RevocationStatus::CertainlyRevoked
+    );
+    assert_eq!(check(&public_ca, 0x04, 0x01), RevocationStatus::NotRevoked);
+
+    // the internal feed fails closed, rather than leaving its CA to the public feed
+    assert_eq!(
+        check(&internal_ca, 0x04, 0x02),
+        RevocationStatus::CertainlyRevoked
+    );
+    assert_eq!(
+        check(&IssuerSpkiHash([0xcc; 32]), 0x04, 0x01),
+        RevocationStatus::NotCoveredByRevocationData
+    );
+}
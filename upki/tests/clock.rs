@@ -0,0 +1,150 @@
+use core::str::FromStr;
+use core::time::Duration;
+use std::fs;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use upki::Config;
+use upki::clock::{self, Clock};
+use upki::revocation::{
+    IntegrityProblem, Manifest, NotCoveredReason, RevocationCheckInput, RevocationStatus, Warning,
+};
+
+#[test]
+fn manifest_ages_with_mock_clock() {
+    let _serial = SERIAL.lock().unwrap();
+    let config = Config::from_file("tests/data/crlite/config.toml".as_ref()).unwrap();
+    let manifest = Manifest::from_config(&config).unwrap();
+    let generated_at = UNIX_EPOCH + Duration::from_secs(manifest.generated_at);
+    let input = RevocationCheckInput::from_str(
+        "qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqo= AQ== AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE=:1750000000000",
+    )
+    .unwrap();
+    let age = || {
+        let explained = manifest
+            .check_detailed(&input, &config)
+            .unwrap()
+            .explain();
+        explained
+            .lines()
+            .find_map(|line| line.strip_prefix("manifest: "))
+            .unwrap()
+            .to_owned()
+    };
+
+    let mock = MockClock::default();
+    clock::set_clock(mock.clone());
+
+    // before the manifest was generated
+    mock.set(generated_at - Duration::from_secs(86_400));
+    assert!(age().ends_with("which is in the future"), "{}", age());
+    assert!(matches!(
+        &manifest
+            .verify_integrity(&config)
+            .unwrap_err()[..],
+        [IntegrityProblem::FutureManifest { .. }]
+    ));
+
+    // fresh
+    mock.set(generated_at + Duration::from_secs(3600));
+    assert!(age().ends_with(", 1 hours old"), "{}", age());
+    assert!(
+        manifest
+            .verify_integrity(&config)
+            .is_ok()
+    );
+
+    // stale
+    mock.set(generated_at + Duration::from_secs(3 * 86_400));
+    assert!(age().ends_with(", 72 hours old"), "{}", age());
+
+    clock::reset_clock();
+    assert!(!age().ends_with(", 72 hours old"), "{}", age());
+}
+
+#[test]
+fn future_scts_are_ignored() {
+    let _serial = SERIAL.lock().unwrap();
+    let config = Config::from_file("tests/data/crlite/config.toml".as_ref()).unwrap();
+    let manifest = Manifest::from_config(&config).unwrap();
+    let sct = "AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE=:1750000000000";
+    let input = RevocationCheckInput::from_str(&format!(
+        "qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqo= AQ== {sct}"
+    ))
+    .unwrap();
+    let sct_time = UNIX_EPOCH + Duration::from_secs(1_750_000_000);
+
+    let mock = MockClock::default();
+    clock::set_clock(mock.clone());
+
+    // slightly in the future, within the default hour of skew
+    mock.set(sct_time - Duration::from_secs(1800));
+    let outcome = manifest
+        .check_detailed(&input, &config)
+        .unwrap();
+    assert_eq!(outcome.status, RevocationStatus::CertainlyRevoked);
+    assert!(outcome.warnings.is_empty());
+
+    // a day in the future
+    mock.set(sct_time - Duration::from_secs(86_400));
+    let outcome = manifest
+        .check_detailed(&input, &config)
+        .unwrap();
+    assert_eq!(outcome.status, RevocationStatus::NotCoveredByRevocationData);
+    assert_eq!(
+        outcome.not_covered_reason,
+        Some(NotCoveredReason::SctsNotCovered)
+    );
+    assert_eq!(
+        outcome.warnings,
+        [Warning::FutureSct {
+            log_id: input.sct_timestamps[0].log_id,
+            timestamp: 1_750_000_000_000,
+        }]
+    );
+
+    // unless the tolerance allows it
+    let temp = tempfile::TempDir::new().unwrap();
+    let path = temp.path().join("config.toml");
+    fs::write(
+        &path,
+        "cache-dir = \"tests/data/crlite/\"\n\
+         [revocation]\n\
+         fetch-url = \"\"\n\
+         sct-skew-tolerance = 172800\n",
+    )
+    .unwrap();
+    let tolerant = Config::from_file(&path).unwrap();
+    assert_eq!(
+        manifest
+            .check(&input, &tolerant)
+            .unwrap(),
+        RevocationStatus::CertainlyRevoked
+    );
+
+    clock::reset_clock();
+}
+
+/// Held by each test while it replaces the clock, which is shared by the process.
+static SERIAL: Mutex<()> = Mutex::new(());
+
+#[derive(Clone)]
+struct MockClock(Arc<Mutex<SystemTime>>);
+
+impl MockClock {
+    fn set(&self, now: SystemTime) {
+        *self.0.lock().unwrap() = now;
+    }
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        Self(Arc::new(Mutex::new(UNIX_EPOCH)))
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> SystemTime {
+        *self.0.lock().unwrap()
+    }
+}
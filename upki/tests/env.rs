@@ -0,0 +1,191 @@
+//! Environment variables are process-wide, so these tests live in their own test
+//! binary: that way nothing else runs concurrently with changes to them.
+
+use std::env;
+use std::fs;
+use std::sync::{Mutex, PoisonError};
+
+use insta::assert_snapshot;
+use tempfile::TempDir;
+use upki::{Config, FieldSource};
+
+#[test]
+fn config_from_env() {
+    let _env = ENV
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    let set = |name, value: Option<&str>| match value {
+        Some(value) => unsafe { env::set_var(name, value) },
+        None => unsafe { env::remove_var(name) },
+    };
+
+    set("UPKI_CACHE_DIR", Some("/var/cache/upki"));
+    set("UPKI_FETCH_URL", None);
+    set("UPKI_NO_SCT_POLICY", None);
+    set("UPKI_ALLOW_DOWNGRADE", None);
+    assert_snapshot!(
+        toml::to_string_pretty(&Config::from_env().unwrap()).unwrap(),
+        @r#"
+    cache-dir = "/var/cache/upki"
+    read-only = false
+
+    [revocation]
+    fetch-url = "https://upki.rustls.dev/"
+    no-sct-policy = "treat-as-not-covered"
+    allow-downgrade = false
+    min-covered-scts = 1
+    min-sct-count = 0
+    bypass-issuers = []
+    approved-logs = []
+    log-windows = []
+    uncovered-policy = "return-uncovered"
+    max-manifest-bytes = 1048576
+    in-scope-issuers = []
+    refresh-splay = 0
+    probabilistic-policy = "lenient"
+    sct-skew-tolerance = 3600
+    duplicate-sct-policy = "use-latest"
+    sct-exempt-issuers = []
+    report-rejected-scts = false
+    retain-epochs = 0
+    stale-coverage-threshold = 0
+    stale-coverage-policy = "degraded"
+    strict-input = false
+    "#
+    );
+
+    set("UPKI_FETCH_URL", Some("https://example.com/"));
+    set("UPKI_NO_SCT_POLICY", Some("use-serial-only"));
+    set("UPKI_ALLOW_DOWNGRADE", Some("true"));
+    assert_snapshot!(
+        toml::to_string_pretty(&Config::from_env().unwrap()).unwrap(),
+        @r#"
+    cache-dir = "/var/cache/upki"
+    read-only = false
+
+    [revocation]
+    fetch-url = "https://example.com/"
+    no-sct-policy = "use-serial-only"
+    allow-downgrade = true
+    min-covered-scts = 1
+    min-sct-count = 0
+    bypass-issuers = []
+    approved-logs = []
+    log-windows = []
+    uncovered-policy = "return-uncovered"
+    max-manifest-bytes = 1048576
+    in-scope-issuers = []
+    refresh-splay = 0
+    probabilistic-policy = "lenient"
+    sct-skew-tolerance = 3600
+    duplicate-sct-policy = "use-latest"
+    sct-exempt-issuers = []
+    report-rejected-scts = false
+    retain-epochs = 0
+    stale-coverage-threshold = 0
+    stale-coverage-policy = "degraded"
+    strict-input = false
+    "#
+    );
+
+    set("UPKI_NO_SCT_POLICY", Some("sometimes"));
+    assert_eq!(
+        Config::from_env()
+            .unwrap_err()
+            .to_string(),
+        "invalid value for environment variable UPKI_NO_SCT_POLICY: 'sometimes'"
+    );
+
+    set("UPKI_NO_SCT_POLICY", None);
+    set("UPKI_ALLOW_DOWNGRADE", Some("yes"));
+    assert_eq!(
+        Config::from_env()
+            .unwrap_err()
+            .to_string(),
+        "invalid value for environment variable UPKI_ALLOW_DOWNGRADE: 'yes'"
+    );
+
+    for name in [
+        "UPKI_CACHE_DIR",
+        "UPKI_FETCH_URL",
+        "UPKI_NO_SCT_POLICY",
+        "UPKI_ALLOW_DOWNGRADE",
+    ] {
+        set(name, None);
+    }
+}
+
+#[test]
+fn config_field_sources() {
+    let _env = ENV
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    unsafe {
+        env::set_var("UPKI_CACHE_DIR", "/var/cache/upki");
+        env::set_var("UPKI_FETCH_URL", "https://env.example.com/");
+        env::set_var("UPKI_ALLOW_DOWNGRADE", "true");
+        env::remove_var("UPKI_NO_SCT_POLICY");
+    }
+
+    let temp = TempDir::new().unwrap();
+    let path = temp.path().join("config.toml");
+    fs::write(
+        &path,
+        "[revocation]\nfetch-url = \"https://file.example.com/\"\nretain-epochs = 2\n",
+    )
+    .unwrap();
+    let config = Config::from_env_and_files(&[&path, &temp.path().join("missing.toml")])
+        .unwrap()
+        .with_setting("revocation.min-covered-scts", "2")
+        .unwrap();
+    let effective = toml::to_string(&config).unwrap();
+    for setting in [
+        "fetch-url = \"https://file.example.com/\"",
+        "allow-downgrade = true",
+        "min-covered-scts = 2",
+    ] {
+        assert!(
+            effective.contains(setting),
+            "{setting} missing from {effective}"
+        );
+    }
+
+    let sources = config.field_sources();
+    let source = |key: &str| {
+        sources
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, source)| *source)
+    };
+    // a file over the environment over defaults, and then code
+    assert_eq!(source("revocation.fetch-url"), Some(FieldSource::File));
+    assert_eq!(source("revocation.retain-epochs"), Some(FieldSource::File));
+    assert_eq!(source("revocation.allow-downgrade"), Some(FieldSource::Env));
+    assert_eq!(source("cache-dir"), Some(FieldSource::Env));
+    assert_eq!(
+        source("revocation.min-covered-scts"),
+        Some(FieldSource::Builder)
+    );
+    assert_eq!(
+        source("revocation.no-sct-policy"),
+        Some(FieldSource::Default)
+    );
+    assert_eq!(source("read-only"), Some(FieldSource::Default));
+    assert_eq!(source("revocation"), None);
+    assert!(sources.is_sorted_by(|a, b| a.0 < b.0));
+
+    assert!(
+        config
+            .clone()
+            .with_setting("revocation.no-such-setting", "1")
+            .is_err()
+    );
+    assert!(
+        config
+            .with_setting("revocation.min-covered-scts", "\"two\"")
+            .is_err()
+    );
+}
+
+/// Held by each test while it changes environment variables.
+static ENV: Mutex<()> = Mutex::new(());
@@ -2,18 +2,38 @@
 // but should work ok on macOS and WSL
 #![cfg(not(target_os = "windows"))]
 
+use core::cell::Cell;
 use core::error::Error;
+use core::hash::{Hash, Hasher};
+use core::str::FromStr;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use core::time::Duration;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
 use std::fs::create_dir;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::sync::Arc;
-use std::{fs, thread};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use std::{fs, io, str, thread};
 
+use aws_lc_rs::digest;
+use clubcard_crlite::CRLiteClubcard;
 use insta::assert_snapshot;
 use insta::internals::SettingsBindDropGuard;
 use insta_cmd::{assert_cmd_snapshot, get_cargo_bin};
 use rand::Rng;
+use rustls_pki_types::pem::PemObject;
+use rustls_pki_types::{CertificateDer, SubjectPublicKeyInfoDer};
 use tempfile::TempDir;
+use upki::Config;
+use upki::revocation::{
+    self, BatchPartition, CancelToken, CertSerial, CheckOutcome, CoverageAdvice, CtLogId,
+    CtTimestamp, DecisionSource, FallbackChain, FastAllowSet, HealthProblem, IssuerSpkiHash,
+    Manifest, MatchKind, NotCoveredReason, ProbeResult, Provenance, RevocationCheckInput,
+    RevocationFallback, RevocationStatus, RevokedRange, SctRejection, SharedManifest, StreamFormat,
+    TraceStep, Warning,
+};
 
 #[test]
 fn version() {
@@ -42,21 +62,261 @@ fn config_unknown_fields() {
     ----- stdout -----
 
     ----- stderr -----
-    Error: failed to parse config file at tests/data/config_unknown_fields/config.toml
-
-    Caused by:
-        TOML parse error at line 1, column 1
-          |
-        1 | cache_dir = "tests/data/config_unknown_fields/"
-          | ^^^^^^^^^
-        unknown field `cache_dir`, expected `cache-dir` or `revocation`
-
+    Error: invalid field `cache_dir` in config file at tests/data/config_unknown_fields/config.toml, line 1 column 1: unknown field `cache_dir`, expected one of `cache-dir`, `read-only`, `revocation`
 
     Location:
         upki/src/main.rs:[LINE]:[COLUMN]
     "###);
 }
 
+#[test]
+fn config_field_errors() {
+    let temp = TempDir::new().unwrap();
+    let path = temp.path().join("config.toml");
+    let load = |content: &str| {
+        fs::write(&path, content).unwrap();
+        Config::from_file(&path).unwrap_err()
+    };
+
+    for (content, expect_key, expect_line, expect_message) in [
+        (
+            "cache-dir = \"x\"\n\n[revocation]\nfetch-url = \"\"\nmin-covered-scts = \"two\"\n",
+            "revocation.min-covered-scts",
+            5,
+            "invalid type: string \"two\", expected usize",
+        ),
+        (
+            "cache-dir = \"x\"\n[revocation]\nfetch-url = \"\"\nno-sct-policy = \"sometimes\"\n",
+            "revocation.no-sct-policy",
+            4,
+            "unknown variant `sometimes`",
+        ),
+        (
+            "cache-dir = \"x\"\n[revocation]\nfetch-url = \"\"\nbypass-issuers = [\"AA==\"]\n",
+            "revocation.bypass-issuers",
+            4,
+            "invalid length for issuer SPKI hash",
+        ),
+        (
+            "cachedir = \"x\"\n",
+            "cachedir",
+            1,
+            "unknown field `cachedir`",
+        ),
+    ] {
+        match load(content) {
+            upki::Error::ConfigField {
+                key,
+                message,
+                line,
+                path: error_path,
+                ..
+            } => {
+                assert_eq!(key, expect_key);
+                assert_eq!(line, expect_line);
+                assert!(message.starts_with(expect_message), "{message}");
+                assert_eq!(error_path, path);
+            }
+            error => panic!("unexpected {error:?}"),
+        }
+    }
+
+    // syntax errors are not about any one field
+    assert!(matches!(
+        load("cache-dir \"x\"\n"),
+        upki::Error::ConfigError { .. }
+    ));
+    assert!(matches!(
+        load("[revocation\n"),
+        upki::Error::ConfigError { .. }
+    ));
+}
+
+#[test]
+fn config_from_files() {
+    let temp = TempDir::new().unwrap();
+    let base = temp.path().join("base.toml");
+    fs::write(
+        &base,
+        format!(
+            "cache-dir = \"/var/cache/upki\"\n\
+             [revocation]\n\
+             fetch-url = \"https://base.example/\"\n\
+             no-sct-policy = \"use-serial-only\"\n\
+             bypass-issuers = [\"{ISSUER_AA}\", \"{ISSUER_AA}\"]\n"
+        ),
+    )
+    .unwrap();
+    let prod = temp.path().join("prod.toml");
+    fs::write(
+        &prod,
+        format!(
+            "[revocation]\n\
+             fetch-url = \"https://prod.example/\"\n\
+             bypass-issuers = [\"{ISSUER_AA}\"]\n"
+        ),
+    )
+    .unwrap();
+    let absent = temp.path().join("absent.toml");
+
+    let config = Config::from_files(&[&base, &absent, &prod]).unwrap();
+    assert_snapshot!(toml::to_string_pretty(&config).unwrap(), @r#"
+    cache-dir = "/var/cache/upki"
+    read-only = false
+
+    [revocation]
+    fetch-url = "https://prod.example/"
+    no-sct-policy = "use-serial-only"
+    allow-downgrade = false
+    min-covered-scts = 1
+    min-sct-count = 0
+    bypass-issuers = ["qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqo="]
+    approved-logs = []
+    log-windows = []
+    uncovered-policy = "return-uncovered"
+    max-manifest-bytes = 1048576
+    in-scope-issuers = []
+    refresh-splay = 0
+    probabilistic-policy = "lenient"
+    sct-skew-tolerance = 3600
+    duplicate-sct-policy = "use-latest"
+    sct-exempt-issuers = []
+    report-rejected-scts = false
+    retain-epochs = 0
+    stale-coverage-threshold = 0
+    stale-coverage-policy = "degraded"
+    strict-input = false
+    "#);
+
+    // the base is required
+    assert!(matches!(
+        Config::from_files(&[&absent, &prod]),
+        Err(upki::Error::FileRead { path, .. }) if path == absent
+    ));
+
+    // an overlay alone need not be a valid configuration, but the result must be
+    fs::write(&prod, "[revocation]\nmin-covered-scts = \"two\"\n").unwrap();
+    assert!(matches!(
+        Config::from_files(&[&base, &prod]),
+        Err(upki::Error::ConfigError { path, .. }) if path == prod
+    ));
+}
+
+#[test]
+fn config_digest() {
+    let temp = TempDir::new().unwrap();
+    let other = "u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7s=";
+    let base = temp.path().join("base.toml");
+    fs::write(
+        &base,
+        format!(
+            "cache-dir = \"/var/cache/upki\"\n\
+             [revocation]\n\
+             fetch-url = \"https://base.example/\"\n\
+             bypass-issuers = [\"{ISSUER_AA}\", \"{other}\"]\n"
+        ),
+    )
+    .unwrap();
+    let host = temp.path().join("host.toml");
+    fs::write(
+        &host,
+        "[revocation]\n\
+         fetch-url = \"https://prod.example/\"\n\
+         refresh-splay-seed = \"host-a\"\n",
+    )
+    .unwrap();
+    let layered = Config::from_files(&[&base, &host]).unwrap();
+
+    // the same settings in another order, with another seed
+    let single = temp.path().join("single.toml");
+    let from_single = |min_covered_scts: u32| {
+        fs::write(
+            &single,
+            format!(
+                "cache-dir = \"/var/cache/upki\"\n\
+                 [revocation]\n\
+                 min-covered-scts = {min_covered_scts}\n\
+                 refresh-splay-seed = \"host-b\"\n\
+                 bypass-issuers = [\"{other}\", \"{ISSUER_AA}\"]\n\
+                 fetch-url = \"https://prod.example/\"\n"
+            ),
+        )
+        .unwrap();
+        Config::from_file(&single).unwrap()
+    };
+    assert_eq!(from_single(1).digest(), layered.digest());
+
+    // any change to an effective setting changes the digest
+    assert_ne!(from_single(2).digest(), layered.digest());
+}
+
+#[test]
+fn load_from_archive() {
+    let temp = TempDir::new().unwrap();
+    let tar = |archive: &Path, dir: &Path, members: &[&str]| {
+        let status = Command::new("tar")
+            .arg("-cf")
+            .arg(archive)
+            .arg("-C")
+            .arg(dir)
+            .args(members)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+    let archive = temp.path().join("bundle.tar");
+    tar(
+        &archive,
+        Path::new("tests/data/crlite"),
+        &["config.toml", "revocation"],
+    );
+
+    let config = Config::from_archive(&archive).unwrap();
+    assert_eq!(
+        Manifest::from_archive(&archive)
+            .unwrap()
+            .generated_at,
+        1_765_445_031
+    );
+    let shared = SharedManifest::from_archive(&archive).unwrap();
+    let sct = CtTimestamp::from_str(SCT).unwrap();
+    assert_eq!(
+        shared.check(&crlite_input(&[0x01], vec![sct.clone()]), &config),
+        RevocationStatus::CertainlyRevoked
+    );
+    assert_eq!(
+        shared.check(&crlite_input(&[0x04], vec![sct]), &config),
+        RevocationStatus::NotRevoked
+    );
+
+    // filters are read from the archive, and checked against their hashes
+    let dir = temp.path().join("snapshot");
+    fs::create_dir_all(dir.join("revocation")).unwrap();
+    for name in ["revocation/manifest.json", "revocation/crlite.filter"] {
+        fs::copy(Path::new("tests/data/crlite").join(name), dir.join(name)).unwrap();
+    }
+    let mut filter = fs::read(dir.join("revocation/crlite.filter")).unwrap();
+    filter[100] ^= 0xff;
+    fs::write(dir.join("revocation/crlite.filter"), filter).unwrap();
+    tar(&archive, &dir, &["."]);
+    assert!(matches!(
+        SharedManifest::from_archive(&archive),
+        Err(revocation::Error::HashMismatch(path)) if path == archive.join("revocation/crlite.filter")
+    ));
+    assert!(matches!(
+        Config::from_archive(&archive),
+        Err(upki::Error::FileRead { error, path })
+            if error.kind() == io::ErrorKind::NotFound && path == archive.join("config.toml")
+    ));
+
+    // not an archive
+    let not_archive = Path::new("tests/data/crlite/config.toml");
+    assert!(matches!(
+        Manifest::from_archive(not_archive),
+        Err(revocation::Error::FileRead { error, .. }) if error.kind() == io::ErrorKind::InvalidData
+    ));
+}
+
 #[test]
 fn show_config_path_fixpoint() {
     let _filters = apply_common_filters();
@@ -88,14 +348,51 @@ fn show_config_fixpoint() {
     exit_code: 0
     ----- stdout -----
     cache-dir = "not-exist/"
+    read-only = false
 
     [revocation]
     fetch-url = ""
+    no-sct-policy = "treat-as-not-covered"
+    allow-downgrade = false
+    min-covered-scts = 1
+    min-sct-count = 0
+    bypass-issuers = []
+    approved-logs = []
+    log-windows = []
+    uncovered-policy = "return-uncovered"
+    max-manifest-bytes = 1048576
+    in-scope-issuers = []
+    refresh-splay = 0
+    probabilistic-policy = "lenient"
+    sct-skew-tolerance = 3600
+    duplicate-sct-policy = "use-latest"
+    sct-exempt-issuers = []
+    report-rejected-scts = false
+    retain-epochs = 0
+    stale-coverage-threshold = 0
+    stale-coverage-policy = "degraded"
+    strict-input = false
 
     ----- stderr -----
     "#);
 }
 
+#[test]
+fn storage_paths() {
+    let config =
+        Config::from_file(Path::new("tests/data/verify_non_existent_dir/config.toml")).unwrap();
+    assert_eq!(config.cache_dir(), Path::new("not-exist/"));
+    assert_eq!(
+        config.revocation_cache_dir(),
+        Path::new("not-exist/revocation")
+    );
+    assert_eq!(
+        config.manifest_path(),
+        Path::new("not-exist/revocation/manifest.json")
+    );
+    assert_eq!(config.manifest_path(), config.manifest_path());
+}
+
 #[test]
 fn verify_of_non_existent_dir() {
     let _filters = apply_common_filters();
@@ -110,10 +407,7 @@ fn verify_of_non_existent_dir() {
     ----- stdout -----
 
     ----- stderr -----
-    Error: cannot read manifest file at "not-exist/revocation/manifest.json"
-
-    Caused by:
-        No such file or directory (os error 2)
+    Error: no manifest file at "not-exist/revocation/manifest.json"
 
     Location:
         upki/src/main.rs:[LINE]:[COLUMN]
@@ -134,41 +428,122 @@ fn verify_of_empty_manifest() {
     ----- stdout -----
 
     ----- stderr -----
+    warning: manifest references no filters, so no certificate is covered
     ");
 }
 
 #[test]
-fn fetch_of_empty_manifest() {
+fn verify_deep() {
     let _filters = apply_common_filters();
-    let (server, _filters) = http_server("tests/data/verify_of_empty_manifest/");
-    let (temp, config_file, _filters) = temp_dir_and_config(server.url());
+    let (temp, config_file, _filters) = temp_dir_and_config("");
+    let cache = temp.path().join("revocation");
+    for name in ["manifest.json", "filter1.filter", "filter3.delta"] {
+        fs::copy(
+            Path::new("tests/data/typical/revocation").join(name),
+            cache.join(name),
+        )
+        .unwrap();
+    }
+    fs::write(cache.join("filter3.delta"), "corrupted").unwrap();
 
+    // the typical test data has the right hashes, but is not really crlite filters
     assert_cmd_snapshot!(
         upki()
             .arg("--config-file")
-            .arg(config_file)
-            .arg("fetch"),
-        @r"
-    success: true
-    exit_code: 0
+            .arg(&config_file)
+            .arg("verify")
+            .arg("--deep"),
+        @r###"
+    success: false
+    exit_code: 1
     ----- stdout -----
 
     ----- stderr -----
-    ");
-    assert_snapshot!(
-        server.into_log(),
-        @"GET /manifest.json  ->  200 OK (81 bytes)"
+    problem: filter "[TEMPDIR]/revocation/filter1.filter" cannot be decoded
+    problem: filter "[TEMPDIR]/revocation/filter2.delta" is missing
+    problem: filter "[TEMPDIR]/revocation/filter3.delta" does not match its hash
+    Error: found 3 integrity problems
+
+    Location:
+        upki/src/main.rs:[LINE]:[COLUMN]
+    "###);
+
+    let (_temp, config) = crlite_config("");
+    let manifest = Manifest::from_config(&config).unwrap();
+    assert!(
+        manifest
+            .verify_integrity(&config)
+            .is_ok()
+    );
+
+    let future = Manifest {
+        generated_at: u64::MAX,
+        ..manifest
+    };
+    assert!(matches!(
+        &future
+            .verify_integrity(&config)
+            .unwrap_err()[..],
+        [revocation::IntegrityProblem::FutureManifest {
+            generated_at: u64::MAX
+        }]
+    ));
+}
+
+#[test]
+fn missing_and_empty_manifests() {
+    let missing = Path::new("tests/data/verify_non_existent_dir/revocation/manifest.json");
+    assert!(matches!(
+        Manifest::from_file(missing),
+        Err(revocation::Error::ManifestMissing(path)) if path == missing
+    ));
+
+    let empty = Manifest::from_file(Path::new(
+        "tests/data/verify_of_empty_manifest/revocation/manifest.json",
+    ))
+    .unwrap();
+    assert!(empty.is_empty());
+
+    let (_temp, config) = crlite_config("");
+    assert!(
+        !Manifest::from_config(&config)
+            .unwrap()
+            .is_empty()
     );
+}
+
+#[test]
+fn manifest_provenance() {
+    let manifest = Manifest::from_bytes(
+        br#"{
+            "generated_at": 1765445031,
+            "comment": "with provenance",
+            "filters": [],
+            "provenance": {"builder": "upki-mirror v0.1.0", "build_id": "run-42"}
+        }"#,
+    )
+    .unwrap();
     assert_eq!(
-        list_dir(&temp.path().join("revocation")),
-        vec!["manifest.json"],
+        manifest.provenance,
+        Some(Provenance {
+            builder: Some("upki-mirror v0.1.0".to_owned()),
+            build_id: Some("run-42".to_owned()),
+        })
+    );
+
+    let (_temp, config) = crlite_config("");
+    assert_eq!(
+        Manifest::from_config(&config)
+            .unwrap()
+            .provenance,
+        None
     );
 }
 
 #[test]
-fn full_fetch() {
+fn fetch_of_empty_manifest() {
     let _filters = apply_common_filters();
-    let (server, _filters) = http_server("tests/data/typical/");
+    let (server, _filters) = http_server("tests/data/verify_of_empty_manifest/");
     let (temp, config_file, _filters) = temp_dir_and_config(server.url());
 
     assert_cmd_snapshot!(
@@ -185,25 +560,16 @@ fn full_fetch() {
     ");
     assert_snapshot!(
         server.into_log(),
-        @r"
-    GET /manifest.json  ->  200 OK (532 bytes)
-    GET /filter1.filter  ->  200 OK (11 bytes)
-    GET /filter2.delta  ->  200 OK (14 bytes)
-    GET /filter3.delta  ->  200 OK (10 bytes)
-    ");
+        @"GET /manifest.json  ->  200 OK (81 bytes)"
+    );
     assert_eq!(
         list_dir(&temp.path().join("revocation")),
-        vec![
-            "filter1.filter",
-            "filter2.delta",
-            "filter3.delta",
-            "manifest.json"
-        ]
+        vec!["highest-accepted", "manifest.json"],
     );
 }
 
 #[test]
-fn full_fetch_and_incremental_update() {
+fn full_fetch() {
     let _filters = apply_common_filters();
     let (server, _filters) = http_server("tests/data/typical/");
     let (temp, config_file, _filters) = temp_dir_and_config(server.url());
@@ -211,7 +577,7 @@ fn full_fetch_and_incremental_update() {
     assert_cmd_snapshot!(
         upki()
             .arg("--config-file")
-            .arg(&config_file)
+            .arg(config_file)
             .arg("fetch"),
         @r"
     success: true
@@ -234,150 +600,3795 @@ fn full_fetch_and_incremental_update() {
             "filter1.filter",
             "filter2.delta",
             "filter3.delta",
-            "manifest.json"
-        ],
-    );
-
-    // now server is updated to "evolution" which requires a partial update
-    // compared to "typical"
-    let (server, _filters) = http_server("tests/data/evolution/");
-    write_config(&temp, server.url());
-    assert_cmd_snapshot!(
-        upki()
-            .arg("--config-file")
-            .arg(&config_file)
-            .arg("fetch"),
-        @r"
-    success: true
-    exit_code: 0
-    ----- stdout -----
-
-    ----- stderr -----
-    ");
-    assert_snapshot!(
-        server.into_log(),
-        @r"
-    GET /manifest.json  ->  200 OK (547 bytes)
-    GET /filter4.delta  ->  200 OK (3 bytes)
-    ");
-    // filter2 is deleted, filter4 is new
-    assert_eq!(
-        list_dir(&temp.path().join("revocation")),
-        vec![
-            "filter1.filter",
-            "filter3.delta",
-            "filter4.delta",
+            "highest-accepted",
             "manifest.json"
         ]
     );
 }
 
 #[test]
-fn typical_incremental_fetch() {
-    let _filters = apply_common_filters();
+fn read_only_cache() {
     let (server, _filters) = http_server("tests/data/typical/");
-    let (temp, config_file, _filters) = temp_dir_and_config(server.url());
-
-    fs::copy(
-        "tests/data/typical/revocation/manifest.json",
-        temp.path()
-            .join("revocation/manifest.json"),
-    )
-    .unwrap();
-    fs::copy(
-        "tests/data/typical/revocation/filter1.filter",
-        temp.path()
-            .join("revocation/filter1.filter"),
-    )
-    .unwrap();
-    fs::copy(
-        "tests/data/typical/revocation/filter3.delta",
-        temp.path()
-            .join("revocation/filter3.delta"),
+    let temp = TempDir::new().unwrap();
+    let dir = temp.path().join("revocation");
+    create_dir(&dir).unwrap();
+    for name in ["manifest.json", "crlite.filter"] {
+        fs::copy(
+            Path::new("tests/data/crlite/revocation").join(name),
+            dir.join(name),
+        )
+        .unwrap();
+    }
+    let config_file = temp.path().join("config.toml");
+    fs::write(
+        &config_file,
+        format!(
+            "cache-dir=\"{}\"\n\
+            read-only=true\n\
+            [revocation]\n\
+            fetch-url=\"{}\"\n",
+            temp.path().display(),
+            server.url(),
+        ),
     )
     .unwrap();
+    let config = Config::from_file(&config_file).unwrap();
+    assert!(config.read_only);
 
-    assert_cmd_snapshot!(
-        upki()
-            .arg("--config-file")
-            .arg(config_file)
-            .arg("fetch"),
-        @r"
-    success: true
-    exit_code: 0
-    ----- stdout -----
+    let mut permissions = fs::metadata(&dir)
+        .unwrap()
+        .permissions();
+    permissions.set_readonly(true);
+    fs::set_permissions(&dir, permissions.clone()).unwrap();
 
-    ----- stderr -----
-    ");
+    // checks work as usual
+    let manifest = Manifest::from_config(&config).unwrap();
+    let input = crlite_input(&[0x01], vec![CtTimestamp::from_str(SCT).unwrap()]);
+    assert_eq!(
+        manifest.check(&input, &config).unwrap(),
+        RevocationStatus::CertainlyRevoked
+    );
 
-    // only fetched the neccessary files
-    assert_snapshot!(
-        server.into_log(),
-        @r"
-    GET /manifest.json  ->  200 OK (532 bytes)
-    GET /filter2.delta  ->  200 OK (14 bytes)
-    ");
+    // but updates are refused before any request is made
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    assert!(matches!(
+        runtime.block_on(revocation::fetch(false, &config)),
+        Err(revocation::Error::ReadOnly)
+    ));
+    assert_eq!(server.into_log(), "");
+    assert_eq!(list_dir(&dir), vec!["crlite.filter", "manifest.json"]);
 
-    assert_eq!(list_dir(temp.path()), vec!["config.toml", "revocation",],);
+    #[expect(clippy::permissions_set_readonly_false)]
+    permissions.set_readonly(false);
+    fs::set_permissions(&dir, permissions).unwrap();
+}
 
+#[test]
+fn fetch_coalesces_concurrent_updates() {
+    let (server, _filters) = http_server("tests/data/typical/");
+    let (_temp, config_file, _filters) = temp_dir_and_config(server.url());
+    let config = Config::from_file(&config_file).unwrap();
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+
+    // the second waits for the first, and joins it rather than downloading again
+    let (first, second) = runtime.block_on(async {
+        tokio::join!(
+            revocation::fetch_with_report(&config),
+            revocation::fetch_with_report(&config)
+        )
+    });
+    let (first, second) = (first.unwrap(), second.unwrap());
+    assert_eq!(first.bytes_downloaded, 532 + 11 + 14 + 10);
     assert_eq!(
-        list_dir(&temp.path().join("revocation")),
-        vec![
-            "filter1.filter",
-            "filter2.delta",
-            "filter3.delta",
-            "manifest.json"
-        ]
+        (second.bytes_downloaded, second.filters_downloaded),
+        (first.bytes_downloaded, first.filters_downloaded)
+    );
+
+    // a later update is not coalesced
+    let later = runtime
+        .block_on(revocation::fetch_with_report(&config))
+        .unwrap();
+    assert_eq!(later.filters_downloaded, 0);
+
+    let log = server.into_log();
+    assert_eq!(
+        log.matches("GET /manifest.json")
+            .count(),
+        2,
+        "{log}"
     );
+    assert_eq!(log.matches("GET /filter1").count(), 1, "{log}");
 }
 
 #[test]
-fn typical_incremental_fetch_dry_run() {
-    let _filters = apply_common_filters();
-    let (server, _filters) = http_server("tests/data/typical/");
-    let (temp, config_file, _filters) = temp_dir_and_config(server.url());
-    fs::copy(
-        "tests/data/typical/revocation/manifest.json",
-        temp.path()
-            .join("revocation/manifest.json"),
+fn manifest_open() {
+    let (_temp, config) = crlite_config("");
+    let sct = CtTimestamp::from_str(SCT).unwrap();
+    // the test data was generated at 1765445031
+    let years = Duration::from_secs(100 * 365 * 86_400);
+
+    let (manifest, health) = Manifest::open(
+        &config,
+        revocation::OpenOptions::new()
+            .max_age(years)
+            .expected_issuers([IssuerSpkiHash([0xaa; 32])]),
     )
     .unwrap();
-    fs::copy(
-        "tests/data/typical/revocation/filter1.filter",
-        temp.path()
-            .join("revocation/filter1.filter"),
+    assert_eq!(health, []);
+    assert_eq!(manifest.generated_at, 1_765_445_031);
+    assert_eq!(
+        manifest
+            .check(&crlite_input(&[0x01], vec![sct.clone()]), &config)
+            .unwrap(),
+        RevocationStatus::CertainlyRevoked
+    );
+
+    // stale, but still usable
+    let (manifest, health) = Manifest::open(
+        &config,
+        revocation::OpenOptions::new().max_age(Duration::from_secs(60)),
     )
     .unwrap();
-    fs::copy(
-        "tests/data/typical/revocation/filter3.delta",
-        temp.path()
-            .join("revocation/filter3.delta"),
+    let [HealthProblem::Stale { age }] = health[..] else {
+        panic!("expected a stale manifest, got {health:?}");
+    };
+    assert!(age > Duration::from_secs(60));
+    assert_eq!(
+        manifest
+            .check(&crlite_input(&[0x01], vec![sct.clone()]), &config)
+            .unwrap(),
+        RevocationStatus::CertainlyRevoked
+    );
+
+    let (_, health) = Manifest::open(
+        &config,
+        revocation::OpenOptions::new()
+            .max_age(years)
+            .expected_issuers([IssuerSpkiHash([0xaa; 32]), IssuerSpkiHash([0xcc; 32])]),
     )
     .unwrap();
+    assert_eq!(
+        health,
+        [HealthProblem::IssuersNotCovered(vec![IssuerSpkiHash(
+            [0xcc; 32]
+        )])]
+    );
+
+    // every problem is reported, not just the first
+    let (_, health) = Manifest::open(
+        &config,
+        revocation::OpenOptions::new()
+            .max_age(Duration::from_secs(60))
+            .expected_issuers([IssuerSpkiHash([0xcc; 32])]),
+    )
+    .unwrap();
+    assert!(matches!(
+        &health[..],
+        [
+            HealthProblem::Stale { .. },
+            HealthProblem::IssuersNotCovered(issuers),
+        ] if issuers == &[IssuerSpkiHash([0xcc; 32])]
+    ));
+
+    // a corrupt filter makes the cache unusable
+    let temp = TempDir::new().unwrap();
+    let cache = temp.path().join("revocation");
+    create_dir(&cache).unwrap();
+    for name in ["manifest.json", "crlite.filter"] {
+        fs::copy(
+            Path::new("tests/data/crlite/revocation").join(name),
+            cache.join(name),
+        )
+        .unwrap();
+    }
+    fs::write(cache.join("crlite.filter"), "corrupted").unwrap();
+    let path = temp.path().join("config.toml");
+    fs::write(
+        &path,
+        format!(
+            "cache-dir={:?}\n[revocation]\nfetch-url=\"\"\n",
+            temp.path()
+        ),
+    )
+    .unwrap();
+    let corrupt = Config::from_file(&path).unwrap();
+    assert!(matches!(
+        Manifest::open(&corrupt, revocation::OpenOptions::new().max_age(years)),
+        Err(revocation::Error::Integrity(problems))
+            if matches!(&problems[..], [revocation::IntegrityProblem::HashMismatch(_)])
+    ));
+
+    let temp = TempDir::new().unwrap();
+    let path = temp.path().join("config.toml");
+    fs::write(
+        &path,
+        format!(
+            "cache-dir={:?}\n[revocation]\nfetch-url=\"\"\n",
+            temp.path()
+        ),
+    )
+    .unwrap();
+    let config = Config::from_file(&path).unwrap();
+    assert!(matches!(
+        Manifest::open(&config, revocation::OpenOptions::default()),
+        Err(revocation::Error::ManifestMissing(_))
+    ));
+}
+
+#[test]
+fn pinned_manifest_digest() {
+    let (_temp, config) = crlite_config("");
+    let digest = Manifest::from_config(&config)
+        .unwrap()
+        .digest();
+
+    let (_temp, config) = crlite_config(&format!(
+        "pinned-manifest-digest = \"{}\"\n",
+        hex::encode(digest)
+    ));
+    assert_eq!(
+        Manifest::from_config(&config)
+            .unwrap()
+            .digest(),
+        digest
+    );
+
+    let (_temp, config) = crlite_config(&format!(
+        "pinned-manifest-digest = \"{}\"\n",
+        hex::encode([0xab; 32])
+    ));
+    assert!(matches!(
+        Manifest::from_config(&config),
+        Err(revocation::Error::DigestMismatch { expected, actual })
+            if expected == [0xab; 32] && actual == digest
+    ));
+
+    // fetching a different manifest leaves the cache alone
+    let (server, _filters) = http_server("tests/data/typical/");
+    let (temp, config_file, _filters) = temp_dir_and_config(server.url());
+    let mut pinned = fs::read_to_string(&config_file).unwrap();
+    pinned.push_str(&format!(
+        "pinned-manifest-digest = \"{}\"\n",
+        hex::encode(digest)
+    ));
+    fs::write(&config_file, &pinned).unwrap();
+    let config = Config::from_file(&config_file).unwrap();
+    assert!(matches!(
+        Manifest::update_blocking(&config),
+        Err(revocation::Error::DigestMismatch { .. })
+    ));
+    assert!(list_dir(&temp.path().join("revocation")).is_empty());
+
+    // but accepts the pinned one
+    let typical = Manifest::from_file("tests/data/typical/revocation/manifest.json".as_ref())
+        .unwrap()
+        .digest();
+    fs::write(
+        &config_file,
+        pinned.replace(&hex::encode(digest), &hex::encode(typical)),
+    )
+    .unwrap();
+    let config = Config::from_file(&config_file).unwrap();
+    assert_eq!(
+        Manifest::update_blocking(&config)
+            .unwrap()
+            .digest(),
+        typical
+    );
+    let saved = fs::read(
+        temp.path()
+            .join("revocation/manifest.json"),
+    )
+    .unwrap();
+    assert_eq!(digest::digest(&digest::SHA256, &saved).as_ref(), typical);
+}
+
+#[test]
+fn fetch_with_report_counts() {
+    let (server, _filters) = http_server("tests/data/typical/");
+    let (temp, config_file, _filters) = temp_dir_and_config(server.url());
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let fetch = |config_file: &Path| {
+        let config = Config::from_file(config_file).unwrap();
+        runtime
+            .block_on(revocation::fetch_with_report(&config))
+            .unwrap()
+    };
+
+    // from scratch: a 532 byte manifest, and filters of 11, 14 and 10 bytes
+    let report = fetch(&config_file);
+    assert!(report.manifest_modified);
+    assert_eq!(report.bytes_downloaded, 532 + 11 + 14 + 10);
+    assert_eq!((report.filters_downloaded, report.filters_reused), (3, 0));
+    drop(server);
+
+    // incrementally: a 547 byte manifest, and one new 3 byte filter
+    let (server, _filters) = http_server("tests/data/evolution/");
+    write_config(&temp, server.url());
+    let report = fetch(&config_file);
+    assert!(report.manifest_modified);
+    assert_eq!(report.bytes_downloaded, 547 + 3);
+    assert_eq!((report.filters_downloaded, report.filters_reused), (1, 2));
+
+    // nothing to do but fetch the manifest again
+    let report = fetch(&config_file);
+    assert_eq!(report.bytes_downloaded, 547);
+    assert_eq!((report.filters_downloaded, report.filters_reused), (0, 3));
+    assert!(report.duration < Duration::from_secs(10));
+}
+
+#[test]
+fn manifest_size_limit() {
+    // the crlite manifest is 237 bytes
+    let (_temp, config) = crlite_config("max-manifest-bytes = 237\n");
+    Manifest::from_config(&config).unwrap();
+    let (_temp, config) = crlite_config("max-manifest-bytes = 236\n");
+    assert!(matches!(
+        Manifest::from_config(&config),
+        Err(revocation::Error::ManifestTooLarge { limit: 236 })
+    ));
+
+    // the typical manifest is 532 bytes
+    let (server, _filters) = http_server("tests/data/typical/");
+    let (temp, config_file, _filters) = temp_dir_and_config(server.url());
+    let mut text = fs::read_to_string(&config_file).unwrap();
+    text.push_str("max-manifest-bytes = 500\n");
+    fs::write(&config_file, text).unwrap();
+    let config = Config::from_file(&config_file).unwrap();
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let error = runtime
+        .block_on(revocation::fetch(false, &config))
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "manifest is larger than the limit of 500 bytes"
+    );
+    assert!(list_dir(&temp.path().join("revocation")).is_empty());
+}
+
+#[test]
+fn refresh_splay_window() {
+    let (_temp, config) = crlite_config("");
+    assert_eq!(config.revocation.refresh_splay(), Duration::ZERO);
+
+    let (_temp, config) = crlite_config("refresh-splay = 60\n");
+    let window = Duration::from_secs(60);
+    let splays = (0..100)
+        .map(|_| config.revocation.refresh_splay())
+        .collect::<HashSet<_>>();
+    assert!(
+        splays
+            .iter()
+            .all(|splay| *splay <= window)
+    );
+    assert!(splays.len() > 1);
+
+    let seeded = |seed: &str| {
+        let (_temp, config) = crlite_config(&format!(
+            "refresh-splay = 60\nrefresh-splay-seed = \"{seed}\"\n"
+        ));
+        let splay = config.revocation.refresh_splay();
+        assert_eq!(splay, config.revocation.refresh_splay());
+        assert!(splay <= window);
+        splay
+    };
+    assert_eq!(seeded("host-a"), seeded("host-a"));
+    assert_ne!(seeded("host-a"), seeded("host-b"));
+}
+
+#[test]
+fn update_blocking() {
+    let (server, _filters) = http_server("tests/data/typical/");
+    let (temp, config_file, _filters) = temp_dir_and_config(server.url());
+    let config = Config::from_file(&config_file).unwrap();
+
+    let manifest = Manifest::update_blocking(&config).unwrap();
+    assert_eq!(manifest.generated_at, 1_765_445_031);
+    assert_eq!(manifest.filters.len(), 3);
+    assert_eq!(
+        list_dir(&temp.path().join("revocation")),
+        [
+            "filter1.filter",
+            "filter2.delta",
+            "filter3.delta",
+            "highest-accepted",
+            "manifest.json",
+        ]
+    );
+
+    // still usable from within a runtime
+    let (server, _filters) = http_server("tests/data/evolution/");
+    write_config(&temp, server.url());
+    let config = Config::from_file(&config_file).unwrap();
+    let manifest = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(async { Manifest::update_blocking(&config) })
+        .unwrap();
+    assert_eq!(manifest.generated_at, 1_765_446_031);
+}
+
+#[test]
+fn update_blocking_cancelled() {
+    // a server that accepts requests but never answers them
+    let port = rand::rng().random_range(4000..12000);
+    let server = Arc::new(tiny_http::Server::http(("127.0.0.1", port)).unwrap());
+    let stalled = thread::spawn({
+        let server = server.clone();
+        move || {
+            server
+                .incoming_requests()
+                .collect::<Vec<_>>()
+        }
+    });
+    let (_temp, config_file, _filters) = temp_dir_and_config(&format!("http://127.0.0.1:{port}/"));
+    let config = Config::from_file(&config_file).unwrap();
+
+    let cancel = CancelToken::new();
+    let canceller = thread::spawn({
+        let cancel = cancel.clone();
+        move || {
+            thread::sleep(Duration::from_millis(200));
+            cancel.cancel();
+        }
+    });
+    let start = Instant::now();
+    assert!(matches!(
+        Manifest::update_blocking_with_cancel(&config, &cancel),
+        Err(revocation::Error::Cancelled)
+    ));
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert!(cancel.is_cancelled());
+    canceller.join().unwrap();
+
+    // an already cancelled token stops the update at once
+    assert!(matches!(
+        Manifest::update_blocking_with_cancel(&config, &cancel),
+        Err(revocation::Error::Cancelled)
+    ));
+
+    server.unblock();
+    assert!(!stalled.join().unwrap().is_empty());
+}
+
+#[test]
+fn update_with_manifest_temp_dir() {
+    let (server, _filters) = http_server("tests/data/typical/");
+    let (temp, config_file, _filters) = temp_dir_and_config(server.url());
+
+    // /dev/shm is usually a separate filesystem, so renaming from it fails with EXDEV
+    let shm = Path::new("/dev/shm");
+    let manifest_temp = match shm.is_dir() {
+        true => TempDir::new_in(shm).unwrap(),
+        false => TempDir::new().unwrap(),
+    };
+    let mut config = fs::read_to_string(&config_file).unwrap();
+    config.push_str(&format!(
+        "manifest-temp-dir=\"{}\"\n",
+        manifest_temp.path().display()
+    ));
+    fs::write(&config_file, config).unwrap();
+    let config = Config::from_file(&config_file).unwrap();
+
+    Manifest::update_blocking(&config).unwrap();
+    let saved = Manifest::from_file(
+        &temp
+            .path()
+            .join("revocation/manifest.json"),
+    )
+    .unwrap();
+    assert_eq!(saved.generated_at, 1_765_445_031);
+    assert!(list_dir(manifest_temp.path()).is_empty());
+    assert_eq!(
+        list_dir(&temp.path().join("revocation")),
+        [
+            "filter1.filter",
+            "filter2.delta",
+            "filter3.delta",
+            "highest-accepted",
+            "manifest.json",
+        ]
+    );
+}
+
+#[test]
+fn auto_refresh_stops_on_drop() {
+    let (server, _filters) = http_server("tests/data/typical/");
+    let (temp, config_file, _filters) = temp_dir_and_config(server.url());
+    let config = Config::from_file(&config_file).unwrap();
+
+    let handle = revocation::spawn_auto_refresh(config, Duration::from_secs(3600));
+
+    // the first refresh happens immediately
+    let manifest = temp
+        .path()
+        .join("revocation/manifest.json");
+    let start = Instant::now();
+    while !manifest.exists() {
+        assert!(start.elapsed() < Duration::from_secs(10));
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    // and dropping the handle interrupts the wait for the next one
+    let start = Instant::now();
+    drop(handle);
+    assert!(start.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+fn shared_manifest_update_during_checks() {
+    let (server, _filters) = http_server("tests/data/crlite/");
+    let (temp, config_file, _filters) = temp_dir_and_config(server.url());
+    fs::write(
+        temp.path()
+            .join("revocation/manifest.json"),
+        r#"{"generated_at": 1765000000, "comment": "", "filters": []}"#,
+    )
+    .unwrap();
+    let config = Arc::new(Config::from_file(&config_file).unwrap());
+
+    let shared = Arc::new(SharedManifest::from_config(&config).unwrap());
+    assert_eq!(shared.version().generated_at, 1_765_000_000);
+
+    let sct = CtTimestamp::from_str(SCT).unwrap();
+    let done = Arc::new(AtomicBool::new(false));
+    let checkers = (0..4)
+        .map(|_| {
+            let (shared, config, done, sct) =
+                (shared.clone(), config.clone(), done.clone(), sct.clone());
+            thread::spawn(move || {
+                let input = crlite_input(&[0x01], vec![sct]);
+                let (mut checks, mut updated) = (0, false);
+                while !done.load(Ordering::Relaxed) {
+                    // before the update nothing is covered; afterwards this is revoked
+                    match shared.check(&input, &config) {
+                        RevocationStatus::NotCoveredByRevocationData => assert!(!updated),
+                        RevocationStatus::CertainlyRevoked => updated = true,
+                        status => panic!("unexpected {status:?}"),
+                    }
+                    checks += 1;
+                }
+                checks
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let version = runtime
+        .block_on(shared.update(&config))
+        .unwrap();
+    assert_eq!(version.generated_at, 1_765_445_031);
+    assert_eq!(shared.version(), version);
+
+    done.store(true, Ordering::Relaxed);
+    for checker in checkers {
+        assert!(checker.join().unwrap() > 0);
+    }
+    assert_eq!(
+        shared.check(&crlite_input(&[0x01], vec![sct]), &config),
+        RevocationStatus::CertainlyRevoked
+    );
+}
+
+#[test]
+fn check_at_generation() {
+    let (server, _filters) = http_server("tests/data/crlite/");
+    let (temp, config_file, _filters) = temp_dir_and_config(server.url());
+    fs::write(
+        temp.path()
+            .join("revocation/manifest.json"),
+        r#"{"generated_at": 1765000000, "comment": "", "filters": []}"#,
+    )
+    .unwrap();
+    let config = Config::from_file(&config_file).unwrap();
+    let input = crlite_input(&[0x01], vec![CtTimestamp::from_str(SCT).unwrap()]);
+
+    let shared = SharedManifest::from_config(&config).unwrap();
+    let stale = shared.version();
+    assert_eq!(
+        shared
+            .check_at_generation(&input, stale, &config)
+            .unwrap(),
+        RevocationStatus::NotCoveredByRevocationData
+    );
+
+    let current = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(shared.update(&config))
+        .unwrap();
+    let err = shared
+        .check_at_generation(&input, stale, &config)
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "manifest generation 1765000000 is not available, 1765445031 is loaded"
+    );
+    assert!(matches!(
+        err,
+        revocation::Error::GenerationUnavailable { requested, loaded }
+            if requested == stale && loaded == current
+    ));
+    assert_eq!(
+        shared
+            .check_at_generation(&input, current, &config)
+            .unwrap(),
+        RevocationStatus::CertainlyRevoked
+    );
+
+    let manifest = Manifest::from_config(&config).unwrap();
+    assert!(matches!(
+        manifest.check_at_generation(&input, stale, &config),
+        Err(revocation::Error::GenerationUnavailable { .. })
+    ));
+    assert_eq!(
+        manifest
+            .check_at_generation(&input, current, &config)
+            .unwrap(),
+        RevocationStatus::CertainlyRevoked
+    );
+}
+
+#[test]
+fn full_fetch_and_incremental_update() {
+    let _filters = apply_common_filters();
+    let (server, _filters) = http_server("tests/data/typical/");
+    let (temp, config_file, _filters) = temp_dir_and_config(server.url());
 
     assert_cmd_snapshot!(
         upki()
             .arg("--config-file")
-            .arg(config_file)
-            .arg("fetch")
-            .arg("--dry-run"),
-        @r#"
+            .arg(&config_file)
+            .arg("fetch"),
+        @r"
     success: true
     exit_code: 0
     ----- stdout -----
-    2 steps required (14 bytes to download)
-    - download 14 bytes from http://127.0.0.1:[PORT]/filter2.delta to "[TEMPDIR]/revocation/filter2.delta"
-    - save new manifest into "[TEMPDIR]/revocation"
 
-    ----- stderr -----
+    ----- stderr -----
+    ");
+    assert_snapshot!(
+        server.into_log(),
+        @r"
+    GET /manifest.json  ->  200 OK (532 bytes)
+    GET /filter1.filter  ->  200 OK (11 bytes)
+    GET /filter2.delta  ->  200 OK (14 bytes)
+    GET /filter3.delta  ->  200 OK (10 bytes)
+    ");
+    assert_eq!(
+        list_dir(&temp.path().join("revocation")),
+        vec![
+            "filter1.filter",
+            "filter2.delta",
+            "filter3.delta",
+            "highest-accepted",
+            "manifest.json"
+        ],
+    );
+
+    // now server is updated to "evolution" which requires a partial update
+    // compared to "typical"
+    let (server, _filters) = http_server("tests/data/evolution/");
+    write_config(&temp, server.url());
+    assert_cmd_snapshot!(
+        upki()
+            .arg("--config-file")
+            .arg(&config_file)
+            .arg("fetch"),
+        @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    ");
+    assert_snapshot!(
+        server.into_log(),
+        @r"
+    GET /manifest.json  ->  200 OK (547 bytes)
+    GET /filter4.delta  ->  200 OK (3 bytes)
+    ");
+    // filter2 is deleted, filter4 is new
+    assert_eq!(
+        list_dir(&temp.path().join("revocation")),
+        vec![
+            "filter1.filter",
+            "filter3.delta",
+            "filter4.delta",
+            "highest-accepted",
+            "manifest.json"
+        ]
+    );
+}
+
+#[test]
+fn fetch_not_modified() {
+    let _filters = apply_common_filters();
+    let (server, _filters) = http_server_with_etags("tests/data/typical/");
+    let (temp, config_file, _filters) = temp_dir_and_config(server.url());
+
+    assert_cmd_snapshot!(
+        upki()
+            .arg("--config-file")
+            .arg(&config_file)
+            .arg("fetch"),
+        @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    ");
+    assert_eq!(
+        list_dir(&temp.path().join("revocation")),
+        vec![
+            "filter1.filter",
+            "filter2.delta",
+            "filter3.delta",
+            "highest-accepted",
+            "manifest.json",
+            "validators.json"
+        ],
+    );
+    assert_eq!(
+        fs::read_to_string(
+            temp.path()
+                .join("revocation/validators.json")
+        )
+        .unwrap(),
+        r#"{"etag":"\"532\""}"#
+    );
+
+    // mark our copy of the manifest, to show it is not replaced
+    let manifest_path = temp
+        .path()
+        .join("revocation/manifest.json");
+    let mut manifest = Manifest::from_file(&manifest_path).unwrap();
+    manifest.comment = "local copy".into();
+    fs::write(&manifest_path, serde_json::to_vec(&manifest).unwrap()).unwrap();
+
+    assert_cmd_snapshot!(
+        upki()
+            .arg("--config-file")
+            .arg(&config_file)
+            .arg("fetch"),
+        @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    ");
+    assert_snapshot!(
+        server.into_log(),
+        @r"
+    GET /manifest.json  ->  200 OK (532 bytes)
+    GET /filter1.filter  ->  200 OK (11 bytes)
+    GET /filter2.delta  ->  200 OK (14 bytes)
+    GET /filter3.delta  ->  200 OK (10 bytes)
+    GET /manifest.json  ->  304 Not Modified (0 bytes)
+    ");
+    assert_eq!(
+        Manifest::from_file(&manifest_path)
+            .unwrap()
+            .comment,
+        "local copy"
+    );
+}
+
+#[test]
+fn fetch_downgrade() {
+    let _filters = apply_common_filters();
+    let (server, _filters) = http_server("tests/data/evolution/");
+    let (temp, config_file, _filters) = temp_dir_and_config(server.url());
+    assert_cmd_snapshot!(
+        upki()
+            .arg("--config-file")
+            .arg(&config_file)
+            .arg("fetch"),
+        @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    ");
+    drop(server);
+
+    // "typical" is older than "evolution"
+    let (server, _filters) = http_server("tests/data/typical/");
+    write_config(&temp, server.url());
+    assert_cmd_snapshot!(
+        upki()
+            .arg("--config-file")
+            .arg(&config_file)
+            .arg("fetch"),
+        @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    Error: manifest generated at 1765445031 is older than previously accepted manifest generated at 1765446031
+
+    Location:
+        upki/src/main.rs:[LINE]:[COLUMN]
+    ");
+    drop(server);
+
+    // the local cache is untouched
+    assert_eq!(
+        Manifest::from_file(
+            &temp
+                .path()
+                .join("revocation/manifest.json")
+        )
+        .unwrap()
+        .generated_at,
+        1765446031
+    );
+
+    let (server, _filters) = http_server("tests/data/typical/");
+    write_config(&temp, server.url());
+    allow_downgrade(&config_file);
+    assert_cmd_snapshot!(
+        upki()
+            .arg("--config-file")
+            .arg(&config_file)
+            .arg("fetch"),
+        @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    ");
+    assert_eq!(
+        Manifest::from_file(
+            &temp
+                .path()
+                .join("revocation/manifest.json")
+        )
+        .unwrap()
+        .generated_at,
+        1765445031
+    );
+
+    // the rollback is now the baseline
+    assert_eq!(
+        fs::read_to_string(
+            temp.path()
+                .join("revocation/highest-accepted")
+        )
+        .unwrap(),
+        "1765445031"
+    );
+}
+
+#[test]
+fn check_at_retained_epoch() {
+    let _filters = apply_common_filters();
+    let (server, _filters) = http_server("tests/data/epochs/1/");
+    let (temp, config_file, _filters) = temp_dir_and_config(server.url());
+    let retain = |config_file: &Path| {
+        let mut config = fs::read_to_string(config_file).unwrap();
+        config.push_str("retain-epochs = 2\n");
+        fs::write(config_file, config).unwrap();
+    };
+    retain(&config_file);
+    let fetch = || {
+        upki()
+            .arg("--config-file")
+            .arg(&config_file)
+            .arg("fetch")
+            .status()
+            .unwrap()
+    };
+    assert!(fetch().success());
+    drop(server);
+
+    let (server, _filters) = http_server("tests/data/epochs/2/");
+    write_config(&temp, server.url());
+    retain(&config_file);
+    assert!(fetch().success());
+    drop(server);
+
+    // the first epoch's filter was kept
+    assert_eq!(
+        list_dir(&temp.path().join("revocation")),
+        vec![
+            "1765445031.filter",
+            "1765531431.filter",
+            "epochs",
+            "highest-accepted",
+            "manifest.json",
+        ]
+    );
+    assert_eq!(
+        list_dir(&temp.path().join("revocation/epochs")),
+        vec!["1.json", "2.json"]
+    );
+
+    let config = Config::from_file(&config_file).unwrap();
+    let input = crlite_input(
+        &[0x04],
+        vec![CtTimestamp {
+            log_id: CtLogId([0x01; 32]),
+            timestamp: 1_750_000_000_000,
+        }],
+    );
+
+    let current = Manifest::from_config(&config).unwrap();
+    assert_eq!(current.epoch(), Some(2));
+    assert_eq!(
+        current.check(&input, &config).unwrap(),
+        RevocationStatus::CertainlyRevoked
+    );
+
+    let earlier = Manifest::from_config_at_epoch(&config, 1).unwrap();
+    assert_eq!(earlier.epoch(), Some(1));
+    assert_eq!(
+        earlier.check(&input, &config).unwrap(),
+        RevocationStatus::NotRevoked
+    );
+
+    assert!(matches!(
+        Manifest::from_config_at_epoch(&config, 3),
+        Err(revocation::Error::ManifestMissing(_))
+    ));
+}
+
+#[test]
+fn load_downgraded_manifest() {
+    let _filters = apply_common_filters();
+    let (server, _filters) = http_server("tests/data/evolution/");
+    let (temp, config_file, _filters) = temp_dir_and_config(server.url());
+    assert_cmd_snapshot!(
+        upki()
+            .arg("--config-file")
+            .arg(&config_file)
+            .arg("fetch"),
+        @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    ");
+
+    // replace the cached manifest with an older one
+    fs::copy(
+        "tests/data/typical/revocation/manifest.json",
+        temp.path()
+            .join("revocation/manifest.json"),
+    )
+    .unwrap();
+
+    let config = Config::from_file(&config_file).unwrap();
+    assert!(matches!(
+        Manifest::from_config(&config),
+        Err(revocation::Error::Downgrade {
+            accepted: 1765446031,
+            offered: 1765445031,
+        })
+    ));
+
+    allow_downgrade(&config_file);
+    let config = Config::from_file(&config_file).unwrap();
+    assert_eq!(
+        Manifest::from_config(&config)
+            .unwrap()
+            .generated_at,
+        1765445031
+    );
+}
+
+#[test]
+fn typical_incremental_fetch() {
+    let _filters = apply_common_filters();
+    let (server, _filters) = http_server("tests/data/typical/");
+    let (temp, config_file, _filters) = temp_dir_and_config(server.url());
+
+    fs::copy(
+        "tests/data/typical/revocation/manifest.json",
+        temp.path()
+            .join("revocation/manifest.json"),
+    )
+    .unwrap();
+    fs::copy(
+        "tests/data/typical/revocation/filter1.filter",
+        temp.path()
+            .join("revocation/filter1.filter"),
+    )
+    .unwrap();
+    fs::copy(
+        "tests/data/typical/revocation/filter3.delta",
+        temp.path()
+            .join("revocation/filter3.delta"),
+    )
+    .unwrap();
+
+    assert_cmd_snapshot!(
+        upki()
+            .arg("--config-file")
+            .arg(config_file)
+            .arg("fetch"),
+        @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    ");
+
+    // only fetched the neccessary files
+    assert_snapshot!(
+        server.into_log(),
+        @r"
+    GET /manifest.json  ->  200 OK (532 bytes)
+    GET /filter2.delta  ->  200 OK (14 bytes)
+    ");
+
+    assert_eq!(list_dir(temp.path()), vec!["config.toml", "revocation",],);
+
+    assert_eq!(
+        list_dir(&temp.path().join("revocation")),
+        vec![
+            "filter1.filter",
+            "filter2.delta",
+            "filter3.delta",
+            "highest-accepted",
+            "manifest.json"
+        ]
+    );
+}
+
+#[test]
+fn typical_incremental_fetch_dry_run() {
+    let _filters = apply_common_filters();
+    let (server, _filters) = http_server("tests/data/typical/");
+    let (temp, config_file, _filters) = temp_dir_and_config(server.url());
+    fs::copy(
+        "tests/data/typical/revocation/manifest.json",
+        temp.path()
+            .join("revocation/manifest.json"),
+    )
+    .unwrap();
+    fs::copy(
+        "tests/data/typical/revocation/filter1.filter",
+        temp.path()
+            .join("revocation/filter1.filter"),
+    )
+    .unwrap();
+    fs::copy(
+        "tests/data/typical/revocation/filter3.delta",
+        temp.path()
+            .join("revocation/filter3.delta"),
+    )
+    .unwrap();
+
+    assert_cmd_snapshot!(
+        upki()
+            .arg("--config-file")
+            .arg(config_file)
+            .arg("fetch")
+            .arg("--dry-run"),
+        @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    2 steps required (14 bytes to download)
+    - download 14 bytes from http://127.0.0.1:[PORT]/filter2.delta to "[TEMPDIR]/revocation/filter2.delta"
+    - save new manifest into "[TEMPDIR]/revocation"
+
+    ----- stderr -----
+    "#);
+
+    // fetched just the manifest
+    assert_eq!(list_dir(temp.path()), vec!["config.toml", "revocation",],);
+
+    assert_eq!(
+        list_dir(&temp.path().join("revocation")),
+        vec!["filter1.filter", "filter3.delta", "manifest.json"]
+    );
+}
+
+#[test]
+fn manifest_openmetrics_text() {
+    let (_temp, config) = crlite_config("");
+    let manifest = Manifest::from_config(&config).unwrap();
+    let text = manifest.openmetrics_text();
+
+    // every sample belongs to a family declared with its type and help
+    let (body, rest) = text.split_once("# EOF\n").unwrap();
+    assert_eq!(rest, "");
+    let mut families = HashMap::new();
+    let mut samples = HashMap::new();
+    for line in body.lines() {
+        if let Some(declaration) = line.strip_prefix("# TYPE ") {
+            let (name, kind) = declaration.split_once(' ').unwrap();
+            assert!(["gauge", "info"].contains(&kind), "{line}");
+            families.insert(name, kind);
+        } else if let Some(help) = line.strip_prefix("# HELP ") {
+            let (name, _) = help.split_once(' ').unwrap();
+            assert!(families.contains_key(name), "{line}");
+        } else {
+            let (series, value) = line.rsplit_once(' ').unwrap();
+            let name = series
+                .split_once('{')
+                .map_or(series, |(name, _)| name);
+            let family = match families.get(name) {
+                Some(_) => name,
+                None => name.strip_suffix("_info").unwrap(),
+            };
+            assert!(families.contains_key(family), "{line}");
+            samples.insert(series, value.parse::<f64>().unwrap());
+        }
+    }
+
+    assert_eq!(
+        samples[r#"upki_manifest_info{generated_at="1765445031"}"#],
+        1.0
+    );
+    assert_eq!(samples["upki_manifest_filters"], 1.0);
+    assert_eq!(samples["upki_manifest_filter_bytes"], 316.0);
+    assert!(samples["upki_manifest_age_seconds"] > 0.0);
+}
+
+#[test]
+fn manifest_summary_json() {
+    let (_temp, config) = crlite_config("");
+    let manifest = Manifest::from_config(&config).unwrap();
+    assert_snapshot!(manifest.summary_json(&config).unwrap(), @r#"
+    {
+      "schema": 1,
+      "generated_at": 1765445031,
+      "stale_after": 1765617831,
+      "filters": 1,
+      "filter_bytes": 316,
+      "coverage": [
+        {
+          "log_id": "AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE=",
+          "min_timestamp": 1700000000000,
+          "max_timestamp": 1760000000000
+        },
+        {
+          "log_id": "AgICAgICAgICAgICAgICAgICAgICAgICAgICAgICAgI=",
+          "min_timestamp": 1700000000000,
+          "max_timestamp": 1730000000000
+        }
+      ],
+      "issuers": {
+        "count": 2,
+        "sample": [
+          "qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqo=",
+          "u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7u7s="
+        ]
+      }
+    }
+    "#);
+}
+
+#[test]
+fn manifest_format_version() {
+    let (temp, config_file, _filters) = temp_dir_and_config("http://127.0.0.1:1/");
+    let manifest_path = temp
+        .path()
+        .join("revocation/manifest.json");
+    let config = Config::from_file(&config_file).unwrap();
+
+    // a supported version, given or not
+    for json in [
+        r#"{"generated_at": 1765000000, "comment": "", "filters": []}"#,
+        r#"{"generated_at": 1765000000, "comment": "", "filters": [], "format_version": 1}"#,
+    ] {
+        fs::write(&manifest_path, json).unwrap();
+        let manifest = Manifest::from_config(&config).unwrap();
+        assert_eq!(manifest.format_version(), 1);
+        assert_eq!(
+            serde_json::to_string(&manifest)
+                .unwrap()
+                .contains("format_version"),
+            json.contains("format_version")
+        );
+    }
+
+    // a newer version is refused for that, even if it would not decode
+    fs::write(
+        &manifest_path,
+        r#"{"format_version": 3, "generated_at": "2026-01-01", "filters": {}}"#,
+    )
+    .unwrap();
+    let error = Manifest::from_config(&config).unwrap_err();
+    let revocation::Error::UnsupportedManifestVersion { found, supported } = &error else {
+        panic!("unexpected error {error:?}");
+    };
+    assert_eq!((*found, supported.clone()), (3, 1..=2));
+    assert_snapshot!(
+        error,
+        @"manifest format version 3 is not supported (this version of upki supports 1-2), upgrade upki to use it"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn try_from_config_while_locked() {
+    let (temp, config_file, _filters) = temp_dir_and_config("http://127.0.0.1:1/");
+    fs::write(
+        temp.path()
+            .join("revocation/manifest.json"),
+        r#"{"generated_at": 1765000000, "comment": "", "filters": []}"#,
+    )
+    .unwrap();
+    let config = Config::from_file(&config_file).unwrap();
+
+    // never locked
+    assert!(
+        Manifest::try_from_config(&config)
+            .unwrap()
+            .is_some()
+    );
+
+    let lock = revocation::CacheWriteLock::acquire(&config).unwrap();
+    let start = Instant::now();
+    assert!(
+        Manifest::try_from_config(&config)
+            .unwrap()
+            .is_none()
+    );
+    assert!(start.elapsed() < Duration::from_secs(1));
+    // readers not using the lock are unaffected
+    Manifest::from_config(&config).unwrap();
+
+    drop(lock);
+    assert_eq!(
+        Manifest::try_from_config(&config)
+            .unwrap()
+            .unwrap()
+            .generated_at,
+        1_765_000_000
+    );
+}
+
+#[test]
+fn manifest_next_update() {
+    let manifest = Manifest::from_bytes(
+        br#"{"generated_at": 1765445031, "comment": "", "filters": [], "next_update": 1765466631}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        manifest.next_update(),
+        Some(UNIX_EPOCH + Duration::from_secs(1_765_466_631))
+    );
+    assert!(
+        serde_json::to_string(&manifest)
+            .unwrap()
+            .contains(r#""next_update":1765466631"#)
+    );
+
+    let manifest =
+        Manifest::from_file(Path::new("tests/data/typical/revocation/manifest.json")).unwrap();
+    assert_eq!(manifest.next_update(), None);
+}
+
+#[test]
+fn manifest_filter_bytes() {
+    let (_temp, config) = crlite_config("");
+    let manifest = Manifest::from_config(&config).unwrap();
+
+    let found = manifest
+        .filter_bytes(&IssuerSpkiHash([0xaa; 32]), &config)
+        .unwrap();
+    assert_eq!(found.len(), 1);
+    let filter = CRLiteClubcard::from_bytes(&found[0]).unwrap();
+    assert!(
+        filter
+            .index()
+            .contains_key([0xaa; 32].as_slice())
+    );
+    // the coverage map is a `HashMap`, so the filter re-encodes in some order
+    assert_eq!(filter.to_bytes().unwrap().len(), found[0].len());
+    assert_eq!(
+        found[0],
+        fs::read("tests/data/crlite/revocation/crlite.filter").unwrap()
+    );
+
+    assert!(
+        manifest
+            .filter_bytes(&IssuerSpkiHash([0xcc; 32]), &config)
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[test]
+fn manifest_artifacts() {
+    let dir = Path::new("tests/data/typical/revocation");
+    let manifest = Manifest::from_file(&dir.join("manifest.json")).unwrap();
+    let artifacts = manifest.artifacts();
+    assert_eq!(
+        artifacts
+            .iter()
+            .map(|a| a.path.to_str().unwrap())
+            .collect::<Vec<_>>(),
+        ["filter1.filter", "filter2.delta", "filter3.delta"]
+    );
+
+    for artifact in &artifacts {
+        let bytes = fs::read(dir.join(&artifact.path)).unwrap();
+        assert_eq!(bytes.len(), artifact.size);
+        assert_eq!(
+            digest::digest(&digest::SHA256, &bytes).as_ref(),
+            artifact.hash
+        );
+    }
+}
+
+#[test]
+fn manifest_estimate_footprint() {
+    // the typical test data has filters of 11, 14 and 10 bytes
+    let temp = TempDir::new().unwrap();
+    let path = temp.path().join("config.toml");
+    fs::write(
+        &path,
+        "cache-dir=\"tests/data/typical/\"\n[revocation]\nfetch-url=\"\"\n",
+    )
+    .unwrap();
+    let config = Config::from_file(&path).unwrap();
+    let estimate = Manifest::estimate_footprint(&config).unwrap();
+    assert_eq!(
+        estimate
+            .filters
+            .iter()
+            .map(|filter| (filter.filename.as_str(), filter.bytes))
+            .collect::<Vec<_>>(),
+        [
+            ("filter1.filter", 11),
+            ("filter2.delta", 14),
+            ("filter3.delta", 10)
+        ]
+    );
+    assert_eq!(estimate.total, 35);
+    assert_eq!(estimate.peak, 35 + 14);
+}
+
+#[test]
+fn manifest_issuer_delta() {
+    let older = Manifest::from_file(Path::new("tests/data/issuer_delta/older.json")).unwrap();
+    let newer = Manifest::from_file(Path::new("tests/data/issuer_delta/newer.json")).unwrap();
+
+    // 0xdd moved between filters, so is changed rather than added or removed, and
+    // 0xaa's filter is the same
+    let delta = newer.issuer_delta(&older);
+    assert_eq!(delta.added, vec![IssuerSpkiHash([0xcc; 32])]);
+    assert_eq!(delta.removed, vec![IssuerSpkiHash([0xbb; 32])]);
+    assert_eq!(delta.changed, vec![IssuerSpkiHash([0xdd; 32])]);
+    assert!(delta.complete);
+    assert!(!delta.is_empty());
+
+    let reversed = older.issuer_delta(&newer);
+    assert_eq!(
+        (reversed.added, reversed.removed, reversed.changed),
+        (delta.removed, delta.added, delta.changed)
+    );
+    assert!(newer.issuer_delta(&newer).is_empty());
+
+    // a new range for an issuer changes it, even though its filter is the same
+    let mut ranged = newer.clone();
+    ranged
+        .revoked_ranges
+        .push(RevokedRange {
+            issuer: IssuerSpkiHash([0xaa; 32]),
+            first: CertSerial(vec![0x05]),
+            last: CertSerial(vec![0x05]),
+        });
+    let delta = ranged.issuer_delta(&newer);
+    assert_eq!(delta.changed, vec![IssuerSpkiHash([0xaa; 32])]);
+    assert!(delta.added.is_empty() && delta.removed.is_empty());
+
+    // crlite filters do not list their issuers
+    let crlite =
+        Manifest::from_file(Path::new("tests/data/crlite/revocation/manifest.json")).unwrap();
+    let delta = crlite.issuer_delta(&older);
+    assert!(delta.added.is_empty());
+    assert_eq!(delta.removed.len(), 3);
+    assert!(!delta.complete);
+}
+
+#[test]
+fn diff_manifests() {
+    let _filters = apply_common_filters();
+    assert_cmd_snapshot!(
+        upki()
+            .arg("diff")
+            .arg("tests/data/typical/revocation/manifest.json")
+            .arg("tests/data/evolution/revocation/manifest.json"),
+        @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    generated at 1765445031 -> 1765446031
+    - filter2.delta (14 bytes)
+    + filter4.delta (14 bytes)
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn check_with_sct() {
+    let (_temp, config) = crlite_config("");
+    let manifest = Manifest::from_config(&config).unwrap();
+
+    let sct = CtTimestamp {
+        log_id: CtLogId([0x01; 32]),
+        timestamp: 1_750_000_000_000,
+    };
+    assert_eq!(
+        manifest
+            .check(&crlite_input(&[0x01], vec![sct.clone()]), &config)
+            .unwrap(),
+        RevocationStatus::CertainlyRevoked
+    );
+    assert_eq!(
+        manifest
+            .check(&crlite_input(&[0x04], vec![sct.clone()]), &config)
+            .unwrap(),
+        RevocationStatus::NotRevoked
+    );
+
+    // outside the coverage of this log
+    let late = CtTimestamp {
+        log_id: CtLogId([0x02; 32]),
+        timestamp: 1_750_000_000_000,
+    };
+    assert_eq!(
+        manifest
+            .check(&crlite_input(&[0x01], vec![late]), &config)
+            .unwrap(),
+        RevocationStatus::NotCoveredByRevocationData
+    );
+}
+
+#[test]
+fn concurrent_checks_on_one_manifest() {
+    let (_temp, config) = crlite_config("");
+    let config = Arc::new(config);
+    let manifest = Arc::new(Manifest::from_config(&config).unwrap());
+    let sct = CtTimestamp::from_str(SCT).unwrap();
+
+    let expected = [
+        (vec![0x01], RevocationStatus::CertainlyRevoked),
+        (vec![0x02, 0x03], RevocationStatus::CertainlyRevoked),
+        (vec![0x04], RevocationStatus::NotRevoked),
+    ];
+    let threads = (0..16)
+        .map(|i| {
+            let (manifest, config, sct, expected) = (
+                manifest.clone(),
+                config.clone(),
+                sct.clone(),
+                expected.clone(),
+            );
+            thread::spawn(move || {
+                for round in 0..20 {
+                    let (serial, status) = &expected[(i + round) % expected.len()];
+                    let input = crlite_input(serial, vec![sct.clone()]);
+                    assert_eq!(manifest.check(&input, &config).unwrap(), *status);
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+}
+
+#[test]
+fn check_covered_sct_count() {
+    let scts = vec![
+        CtTimestamp {
+            log_id: CtLogId([0x01; 32]),
+            timestamp: 1_750_000_000_000,
+        },
+        // outside the coverage of this log
+        CtTimestamp {
+            log_id: CtLogId([0x02; 32]),
+            timestamp: 1_750_000_000_000,
+        },
+    ];
+
+    let (_temp, config) = crlite_config("");
+    let manifest = Manifest::from_config(&config).unwrap();
+    let outcome = manifest
+        .check_detailed(&crlite_input(&[0x01], scts.clone()), &config)
+        .unwrap();
+    assert_eq!(outcome.status, RevocationStatus::CertainlyRevoked);
+    assert_eq!(outcome.covered_sct_count, 1);
+
+    let (_temp, config) = crlite_config("min-covered-scts = 2\n");
+    let manifest = Manifest::from_config(&config).unwrap();
+    let outcome = manifest
+        .check_detailed(&crlite_input(&[0x01], scts), &config)
+        .unwrap();
+    assert_eq!(outcome.status, RevocationStatus::NotCoveredByRevocationData);
+    assert_eq!(
+        outcome.not_covered_reason,
+        Some(NotCoveredReason::SctsNotCovered)
+    );
+    assert_eq!(outcome.covered_sct_count, 1);
+}
+
+#[test]
+fn check_with_uncovered_policy() {
+    let sct = CtTimestamp::from_str(SCT).unwrap();
+    let unenrolled = RevocationCheckInput {
+        issuer_spki_hash: IssuerSpkiHash([0xcc; 32]),
+        ..crlite_input(&[0x01], vec![sct.clone()])
+    };
+    let revoked = crlite_input(&[0x01], vec![sct.clone()]);
+    let good = crlite_input(&[0x04], vec![sct]);
+
+    for (policy, uncovered) in [
+        ("fail-open", RevocationStatus::NotRevoked),
+        ("fail-closed", RevocationStatus::CertainlyRevoked),
+        (
+            "return-uncovered",
+            RevocationStatus::NotCoveredByRevocationData,
+        ),
+    ] {
+        let (_temp, config) = crlite_config(&format!("uncovered-policy = \"{policy}\"\n"));
+        let manifest = Manifest::from_config(&config).unwrap();
+        let check = |input| {
+            manifest
+                .check_with_policy(input, &config)
+                .unwrap()
+        };
+        assert_eq!(check(&unenrolled), uncovered, "{policy}");
+        assert_eq!(check(&revoked), RevocationStatus::CertainlyRevoked);
+        assert_eq!(check(&good), RevocationStatus::NotRevoked);
+        assert_eq!(
+            manifest
+                .check(&unenrolled, &config)
+                .unwrap(),
+            RevocationStatus::NotCoveredByRevocationData
+        );
+    }
+}
+
+#[test]
+fn is_revoked() {
+    let sct = CtTimestamp::from_str(SCT).unwrap();
+    let unenrolled = RevocationCheckInput {
+        issuer_spki_hash: IssuerSpkiHash([0xcc; 32]),
+        ..crlite_input(&[0x01], vec![sct.clone()])
+    };
+    let revoked = crlite_input(&[0x01], vec![sct.clone()]);
+    let good = crlite_input(&[0x04], vec![sct]);
+
+    for (policy, uncovered) in [
+        ("fail-open", false),
+        ("fail-closed", true),
+        ("return-uncovered", false),
+    ] {
+        let (_temp, config) = crlite_config(&format!("uncovered-policy = \"{policy}\"\n"));
+        let manifest = Manifest::from_config(&config).unwrap();
+        let is_revoked = |input| {
+            manifest
+                .is_revoked(input, &config)
+                .unwrap()
+        };
+        assert_eq!(is_revoked(&unenrolled), uncovered, "{policy}");
+        assert!(is_revoked(&revoked));
+        assert!(!is_revoked(&good));
+    }
+}
+
+#[test]
+fn check_with_probabilistic_policy() {
+    let revoked = crlite_input(&[0x01], vec![]);
+    let good = crlite_input(&[0x04], vec![]);
+    let covered = crlite_input(&[0x01], vec![CtTimestamp::from_str(SCT).unwrap()]);
+
+    for (policy, expected) in [
+        ("lenient", (RevocationStatus::CertainlyRevoked, None)),
+        (
+            "strict",
+            (
+                RevocationStatus::NotCoveredByRevocationData,
+                Some(NotCoveredReason::NeedsConfirmation),
+            ),
+        ),
+    ] {
+        let (_temp, config) = crlite_config(&format!(
+            "no-sct-policy = \"use-serial-only\"\nprobabilistic-policy = \"{policy}\"\n"
+        ));
+        let manifest = Manifest::from_config(&config).unwrap();
+        let check = |input| {
+            manifest
+                .check_detailed(input, &config)
+                .unwrap()
+        };
+
+        let outcome = check(&revoked);
+        assert_eq!(
+            (outcome.status, outcome.not_covered_reason),
+            expected,
+            "{policy}"
+        );
+        assert_eq!(outcome.match_kind, MatchKind::Probabilistic);
+        assert_eq!(CheckOutcome::decode(&outcome.encode()).unwrap(), outcome);
+
+        // only probabilistic revocations are affected
+        assert_eq!(
+            check(&good).not_covered_reason,
+            Some(NotCoveredReason::NoScts)
+        );
+        assert_eq!(check(&covered).status, RevocationStatus::CertainlyRevoked);
+    }
+}
+
+#[test]
+fn check_any_issuer() {
+    let (_temp, config) = crlite_config("");
+    let manifest = Manifest::from_config(&config).unwrap();
+    let scts = [CtTimestamp::from_str(SCT).unwrap()];
+    let (enrolled, other) = (IssuerSpkiHash([0xaa; 32]), IssuerSpkiHash([0xcc; 32]));
+    let check = |serial: u8, issuers: &[IssuerSpkiHash]| {
+        manifest
+            .check_any_issuer(&CertSerial(vec![serial]), issuers, &scts, &config)
+            .unwrap()
+    };
+
+    // revoked under the second candidate
+    assert_eq!(
+        check(0x01, &[other.clone(), enrolled.clone()]),
+        RevocationStatus::CertainlyRevoked
+    );
+    assert_eq!(
+        check(0x04, &[other.clone(), enrolled]),
+        RevocationStatus::NotRevoked
+    );
+    assert_eq!(
+        check(0x01, &[other]),
+        RevocationStatus::NotCoveredByRevocationData
+    );
+    assert_eq!(
+        check(0x01, &[]),
+        RevocationStatus::NotCoveredByRevocationData
+    );
+}
+
+#[test]
+fn check_warnings() {
+    let (_temp, config) = crlite_config("");
+    let (manifest, report) = Manifest::from_config_with_report(&config).unwrap();
+    // the test data is far older than two days
+    assert_eq!(
+        report.warnings,
+        [Warning::StaleManifest {
+            generated_at: 1_765_445_031
+        }]
+    );
+
+    let covered = CtTimestamp::from_str(SCT).unwrap();
+    let outcome = manifest
+        .check_detailed(&crlite_input(&[0x01], vec![covered.clone()]), &config)
+        .unwrap();
+    assert!(outcome.warnings.is_empty());
+
+    // an SCT from an unknown log does not change the status
+    let unknown = CtTimestamp {
+        log_id: CtLogId([0x09; 32]),
+        ..covered.clone()
+    };
+    let outcome = manifest
+        .check_detailed(
+            &crlite_input(&[0x01], vec![unknown.clone(), covered, unknown]),
+            &config,
+        )
+        .unwrap();
+    assert_eq!(outcome.status, RevocationStatus::CertainlyRevoked);
+    assert_eq!(
+        outcome.warnings,
+        [Warning::UnknownLog {
+            log_id: CtLogId([0x09; 32])
+        }]
+    );
+    assert_eq!(
+        outcome.warnings[0].to_string(),
+        "no filter covers CT log CQkJCQkJCQkJCQkJCQkJCQkJCQkJCQkJCQkJCQkJCQk="
+    );
+}
+
+#[test]
+fn check_sct_gap() {
+    let (_temp, config) = crlite_config("");
+    let manifest = Manifest::from_config(&config).unwrap();
+    // log 0x01 is covered until 1760000000000
+    let too_new = CtTimestamp {
+        log_id: CtLogId([0x01; 32]),
+        timestamp: 1_760_000_100_000,
+    };
+    let check = |input| {
+        manifest
+            .check_detailed(&input, &config)
+            .unwrap()
+    };
+
+    let outcome = check(crlite_input(&[0x04], vec![too_new.clone()]));
+    assert_eq!(
+        outcome.not_covered_reason,
+        Some(NotCoveredReason::SctsNotCovered)
+    );
+    let gap = outcome.sct_gap.unwrap();
+    assert!(gap.issuer_covered);
+    assert_eq!(gap.coverage_end, Some(1_760_000_000_000));
+    assert_eq!(gap.earliest_sct, 1_760_000_100_000);
+    assert_eq!(gap.gap(), Some(Duration::from_secs(100)));
+
+    // a certificate from an issuer that is not tracked
+    let outcome = check(RevocationCheckInput {
+        issuer_spki_hash: IssuerSpkiHash([0xcc; 32]),
+        ..crlite_input(&[0x04], vec![too_new])
+    });
+    assert_eq!(
+        outcome.not_covered_reason,
+        Some(NotCoveredReason::SctsNotCovered)
+    );
+    assert!(!outcome.sct_gap.unwrap().issuer_covered);
+
+    let covered = check(crlite_input(
+        &[0x04],
+        vec![CtTimestamp::from_str(SCT).unwrap()],
+    ));
+    assert_eq!(covered.sct_gap, None);
+}
+
+#[test]
+fn check_conflicting_scts() {
+    // log 0x01 is covered until 1760000000000
+    let log_id = CtLogId([0x01; 32]);
+    let input = crlite_input(
+        &[0x01],
+        vec![
+            CtTimestamp {
+                log_id,
+                timestamp: 1_770_000_000_000,
+            },
+            CtTimestamp::from_str(SCT).unwrap(),
+        ],
+    );
+
+    for (policy, status, reason, used) in [
+        (
+            "use-latest",
+            RevocationStatus::NotCoveredByRevocationData,
+            Some(NotCoveredReason::SctsNotCovered),
+            Some(1_770_000_000_000),
+        ),
+        (
+            "use-earliest",
+            RevocationStatus::CertainlyRevoked,
+            None,
+            Some(1_750_000_000_000),
+        ),
+        (
+            "ignore",
+            RevocationStatus::NotCoveredByRevocationData,
+            Some(NotCoveredReason::NoScts),
+            None,
+        ),
+    ] {
+        let (_temp, config) = crlite_config(&format!("duplicate-sct-policy = \"{policy}\"\n"));
+        let outcome = Manifest::from_config(&config)
+            .unwrap()
+            .check_detailed(&input, &config)
+            .unwrap();
+        assert_eq!(outcome.status, status, "{policy}");
+        assert_eq!(outcome.not_covered_reason, reason, "{policy}");
+        assert_eq!(
+            outcome.warnings,
+            [Warning::ConflictingScts { log_id, used }],
+            "{policy}"
+        );
+    }
+
+    // identical SCTs do not conflict
+    let (_temp, config) = crlite_config("");
+    let sct = CtTimestamp::from_str(SCT).unwrap();
+    let outcome = Manifest::from_config(&config)
+        .unwrap()
+        .check_detailed(&crlite_input(&[0x01], vec![sct.clone(), sct]), &config)
+        .unwrap();
+    assert_eq!(outcome.status, RevocationStatus::CertainlyRevoked);
+    assert!(outcome.warnings.is_empty());
+}
+
+#[test]
+fn check_reports_rejected_scts() {
+    let sct = |log: u8, timestamp: u64| CtTimestamp {
+        log_id: CtLogId([log; 32]),
+        timestamp,
+    };
+    let input = crlite_input(
+        &[0x01],
+        vec![
+            sct(0x01, 1_750_000_000_000),
+            sct(0x01, 1_740_000_000_000),
+            sct(0x02, 1_750_000_000_000),
+            sct(0x03, 1_750_000_000_000),
+            sct(0x04, u64::MAX),
+        ],
+    );
+    let approved = "approved-logs = [\
+                    \"AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE=\", \
+                    \"AwMDAwMDAwMDAwMDAwMDAwMDAwMDAwMDAwMDAwMDAwM=\", \
+                    \"BAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQ=\"]\n";
+
+    // off by default
+    let (_temp, config) = crlite_config(approved);
+    let outcome = Manifest::from_config(&config)
+        .unwrap()
+        .check_detailed(&input, &config)
+        .unwrap();
+    assert_eq!(outcome.status, RevocationStatus::CertainlyRevoked);
+    assert!(outcome.rejected_scts.is_empty());
+
+    let (_temp, config) = crlite_config(&format!("{approved}report-rejected-scts = true\n"));
+    let outcome = Manifest::from_config(&config)
+        .unwrap()
+        .check_detailed(&input, &config)
+        .unwrap();
+    assert_eq!(outcome.status, RevocationStatus::CertainlyRevoked);
+    assert_eq!(
+        outcome
+            .rejected_scts
+            .iter()
+            .map(|rejected| (rejected.sct.clone(), rejected.reason))
+            .collect::<Vec<_>>(),
+        [
+            (sct(0x01, 1_740_000_000_000), SctRejection::Conflicting),
+            (sct(0x02, 1_750_000_000_000), SctRejection::UnapprovedLog),
+            (sct(0x03, 1_750_000_000_000), SctRejection::UnknownLog),
+            (sct(0x04, u64::MAX), SctRejection::Future),
+        ]
+    );
+}
+
+#[test]
+fn check_sct_exempt_issuers() {
+    let check = |config: &Config, input: &RevocationCheckInput| {
+        let outcome = Manifest::from_config(config)
+            .unwrap()
+            .check_detailed(input, config)
+            .unwrap();
+        (outcome.status, outcome.not_covered_reason)
+    };
+    let not_revoked = crlite_input(&[0x04], vec![]);
+
+    let (_temp, config) = crlite_config("");
+    assert_eq!(
+        check(&config, &not_revoked),
+        (
+            RevocationStatus::NotCoveredByRevocationData,
+            Some(NotCoveredReason::NoScts)
+        )
+    );
+
+    let (_temp, config) = crlite_config(&format!("sct-exempt-issuers = [\"{ISSUER_AA}\"]\n"));
+    assert_eq!(
+        check(&config, &not_revoked),
+        (RevocationStatus::NotRevoked, None)
+    );
+    assert_eq!(
+        check(&config, &crlite_input(&[0x01], vec![])),
+        (RevocationStatus::CertainlyRevoked, None)
+    );
+
+    // SCTs are not needed, so ones outside coverage do not matter either
+    let uncovered = CtTimestamp {
+        log_id: CtLogId([0x02; 32]),
+        timestamp: 1_750_000_000_000,
+    };
+    assert_eq!(
+        check(&config, &crlite_input(&[0x04], vec![uncovered])),
+        (RevocationStatus::NotRevoked, None)
+    );
+
+    // coverage still needs the issuer to be in the revocation data
+    let unknown = RevocationCheckInput {
+        issuer_spki_hash: IssuerSpkiHash([0xcc; 32]),
+        ..not_revoked.clone()
+    };
+    let (_temp, config) =
+        crlite_config("sct-exempt-issuers = [\"zMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMw=\"]\n");
+    assert_eq!(
+        check(&config, &unknown),
+        (
+            RevocationStatus::NotCoveredByRevocationData,
+            Some(NotCoveredReason::IssuerNotEnrolled)
+        )
+    );
+
+    // revocation lookups are unaffected by the exemption
+    let (_temp, config) = crlite_config(&format!(
+        "sct-exempt-issuers = [\"{ISSUER_AA}\"]\n\
+         probabilistic-policy = \"strict\"\n"
+    ));
+    assert_eq!(
+        check(&config, &crlite_input(&[0x01], vec![])),
+        (
+            RevocationStatus::NotCoveredByRevocationData,
+            Some(NotCoveredReason::NeedsConfirmation)
+        )
+    );
+    let trace = Manifest::from_config(&config)
+        .unwrap()
+        .check_trace(&not_revoked, &config)
+        .unwrap();
+    assert!(
+        trace
+            .steps
+            .contains(&TraceStep::SctExempt)
+    );
+}
+
+#[test]
+fn check_lenient() {
+    let temp = TempDir::new().unwrap();
+    create_dir(temp.path().join("revocation")).unwrap();
+    for name in ["manifest.json", "crlite.filter"] {
+        fs::copy(
+            Path::new("tests/data/crlite/revocation").join(name),
+            temp.path()
+                .join("revocation")
+                .join(name),
+        )
+        .unwrap();
+    }
+    write_config(&temp, "");
+    let config = Config::from_file(&temp.path().join("config.toml")).unwrap();
+    let manifest = Manifest::from_config(&config).unwrap();
+    let revoked = crlite_input(&[0x01], vec![CtTimestamp::from_str(SCT).unwrap()]);
+
+    // intact data: the same as a strict check
+    let outcome = manifest.check_lenient(&revoked, &config);
+    assert_eq!(
+        outcome,
+        manifest
+            .check_detailed(&revoked, &config)
+            .unwrap()
+    );
+    assert_eq!(outcome.status, RevocationStatus::CertainlyRevoked);
+
+    // corrupt filter: a strict check fails, a lenient one is not covered
+    fs::write(
+        temp.path()
+            .join("revocation/crlite.filter"),
+        b"garbage",
+    )
+    .unwrap();
+    assert!(
+        manifest
+            .check(&revoked, &config)
+            .is_err()
+    );
+    let outcome = manifest.check_lenient(&revoked, &config);
+    assert_eq!(
+        (outcome.status, outcome.not_covered_reason),
+        (
+            RevocationStatus::NotCoveredByRevocationData,
+            Some(NotCoveredReason::CheckError)
+        )
+    );
+    assert_eq!(CheckOutcome::decode(&outcome.encode()).unwrap(), outcome);
+    assert_eq!(
+        outcome.coverage_advice(UNIX_EPOCH),
+        Some(CoverageAdvice::CheckError)
+    );
+}
+
+#[test]
+fn in_scope_issuers_skip_filters() {
+    // the second filter is listed for another issuer, and does not exist
+    let temp = TempDir::new().unwrap();
+    create_dir(temp.path().join("revocation")).unwrap();
+    fs::copy(
+        "tests/data/crlite/revocation/crlite.filter",
+        temp.path()
+            .join("revocation/crlite.filter"),
+    )
+    .unwrap();
+    fs::write(
+        temp.path()
+            .join("revocation/manifest.json"),
+        format!(
+            r#"{{
+                "generated_at": 1765445031,
+                "comment": "",
+                "filters": [
+                    {{
+                        "filename": "crlite.filter",
+                        "size": 316,
+                        "hash": "3aaae14e40075cce35586e6699d6df328eb511da2d905e356576089c6e1c081f",
+                        "issuers": ["{ISSUER_AA}"]
+                    }},
+                    {{
+                        "filename": "missing.filter",
+                        "size": 316,
+                        "hash": "00",
+                        "issuers": ["zMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMw="]
+                    }}
+                ]
+            }}"#
+        ),
+    )
+    .unwrap();
+    write_config(&temp, "");
+    let config_file = temp.path().join("config.toml");
+    let revoked = crlite_input(&[0x01], vec![CtTimestamp::from_str(SCT).unwrap()]);
+
+    let config = Config::from_file(&config_file).unwrap();
+    let manifest = Manifest::from_config(&config).unwrap();
+    assert!(matches!(
+        manifest.check(&revoked, &config),
+        Err(revocation::Error::FilterRead { .. })
+    ));
+
+    let mut contents = fs::read_to_string(&config_file).unwrap();
+    contents.push_str(&format!("in-scope-issuers = [\"{ISSUER_AA}\"]\n"));
+    fs::write(&config_file, contents).unwrap();
+    let config = Config::from_file(&config_file).unwrap();
+    assert_eq!(
+        manifest
+            .check(&revoked, &config)
+            .unwrap(),
+        RevocationStatus::CertainlyRevoked
+    );
+    let outcome = manifest
+        .check_detailed(
+            &RevocationCheckInput {
+                issuer_spki_hash: IssuerSpkiHash([0xcc; 32]),
+                ..revoked.clone()
+            },
+            &config,
+        )
+        .unwrap();
+    assert_eq!(
+        outcome.not_covered_reason,
+        Some(NotCoveredReason::OutOfScope)
+    );
+}
+
+#[test]
+fn check_min_sct_count() {
+    let (_temp, config) = crlite_config("min-sct-count = 2\n");
+    let manifest = Manifest::from_config(&config).unwrap();
+    let sct = CtTimestamp::from_str(SCT).unwrap();
+    let second = CtTimestamp {
+        log_id: CtLogId([0x02; 32]),
+        ..sct.clone()
+    };
+
+    // below the threshold: not covered, even though the data has it as revoked
+    let below = crlite_input(&[0x01], vec![sct.clone()]);
+    let outcome = manifest
+        .check_detailed(&below, &config)
+        .unwrap();
+    assert_eq!(
+        (outcome.status, outcome.not_covered_reason),
+        (
+            RevocationStatus::NotCoveredByRevocationData,
+            Some(NotCoveredReason::InsufficientScts)
+        )
+    );
+    assert_eq!(CheckOutcome::decode(&outcome.encode()).unwrap(), outcome);
+    assert_eq!(
+        outcome.coverage_advice(UNIX_EPOCH),
+        Some(CoverageAdvice::InsufficientScts)
+    );
+    assert_snapshot!(manifest.check_trace(&below, &config).unwrap(), @r"
+    1. issuer qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqo= is enrolled in 1 filters
+    2. issuer is not bypassed
+    3. 1 SCTs is fewer than the 2 required
+    outcome: NotCoveredByRevocationData (InsufficientScts)
+    ");
+
+    // at the threshold: checked as normal
+    let at = crlite_input(&[0x01], vec![sct, second]);
+    assert_eq!(
+        manifest.check(&at, &config).unwrap(),
+        RevocationStatus::CertainlyRevoked
+    );
+}
+
+#[test]
+fn check_stale_coverage() {
+    // the test data covers log 01 up to 1760000000000, a while ago
+    let within = "stale-coverage-threshold = 3153600000\n";
+    let beyond = "stale-coverage-threshold = 60\n";
+    let recent = CtTimestamp {
+        timestamp: 1_770_000_000_000,
+        ..CtTimestamp::from_str(SCT).unwrap()
+    };
+    let stale = Warning::StaleCoverage {
+        coverage_end: 1_760_000_000_000,
+    };
+
+    for policy in ["degraded", "serial-only"] {
+        // within the threshold: checked as normal, without the warning
+        let (_temp, config) =
+            crlite_config(&format!("{within}stale-coverage-policy = \"{policy}\"\n"));
+        let manifest = Manifest::from_config(&config).unwrap();
+        let outcome = manifest
+            .check_detailed(&crlite_input(&[0x04], vec![recent.clone()]), &config)
+            .unwrap();
+        assert_eq!(
+            outcome.not_covered_reason,
+            Some(NotCoveredReason::SctsNotCovered)
+        );
+        assert!(!outcome.warnings.contains(&stale));
+    }
+
+    // beyond the threshold, when degraded: checked as normal, with the warning
+    let (_temp, config) = crlite_config(&format!("{beyond}stale-coverage-policy = \"degraded\"\n"));
+    let manifest = Manifest::from_config(&config).unwrap();
+    let outcome = manifest
+        .check_detailed(&crlite_input(&[0x04], vec![recent.clone()]), &config)
+        .unwrap();
+    assert_eq!(
+        outcome.not_covered_reason,
+        Some(NotCoveredReason::SctsNotCovered)
+    );
+    assert!(outcome.warnings.contains(&stale));
+
+    // beyond the threshold, when serial-only: coverage is ignored
+    let (_temp, config) = crlite_config(&format!(
+        "{beyond}stale-coverage-policy = \"serial-only\"\n"
+    ));
+    let manifest = Manifest::from_config(&config).unwrap();
+    let input = crlite_input(&[0x04], vec![recent.clone()]);
+    let outcome = manifest
+        .check_detailed(&input, &config)
+        .unwrap();
+    assert_eq!(outcome.status, RevocationStatus::NotRevoked);
+    assert!(outcome.warnings.contains(&stale));
+    assert_eq!(
+        manifest
+            .check(&crlite_input(&[0x01], vec![recent]), &config)
+            .unwrap(),
+        RevocationStatus::CertainlyRevoked
+    );
+    assert_snapshot!(manifest.check_trace(&input, &config).unwrap(), @r"
+    1. issuer qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqo= is enrolled in 1 filters
+    2. issuer is not bypassed
+    3. coverage ending at 1760000000000 is stale, so using serial only
+    4. filter crlite.filter by serial only: NotRevoked
+    outcome: NotRevoked
+    warning: revocation data coverage ending at 1760000000000 is stale
+    ");
+}
+
+#[test]
+fn check_at_evaluated_at() {
+    let (_temp, config) = crlite_config("");
+    let manifest = Manifest::from_config(&config).unwrap();
+    // between the coverage end and the SCT below
+    let then = UNIX_EPOCH + Duration::from_secs(1_765_000_000);
+    let recent = CtTimestamp {
+        timestamp: 1_770_000_000_000,
+        ..CtTimestamp::from_str(SCT).unwrap()
+    };
+    let input = crlite_input(&[0x04], vec![recent.clone()]);
+    let future = Warning::FutureSct {
+        log_id: recent.log_id,
+        timestamp: recent.timestamp,
+    };
+
+    let outcome = manifest
+        .check_at(&input, then, &config)
+        .unwrap();
+    assert_eq!(outcome.evaluated_at, then);
+    assert!(outcome.warnings.contains(&future));
+
+    let before = SystemTime::now();
+    let current = manifest
+        .check_detailed(&input, &config)
+        .unwrap();
+    assert!(current.evaluated_at >= before);
+    assert!(current.evaluated_at <= SystemTime::now());
+    assert!(!current.warnings.contains(&future));
+
+    // not part of equality, nor of the encoding
+    let later = manifest
+        .check_at(&input, then + Duration::from_secs(1), &config)
+        .unwrap();
+    assert_eq!(later, outcome);
+    let decoded = CheckOutcome::decode(&current.encode()).unwrap();
+    assert_eq!(decoded.evaluated_at, UNIX_EPOCH);
+}
+
+#[test]
+fn check_revoked_range() {
+    let (_temp, config) = crlite_config("");
+    let mut manifest = Manifest::from_config(&config).unwrap();
+    let sct = CtTimestamp::from_str(SCT).unwrap();
+    let issuer = IssuerSpkiHash([0xaa; 32]);
+    assert!(!manifest.is_serial_in_revoked_range(&issuer, &CertSerial(vec![0x05])));
+
+    manifest
+        .revoked_ranges
+        .push(RevokedRange {
+            issuer: issuer.clone(),
+            first: CertSerial(vec![0x05]),
+            last: CertSerial(vec![0x01, 0x00]),
+        });
+    for (serial, in_range) in [
+        (&[0x04][..], false),
+        (&[0x05][..], true),
+        (&[0x06][..], true),
+        (&[0x00, 0x80][..], true),
+        (&[0x01, 0x00][..], true),
+        (&[0x01, 0x01][..], false),
+        (&[0x01, 0x00, 0x00][..], false),
+    ] {
+        let serial = CertSerial(serial.to_vec());
+        assert_eq!(
+            manifest.is_serial_in_revoked_range(&issuer, &serial),
+            in_range,
+            "{serial:?}"
+        );
+        if in_range {
+            assert_eq!(
+                manifest
+                    .check(&crlite_input(&serial.0, vec![sct.clone()]), &config)
+                    .unwrap(),
+                RevocationStatus::CertainlyRevoked,
+                "{serial:?}"
+            );
+        }
+    }
+    // the filter alone says the serial next to the range is not revoked
+    assert_eq!(
+        manifest
+            .check(&crlite_input(&[0x04], vec![sct.clone()]), &config)
+            .unwrap(),
+        RevocationStatus::NotRevoked
+    );
+
+    // even without SCTs
+    assert_eq!(
+        manifest
+            .check(&crlite_input(&[0x05], vec![]), &config)
+            .unwrap(),
+        RevocationStatus::CertainlyRevoked
+    );
+    assert!(
+        !manifest.is_serial_in_revoked_range(&IssuerSpkiHash([0xcc; 32]), &CertSerial(vec![0x05]))
+    );
+    let trace = manifest
+        .check_trace(&crlite_input(&[0x05], vec![sct.clone()]), &config)
+        .unwrap();
+    assert_snapshot!(trace, @r"
+    1. issuer qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqo= is enrolled in 1 filters
+    2. issuer is not bypassed
+    3. serial is in a revoked range
+    outcome: CertainlyRevoked
+    ");
+
+    // bypassed issuers are still not checked
+    let (_temp, bypass) = crlite_config(&format!("bypass-issuers = [\"{ISSUER_AA}\"]\n"));
+    assert_eq!(
+        manifest
+            .check(&crlite_input(&[0x05], vec![sct]), &bypass)
+            .unwrap(),
+        RevocationStatus::NotCoveredByRevocationData
+    );
+
+    let json = serde_json::to_string(&manifest).unwrap();
+    assert!(json.contains(&format!(
+        r#""revoked_ranges":[{{"issuer":"{ISSUER_AA}","first":"BQ==","last":"AQA="}}]"#
+    )));
+    let decoded = Manifest::from_bytes(
+        br#"{"format_version": 2, "generated_at": 1765445031, "comment": "", "filters": [],
+            "revoked_ranges": [{"issuer": "qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqo=", "first": "BQ==", "last": "AQA="}]}"#,
+    )
+    .unwrap();
+    assert_eq!(decoded.revoked_ranges, manifest.revoked_ranges);
+
+    // ranges must be declared with format version 2
+    assert!(matches!(
+        Manifest::from_bytes(
+            br#"{"generated_at": 1765445031, "comment": "", "filters": [],
+                "revoked_ranges": [{"issuer": "qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqo=", "first": "BQ==", "last": "AQA="}]}"#,
+        ),
+        Err(revocation::Error::ManifestDecode { offset: None, .. })
+    ));
+
+    // an issuer listed only in a range is covered
+    let issuer = IssuerSpkiHash([0xcc; 32]);
+    assert!(
+        !manifest
+            .covers_issuer(&issuer, &config)
+            .unwrap()
+    );
+    manifest
+        .revoked_ranges
+        .push(RevokedRange {
+            issuer: issuer.clone(),
+            first: CertSerial(vec![0x05]),
+            last: CertSerial(vec![0x05]),
+        });
+    assert!(
+        manifest
+            .covers_issuer(&issuer, &config)
+            .unwrap()
+    );
+}
+
+#[test]
+fn check_strict_input() {
+    let sct = CtTimestamp::from_str(SCT).unwrap();
+    let padded = crlite_input(&[0x00, 0x01], vec![sct.clone()]);
+    assert!(!padded.cert_serial.is_canonical());
+    assert!(CertSerial(vec![0x00, 0x80]).is_canonical());
+    assert!(!CertSerial(vec![]).is_canonical());
+
+    // lenient: the redundant leading zero is removed, finding the revocation of 0x01
+    let (_temp, config) = crlite_config("");
+    let manifest = Manifest::from_config(&config).unwrap();
+    assert_eq!(
+        manifest
+            .check(&padded, &config)
+            .unwrap(),
+        RevocationStatus::CertainlyRevoked
+    );
+
+    // strict: rejected, alone or in a batch
+    let (_temp, config) = crlite_config("strict-input = true\n");
+    let manifest = Manifest::from_config(&config).unwrap();
+    assert!(matches!(
+        manifest.check(&padded, &config),
+        Err(revocation::Error::NonCanonicalSerial { serial, index: None })
+            if serial == padded.cert_serial
+    ));
+    let canonical = crlite_input(&[0x01], vec![sct]);
+    let batch = [canonical.clone(), padded.clone(), canonical.clone()];
+    let error = manifest
+        .check_batch(&batch, &config)
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        revocation::Error::NonCanonicalSerial { index: Some(1), .. }
+    ));
+    assert_eq!(
+        error.to_string(),
+        "serial number AAE= of input 1 is not canonically encoded"
+    );
+
+    // check_iter reports it in place of the input's outcome, and carries on
+    let mut outcomes = manifest
+        .check_iter(batch.map(Ok::<_, revocation::Error>), &config)
+        .unwrap();
+    assert!(outcomes.next().unwrap().is_ok());
+    assert!(matches!(
+        outcomes.next().unwrap(),
+        Err(revocation::Error::NonCanonicalSerial { index: Some(1), .. })
+    ));
+    assert!(outcomes.next().unwrap().is_ok());
+    assert_eq!(
+        manifest
+            .check(&canonical, &config)
+            .unwrap(),
+        RevocationStatus::CertainlyRevoked
+    );
+}
+
+#[test]
+fn coverage_report() {
+    let (_temp, config) = crlite_config("");
+    let manifest = Manifest::from_config(&config).unwrap();
+    let sct = CtTimestamp::from_str(SCT).unwrap();
+    let unenrolled = RevocationCheckInput {
+        issuer_spki_hash: IssuerSpkiHash([0xcc; 32]),
+        ..crlite_input(&[0x01], vec![sct.clone()])
+    };
+    let inputs = [
+        crlite_input(&[0x01], vec![sct.clone()]),
+        crlite_input(&[0x04], vec![sct.clone()]),
+        crlite_input(&[0x05], vec![sct]),
+        crlite_input(&[0x04], vec![]),
+        unenrolled.clone(),
+        unenrolled,
+    ];
+
+    let report = manifest
+        .coverage_report(&inputs, &config)
+        .unwrap();
+    assert_eq!(report.version, manifest.version());
+    assert_eq!(report.total, 6);
+    assert_eq!((report.revoked, report.not_revoked), (1, 2));
+    assert_eq!(report.covered(), 3);
+    assert_eq!(
+        report.not_covered,
+        HashMap::from([
+            (Some(NotCoveredReason::NoScts), 1),
+            (Some(NotCoveredReason::IssuerNotEnrolled), 2),
+        ])
+    );
+
+    // the same statuses as a batch check
+    let statuses = manifest
+        .check_batch(&inputs, &config)
+        .unwrap();
+    assert_eq!(
+        statuses
+            .iter()
+            .filter(|status| **status == RevocationStatus::NotRevoked)
+            .count(),
+        report.not_revoked
+    );
+}
+
+#[test]
+fn check_trace() {
+    let (_temp, config) = crlite_config("");
+    let manifest = Manifest::from_config(&config).unwrap();
+    let input = crlite_input(&[0x01], vec![CtTimestamp::from_str(SCT).unwrap()]);
+    let trace = manifest
+        .check_trace(&input, &config)
+        .unwrap();
+    assert_eq!(
+        trace.outcome,
+        manifest
+            .check_detailed(&input, &config)
+            .unwrap()
+    );
+    assert!(trace.steps.contains(&TraceStep::Probe {
+        filter: "crlite.filter".into(),
+        sct: Some(input.sct_timestamps[0].clone()),
+        result: ProbeResult::Revoked,
+    }));
+    assert_snapshot!(trace, @r"
+    1. issuer qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqo= is enrolled in 1 filters
+    2. issuer is not bypassed
+    3. SCT AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE=:1750000000000: used
+    4. filter crlite.filter with SCT AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE=:1750000000000: Revoked
+    5. filter crlite.filter covers 1 SCTs, of 1 required
+    outcome: CertainlyRevoked
+    ");
+
+    // without SCTs, under the default policy
+    let trace = manifest
+        .check_trace(&crlite_input(&[0x01], vec![]), &config)
+        .unwrap();
+    assert_snapshot!(trace, @r"
+    1. issuer qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqo= is enrolled in 1 filters
+    2. issuer is not bypassed
+    3. no SCTs, so applying TreatAsNotCovered
+    outcome: NotCoveredByRevocationData (NoScts)
+    ");
+}
+
+#[test]
+fn check_decision_source() {
+    struct Revoke;
+
+    impl RevocationFallback for Revoke {
+        fn check(
+            &self,
+            _input: &RevocationCheckInput,
+            _outcome: &CheckOutcome,
+        ) -> Result<RevocationStatus, Box<dyn Error + Send + Sync>> {
+            Ok(RevocationStatus::CertainlyRevoked)
+        }
+    }
+
+    let (_temp, config) = crlite_config("");
+    let manifest = Manifest::from_config(&config).unwrap();
+    let sct = CtTimestamp::from_str(SCT).unwrap();
+    let revoked = crlite_input(&[0x01], vec![sct.clone()]);
+    let not_revoked = crlite_input(&[0x04], vec![sct.clone()]);
+    let unenrolled = RevocationCheckInput {
+        issuer_spki_hash: IssuerSpkiHash([0xcc; 32]),
+        ..revoked.clone()
+    };
+
+    // a normal hit, and a miss, are decided by the filter
+    for input in [&revoked, &unenrolled] {
+        assert_eq!(
+            manifest
+                .check_detailed(input, &config)
+                .unwrap()
+                .source,
+            DecisionSource::Filter
+        );
+    }
+
+    let mut fast_allow = FastAllowSet::new(&manifest);
+    fast_allow.insert(&not_revoked.issuer_spki_hash, &not_revoked.cert_serial);
+    let outcome = manifest
+        .check_detailed_with_fast_path(&not_revoked, &fast_allow, &config)
+        .unwrap();
+    assert_eq!(
+        (outcome.status, outcome.source),
+        (RevocationStatus::NotRevoked, DecisionSource::Allowlist)
+    );
+    assert_eq!(
+        manifest
+            .check_detailed_with_fast_path(&revoked, &fast_allow, &config)
+            .unwrap()
+            .source,
+        DecisionSource::Filter
+    );
+
+    let outcome = manifest
+        .check_detailed_with_fallback(&unenrolled, &Revoke, &config)
+        .unwrap();
+    assert_eq!(
+        (outcome.status, outcome.not_covered_reason, outcome.source),
+        (
+            RevocationStatus::CertainlyRevoked,
+            None,
+            DecisionSource::Fallback
+        )
+    );
+    assert_eq!(
+        manifest
+            .check_detailed_with_fallback(&not_revoked, &Revoke, &config)
+            .unwrap()
+            .source,
+        DecisionSource::Filter
+    );
+
+    let (_temp, config) = crlite_config(&format!("bypass-issuers = [\"{ISSUER_AA}\"]\n"));
+    let outcome = manifest
+        .check_detailed(&revoked, &config)
+        .unwrap();
+    assert_eq!(outcome.source, DecisionSource::Bypass);
+    assert_eq!(CheckOutcome::decode(&outcome.encode()).unwrap(), outcome);
+}
+
+#[test]
+fn check_with_chain() {
+    struct Stub {
+        calls: Arc<AtomicUsize>,
+        delay: Duration,
+        answer: Result<RevocationStatus, &'static str>,
+    }
+
+    impl RevocationFallback for Stub {
+        fn check(
+            &self,
+            _input: &RevocationCheckInput,
+            _outcome: &CheckOutcome,
+        ) -> Result<RevocationStatus, Box<dyn Error + Send + Sync>> {
+            self.calls
+                .fetch_add(1, Ordering::SeqCst);
+            thread::sleep(self.delay);
+            self.answer.map_err(Into::into)
+        }
+    }
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let stub = |delay, answer| Stub {
+        calls: calls.clone(),
+        delay,
+        answer,
+    };
+    let timeout = Duration::from_millis(100);
+    let inconclusive = || {
+        FallbackChain::new()
+            .step(
+                stub(
+                    Duration::ZERO,
+                    Ok(RevocationStatus::NotCoveredByRevocationData),
+                ),
+                timeout,
+            )
+            .step(
+                stub(Duration::from_secs(2), Ok(RevocationStatus::NotRevoked)),
+                timeout,
+            )
+            .step(stub(Duration::ZERO, Err("responder unavailable")), timeout)
+    };
+
+    let sct = CtTimestamp::from_str(SCT).unwrap();
+    let unenrolled = RevocationCheckInput {
+        issuer_spki_hash: IssuerSpkiHash([0xcc; 32]),
+        ..crlite_input(&[0x01], vec![sct.clone()])
+    };
+    let (_temp, config) = crlite_config("");
+    let manifest = Manifest::from_config(&config).unwrap();
+
+    // the first three steps are passed over, and the fourth decides
+    let chain = inconclusive().step(
+        stub(Duration::ZERO, Ok(RevocationStatus::CertainlyRevoked)),
+        timeout,
+    );
+    assert_eq!(chain.len(), 4);
+    let started = Instant::now();
+    let outcome = manifest
+        .check_with_chain(&unenrolled, &chain, &config)
+        .unwrap();
+    assert!(started.elapsed() < Duration::from_secs(2));
+    assert_eq!(
+        (outcome.status, outcome.not_covered_reason, outcome.source),
+        (
+            RevocationStatus::CertainlyRevoked,
+            None,
+            DecisionSource::Fallback
+        )
+    );
+    assert_eq!(calls.load(Ordering::SeqCst), 4);
+
+    // nothing definite, so not covered
+    let outcome = manifest
+        .check_with_chain(&unenrolled, &inconclusive(), &config)
+        .unwrap();
+    assert_eq!(
+        (outcome.status, outcome.not_covered_reason, outcome.source),
+        (
+            RevocationStatus::NotCoveredByRevocationData,
+            Some(NotCoveredReason::IssuerNotEnrolled),
+            DecisionSource::Filter
+        )
+    );
+
+    // the chain is not consulted for certificates the data covers
+    let calls_before = calls.load(Ordering::SeqCst);
+    assert_eq!(
+        manifest
+            .check_with_chain(&crlite_input(&[0x04], vec![sct]), &chain, &config)
+            .unwrap()
+            .status,
+        RevocationStatus::NotRevoked
+    );
+    assert_eq!(calls.load(Ordering::SeqCst), calls_before);
+}
+
+#[test]
+fn check_with_fallback() {
+    struct Stub {
+        calls: Cell<usize>,
+        answer: Result<RevocationStatus, &'static str>,
+    }
+
+    impl RevocationFallback for Stub {
+        fn check(
+            &self,
+            input: &RevocationCheckInput,
+            outcome: &CheckOutcome,
+        ) -> Result<RevocationStatus, Box<dyn Error + Send + Sync>> {
+            assert_eq!(input.issuer_spki_hash, IssuerSpkiHash([0xcc; 32]));
+            assert_eq!(
+                outcome.not_covered_reason,
+                Some(NotCoveredReason::IssuerNotEnrolled)
+            );
+            self.calls.set(self.calls.get() + 1);
+            self.answer.map_err(Into::into)
+        }
+    }
+
+    let sct = CtTimestamp::from_str(SCT).unwrap();
+    let unenrolled = RevocationCheckInput {
+        issuer_spki_hash: IssuerSpkiHash([0xcc; 32]),
+        ..crlite_input(&[0x01], vec![sct.clone()])
+    };
+    let (_temp, config) = crlite_config("");
+    let manifest = Manifest::from_config(&config).unwrap();
+    let stub = Stub {
+        calls: Cell::new(0),
+        answer: Ok(RevocationStatus::CertainlyRevoked),
+    };
+
+    for (serial, expected) in [
+        (&[0x01][..], RevocationStatus::CertainlyRevoked),
+        (&[0x04][..], RevocationStatus::NotRevoked),
+    ] {
+        assert_eq!(
+            manifest
+                .check_with_fallback(&crlite_input(serial, vec![sct.clone()]), &stub, &config)
+                .unwrap(),
+            expected
+        );
+    }
+    assert_eq!(stub.calls.get(), 0);
+
+    assert_eq!(
+        manifest
+            .check_with_fallback(&unenrolled, &stub, &config)
+            .unwrap(),
+        RevocationStatus::CertainlyRevoked
+    );
+    assert_eq!(stub.calls.get(), 1);
+
+    let failing = Stub {
+        calls: Cell::new(0),
+        answer: Err("responder unavailable"),
+    };
+    let error = manifest
+        .check_with_fallback(&unenrolled, &failing, &config)
+        .unwrap_err();
+    assert_eq!(error.to_string(), "fallback revocation check failed");
+    assert_eq!(error.source().unwrap().to_string(), "responder unavailable");
+}
+
+#[test]
+fn check_approved_logs() {
+    const LOG_01: &str = "AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE=";
+    const LOG_03: &str = "AwMDAwMDAwMDAwMDAwMDAwMDAwMDAwMDAwMDAwMDAwM=";
+    let sct = CtTimestamp::from_str(SCT).unwrap();
+    let input = crlite_input(&[0x01], vec![sct.clone()]);
+    let now = UNIX_EPOCH + Duration::from_secs(1_790_000_000);
+
+    // the only SCT is from a log outside the policy
+    let (_temp, config) = crlite_config(&format!("approved-logs = [\"{LOG_03}\"]\n"));
+    let manifest = Manifest::from_config(&config).unwrap();
+    let outcome = manifest
+        .check_detailed(&input, &config)
+        .unwrap();
+    assert_eq!(outcome.status, RevocationStatus::NotCoveredByRevocationData);
+    assert_eq!(
+        outcome.not_covered_reason,
+        Some(NotCoveredReason::SctsNotCovered)
+    );
+    assert_eq!(outcome.covered_sct_count, 0);
+    assert_eq!(
+        manifest
+            .sct_coverage(&input.sct_timestamps, &config, now)
+            .unwrap()
+            .covered,
+        0
+    );
+
+    // an approved log counts as usual
+    let (_temp, config) = crlite_config(&format!("approved-logs = [\"{LOG_03}\", \"{LOG_01}\"]\n"));
+    assert_eq!(
+        manifest.check(&input, &config).unwrap(),
+        RevocationStatus::CertainlyRevoked
+    );
+
+    // requiring two approved logs is not met by one approved and one other
+    let other = CtTimestamp {
+        log_id: CtLogId([0x03; 32]),
+        ..sct.clone()
+    };
+    let (_temp, config) = crlite_config(&format!(
+        "approved-logs = [\"{LOG_01}\"]\nmin-covered-scts = 2\n"
+    ));
+    assert_eq!(
+        manifest
+            .check(&crlite_input(&[0x01], vec![sct, other]), &config)
+            .unwrap(),
+        RevocationStatus::NotCoveredByRevocationData
+    );
+}
+
+#[test]
+fn check_log_windows() {
+    const LOG_01: &str = "AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE=";
+    let sct = CtTimestamp::from_str(SCT).unwrap();
+    let input = crlite_input(&[0x01], vec![sct.clone()]);
+
+    // the only SCT is dated after its log was retired
+    let (_temp, config) = crlite_config(&format!(
+        "log-windows = [{{ log-id = \"{LOG_01}\", retired-after = 1740000000000 }}]\n\
+        report-rejected-scts = true\n"
+    ));
+    let manifest = Manifest::from_config(&config).unwrap();
+    let outcome = manifest
+        .check_detailed(&input, &config)
+        .unwrap();
+    assert_eq!(outcome.status, RevocationStatus::NotCoveredByRevocationData);
+    assert_eq!(
+        outcome.not_covered_reason,
+        Some(NotCoveredReason::SctsNotCovered)
+    );
+    assert_eq!(outcome.covered_sct_count, 0);
+    assert!(
+        outcome
+            .warnings
+            .contains(&Warning::DisqualifiedSct {
+                log_id: sct.log_id,
+                timestamp: sct.timestamp,
+            })
+    );
+    assert_eq!(outcome.rejected_scts.len(), 1);
+    assert_eq!(outcome.rejected_scts[0].reason, SctRejection::Disqualified);
+    let now = UNIX_EPOCH + Duration::from_secs(1_790_000_000);
+    assert_eq!(
+        manifest
+            .sct_coverage(&input.sct_timestamps, &config, now)
+            .unwrap()
+            .covered,
+        0
+    );
+
+    // an SCT within the window counts as usual
+    let (_temp, config) = crlite_config(&format!(
+        "log-windows = [{{ log-id = \"{LOG_01}\", usable-from = 1700000000000, \
+        retired-after = 1760000000000 }}]\n"
+    ));
+    let outcome = manifest
+        .check_detailed(&input, &config)
+        .unwrap();
+    assert_eq!(outcome.status, RevocationStatus::CertainlyRevoked);
+    assert!(outcome.warnings.is_empty());
+}
+
+#[test]
+fn check_bypassed_issuer() {
+    let (_temp, config) =
+        crlite_config("bypass-issuers = [\"qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqo=\"]\n");
+    let manifest = Manifest::from_config(&config).unwrap();
+
+    let sct = CtTimestamp {
+        log_id: CtLogId([0x01; 32]),
+        timestamp: 1_750_000_000_000,
+    };
+    for input in [
+        crlite_input(&[0x01], vec![sct.clone()]),
+        crlite_input(&[0x04], vec![sct]),
+        crlite_input(&[0x01], vec![]),
+    ] {
+        let outcome = manifest
+            .check_detailed(&input, &config)
+            .unwrap();
+        assert_eq!(outcome.status, RevocationStatus::NotCoveredByRevocationData);
+        assert_eq!(outcome.not_covered_reason, Some(NotCoveredReason::Bypassed));
+    }
+}
+
+#[test]
+fn check_in_scope_issuers() {
+    let check = |config: &Config, input: &RevocationCheckInput| {
+        let outcome = Manifest::from_config(config)
+            .unwrap()
+            .check_detailed(input, config)
+            .unwrap();
+        (outcome.status, outcome.not_covered_reason)
+    };
+    let revoked = crlite_input(&[0x01], vec![CtTimestamp::from_str(SCT).unwrap()]);
+
+    // in scope: checked as normal
+    let (_temp, config) = crlite_config(&format!("in-scope-issuers = [\"{ISSUER_AA}\"]\n"));
+    assert_eq!(
+        check(&config, &revoked),
+        (RevocationStatus::CertainlyRevoked, None)
+    );
+
+    // out of scope: not checked, even though the revocation data has it as revoked
+    let (_temp, config) =
+        crlite_config("in-scope-issuers = [\"zMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMw=\"]\n");
+    let outcome = Manifest::from_config(&config)
+        .unwrap()
+        .check_detailed(&revoked, &config)
+        .unwrap();
+    assert_eq!(
+        (outcome.status, outcome.not_covered_reason),
+        (
+            RevocationStatus::NotCoveredByRevocationData,
+            Some(NotCoveredReason::OutOfScope)
+        )
+    );
+    assert_eq!(CheckOutcome::decode(&outcome.encode()).unwrap(), outcome);
+    assert_eq!(
+        outcome.coverage_advice(UNIX_EPOCH),
+        Some(CoverageAdvice::OutOfScope)
+    );
+
+    let trace = Manifest::from_config(&config)
+        .unwrap()
+        .check_trace(&revoked, &config)
+        .unwrap();
+    assert_snapshot!(trace, @r"
+    1. issuer qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqo= is enrolled in 1 filters
+    2. issuer is out of scope
+    outcome: NotCoveredByRevocationData (OutOfScope)
+    ");
+
+    // out of scope takes precedence over bypass
+    let (_temp, config) = crlite_config(
+        "in-scope-issuers = [\"zMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMw=\"]\n\
+         bypass-issuers = [\"qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqo=\"]\n",
+    );
+    assert_eq!(
+        check(&config, &revoked),
+        (
+            RevocationStatus::NotCoveredByRevocationData,
+            Some(NotCoveredReason::OutOfScope)
+        )
+    );
+}
+
+#[test]
+fn check_input_builder() {
+    let input = RevocationCheckInput::builder()
+        .serial(vec![0x01])
+        .issuer_spki_hash([0xaa; 32])
+        .build()
+        .unwrap();
+    assert_eq!(input, crlite_input(&[0x01], vec![]));
+
+    let scts = vec![
+        CtTimestamp::from_str(SCT).unwrap(),
+        CtTimestamp {
+            log_id: CtLogId([0x02; 32]),
+            timestamp: 1_750_000_000_000,
+        },
+    ];
+    let input = scts
+        .iter()
+        .cloned()
+        .fold(
+            RevocationCheckInput::builder()
+                .issuer_spki_hash([0xaa; 32])
+                .serial(&[0x02, 0x03][..]),
+            |builder, sct| builder.sct(sct),
+        )
+        .build()
+        .unwrap();
+    assert_eq!(input, crlite_input(&[0x02, 0x03], scts));
+
+    for (builder, message) in [
+        (
+            RevocationCheckInput::builder().issuer_spki_hash([0xaa; 32]),
+            "check input is missing its serial",
+        ),
+        (
+            RevocationCheckInput::builder().serial(vec![0x01]),
+            "check input is missing its issuer SPKI hash",
+        ),
+    ] {
+        assert_eq!(builder.build().unwrap_err().to_string(), message);
+    }
+}
+
+#[test]
+fn check_input_equality_and_hash() {
+    let sct = |log, timestamp| CtTimestamp {
+        log_id: CtLogId([log; 32]),
+        timestamp,
+    };
+    let input = crlite_input(&[0x01], vec![sct(0x01, 1), sct(0x02, 2)]);
+    let hash = |input: &RevocationCheckInput| {
+        let mut hasher = DefaultHasher::new();
+        input.hash(&mut hasher);
+        hasher.finish()
+    };
+
+    // the order of SCTs does not matter
+    let reordered = crlite_input(&[0x01], vec![sct(0x02, 2), sct(0x01, 1)]);
+    assert_eq!(input, reordered);
+    assert_eq!(hash(&input), hash(&reordered));
+    assert_eq!(input.clone(), input);
+
+    // but every field's contents do
+    for different in [
+        crlite_input(&[0x02], vec![sct(0x01, 1), sct(0x02, 2)]),
+        crlite_input(&[0x01], vec![sct(0x01, 1), sct(0x02, 3)]),
+        crlite_input(&[0x01], vec![sct(0x01, 1)]),
+        crlite_input(&[0x01], vec![sct(0x01, 1), sct(0x01, 1), sct(0x02, 2)]),
+        RevocationCheckInput {
+            issuer_spki_hash: IssuerSpkiHash([0xcc; 32]),
+            ..input.clone()
+        },
+    ] {
+        assert_ne!(input, different);
+    }
+
+    let mut cache = HashMap::new();
+    cache.insert(input, RevocationStatus::CertainlyRevoked);
+    assert_eq!(
+        cache.get(&reordered),
+        Some(&RevocationStatus::CertainlyRevoked)
+    );
+    assert!(
+        [RevocationStatus::NotRevoked]
+            .into_iter()
+            .collect::<HashSet<_>>()
+            .contains(&RevocationStatus::NotRevoked)
+    );
+}
+
+#[test]
+fn ct_log_id_conversions() {
+    let id = CtLogId([0x01; 32]);
+    assert_eq!(
+        id.to_string(),
+        "AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE="
+    );
+    assert_eq!(CtLogId::from_str(&id.to_string()).unwrap(), id);
+    assert_eq!(CtLogId::from([0x01; 32]), id);
+    assert!(matches!(
+        CtLogId::from_str("AQE="),
+        Err(revocation::Error::InvalidLength {
+            expected: 32,
+            actual: 2,
+            ..
+        })
+    ));
+    assert!(matches!(
+        CtLogId::from_str("!"),
+        Err(revocation::Error::InvalidBase64 { .. })
+    ));
+
+    let sct = CtTimestamp::from_str(SCT).unwrap();
+    assert_eq!(sct.log_id, id);
+    assert_eq!(sct.timestamp, 1_750_000_000_000);
+    assert_eq!(sct.to_string(), SCT);
+}
+
+#[test]
+fn ct_timestamp_to_system_time() {
+    let at = |timestamp| CtTimestamp {
+        log_id: CtLogId([0x01; 32]),
+        timestamp,
+    };
+
+    assert_eq!(at(0).to_system_time(), Some(UNIX_EPOCH));
+    assert_eq!(
+        at(1_750_000_000_123).to_system_time(),
+        Some(UNIX_EPOCH + Duration::from_millis(1_750_000_000_123))
+    );
+
+    // representable on some platforms and not others, but never a panic
+    if let Some(time) = at(u64::MAX).to_system_time() {
+        assert_eq!(
+            time.duration_since(UNIX_EPOCH).unwrap(),
+            Duration::from_millis(u64::MAX)
+        );
+    }
+}
+
+#[test]
+fn sct_coverage_window() {
+    let (_temp, config) = crlite_config("");
+    let manifest = Manifest::from_config(&config).unwrap();
+    // log 0x01 is covered from 1_700_000_000_000 to 1_760_000_000_000
+    let sct = |timestamp| CtTimestamp {
+        log_id: CtLogId([0x01; 32]),
+        timestamp,
+    };
+    let now = UNIX_EPOCH + Duration::from_secs(1_790_000_000);
+    let coverage = |scts: &[CtTimestamp], config: &Config| {
+        manifest
+            .sct_coverage(scts, config, now)
+            .unwrap()
+    };
+
+    // inside
+    let inside = coverage(&[sct(1_750_000_000_000)], &config);
+    assert_eq!((inside.covered, inside.future), (1, 0));
+    assert!(inside.sufficient);
+
+    // straddling the end of coverage
+    let straddling = [sct(1_750_000_000_000), sct(1_770_000_000_000)];
+    let result = coverage(&straddling, &config);
+    assert_eq!((result.covered, result.future), (1, 0));
+    assert!(result.sufficient);
+    let (_temp, two) = crlite_config("min-covered-scts = 2\n");
+    assert!(!coverage(&straddling, &two).sufficient);
+
+    // entirely past the end of coverage, and in the future
+    let past = coverage(&[sct(1_770_000_000_000), sct(1_800_000_000_000)], &config);
+    assert_eq!((past.covered, past.future), (0, 1));
+    assert!(!past.sufficient);
+
+    // an uncovered log, and no SCTs at all
+    let other = CtTimestamp {
+        log_id: CtLogId([0x02; 32]),
+        timestamp: 1_750_000_000_000,
+    };
+    assert_eq!(coverage(&[other], &config).covered, 0);
+    assert!(!coverage(&[], &config).sufficient);
+}
+
+#[test]
+fn check_with_fast_path() {
+    let (_temp, config) = crlite_config("");
+    let manifest = Manifest::from_config(&config).unwrap();
+    let sct = CtTimestamp {
+        log_id: CtLogId([0x01; 32]),
+        timestamp: 1_750_000_000_000,
+    };
+    let allowed = crlite_input(&[0x04], vec![sct.clone()]);
+    // deliberately wrong, to show when the set is consulted
+    let wrongly_allowed = crlite_input(&[0x01], vec![sct]);
+
+    let mut fast_allow = FastAllowSet::new(&manifest);
+    fast_allow.insert(&allowed.issuer_spki_hash, &allowed.cert_serial);
+    fast_allow.insert(
+        &wrongly_allowed.issuer_spki_hash,
+        &wrongly_allowed.cert_serial,
+    );
+
+    let check = |manifest: &Manifest, input| {
+        manifest
+            .check_with_fast_path(input, &fast_allow, &config)
+            .unwrap()
+    };
+    assert_eq!(check(&manifest, &allowed), RevocationStatus::NotRevoked);
+    assert_eq!(
+        check(&manifest, &wrongly_allowed),
+        RevocationStatus::NotRevoked
+    );
+
+    // after a version bump, the set is ignored
+    let mut newer = manifest.clone();
+    newer.generated_at += 1;
+    assert_eq!(check(&newer, &allowed), RevocationStatus::NotRevoked);
+    assert_eq!(
+        check(&newer, &wrongly_allowed),
+        RevocationStatus::CertainlyRevoked
+    );
+}
+
+#[test]
+fn check_stream_formats() {
+    let (_temp, config) = crlite_config("");
+    let manifest = Manifest::from_config(&config).unwrap();
+    let issuer = "aa".repeat(32);
+    let input = format!(
+        "{issuer} 01 {SCT}\n\
+         \n\
+         {issuer} 04 {SCT}\n\
+         {issuer}\n\
+         {issuer} 04\n\
+         {ISSUER_AA} 04\n"
+    );
+
+    let mut output = Vec::new();
+    manifest
+        .check_stream(input.as_bytes(), &mut output, StreamFormat::Csv, &config)
+        .unwrap();
+    assert_snapshot!(String::from_utf8(output).unwrap(), @r#"
+    line,status,error
+    1,CertainlyRevoked,
+    3,NotRevoked,
+    4,,"invalid check input: expected an issuer SPKI hash and a certificate serial"
+    5,NotCoveredByRevocationData,
+    6,,"invalid hex for issuer SPKI hash"
     "#);
 
-    // fetched just the manifest
-    assert_eq!(list_dir(temp.path()), vec!["config.toml", "revocation",],);
+    let mut output = Vec::new();
+    manifest
+        .check_stream(
+            input.as_bytes(),
+            &mut output,
+            StreamFormat::JsonLines,
+            &config,
+        )
+        .unwrap();
+    assert_snapshot!(String::from_utf8(output).unwrap(), @r#"
+    {"line":1,"status":"CertainlyRevoked"}
+    {"line":3,"status":"NotRevoked"}
+    {"line":4,"error":"invalid check input: expected an issuer SPKI hash and a certificate serial"}
+    {"line":5,"status":"NotCoveredByRevocationData"}
+    {"line":6,"error":"invalid hex for issuer SPKI hash"}
+    "#);
+}
+
+#[test]
+fn check_stream_large_input() {
+    /// Generates `remaining` lines on demand, every tenth of which is malformed.
+    struct Generated {
+        remaining: usize,
+        line: Vec<u8>,
+    }
+
+    impl io::Read for Generated {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.line.is_empty() {
+                if self.remaining == 0 {
+                    return Ok(0);
+                }
+                self.line = match self.remaining % 10 {
+                    0 => b"malformed\n".to_vec(),
+                    _ => format!("{} 04 {SCT}\n", "aa".repeat(32)).into_bytes(),
+                };
+                self.remaining -= 1;
+            }
+
+            let len = buf.len().min(self.line.len());
+            buf[..len].copy_from_slice(&self.line[..len]);
+            self.line.drain(..len);
+            Ok(len)
+        }
+    }
+
+    /// Counts output lines, keeping only the current one.
+    #[derive(Default)]
+    struct Counted {
+        line: Vec<u8>,
+        statuses: usize,
+        errors: usize,
+    }
+
+    impl io::Write for Counted {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            for &byte in buf {
+                if byte != b'\n' {
+                    self.line.push(byte);
+                    continue;
+                }
+
+                let line = str::from_utf8(&self.line).unwrap();
+                match line.contains("\"status\"") {
+                    true => self.statuses += 1,
+                    false => self.errors += 1,
+                }
+                self.line.clear();
+            }
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let (_temp, config) = crlite_config("");
+    let manifest = Manifest::from_config(&config).unwrap();
+    let input = io::BufReader::new(Generated {
+        remaining: 20_000,
+        line: Vec::new(),
+    });
+    let mut output = Counted::default();
+    manifest
+        .check_stream(input, &mut output, StreamFormat::JsonLines, &config)
+        .unwrap();
+    assert_eq!(output.statuses, 18_000);
+    assert_eq!(output.errors, 2_000);
+}
+
+#[test]
+fn check_without_sct_default_policy() {
+    let (_temp, config) = crlite_config("");
+    let manifest = Manifest::from_config(&config).unwrap();
+
+    for serial in [&[0x01][..], &[0x04]] {
+        assert_eq!(
+            manifest
+                .check(&crlite_input(serial, vec![]), &config)
+                .unwrap(),
+            RevocationStatus::NotCoveredByRevocationData
+        );
+    }
+}
+
+#[test]
+fn check_without_sct_use_serial_only() {
+    let (_temp, config) = crlite_config("no-sct-policy = \"use-serial-only\"\n");
+    let manifest = Manifest::from_config(&config).unwrap();
 
     assert_eq!(
-        list_dir(&temp.path().join("revocation")),
-        vec!["filter1.filter", "filter3.delta", "manifest.json"]
+        manifest
+            .check(&crlite_input(&[0x01], vec![]), &config)
+            .unwrap(),
+        RevocationStatus::CertainlyRevoked
+    );
+    assert_eq!(
+        manifest
+            .check(&crlite_input(&[0x04], vec![]), &config)
+            .unwrap(),
+        RevocationStatus::NotCoveredByRevocationData
+    );
+}
+
+#[test]
+fn check_without_sct_treat_as_covered() {
+    let (_temp, config) = crlite_config("no-sct-policy = \"treat-as-covered\"\n");
+    let manifest = Manifest::from_config(&config).unwrap();
+
+    assert_eq!(
+        manifest
+            .check(&crlite_input(&[0x02, 0x03], vec![]), &config)
+            .unwrap(),
+        RevocationStatus::CertainlyRevoked
+    );
+    assert_eq!(
+        manifest
+            .check(&crlite_input(&[0x04], vec![]), &config)
+            .unwrap(),
+        RevocationStatus::NotRevoked
+    );
+
+    // issuer not enrolled in the filter
+    let mut input = crlite_input(&[0x04], vec![]);
+    input.issuer_spki_hash = IssuerSpkiHash([0xcc; 32]);
+    assert_eq!(
+        manifest.check(&input, &config).unwrap(),
+        RevocationStatus::NotCoveredByRevocationData
+    );
+}
+
+#[test]
+fn explain_not_covered() {
+    let (_temp, config) = crlite_config("");
+    let manifest = Manifest::from_config(&config).unwrap();
+
+    let explanation = manifest
+        .check_detailed(&crlite_input(&[0x01], vec![]), &config)
+        .unwrap()
+        .explain();
+    assert!(explanation.contains("not covered by revocation data"));
+    assert!(explanation.contains("no SCTs"));
+    assert!(explanation.contains("generated at 1765445031 (2025-12-11T09:23:51+00:00)"));
+    assert!(explanation.contains("OCSP"));
+}
+
+#[test]
+fn coverage_advice_for_reasons() {
+    let fresh = UNIX_EPOCH + Duration::from_secs(1_765_445_031 + 3600);
+    let stale = UNIX_EPOCH + Duration::from_secs(1_765_445_031 + 10 * 86_400);
+    let sct = CtTimestamp::from_str(SCT).unwrap();
+    let other_log = CtTimestamp {
+        log_id: CtLogId([0x02; 32]),
+        timestamp: 1_750_000_000_000,
+    };
+    let unenrolled = RevocationCheckInput {
+        issuer_spki_hash: IssuerSpkiHash([0xcc; 32]),
+        ..crlite_input(&[0x01], vec![sct.clone()])
+    };
+
+    let (_temp, config) = crlite_config("");
+    let manifest = Manifest::from_config(&config).unwrap();
+    let advice = |input: &RevocationCheckInput, now| {
+        manifest
+            .check_detailed(input, &config)
+            .unwrap()
+            .coverage_advice(now)
+    };
+
+    assert_eq!(
+        advice(&crlite_input(&[0x01], vec![sct.clone()]), fresh),
+        None
+    );
+    assert_eq!(
+        advice(&crlite_input(&[0x01], vec![]), stale),
+        Some(CoverageAdvice::NoScts)
+    );
+    assert_eq!(
+        advice(&unenrolled, fresh),
+        Some(CoverageAdvice::PrivateIssuer)
+    );
+    assert_eq!(
+        advice(&crlite_input(&[0x01], vec![other_log.clone()]), fresh),
+        Some(CoverageAdvice::TooNew)
+    );
+    for input in [&unenrolled, &crlite_input(&[0x01], vec![other_log])] {
+        assert_eq!(
+            advice(input, stale),
+            Some(CoverageAdvice::StaleManifest { age_days: 10 })
+        );
+    }
+    assert_eq!(
+        CoverageAdvice::StaleManifest { age_days: 10 }.to_string(),
+        "manifest is 10 days stale -- run `upki fetch`"
+    );
+
+    let (_temp, config) = crlite_config(&format!("bypass-issuers = [\"{ISSUER_AA}\"]\n"));
+    assert_eq!(
+        manifest
+            .check_detailed(&crlite_input(&[0x01], vec![sct]), &config)
+            .unwrap()
+            .coverage_advice(fresh),
+        Some(CoverageAdvice::Bypassed)
+    );
+}
+
+#[test]
+fn check_outcome_encoding() {
+    for status in 0..3u8 {
+        for reason in 0..5u8 {
+            for match_kind in 0..2u8 {
+                let mut bytes = vec![1, status, reason, match_kind];
+                bytes.extend_from_slice(&3u64.to_be_bytes());
+                bytes.extend_from_slice(&1_765_445_031u64.to_be_bytes());
+                let decoded = CheckOutcome::decode(&bytes);
+                match (status == 0) == (reason != 0) {
+                    true => assert_eq!(decoded.unwrap().encode(), bytes),
+                    false => assert!(matches!(
+                        decoded,
+                        Err(revocation::Error::InvalidOutcomeEncoding(_))
+                    )),
+                }
+            }
+        }
+    }
+
+    let (_temp, config) = crlite_config("");
+    let manifest = Manifest::from_config(&config).unwrap();
+    let sct = CtTimestamp::from_str(SCT).unwrap();
+    let outcome = manifest
+        .check_detailed(&crlite_input(&[0x01], vec![sct]), &config)
+        .unwrap();
+    let encoded = outcome.encode();
+    assert_eq!(encoded.len(), CheckOutcome::ENCODED_LEN);
+    assert_eq!(CheckOutcome::decode(&encoded).unwrap(), outcome);
+
+    let mut bad_version = encoded.clone();
+    bad_version[0] = 2;
+    let mut bad_status = encoded.clone();
+    bad_status[1] = 3;
+    for (bytes, message) in [
+        (&encoded[..19], "wrong length"),
+        (&bad_version[..], "unsupported version"),
+        (&bad_status[..], "unknown status"),
+    ] {
+        assert_eq!(
+            CheckOutcome::decode(bytes)
+                .unwrap_err()
+                .to_string(),
+            format!("invalid encoded check outcome: {message}")
+        );
+    }
+}
+
+#[test]
+fn inputs_from_pem_bundle() {
+    let bundle = fs::read_to_string("tests/data/bundle/amazon-root-ca-3.pem").unwrap();
+    let inputs = RevocationCheckInput::from_pem_bundle(&bundle)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    // the trailing root is not checked
+    assert_eq!(inputs.len(), 2);
+
+    assert_eq!(
+        hex::encode(&inputs[0].cert_serial.0),
+        "0116696743e9d9143c7ec6c9331ca364"
+    );
+    assert_eq!(
+        hex::encode(inputs[0].issuer_spki_hash.0),
+        "f1d71264818e9242405d5ef5307da5b0526e1bc4f61a4900ab7a06c1d0b8b07a"
+    );
+    assert_eq!(inputs[0].sct_timestamps.len(), 3);
+
+    assert_eq!(
+        hex::encode(&inputs[1].cert_serial.0),
+        "07731272b752ac8adccb0a3027f7dfec18424a"
+    );
+    assert_eq!(
+        hex::encode(inputs[1].issuer_spki_hash.0),
+        "36abc32656acfc645c61b71613c4bf21c787f5cabbee48348d58597803d7abc9"
+    );
+    assert!(inputs[1].sct_timestamps.is_empty());
+
+    // out of order: the end-entity certificate is not followed by its issuer
+    let mut blocks = bundle
+        .split_inclusive("-----END CERTIFICATE-----\n")
+        .collect::<Vec<_>>();
+    blocks.swap(1, 2);
+    let results = RevocationCheckInput::from_pem_bundle(&blocks.concat()).collect::<Vec<_>>();
+    assert_eq!(results.len(), 2);
+    assert!(matches!(results[0], Err(revocation::Error::NoIssuer)));
+    assert!(matches!(results[1], Err(revocation::Error::NoIssuer)));
+
+    let results = RevocationCheckInput::from_pem_bundle(
+        "-----BEGIN CERTIFICATE-----\nnot base64!\n-----END CERTIFICATE-----\n",
+    )
+    .collect::<Vec<_>>();
+    assert_eq!(results.len(), 1);
+    assert!(matches!(results[0], Err(revocation::Error::InvalidPem(_))));
+}
+
+#[test]
+fn resolve_issuer_in_chain() {
+    let bundle = fs::read_to_string("tests/data/bundle/amazon-root-ca-3.pem").unwrap();
+    let ders = CertificateDer::pem_slice_iter(bundle.as_bytes())
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let [leaf, intermediate, root] = [&ders[0][..], &ders[1][..], &ders[2][..]];
+    let leaf_issuer = "f1d71264818e9242405d5ef5307da5b0526e1bc4f61a4900ab7a06c1d0b8b07a";
+    let intermediate_issuer = "36abc32656acfc645c61b71613c4bf21c787f5cabbee48348d58597803d7abc9";
+    let resolve = |chain: &[&[u8]], index| {
+        revocation::resolve_issuer_spki_hash(chain, index).map(|hash| hex::encode(hash.0))
+    };
+
+    // ordered
+    let ordered = [leaf, intermediate, root];
+    assert_eq!(resolve(&ordered, 0).unwrap(), leaf_issuer);
+    assert_eq!(resolve(&ordered, 1).unwrap(), intermediate_issuer);
+
+    // reordered
+    let reordered = [root, leaf, intermediate];
+    assert_eq!(resolve(&reordered, 1).unwrap(), leaf_issuer);
+    assert_eq!(resolve(&reordered, 2).unwrap(), intermediate_issuer);
+
+    // incomplete
+    assert!(matches!(
+        resolve(&[leaf, root], 0),
+        Err(revocation::Error::NoIssuer)
+    ));
+    assert!(matches!(
+        resolve(&[leaf], 0),
+        Err(revocation::Error::NoIssuer)
+    ));
+    assert!(matches!(
+        resolve(&ordered, 3),
+        Err(revocation::Error::TooFewCertificates)
+    ));
+}
+
+#[test]
+fn sct_log_ids() {
+    let sct = |log: u8, timestamp| CtTimestamp {
+        log_id: CtLogId([log; 32]),
+        timestamp,
+    };
+    let input = crlite_input(
+        &[0x01],
+        vec![
+            sct(0x02, 1),
+            sct(0x01, 2),
+            sct(0x02, 3),
+            sct(0x03, 4),
+            sct(0x01, 5),
+        ],
+    );
+    assert_eq!(
+        input.sct_log_ids().collect::<Vec<_>>(),
+        [
+            CtLogId([0x02; 32]),
+            CtLogId([0x01; 32]),
+            CtLogId([0x03; 32])
+        ]
+    );
+    assert_eq!(
+        crlite_input(&[0x01], vec![])
+            .sct_log_ids()
+            .count(),
+        0
+    );
+}
+
+#[test]
+fn telemetry_id() {
+    let sct = CtTimestamp::from_str(SCT).unwrap();
+    let input = crlite_input(&[0x01], vec![sct.clone()]);
+    let id = input.telemetry_id(b"deployment one");
+    assert_eq!(
+        hex::encode(id),
+        "663f7f4bc51049ab0292efadaef3dd84b19d2dad7c1ebdfdebc4b78abde23e45"
+    );
+
+    // stable for the same certificate and salt, whatever the SCTs or serial encoding
+    assert_eq!(input.telemetry_id(b"deployment one"), id);
+    assert_eq!(
+        crlite_input(&[0x00, 0x01], vec![]).telemetry_id(b"deployment one"),
+        id
+    );
+
+    // different for another salt or certificate
+    assert_ne!(input.telemetry_id(b"deployment two"), id);
+    assert_ne!(
+        crlite_input(&[0x02], vec![sct]).telemetry_id(b"deployment one"),
+        id
+    );
+}
+
+#[test]
+fn issuer_spki_hash() {
+    let bundle = fs::read_to_string("tests/data/bundle/amazon-root-ca-3.pem").unwrap();
+    let ders = CertificateDer::pem_slice_iter(bundle.as_bytes())
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let spki =
+        SubjectPublicKeyInfoDer::from_pem_file("tests/data/bundle/amazon-intermediate-spki.pem")
+            .unwrap();
+    let leaf_issuer = "f1d71264818e9242405d5ef5307da5b0526e1bc4f61a4900ab7a06c1d0b8b07a";
+
+    // the intermediate issued the leaf
+    let from_cert = revocation::issuer_spki_hash_from_cert(&ders[1]).unwrap();
+    assert_eq!(hex::encode(from_cert.0), leaf_issuer);
+    assert_eq!(
+        hex::encode(revocation::issuer_spki_hash(&spki).0),
+        leaf_issuer
+    );
+    assert_eq!(
+        RevocationCheckInput::from_certificates(&ders[..2])
+            .unwrap()
+            .issuer_spki_hash,
+        from_cert
+    );
+
+    // hashing the certificate rather than its key gives a different value
+    assert_ne!(revocation::issuer_spki_hash(&ders[1]), from_cert);
+    assert!(matches!(
+        revocation::issuer_spki_hash_from_cert(&spki),
+        Err(revocation::Error::InvalidIssuerCertificate(_))
+    ));
+}
+
+#[test]
+fn check_iter_is_lazy() {
+    let (_temp, config) = crlite_config("");
+    let manifest = Manifest::from_config(&config).unwrap();
+    let sct = CtTimestamp::from_str(SCT).unwrap();
+    let pulled = Cell::new(0);
+    let inputs = [
+        Ok(crlite_input(&[0x01], vec![sct.clone()])),
+        Err(revocation::Error::InvalidCheckInput),
+        Ok(crlite_input(&[0x04], vec![sct])),
+    ]
+    .into_iter()
+    .inspect(|_| pulled.set(pulled.get() + 1));
+
+    let mut outcomes = manifest
+        .check_iter(inputs, &config)
+        .unwrap();
+    assert_eq!(pulled.get(), 0);
+    assert_eq!(
+        outcomes.next().unwrap().unwrap().status,
+        RevocationStatus::CertainlyRevoked
+    );
+    assert_eq!(pulled.get(), 1);
+    assert!(matches!(
+        outcomes.next().unwrap(),
+        Err(revocation::Error::InvalidCheckInput)
+    ));
+    assert_eq!(
+        outcomes.next().unwrap().unwrap().status,
+        RevocationStatus::NotRevoked
+    );
+    assert!(outcomes.next().is_none());
+}
+
+#[test]
+fn check_batch_with_deadline() {
+    let (_temp, config) = crlite_config("");
+    let manifest = Manifest::from_config(&config).unwrap();
+
+    let sct = CtTimestamp {
+        log_id: CtLogId([0x01; 32]),
+        timestamp: 1_750_000_000_000,
+    };
+    let inputs = [&[0x01][..], &[0x04], &[0x02, 0x03]]
+        .into_iter()
+        .map(|serial| crlite_input(serial, vec![sct.clone()]))
+        .collect::<Vec<_>>();
+    let expected = vec![
+        RevocationStatus::CertainlyRevoked,
+        RevocationStatus::NotRevoked,
+        RevocationStatus::CertainlyRevoked,
+    ];
+
+    assert_eq!(
+        manifest
+            .check_batch(&inputs, &config)
+            .unwrap(),
+        expected
+    );
+
+    let result = manifest
+        .check_batch_deadline(&inputs, &config, Instant::now() + Duration::from_secs(60))
+        .unwrap();
+    assert_eq!(result.statuses, expected);
+    assert_eq!(result.remaining, 0);
+
+    let result = manifest
+        .check_batch_deadline(&inputs, &config, Instant::now())
+        .unwrap();
+    assert!(result.statuses.is_empty());
+    assert_eq!(result.remaining, 3);
+}
+
+#[test]
+fn check_batch_collect_uncovered() {
+    let (_temp, config) = crlite_config("");
+    let manifest = Manifest::from_config(&config).unwrap();
+
+    let sct = CtTimestamp::from_str(SCT).unwrap();
+    let unenrolled = RevocationCheckInput {
+        issuer_spki_hash: IssuerSpkiHash([0xcc; 32]),
+        ..crlite_input(&[0x01], vec![sct.clone()])
+    };
+    let inputs = vec![
+        crlite_input(&[0x01], vec![sct.clone()]),
+        unenrolled,
+        crlite_input(&[0x04], vec![sct]),
+        crlite_input(&[0x01], vec![]),
+    ];
+
+    let BatchPartition { decided, uncovered } = manifest
+        .check_batch_collect_uncovered(&inputs, &config)
+        .unwrap();
+    assert_eq!(
+        decided,
+        [
+            (0, RevocationStatus::CertainlyRevoked),
+            (2, RevocationStatus::NotRevoked)
+        ]
+    );
+    assert_eq!(uncovered, [1, 3]);
+
+    // the same inputs are uncovered when checked one at a time
+    let single = inputs
+        .iter()
+        .enumerate()
+        .filter(|(_, input)| {
+            manifest.check(input, &config).unwrap() == RevocationStatus::NotCoveredByRevocationData
+        })
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    assert_eq!(uncovered, single);
+}
+
+#[test]
+fn truncated_manifest_offset() {
+    let bytes = fs::read("tests/data/typical/revocation/manifest.json").unwrap();
+    for len in [1, 30, 100, 250, bytes.len() - 3] {
+        let Err(revocation::Error::ManifestDecode {
+            offset: Some(offset),
+            ..
+        }) = Manifest::from_bytes(&bytes[..len])
+        else {
+            panic!("truncation at {len} did not report an offset");
+        };
+
+        assert!(
+            offset <= len && offset + 2 >= len,
+            "offset {offset} for length {len}"
+        );
+    }
+
+    // an error not at the end of the input: corrupt the ':' after "comment"
+    let colon = bytes
+        .windows(10)
+        .position(|w| w == b"\"comment\":")
+        .unwrap()
+        + 9;
+    let mut bytes = bytes.clone();
+    bytes[colon] = b'!';
+    let Err(revocation::Error::ManifestDecode {
+        offset: Some(offset),
+        ..
+    }) = Manifest::from_bytes(&bytes)
+    else {
+        panic!("corruption did not report an offset");
+    };
+    assert_eq!(offset, colon + 1);
+}
+
+#[test]
+fn worse_of_status() {
+    use RevocationStatus::*;
+
+    for status in [NotCoveredByRevocationData, CertainlyRevoked, NotRevoked] {
+        assert_eq!(status.worse_of(CertainlyRevoked), CertainlyRevoked);
+        assert_eq!(CertainlyRevoked.worse_of(status), CertainlyRevoked);
+        assert_eq!(status.worse_of(status), status);
+    }
+
+    assert_eq!(
+        NotRevoked.worse_of(NotCoveredByRevocationData),
+        NotCoveredByRevocationData
+    );
+    assert_eq!(
+        NotCoveredByRevocationData.worse_of(NotRevoked),
+        NotCoveredByRevocationData
     );
 }
 
@@ -386,6 +4397,15 @@ fn upki() -> Command {
 }
 
 fn http_server(root: &str) -> (TestHttpServer, SettingsBindDropGuard) {
+    http_server_inner(root, false)
+}
+
+/// Like `http_server`, but the server sends `ETag`s and honours `If-None-Match`.
+fn http_server_with_etags(root: &str) -> (TestHttpServer, SettingsBindDropGuard) {
+    http_server_inner(root, true)
+}
+
+fn http_server_inner(root: &str, etags: bool) -> (TestHttpServer, SettingsBindDropGuard) {
     let port = rand::rng().random_range(4000..12000);
 
     // add a filter eliding the (random) port in logs
@@ -395,7 +4415,7 @@ fn http_server(root: &str) -> (TestHttpServer, SettingsBindDropGuard) {
     root.push("revocation");
 
     (
-        TestHttpServer::new(("127.0.0.1", port), &root).unwrap(),
+        TestHttpServer::new(("127.0.0.1", port), &root, etags).unwrap(),
         current_filters.bind_to_scope(),
     )
 }
@@ -445,6 +4465,47 @@ fn write_config(temp: &TempDir, fetch_url: &str) {
     .unwrap();
 }
 
+/// Make a configuration using the `tests/data/crlite` filter, with `extra` appended
+/// to the `[revocation]` section.
+fn crlite_config(extra: &str) -> (TempDir, Config) {
+    let temp = TempDir::new().unwrap();
+    let path = temp.path().join("config.toml");
+    fs::write(
+        &path,
+        format!(
+            "cache-dir=\"tests/data/crlite/\"\n\
+            [revocation]\n\
+            fetch-url=\"\"\n\
+            {extra}"
+        ),
+    )
+    .unwrap();
+
+    let config = Config::from_file(&path).unwrap();
+    (temp, config)
+}
+
+/// Base64 encoding of the issuer SPKI hash enrolled in the crlite test data.
+const ISSUER_AA: &str = "qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqo=";
+
+/// An SCT within the coverage of the crlite test data.
+const SCT: &str = "AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE=:1750000000000";
+
+/// A check input for `serial` issued by the issuer enrolled in `tests/data/crlite`.
+fn crlite_input(serial: &[u8], sct_timestamps: Vec<CtTimestamp>) -> RevocationCheckInput {
+    RevocationCheckInput {
+        cert_serial: CertSerial(serial.to_vec()),
+        issuer_spki_hash: IssuerSpkiHash([0xaa; 32]),
+        sct_timestamps,
+    }
+}
+
+fn allow_downgrade(config_file: &Path) {
+    let mut config = fs::read_to_string(config_file).unwrap();
+    config.push_str("allow-downgrade = true\n");
+    fs::write(config_file, config).unwrap();
+}
+
 fn apply_common_filters() -> SettingsBindDropGuard {
     let mut settings = insta::Settings::clone_current();
     // remove source locations in errors
@@ -468,6 +4529,7 @@ impl TestHttpServer {
     pub fn new(
         addr: (&str, u16),
         server_root: &Path,
+        etags: bool,
     ) -> Result<Self, Box<dyn Error + Send + Sync>> {
         let server = Arc::new(tiny_http::Server::http(addr)?);
 
@@ -481,6 +4543,21 @@ impl TestHttpServer {
                 let target = server_root.join(request.url().strip_prefix("/").unwrap());
 
                 let response = match fs::read(&target) {
+                    // a weak but adequate etag for test data
+                    Ok(data) if etags => {
+                        let etag = format!("\"{}\"", data.len());
+                        let header =
+                            tiny_http::Header::from_bytes("ETag", etag.as_bytes()).unwrap();
+                        let matched = request
+                            .headers()
+                            .iter()
+                            .any(|h| h.field.equiv("If-None-Match") && h.value == *etag);
+                        match matched {
+                            true => tiny_http::Response::from_data(vec![]).with_status_code(304),
+                            false => tiny_http::Response::from_data(data),
+                        }
+                        .with_header(header)
+                    }
                     Ok(data) => tiny_http::Response::from_data(data),
                     Err(e) => tiny_http::Response::from_string(e.to_string()).with_status_code(404),
                 };
@@ -0,0 +1,44 @@
+use core::str::FromStr;
+
+use futures_util::StreamExt;
+use upki::Config;
+use upki::revocation::{
+    CertSerial, CtTimestamp, IssuerSpkiHash, Manifest, RevocationCheckInput, RevocationStatus,
+};
+
+#[test]
+fn check_batch_stream() {
+    let config = Config::from_file("tests/data/crlite/config.toml".as_ref()).unwrap();
+    let manifest = Manifest::from_config(&config).unwrap();
+    let sct = CtTimestamp::from_str("AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE=:1750000000000")
+        .unwrap();
+    let input = |issuer, serial: u8| RevocationCheckInput {
+        cert_serial: CertSerial(vec![serial]),
+        issuer_spki_hash: IssuerSpkiHash([issuer; 32]),
+        sct_timestamps: vec![sct.clone()],
+    };
+    let inputs = vec![input(0xaa, 0x01), input(0xaa, 0x04), input(0xcc, 0x01)];
+    let expected = [
+        RevocationStatus::CertainlyRevoked,
+        RevocationStatus::NotRevoked,
+        RevocationStatus::NotCoveredByRevocationData,
+    ];
+
+    let stream = manifest
+        .check_batch_stream(inputs, &config)
+        .unwrap();
+    let mut results = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap()
+        .block_on(stream.collect::<Vec<_>>());
+
+    // results may arrive in any order, but each input has one
+    results.sort_by_key(|(index, _)| *index);
+    assert_eq!(
+        results,
+        expected
+            .into_iter()
+            .enumerate()
+            .collect::<Vec<_>>()
+    );
+}
@@ -0,0 +1,10 @@
+use std::path::Path;
+
+use upki::revocation::run_test_vectors;
+
+#[test]
+fn bundled_vectors() {
+    let report = run_test_vectors(Path::new("tests/data/vectors.json")).unwrap();
+    assert!(report.mismatches.is_empty(), "{:#?}", report.mismatches);
+    assert_eq!(report.passed, 7);
+}
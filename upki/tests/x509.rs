@@ -0,0 +1,161 @@
+use core::str::FromStr;
+use std::fs;
+
+use rustls_pki_types::CertificateDer;
+use rustls_pki_types::pem::PemObject;
+use upki::Config;
+use upki::revocation::{self, CtTimestamp, Manifest, RevocationCheckInput, RevocationStatus};
+use x509_parser::certificate::X509Certificate;
+use x509_parser::prelude::FromDer;
+
+#[test]
+fn input_from_parsed_certificates() {
+    let bundle = fs::read_to_string("tests/data/bundle/amazon-root-ca-3.pem").unwrap();
+    let ders = CertificateDer::pem_slice_iter(bundle.as_bytes())
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let parsed = ders
+        .iter()
+        .map(|der| {
+            X509Certificate::from_der(der)
+                .unwrap()
+                .1
+        })
+        .collect::<Vec<_>>();
+
+    let expected = RevocationCheckInput::from_pem_bundle(&bundle)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    for (i, expected) in expected.iter().enumerate() {
+        let input = RevocationCheckInput::from_x509_parser(&parsed[i], &parsed[i + 1]).unwrap();
+        assert_eq!(&input, expected);
+    }
+
+    // the root is not the end-entity certificate's issuer
+    assert!(matches!(
+        RevocationCheckInput::from_x509_parser(&parsed[0], &parsed[2]),
+        Err(revocation::Error::NoIssuer)
+    ));
+
+    // this issuer is not enrolled in the crlite test data
+    let config = Config::from_file("tests/data/crlite/config.toml".as_ref()).unwrap();
+    let manifest = Manifest::from_config(&config).unwrap();
+    let input = RevocationCheckInput::from_x509_parser(&parsed[0], &parsed[1]).unwrap();
+    assert_eq!(
+        manifest.check(&input, &config).unwrap(),
+        RevocationStatus::NotCoveredByRevocationData
+    );
+}
+
+#[test]
+fn input_from_precertificate() {
+    let read = |name: &str| {
+        CertificateDer::from_pem_file(format!("tests/data/precert/{name}.pem")).unwrap()
+    };
+    let (ca, signer, final_cert, precert, precert_via_signer) = (
+        read("ca"),
+        read("signer"),
+        read("final"),
+        read("precert"),
+        read("precert-via-signer"),
+    );
+    let parse = |der| {
+        X509Certificate::from_der(der)
+            .unwrap()
+            .1
+    };
+
+    let expected =
+        RevocationCheckInput::from_x509_parser(&parse(&final_cert), &parse(&ca)).unwrap();
+    assert_eq!(
+        expected.sct_timestamps,
+        [
+            CtTimestamp::from_str("AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE=:1750000000000")
+                .unwrap()
+        ]
+    );
+
+    let chain = [parse(&ca), parse(&signer)];
+    for precert in [&precert, &precert_via_signer] {
+        let input = RevocationCheckInput::from_precertificate(
+            &parse(precert),
+            &chain,
+            expected.sct_timestamps.clone(),
+        )
+        .unwrap();
+        assert_eq!(input, expected);
+    }
+
+    // the signing certificate's issuer must be available
+    assert!(matches!(
+        RevocationCheckInput::from_precertificate(&parse(&precert_via_signer), &chain[1..], vec![]),
+        Err(revocation::Error::NoIssuer)
+    ));
+
+    // a final certificate is not a precertificate
+    assert!(matches!(
+        RevocationCheckInput::from_precertificate(&parse(&final_cert), &chain, vec![]),
+        Err(revocation::Error::InvalidEndEntityCertificate(_))
+    ));
+
+    // webpki rejects the critical poison extension
+    assert!(matches!(
+        RevocationCheckInput::from_certificates(&[precert, ca.clone()]),
+        Err(revocation::Error::InvalidEndEntityCertificate(_))
+    ));
+
+    // the final certificate gives the same input however it is parsed
+    let final_input = RevocationCheckInput::from_certificates(&[final_cert, ca]).unwrap();
+    assert_eq!(final_input, expected);
+}
+
+#[test]
+fn input_from_tbs_certificate() {
+    let bundle = fs::read_to_string("tests/data/bundle/amazon-root-ca-3.pem").unwrap();
+    let ders = CertificateDer::pem_slice_iter(bundle.as_bytes())
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let end_entity = X509Certificate::from_der(&ders[0])
+        .unwrap()
+        .1;
+    let tbs = end_entity.tbs_certificate.as_ref();
+
+    let expected = RevocationCheckInput::from_certificates(&ders[..2]).unwrap();
+    let input = RevocationCheckInput::from_tbs_certificate(tbs, &ders[1]).unwrap();
+    assert_eq!(input, expected);
+    assert!(!input.sct_timestamps.is_empty());
+
+    // the same result from the crlite test data
+    let config = Config::from_file("tests/data/crlite/config.toml".as_ref()).unwrap();
+    let manifest = Manifest::from_config(&config).unwrap();
+    assert_eq!(
+        manifest.check(&input, &config).unwrap(),
+        manifest
+            .check(&expected, &config)
+            .unwrap()
+    );
+
+    // the full certificate is not a TBSCertificate
+    assert!(matches!(
+        RevocationCheckInput::from_tbs_certificate(&ders[0], &ders[1]),
+        Err(revocation::Error::InvalidEndEntityCertificate(_))
+    ));
+
+    // nor is a truncated one
+    assert!(matches!(
+        RevocationCheckInput::from_tbs_certificate(&tbs[..tbs.len() - 1], &ders[1]),
+        Err(revocation::Error::InvalidEndEntityCertificate(_))
+    ));
+
+    // the issuer must be a certificate
+    assert!(matches!(
+        RevocationCheckInput::from_tbs_certificate(tbs, tbs),
+        Err(revocation::Error::InvalidIssuerCertificate(_))
+    ));
+
+    // the root is not the end-entity certificate's issuer
+    assert!(matches!(
+        RevocationCheckInput::from_tbs_certificate(tbs, &ders[2]),
+        Err(revocation::Error::NoIssuer)
+    ));
+}